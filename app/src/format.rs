@@ -0,0 +1,176 @@
+//! How rows and types are printed.
+use std::io::{self, Write};
+
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Number, Value};
+
+use parser::{
+    types::{VType, VTypeShape},
+    HailValue,
+};
+
+/// Converts a row to JSON, following the layout of Hail's own JSON export: loci are objects with
+/// a `contig` and a `position`, calls are strings like `0/1`, dicts are arrays of `key` and `value`
+/// pairs, and missing values are `null`.
+pub fn to_json(value: &HailValue) -> Value {
+    match value {
+        HailValue::Struct(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, field)| (name.clone(), to_json(field)))
+                .collect(),
+        ),
+        HailValue::Tuple(values) | HailValue::Array(values) => {
+            Value::Array(values.iter().map(to_json).collect())
+        }
+        HailValue::Set(values) => Value::Array(values.iter().map(to_json).collect()),
+        HailValue::Dict(entries) => Value::Array(
+            entries
+                .iter()
+                .map(|(key, value)| {
+                    let mut entry = Map::new();
+                    entry.insert("key".to_owned(), to_json(key));
+                    entry.insert("value".to_owned(), to_json(value));
+                    Value::Object(entry)
+                })
+                .collect(),
+        ),
+        HailValue::NDArray(array) => {
+            let mut object = Map::new();
+            object.insert("shape".to_owned(), Value::from(array.shape().to_vec()));
+            // Row major, like the nested lists of numpy.
+            let data = array.iter().map(to_json).collect();
+            object.insert("data".to_owned(), Value::Array(data));
+            Value::Object(object)
+        }
+        HailValue::Interval {
+            start,
+            end,
+            includes_start,
+            includes_end,
+        } => {
+            let mut object = Map::new();
+            object.insert("start".to_owned(), to_json(start));
+            object.insert("end".to_owned(), to_json(end));
+            object.insert("includesStart".to_owned(), Value::Bool(*includes_start));
+            object.insert("includesEnd".to_owned(), Value::Bool(*includes_end));
+            Value::Object(object)
+        }
+        HailValue::String(string) => Value::String(string.clone()),
+        HailValue::Binary(bytes) => Value::from(bytes.clone()),
+        HailValue::Float32(float) => float_to_json(f64::from(*float)),
+        HailValue::Float64(float) => float_to_json(*float),
+        HailValue::Int32(int) => Value::from(*int),
+        HailValue::Int64(int) => Value::from(*int),
+        HailValue::Boolean(flag) => Value::Bool(*flag),
+        HailValue::Locus {
+            contig, position, ..
+        } => {
+            let mut object = Map::new();
+            object.insert("contig".to_owned(), Value::String(contig.clone()));
+            object.insert("position".to_owned(), Value::from(*position));
+            Value::Object(object)
+        }
+        HailValue::Call(packed) => match value.as_call() {
+            Some(call) => Value::String(call.to_string()),
+            None => Value::from(*packed),
+        },
+        HailValue::Missing => Value::Null,
+    }
+}
+
+/// JSON has no infinities, they are written as strings (as Hail does).
+fn float_to_json(float: f64) -> Value {
+    match Number::from_f64(float) {
+        Some(number) => Value::Number(number),
+        None if float > 0.0 => Value::String("Infinity".to_owned()),
+        None => Value::String("-Infinity".to_owned()),
+    }
+}
+
+/// The columns of a TSV export: the fields of the rows, in order.
+pub fn tsv_fields(row_type: &VType) -> Result<Vec<String>> {
+    match &row_type.shape {
+        VTypeShape::Struct(fields) => Ok(fields.iter().map(|(name, _)| name.clone()).collect()),
+        _ => Err(anyhow!("the rows must be structs, found {}", row_type)),
+    }
+}
+
+/// A row of a TSV export, with `NA` for missing values and JSON for nested values (as Hail's
+/// `export` does).
+pub fn to_tsv(row: &HailValue, fields: &[String]) -> String {
+    let columns: Vec<String> = fields
+        .iter()
+        .map(|name| match row {
+            HailValue::Struct(values) => values.get(name).map_or_else(String::new, tsv_value),
+            _ => String::new(),
+        })
+        .collect();
+    columns.join("\t")
+}
+
+fn tsv_value(value: &HailValue) -> String {
+    match value {
+        HailValue::Missing => "NA".to_owned(),
+        HailValue::String(string) => string.clone(),
+        HailValue::Float32(float) => float.to_string(),
+        HailValue::Float64(float) => float.to_string(),
+        HailValue::Int32(int) => int.to_string(),
+        HailValue::Int64(int) => int.to_string(),
+        HailValue::Boolean(flag) => flag.to_string(),
+        HailValue::Locus {
+            contig, position, ..
+        } => format!("{}:{}", contig, position),
+        HailValue::Call(packed) => value
+            .as_call()
+            .map_or_else(|| packed.to_string(), |call| call.to_string()),
+        value => to_json(value).to_string(),
+    }
+}
+
+/// Writes the fields of a struct as a tree, one per line, with nested structs (and the structs in
+/// arrays, sets, and dicts) indented below their field.
+pub fn write_tree(out: &mut impl Write, v_type: &VType, depth: usize) -> io::Result<()> {
+    let fields = match struct_fields(v_type) {
+        Some(fields) => fields,
+        None => return writeln!(out, "{}{}", "  ".repeat(depth), v_type),
+    };
+    if fields.is_empty() {
+        writeln!(out, "{}(no fields)", "  ".repeat(depth))?;
+    }
+    for (name, field) in fields {
+        writeln!(out, "{}{}: {}", "  ".repeat(depth), name, summary(field))?;
+        if struct_fields(field).is_some_and(|fields| !fields.is_empty()) {
+            write_tree(out, field, depth + 1)?;
+        }
+    }
+    Ok(())
+}
+
+/// The fields of a struct, or of the structs held by a collection.
+fn struct_fields(v_type: &VType) -> Option<&[(String, VType)]> {
+    match &v_type.shape {
+        VTypeShape::Struct(fields) => Some(fields),
+        VTypeShape::Array(element) | VTypeShape::Set(element) => struct_fields(element),
+        VTypeShape::Dict(_, value) => struct_fields(value),
+        _ => None,
+    }
+}
+
+/// A type, with the fields of its structs left to the tree.
+fn summary(v_type: &VType) -> String {
+    let required = if v_type.required { "+" } else { "" };
+    match &v_type.shape {
+        VTypeShape::Struct(_) => format!("{}Struct", required),
+        VTypeShape::Array(element) if struct_fields(element).is_some() => {
+            format!("{}Array[{}]", required, summary(element))
+        }
+        VTypeShape::Set(element) if struct_fields(element).is_some() => {
+            format!("{}Set[{}]", required, summary(element))
+        }
+        VTypeShape::Dict(key, value) if struct_fields(value).is_some() => {
+            format!("{}Dict[{},{}]", required, key, summary(value))
+        }
+        _ => v_type.to_string(),
+    }
+}
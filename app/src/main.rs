@@ -1,12 +1,468 @@
-use std::path::PathBuf;
+mod format;
 
-use anyhow::Result;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+};
 
-fn main() -> Result<()> {
-    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../resources/sample.vcf.mt");
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand, ValueEnum};
 
-    let m = parser::load::matrix(path)?;
+use parser::{
+    load::metadata::{self, AnyMetadata},
+    types::{
+        metadata::{shared::ComponentReference, ComponentMetadata},
+        ReferenceGenome, VType,
+    },
+};
 
-    println!("{:#?}", m.entries.metadata.encoded_type);
+/// Inspects and exports Hail tables, matrix tables, and their components.
+#[derive(Parser)]
+#[command(name = "app")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prints what the path holds: its Hail version, keys, partition counts, and schema.
+    Describe { path: PathBuf },
+    /// Prints the virtual and encoded types of the rows of each component.
+    Schema { path: PathBuf },
+    /// Prints the first rows, as JSON.
+    Head {
+        path: PathBuf,
+        /// The number of rows to print.
+        #[arg(short = 'n', default_value_t = 10)]
+        rows: usize,
+        #[arg(long, value_enum)]
+        component: Option<Part>,
+    },
+    /// Counts the rows, from the partition counts when they are recorded.
+    Count {
+        path: PathBuf,
+        /// Counts the rows by scanning the part files, even when the counts are recorded.
+        #[arg(long)]
+        scan: bool,
+        #[arg(long, value_enum)]
+        component: Option<Part>,
+    },
+    /// Exports the rows.
+    Export {
+        path: PathBuf,
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+        /// The file to write, the standard output by default (required for Parquet).
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum)]
+        component: Option<Part>,
+    },
+    /// Checks that the rows conform to the schema, and prints the values that do not.
+    Validate {
+        path: PathBuf,
+        /// Only checks that the encoded types can be read as the virtual types, without reading
+        /// the rows.
+        #[arg(long)]
+        schema_only: bool,
+    },
+}
+
+/// The component of a table or matrix table to read, the rows by default.
+#[derive(Clone, Copy, ValueEnum)]
+enum Part {
+    Globals,
+    Rows,
+    Cols,
+    Entries,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    /// One JSON object per row.
+    Json,
+    /// Tab separated values, with a header and a column for each field of the rows.
+    Tsv,
+    /// A Parquet file, with a row group for each partition.
+    Parquet,
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    if let Err(error) = run(cli.command) {
+        // The output was closed early, as by `head`, which is not a failure.
+        if let Some(error) = error.downcast_ref::<io::Error>() {
+            if error.kind() == io::ErrorKind::BrokenPipe {
+                return;
+            }
+        }
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
+
+fn run(command: Command) -> Result<()> {
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+
+    match command {
+        Command::Describe { path } => describe(&mut out, &path)?,
+        Command::Schema { path } => schema(&mut out, &path)?,
+        Command::Head {
+            path,
+            rows,
+            component,
+        } => {
+            let path = component_path(&path, component)?;
+            for row in parser::load::component_iter(&path)?.take(rows) {
+                writeln!(out, "{}", format::to_json(&row?))?;
+            }
+        }
+        Command::Count {
+            path,
+            scan,
+            component,
+        } => count(&mut out, &path, scan, component)?,
+        Command::Export {
+            path,
+            format,
+            output,
+            component,
+        } => {
+            let path = component_path(&path, component)?;
+            match (format, output) {
+                (Format::Parquet, Some(output)) => {
+                    parser::arrow::parquet::write_component(&path, &output)?;
+                }
+                (Format::Parquet, None) => return Err(anyhow!("Parquet needs an --output file")),
+                (format, Some(output)) => {
+                    let file = File::create(&output).map_err(|error| {
+                        anyhow!("unable to create {}: {}", output.display(), error)
+                    })?;
+                    export(&mut BufWriter::new(file), &path, format)?;
+                }
+                (format, None) => export(&mut out, &path, format)?,
+            }
+        }
+        Command::Validate { path, schema_only } => {
+            let violations = validate(&mut out, &path, schema_only)?;
+            if violations > 0 {
+                out.flush()?;
+                return Err(anyhow!(
+                    "{} values do not conform to the schema",
+                    violations
+                ));
+            }
+        }
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+fn describe(out: &mut impl Write, path: &Path) -> Result<()> {
+    match metadata::load_any_metadata(path)? {
+        AnyMetadata::Matrix(metadata) => {
+            let schema = &metadata.matrix_type;
+            let components = &metadata.components;
+            writeln!(
+                out,
+                "Matrix table, written by Hail {}",
+                metadata.hail_version
+            )?;
+            write_counts(out, "Rows", &to_u64(&components.partition_counts.counts))?;
+            match table_counts(path, &components.cols) {
+                Some(counts) => write_counts(out, "Columns", &counts)?,
+                None => writeln!(out, "Columns: unknown")?,
+            }
+            writeln!(out, "Row key: {}", key_names(&schema.row_keys))?;
+            writeln!(out, "Column key: {}", key_names(&schema.col_keys))?;
+            writeln!(out, "References: {}", references(&metadata.references))?;
+            write_tree(out, "Globals", &schema.global_schema)?;
+            write_tree(out, "Columns", &schema.col_schema)?;
+            write_tree(out, "Rows", &schema.row_schema)?;
+            write_tree(out, "Entries", &schema.entry_schema)?;
+        }
+        AnyMetadata::Table(metadata) => {
+            let schema = &metadata.table_type;
+            writeln!(out, "Table, written by Hail {}", metadata.hail_version)?;
+            let counts = to_u64(&metadata.components.partition_counts.counts);
+            write_counts(out, "Rows", &counts)?;
+            writeln!(out, "Key: {}", key_names(&schema.row_keys))?;
+            writeln!(out, "References: {}", references(&metadata.references))?;
+            write_tree(out, "Globals", &schema.global_schema)?;
+            write_tree(out, "Rows", &schema.row_schema)?;
+        }
+        AnyMetadata::Component(metadata) => {
+            writeln!(out, "Component")?;
+            writeln!(out, "Partitions: {}", metadata.part_files.len())?;
+            writeln!(out, "Key: {}", or_none(metadata.key.join(", ")))?;
+            write_tree(out, "Rows", &metadata.virtual_type)?;
+        }
+    }
+    Ok(())
+}
+
+fn schema(out: &mut impl Write, path: &Path) -> Result<()> {
+    match metadata::load_any_metadata(path)? {
+        AnyMetadata::Component(metadata) => write_types(out, "Rows", &metadata),
+        AnyMetadata::Table(metadata) => {
+            let components = &metadata.components;
+            write_component_types(
+                out,
+                path,
+                &[("Globals", &components.globals), ("Rows", &components.rows)],
+            )
+        }
+        AnyMetadata::Matrix(metadata) => {
+            let components = &metadata.components;
+            write_component_types(
+                out,
+                path,
+                &[
+                    ("Globals", &components.globals),
+                    ("Columns", &components.cols),
+                    ("Rows", &components.rows),
+                    ("Entries", &components.entries),
+                ],
+            )
+        }
+    }
+}
+
+fn write_component_types(
+    out: &mut impl Write,
+    path: &Path,
+    components: &[(&str, &ComponentReference)],
+) -> Result<()> {
+    for (name, component) in components {
+        let metadata = metadata::load_component_metadata(path.join(&component.rel_path))?;
+        write_types(out, name, &metadata)?;
+    }
+    Ok(())
+}
+
+fn write_types(out: &mut impl Write, name: &str, metadata: &ComponentMetadata) -> Result<()> {
+    writeln!(out, "{}", name)?;
+    writeln!(out, "  virtual: {}", metadata.virtual_type)?;
+    writeln!(out, "  encoded: {}", metadata.encoded_type)?;
+    Ok(())
+}
+
+fn count(out: &mut impl Write, path: &Path, scan: bool, component: Option<Part>) -> Result<()> {
+    let metadata = metadata::load_any_metadata(path)?;
+    match (&metadata, component) {
+        (AnyMetadata::Matrix(_), None) => {
+            let rows = count_rows(path, &metadata, Some(Part::Rows), scan)?;
+            let cols = count_rows(path, &metadata, Some(Part::Cols), scan)?;
+            writeln!(out, "Rows: {}", rows)?;
+            writeln!(out, "Columns: {}", cols)?;
+        }
+        _ => writeln!(out, "{}", count_rows(path, &metadata, component, scan)?)?,
+    }
+    Ok(())
+}
+
+/// The number of rows of a component, from the partition counts when they are recorded (the
+/// entries of a matrix table have a row for each of its rows), or by scanning the part files.
+fn count_rows(
+    path: &Path,
+    metadata: &AnyMetadata,
+    component: Option<Part>,
+    scan: bool,
+) -> Result<u64> {
+    let recorded = match (metadata, component) {
+        (AnyMetadata::Table(metadata), None | Some(Part::Rows)) => {
+            Some(to_u64(&metadata.components.partition_counts.counts))
+        }
+        (AnyMetadata::Matrix(metadata), None | Some(Part::Rows) | Some(Part::Entries)) => {
+            Some(to_u64(&metadata.components.partition_counts.counts))
+        }
+        (AnyMetadata::Matrix(metadata), Some(Part::Cols)) => {
+            table_counts(path, &metadata.components.cols)
+        }
+        _ => None,
+    };
+
+    let counts = match (scan, recorded) {
+        (false, Some(counts)) => counts,
+        _ => {
+            let path = component_path_of(path, metadata, component)?;
+            parser::load::component_partition_counts(path)?
+        }
+    };
+    Ok(counts.iter().sum())
+}
+
+fn export(out: &mut impl Write, path: &Path, format: Format) -> Result<()> {
+    let rows = parser::load::component_iter(path)?;
+    match format {
+        Format::Json => {
+            for row in rows {
+                writeln!(out, "{}", format::to_json(&row?))?;
+            }
+        }
+        Format::Tsv => {
+            let metadata = metadata::load_component_metadata(path)?;
+            let fields = format::tsv_fields(&metadata.virtual_type)?;
+            writeln!(out, "{}", fields.join("\t"))?;
+            for row in rows {
+                writeln!(out, "{}", format::to_tsv(&row?, &fields))?;
+            }
+        }
+        Format::Parquet => unreachable!("Parquet files are written by the Arrow writer"),
+    }
+    Ok(())
+}
+
+/// Prints the violations of each component, and returns their number.
+///
+/// The types are checked whenever the metadata of a component is loaded, so with `schema_only`
+/// the metadata is all that is loaded.
+fn validate(out: &mut impl Write, path: &Path, schema_only: bool) -> Result<usize> {
+    let metadata = metadata::load_any_metadata(path)?;
+    let reports = match (&metadata, schema_only) {
+        (AnyMetadata::Component(_), false) => vec![("rows", parser::validate::component(path)?)],
+        (AnyMetadata::Table(_), false) => parser::validate::table(path)?,
+        (AnyMetadata::Matrix(_), false) => parser::validate::matrix(path)?,
+        (AnyMetadata::Component(_), true) => vec![("rows", vec![])],
+        (AnyMetadata::Table(metadata), true) => {
+            let components = &metadata.components;
+            load_components(
+                path,
+                &[("globals", &components.globals), ("rows", &components.rows)],
+            )?
+        }
+        (AnyMetadata::Matrix(metadata), true) => {
+            let components = &metadata.components;
+            load_components(
+                path,
+                &[
+                    ("globals", &components.globals),
+                    ("cols", &components.cols),
+                    ("rows", &components.rows),
+                    ("entries", &components.entries),
+                ],
+            )?
+        }
+    };
+
+    let mut count = 0;
+    for (name, violations) in &reports {
+        match violations.len() {
+            0 => writeln!(out, "{}: ok", name)?,
+            n => writeln!(out, "{}: {} violations", name, n)?,
+        }
+        for violation in violations {
+            writeln!(out, "  {}", violation)?;
+        }
+        count += violations.len();
+    }
+    Ok(count)
+}
+
+/// Loads the metadata of the components, which checks their types, without reading their rows.
+fn load_components(
+    path: &Path,
+    components: &[(&'static str, &ComponentReference)],
+) -> Result<Vec<(&'static str, Vec<parser::validate::Violation>)>> {
+    components
+        .iter()
+        .map(|(name, component)| {
+            metadata::load_component_metadata(path.join(&component.rel_path))?;
+            Ok((*name, vec![]))
+        })
+        .collect()
+}
+
+/// The path of the selected component of a table or matrix table, or the path itself for a
+/// component.
+fn component_path(path: &Path, component: Option<Part>) -> Result<PathBuf> {
+    component_path_of(path, &metadata::load_any_metadata(path)?, component)
+}
+
+fn component_path_of(
+    path: &Path,
+    metadata: &AnyMetadata,
+    component: Option<Part>,
+) -> Result<PathBuf> {
+    let reference = match (metadata, component) {
+        (AnyMetadata::Component(_), None) => return Ok(path.to_owned()),
+        (AnyMetadata::Component(_), Some(_)) => {
+            return Err(anyhow!("{} is a single component", path.display()))
+        }
+        (AnyMetadata::Table(metadata), Some(Part::Globals)) => &metadata.components.globals,
+        (AnyMetadata::Table(metadata), None) | (AnyMetadata::Table(metadata), Some(Part::Rows)) => {
+            &metadata.components.rows
+        }
+        (AnyMetadata::Table(_), Some(_)) => {
+            return Err(anyhow!(
+                "{} is a table, it only has globals and rows",
+                path.display()
+            ))
+        }
+        (AnyMetadata::Matrix(metadata), Some(Part::Globals)) => &metadata.components.globals,
+        (AnyMetadata::Matrix(metadata), None)
+        | (AnyMetadata::Matrix(metadata), Some(Part::Rows)) => &metadata.components.rows,
+        (AnyMetadata::Matrix(metadata), Some(Part::Cols)) => &metadata.components.cols,
+        (AnyMetadata::Matrix(metadata), Some(Part::Entries)) => &metadata.components.entries,
+    };
+    Ok(path.join(&reference.rel_path))
+}
+
+/// The partition counts of a component of a matrix table, recorded by the table holding it (the
+/// component is the `rows` of that table).
+fn table_counts(path: &Path, component: &ComponentReference) -> Option<Vec<u64>> {
+    let table = path.join(&component.rel_path);
+    let metadata = metadata::load_table_metadata(table.parent()?).ok()?;
+    Some(to_u64(&metadata.components.partition_counts.counts))
+}
+
+fn to_u64(counts: &[u32]) -> Vec<u64> {
+    counts.iter().map(|&count| count as u64).collect()
+}
+
+fn write_counts(out: &mut impl Write, name: &str, counts: &[u64]) -> Result<()> {
+    let counts_list: Vec<String> = counts.iter().map(u64::to_string).collect();
+    writeln!(
+        out,
+        "{}: {} in {} partitions ({})",
+        name,
+        counts.iter().sum::<u64>(),
+        counts.len(),
+        counts_list.join(", ")
+    )?;
+    Ok(())
+}
+
+fn key_names(keys: &[(String, VType)]) -> String {
+    let names: Vec<&str> = keys.iter().map(|(name, _)| name.as_str()).collect();
+    or_none(names.join(", "))
+}
+
+/// The reference genomes of the loci, with their number of contigs.
+fn references(references: &BTreeMap<String, ReferenceGenome>) -> String {
+    let names: Vec<String> = references
+        .values()
+        .map(|genome| format!("{} ({} contigs)", genome.name(), genome.contigs().len()))
+        .collect();
+    or_none(names.join(", "))
+}
+
+fn or_none(names: String) -> String {
+    match names.is_empty() {
+        true => "(none)".to_owned(),
+        false => names,
+    }
+}
+
+fn write_tree(out: &mut impl Write, name: &str, v_type: &VType) -> Result<()> {
+    writeln!(out, "{}:", name)?;
+    format::write_tree(out, v_type, 1)?;
     Ok(())
 }
@@ -0,0 +1,166 @@
+use std::fs;
+
+use anyhow::Result;
+
+use parser::HailValue;
+
+use crate::common::{app, failure, output, resource, success};
+
+#[test]
+fn describe() {
+    let out = success(app(&["describe"], &resource("three_key.ht")));
+    assert!(out.starts_with("Table, written by Hail 0.2.39"), "{}", out);
+    assert!(out.contains("Rows: 120 in 3 partitions (4, 52, 64)\nKey: x, y, z\n"));
+
+    let out = success(app(&["describe"], &resource("sample.vcf.mt")));
+    assert!(out.starts_with("Matrix table"), "{}", out);
+    assert!(out.contains("References: GRCh37 (86 contigs)\n"), "{}", out);
+
+    let error = failure(app(&["describe"], &resource("missing.ht")));
+    assert!(error.starts_with("error: "), "{}", error);
+}
+
+#[test]
+fn schema() {
+    let out = success(app(&["schema"], &resource("three_key.ht")));
+    assert_eq!(
+        out,
+        "Globals\n  virtual: Struct{}\n  encoded: +EBaseStruct{}\n\
+        Rows\n  virtual: Struct{x:Int32,y:Int32,z:Int32}\n  \
+        encoded: +EBaseStruct{x:+EInt32,y:+EInt32,z:+EInt32}\n"
+    );
+
+    failure(app(&["schema"], &resource("missing.ht")));
+}
+
+#[test]
+fn head() {
+    let out = success(app(&["head", "-n", "2"], &resource("three_key.ht")));
+    assert_eq!(
+        out,
+        "{\"x\":2,\"y\":1,\"z\":0}\n{\"x\":3,\"y\":1,\"z\":0}\n"
+    );
+
+    let args = ["head", "-n", "3", "--component", "entries"];
+    let out = success(app(&args, &resource("sample.vcf.mt")));
+    assert_eq!(out.lines().count(), 3);
+
+    let error = failure(app(
+        &["head", "--component", "cols"],
+        &resource("three_key.ht"),
+    ));
+    assert!(error.contains("only has globals and rows"), "{}", error);
+}
+
+#[test]
+fn count() {
+    let out = success(app(&["count"], &resource("sample.vcf.mt")));
+    assert_eq!(out, "Rows: 346\nColumns: 100\n");
+    // Scanning the part files agrees with the recorded counts.
+    let out = success(app(&["count", "--scan"], &resource("sample.vcf.mt")));
+    assert_eq!(out, "Rows: 346\nColumns: 100\n");
+    let out = success(app(&["count"], &resource("sample.vcf.mt/rows/rows")));
+    assert_eq!(out, "346\n");
+
+    let args = ["count", "--component", "rows"];
+    let error = failure(app(&args, &resource("sample.vcf.mt/rows/rows")));
+    assert!(error.contains("is a single component"), "{}", error);
+}
+
+#[test]
+fn export_json() {
+    let out = success(app(&["export"], &resource("three_key.ht")));
+    assert_eq!(out.lines().count(), 120);
+    assert!(out.starts_with("{\"x\":2,\"y\":1,\"z\":0}\n"), "{}", out);
+
+    failure(app(
+        &["export", "--format", "json"],
+        &resource("missing.ht"),
+    ));
+}
+
+#[test]
+fn export_tsv() -> Result<()> {
+    let file = output("three_key.tsv");
+    let args = [
+        "export",
+        "--format",
+        "tsv",
+        "--output",
+        file.to_str().unwrap(),
+    ];
+    assert_eq!(success(app(&args, &resource("three_key.ht"))), "");
+    let tsv = fs::read_to_string(&file)?;
+    assert_eq!(tsv.lines().count(), 121);
+    assert!(tsv.starts_with("x\ty\tz\n2\t1\t0\n"), "{}", tsv);
+
+    // The output folder does not exist.
+    let file = output("missing").join("three_key.tsv");
+    let args = [
+        "export",
+        "--format",
+        "tsv",
+        "--output",
+        file.to_str().unwrap(),
+    ];
+    let error = failure(app(&args, &resource("three_key.ht")));
+    assert!(error.contains("unable to create"), "{}", error);
+    Ok(())
+}
+
+#[test]
+fn export_parquet() -> Result<()> {
+    let file = output("sample_vcf.parquet");
+    let args = [
+        "export",
+        "--format",
+        "parquet",
+        "-o",
+        file.to_str().unwrap(),
+    ];
+    success(app(&args, &resource("sample.vcf.mt")));
+    let parquet = fs::read(&file)?;
+    assert!(parquet.starts_with(b"PAR1") && parquet.ends_with(b"PAR1"));
+
+    let error = failure(app(
+        &["export", "--format", "parquet"],
+        &resource("sample.vcf.mt"),
+    ));
+    assert!(
+        error.contains("Parquet needs an --output file"),
+        "{}",
+        error
+    );
+    Ok(())
+}
+
+#[test]
+fn validate() -> Result<()> {
+    let out = success(app(&["validate"], &resource("three_key.ht")));
+    assert_eq!(out, "globals: ok\nrows: ok\n");
+    let out = success(app(
+        &["validate", "--schema-only"],
+        &resource("sample.vcf.mt"),
+    ));
+    assert_eq!(out, "globals: ok\ncols: ok\nrows: ok\nentries: ok\n");
+
+    // The last locus is moved past the end of its contig, which keeps the rows sorted.
+    let mut component = parser::load::component(resource("sample.vcf.mt/rows/rows"))?;
+    let last = component.data.iter_mut().flatten().last().unwrap();
+    match last {
+        HailValue::Struct(fields) => match fields.get_mut("locus") {
+            Some(HailValue::Locus { position, .. }) => *position = 300_000_000,
+            value => panic!("expected a locus, found {:?}", value),
+        },
+        value => panic!("expected a struct, found {:?}", value),
+    }
+    let path = output("sample_vcf_rows_out_of_range");
+    parser::write::component(&component, &path)?;
+    let run = app(&["validate"], &path);
+    let out = String::from_utf8(run.stdout.clone())?;
+    let error = failure(run);
+    assert!(out.starts_with("rows: 1 violations\n"), "{}", out);
+    assert!(out.contains("the position 300000000 is outside"), "{}", out);
+    assert_eq!(error, "error: 1 values do not conform to the schema\n");
+    Ok(())
+}
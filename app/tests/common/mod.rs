@@ -0,0 +1,45 @@
+//! Helpers shared by the integration tests.
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+/// A file or folder of the `resources` folder, at the root of the repository.
+pub fn resource(file: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../resources")
+        .join(file)
+}
+
+/// A fresh output file or folder for each test, as tests run concurrently.
+pub fn output(name: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&path);
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// Runs the command line tool with the given arguments, followed by a path.
+pub fn app(args: &[&str], path: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_app"))
+        .args(args)
+        .arg(path)
+        .output()
+        .expect("the app runs")
+}
+
+/// The standard output of a successful run.
+pub fn success(output: Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("the output is UTF-8")
+}
+
+/// The standard error of a failed run, which exits with a code of 1.
+pub fn failure(output: Output) -> String {
+    assert_eq!(output.status.code(), Some(1));
+    String::from_utf8(output.stderr).expect("the errors are UTF-8")
+}
@@ -0,0 +1,3 @@
+mod common;
+
+mod commands;
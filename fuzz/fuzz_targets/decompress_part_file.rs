@@ -0,0 +1,7 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// Any sequence of bytes is either decompressed or refused with an error.
+fuzz_target!(|raw: &[u8]| {
+    let _ = parser::load::compression::decompress_part_file(raw);
+});
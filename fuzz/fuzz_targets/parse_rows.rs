@@ -0,0 +1,36 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use parser::{
+    parse::{data, Encoding, StandardEncoder, UnsignedLEB128Encoder},
+    types::EType,
+};
+
+/// Row types covering every shape (and virtual hint) the decoder handles.
+const ROW_TYPES: &[&str] = &[
+    "+EBaseStruct{a:+EInt32,b:EInt64,c:EFloat32,d:+EFloat64,e:EBoolean,f:EBinary}",
+    "+EBaseStruct{a:EArray[EInt32],b:+EArray[+EArray[EBinary]],c:EArray[+EBaseStruct{}]}",
+    "+EBaseStruct{a:ENDArrayColumnMajor[+EFloat64,2],b:ENDArrayColumnMajor[+EBaseStruct{},3]}",
+    "+EBaseStruct{a:EBaseStruct{x:EInt32,y:+EBinary},b:+EBaseStruct{`0`:EInt64,`1`:EBinary}}",
+];
+
+fn decode<E: Encoding>(row_type: &EType, data: &[u8]) {
+    let _ = data::parse_rows::<E>(data, row_type);
+    let _ = data::parse_rows_ref::<E>(data, row_type);
+}
+
+// The first byte picks the row type and encoding, the rest is decoded as a part file.
+fuzz_target!(|input: &[u8]| {
+    let (selector, data) = match input.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    let row_type: EType = match ROW_TYPES[(*selector as usize / 2) % ROW_TYPES.len()].parse() {
+        Ok(row_type) => row_type,
+        Err(_) => return,
+    };
+    match selector % 2 {
+        0 => decode::<StandardEncoder>(&row_type, data),
+        _ => decode::<UnsignedLEB128Encoder>(&row_type, data),
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use parser::types::{EType, VType};
+
+// Schemas come from the metadata, any string is either parsed or refused with an error.
+fuzz_target!(|schema: &str| {
+    let _ = schema.parse::<EType>();
+    let _ = schema.parse::<VType>();
+});
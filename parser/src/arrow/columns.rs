@@ -0,0 +1,453 @@
+use std::{convert::TryFrom, sync::Arc};
+
+use arrow::{
+    array::{
+        builder::NullBufferBuilder, ArrayRef, BinaryBuilder, BooleanBuilder, FixedSizeListArray,
+        Float32Builder, Float64Builder, Int32Builder, Int64Array, Int64Builder, ListArray,
+        MapArray, StringBuilder, StructArray,
+    },
+    buffer::{OffsetBuffer, ScalarBuffer},
+    datatypes::{DataType, FieldRef, Fields},
+    error::ArrowError,
+};
+use nom::IResult;
+
+use crate::{
+    parse::{data::helpers, Encoding},
+    types::{EType, ETypeShape, VType, VTypeShape},
+};
+
+use super::types::{self, ITEM};
+
+/// The Arrow builders of a value, mirroring its [VType] (see [data_type](super::data_type)).
+///
+/// Values are decoded straight into the builders, following the layout of the [EType] the column
+/// was built for. Missing values become nulls, set in the validity bitmap of the column.
+pub(super) enum Column {
+    Boolean(BooleanBuilder),
+    Int32(Int32Builder),
+    Int64(Int64Builder),
+    Float32(Float32Builder),
+    Float64(Float64Builder),
+    String(StringBuilder),
+    Binary(BinaryBuilder),
+    Struct(StructColumn),
+    List(ListColumn),
+    NDArray(NDArrayColumn),
+}
+
+/// The fields of a struct, along with the layout of the encoded struct.
+pub(super) struct StructColumn {
+    fields: Fields,
+    columns: Vec<Column>,
+    /// The encoded fields, in order.
+    encoded: Vec<EncodedField>,
+    nulls: NullBufferBuilder,
+}
+
+struct EncodedField {
+    required: bool,
+    /// The column the field is decoded into, encoded fields without one are skipped.
+    column: Option<usize>,
+    e_type: EType,
+}
+
+/// Arrays, sets, and dicts (as maps).
+pub(super) struct ListColumn {
+    field: FieldRef,
+    element: EType,
+    offsets: Vec<i32>,
+    values: Box<Column>,
+    nulls: NullBufferBuilder,
+    is_map: bool,
+}
+
+pub(super) struct NDArrayColumn {
+    fields: Fields,
+    element: EType,
+    n: u32,
+    shape: Int64Builder,
+    offsets: Vec<i32>,
+    values: Box<Column>,
+    nulls: NullBufferBuilder,
+}
+
+impl Column {
+    /// The column for values of the virtual type, encoded as the encoded type.
+    pub(super) fn new(v_type: &VType, e_type: &EType) -> Result<Self, ArrowError> {
+        let column = match (&v_type.shape, &e_type.shape) {
+            (VTypeShape::Boolean, ETypeShape::Boolean) => Column::Boolean(BooleanBuilder::new()),
+            (VTypeShape::Int32, ETypeShape::Int32) | (VTypeShape::Call, ETypeShape::Int32) => {
+                Column::Int32(Int32Builder::new())
+            }
+            (VTypeShape::Int64, ETypeShape::Int64) => Column::Int64(Int64Builder::new()),
+            (VTypeShape::Float32, ETypeShape::Float32) => Column::Float32(Float32Builder::new()),
+            (VTypeShape::Float64, ETypeShape::Float64) => Column::Float64(Float64Builder::new()),
+            (VTypeShape::String, ETypeShape::Binary) => Column::String(StringBuilder::new()),
+            (VTypeShape::Binary, ETypeShape::Binary) => Column::Binary(BinaryBuilder::new()),
+
+            (VTypeShape::Struct(fields), ETypeShape::BaseStruct(encoded)) => {
+                Column::Struct(StructColumn::by_name(fields, encoded)?)
+            }
+            (VTypeShape::Locus(_), ETypeShape::BaseStruct(encoded)) => {
+                Column::Struct(StructColumn::by_name(&types::locus_fields(), encoded)?)
+            }
+            (VTypeShape::Interval(point), ETypeShape::BaseStruct(encoded)) => Column::Struct(
+                StructColumn::by_name(&types::interval_fields(point), encoded)?,
+            ),
+            // The names of the encoded fields of a tuple vary, only their position matters.
+            (VTypeShape::Tuple(elements), ETypeShape::BaseStruct(encoded))
+                if elements.len() == encoded.len() =>
+            {
+                Column::Struct(StructColumn::new(
+                    &types::tuple_fields(elements),
+                    encoded,
+                    |index, _| Some(index),
+                )?)
+            }
+
+            (VTypeShape::Array(element), ETypeShape::Array(encoded))
+            | (VTypeShape::Set(element), ETypeShape::Array(encoded)) => Column::List(ListColumn {
+                field: Arc::new(types::field(ITEM, element)),
+                element: encoded.as_ref().clone(),
+                offsets: vec![0],
+                values: Box::new(Column::new(element, encoded)?),
+                nulls: NullBufferBuilder::new(0),
+                is_map: false,
+            }),
+            (VTypeShape::Dict(key, value), ETypeShape::Array(encoded)) => {
+                let entry = VType {
+                    shape: VTypeShape::Struct(types::entry_fields(key, value)),
+                    required: true,
+                };
+                Column::List(ListColumn {
+                    field: Arc::new(types::field(types::ENTRIES, &entry)),
+                    element: encoded.as_ref().clone(),
+                    offsets: vec![0],
+                    values: Box::new(Column::new(&entry, encoded)?),
+                    nulls: NullBufferBuilder::new(0),
+                    is_map: true,
+                })
+            }
+            (VTypeShape::NDArray(element, n), ETypeShape::NdArrayColumnMajor(encoded, m))
+                if n == m && i32::try_from(*n).is_ok() =>
+            {
+                Column::NDArray(NDArrayColumn {
+                    fields: types::ndarray_fields(element, *n),
+                    element: encoded.as_ref().clone(),
+                    n: *n,
+                    shape: Int64Builder::new(),
+                    offsets: vec![0],
+                    values: Box::new(Column::new(element, encoded)?),
+                    nulls: NullBufferBuilder::new(0),
+                })
+            }
+
+            _ => {
+                return Err(ArrowError::SchemaError(format!(
+                    "the encoded type {} does not match the virtual type {}",
+                    e_type, v_type
+                )))
+            }
+        };
+        Ok(column)
+    }
+
+    /// Decodes a present value into the column.
+    pub(super) fn decode<'i, E: Encoding>(&mut self, i: &'i [u8]) -> IResult<&'i [u8], ()> {
+        match self {
+            Column::Boolean(builder) => {
+                let (rest, value) = E::bool(i)?;
+                builder.append_value(value);
+                Ok((rest, ()))
+            }
+            Column::Int32(builder) => {
+                let (rest, value) = E::i32(i)?;
+                builder.append_value(value);
+                Ok((rest, ()))
+            }
+            Column::Int64(builder) => {
+                let (rest, value) = E::i64(i)?;
+                builder.append_value(value);
+                Ok((rest, ()))
+            }
+            Column::Float32(builder) => {
+                let (rest, value) = E::f32(i)?;
+                builder.append_value(value);
+                Ok((rest, ()))
+            }
+            Column::Float64(builder) => {
+                let (rest, value) = E::f64(i)?;
+                builder.append_value(value);
+                Ok((rest, ()))
+            }
+            Column::String(builder) => {
+                let (rest, value) = E::str(i)?;
+                builder.append_value(value);
+                Ok((rest, ()))
+            }
+            Column::Binary(builder) => {
+                let (rest, value) = E::bytes(i)?;
+                builder.append_value(value);
+                Ok((rest, ()))
+            }
+            Column::Struct(column) => column.decode::<E>(i),
+            Column::List(column) => column.decode::<E>(i),
+            Column::NDArray(column) => column.decode::<E>(i),
+        }
+    }
+
+    /// Appends a missing value.
+    ///
+    /// The children of structs get a null as well (hidden by the null of the struct), as they
+    /// need a value for each struct, while lists simply get no elements.
+    pub(super) fn append_null(&mut self) {
+        match self {
+            Column::Boolean(builder) => builder.append_null(),
+            Column::Int32(builder) => builder.append_null(),
+            Column::Int64(builder) => builder.append_null(),
+            Column::Float32(builder) => builder.append_null(),
+            Column::Float64(builder) => builder.append_null(),
+            Column::String(builder) => builder.append_null(),
+            Column::Binary(builder) => builder.append_null(),
+            Column::Struct(column) => {
+                column.columns.iter_mut().for_each(Column::append_null);
+                column.nulls.append_null();
+            }
+            Column::List(column) => {
+                push_offset(&mut column.offsets, 0);
+                column.nulls.append_null();
+            }
+            Column::NDArray(column) => {
+                // The shape has a fixed size, the dimensions of a missing NDArray are zeros.
+                for _ in 0..column.n {
+                    column.shape.append_value(0);
+                }
+                push_offset(&mut column.offsets, 0);
+                column.nulls.append_null();
+            }
+        }
+    }
+
+    /// Builds the array of the values decoded so far, and resets the column.
+    pub(super) fn finish(&mut self) -> Result<ArrayRef, ArrowError> {
+        let array: ArrayRef = match self {
+            Column::Boolean(builder) => Arc::new(builder.finish()),
+            Column::Int32(builder) => Arc::new(builder.finish()),
+            Column::Int64(builder) => Arc::new(builder.finish()),
+            Column::Float32(builder) => Arc::new(builder.finish()),
+            Column::Float64(builder) => Arc::new(builder.finish()),
+            Column::String(builder) => Arc::new(builder.finish()),
+            Column::Binary(builder) => Arc::new(builder.finish()),
+            Column::Struct(column) => Arc::new(column.finish()?),
+            Column::List(column) => column.finish()?,
+            Column::NDArray(column) => Arc::new(column.finish()?),
+        };
+        Ok(array)
+    }
+}
+
+impl StructColumn {
+    /// Matches the encoded fields to the virtual ones by name.
+    pub(super) fn by_name(
+        fields: &[(String, VType)],
+        encoded: &[(String, EType)],
+    ) -> Result<Self, ArrowError> {
+        Self::new(fields, encoded, |_, name| {
+            fields.iter().position(|(field, _)| field == name)
+        })
+    }
+
+    /// `column_of` gives the virtual field each encoded field (by position and name) is decoded
+    /// into, if any. Every virtual field should be encoded.
+    fn new<F>(
+        fields: &[(String, VType)],
+        encoded: &[(String, EType)],
+        column_of: F,
+    ) -> Result<Self, ArrowError>
+    where
+        F: Fn(usize, &str) -> Option<usize>,
+    {
+        let mut columns: Vec<Option<Column>> = fields.iter().map(|_| None).collect();
+        let mut encoded_fields = Vec::with_capacity(encoded.len());
+        for (index, (name, e_type)) in encoded.iter().enumerate() {
+            let column = column_of(index, name);
+            if let Some(column) = column {
+                let (_, v_type) = &fields[column];
+                columns[column] = Some(Column::new(v_type, e_type)?);
+            }
+            encoded_fields.push(EncodedField {
+                required: e_type.required,
+                column,
+                e_type: e_type.clone(),
+            });
+        }
+
+        let columns = columns
+            .into_iter()
+            .zip(fields)
+            .map(|(column, (name, _))| {
+                column.ok_or_else(|| {
+                    ArrowError::SchemaError(format!("the field `{}` is not encoded", name))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(StructColumn {
+            fields: types::fields_of(fields),
+            columns,
+            encoded: encoded_fields,
+            nulls: NullBufferBuilder::new(0),
+        })
+    }
+
+    /// Decodes a present struct, the presence flags of its optional fields come first.
+    pub(super) fn decode<'i, E: Encoding>(&mut self, i: &'i [u8]) -> IResult<&'i [u8], ()> {
+        let number_of_optional_fields = self.encoded.iter().filter(|f| !f.required).count();
+        let (mut rest, are_present) = helpers::presence_array(i, number_of_optional_fields)?;
+        let mut are_present = are_present.into_iter();
+
+        for field in &self.encoded {
+            let is_present = field.required || are_present.next() == Some(true);
+            rest = match (field.column, is_present) {
+                (Some(column), true) => self.columns[column].decode::<E>(rest)?.0,
+                (Some(column), false) => {
+                    self.columns[column].append_null();
+                    rest
+                }
+                (None, true) => field.e_type.skip_from::<E>(rest)?.0,
+                (None, false) => rest,
+            };
+        }
+        self.nulls.append_non_null();
+        Ok((rest, ()))
+    }
+
+    /// The arrays of the fields, as the columns of a record batch.
+    pub(super) fn finish_columns(&mut self) -> Result<Vec<ArrayRef>, ArrowError> {
+        self.nulls = NullBufferBuilder::new(0);
+        self.columns.iter_mut().map(Column::finish).collect()
+    }
+
+    fn finish(&mut self) -> Result<StructArray, ArrowError> {
+        let nulls = self.nulls.finish();
+        let columns = self
+            .columns
+            .iter_mut()
+            .map(Column::finish)
+            .collect::<Result<_, _>>()?;
+        StructArray::try_new(self.fields.clone(), columns, nulls)
+    }
+}
+
+impl ListColumn {
+    /// Decodes a present array, its length and (if its elements are optional) their presence
+    /// flags come first.
+    fn decode<'i, E: Encoding>(&mut self, i: &'i [u8]) -> IResult<&'i [u8], ()> {
+        let (rest, len) = E::u32(i)?;
+        let len = len as usize;
+        let (mut rest, are_present) = match self.element.required {
+            true => {
+                helpers::check_length(rest, &self.element, len)?;
+                (rest, vec![])
+            }
+            false => helpers::presence_array(rest, len)?,
+        };
+
+        for index in 0..len {
+            match are_present.get(index) {
+                Some(false) => self.values.append_null(),
+                _ => rest = self.values.decode::<E>(rest)?.0,
+            }
+        }
+        if !push_offset(&mut self.offsets, len) {
+            return Err(too_many_values(i));
+        }
+        self.nulls.append_non_null();
+        Ok((rest, ()))
+    }
+
+    fn finish(&mut self) -> Result<ArrayRef, ArrowError> {
+        let offsets = finish_offsets(&mut self.offsets);
+        let nulls = self.nulls.finish();
+        let values = self.values.finish()?;
+        match self.is_map {
+            true => {
+                let entries = match values.as_any().downcast_ref::<StructArray>() {
+                    Some(entries) => entries.clone(),
+                    None => return Err(ArrowError::SchemaError("map entries".to_owned())),
+                };
+                let map = MapArray::try_new(self.field.clone(), offsets, entries, nulls, false)?;
+                Ok(Arc::new(map))
+            }
+            false => Ok(Arc::new(ListArray::try_new(
+                self.field.clone(),
+                offsets,
+                values,
+                nulls,
+            )?)),
+        }
+    }
+}
+
+impl NDArrayColumn {
+    /// Decodes a present NDArray, its shape comes first and its elements are always present.
+    fn decode<'i, E: Encoding>(&mut self, i: &'i [u8]) -> IResult<&'i [u8], ()> {
+        let (mut rest, (dims, len)) = helpers::ndarray_shape::<E>(i, self.n)?;
+        helpers::check_length(rest, &self.element, len)?;
+        for _ in 0..len {
+            rest = self.values.decode::<E>(rest)?.0;
+        }
+        dims.iter().for_each(|&d| self.shape.append_value(d as i64));
+        if !push_offset(&mut self.offsets, len) {
+            return Err(too_many_values(i));
+        }
+        self.nulls.append_non_null();
+        Ok((rest, ()))
+    }
+
+    fn finish(&mut self) -> Result<StructArray, ArrowError> {
+        let (shape_field, data_field) =
+            match (self.fields[0].data_type(), self.fields[1].data_type()) {
+                (DataType::FixedSizeList(shape, _), DataType::List(data)) => {
+                    (shape.clone(), data.clone())
+                }
+                _ => return Err(ArrowError::SchemaError("ndarray fields".to_owned())),
+            };
+        let shape: Int64Array = self.shape.finish();
+        let shape = FixedSizeListArray::try_new(shape_field, self.n as i32, Arc::new(shape), None)?;
+        let offsets = finish_offsets(&mut self.offsets);
+        let data = ListArray::try_new(data_field, offsets, self.values.finish()?, None)?;
+        StructArray::try_new(
+            self.fields.clone(),
+            vec![Arc::new(shape), Arc::new(data)],
+            self.nulls.finish(),
+        )
+    }
+}
+
+/// Adds the offset of the next list, `len` values after the previous one, returning `false` if
+/// the values no longer fit in the (32 bits) offsets of an Arrow list.
+fn push_offset(offsets: &mut Vec<i32>, len: usize) -> bool {
+    let last = offsets.last().copied().unwrap_or(0);
+    match i32::try_from(len)
+        .ok()
+        .and_then(|len| last.checked_add(len))
+    {
+        Some(offset) => {
+            offsets.push(offset);
+            true
+        }
+        None => false,
+    }
+}
+
+/// The offsets of the lists decoded so far, leaving room for the next ones.
+fn finish_offsets(offsets: &mut Vec<i32>) -> OffsetBuffer<i32> {
+    let offsets = std::mem::replace(offsets, vec![0]);
+    OffsetBuffer::new(ScalarBuffer::from(offsets))
+}
+
+fn too_many_values(i: &[u8]) -> nom::Err<nom::error::Error<&[u8]>> {
+    nom::Err::Failure(nom::error::Error::new(i, nom::error::ErrorKind::TooLarge))
+}
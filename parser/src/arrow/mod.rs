@@ -0,0 +1,266 @@
+//! Decoding of components into Arrow [RecordBatch]es, behind the `arrow` feature.
+//!
+//! Each partition of a component becomes a record batch, with a column for each field of the
+//! rows (see [data_type] for how each [VType] is mapped).
+//! Values are decoded straight into the Arrow builders, without going through [HailValue], and
+//! missing values become nulls in the validity bitmaps.
+//!
+//! [HailValue]: crate::HailValue
+mod columns;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+mod types;
+
+use std::{path::Path, sync::Arc};
+
+use arrow::{
+    datatypes::{Schema, SchemaRef},
+    error::ArrowError,
+    record_batch::{RecordBatch, RecordBatchOptions},
+};
+
+use crate::{
+    error::{DecodeErrorKind, Error, Result},
+    load::{helpers::map_file, metadata, parallel, stream::PartDecoder},
+    parse::{data::row_error, Encoding, StandardEncoder, UnsignedLEB128Encoder},
+    types::{
+        metadata::{ComponentMetadata, MatrixMetadata, TableMetadata},
+        EType, ETypeShape, Projection, VType, VTypeShape,
+    },
+};
+
+use columns::StructColumn;
+
+pub use types::{data_type, field, schema};
+
+/// A [Component](crate::Component) decoded into Arrow, with a record batch for each partition.
+#[derive(Clone, Debug)]
+pub struct ArrowComponent {
+    pub schema: SchemaRef,
+    pub batches: Vec<RecordBatch>,
+    pub metadata: ComponentMetadata,
+}
+
+/// A [Table](crate::Table) decoded into Arrow.
+#[derive(Clone, Debug)]
+pub struct ArrowTable {
+    pub globals: ArrowComponent,
+    pub rows: ArrowComponent,
+    pub metadata: TableMetadata,
+}
+
+/// A [Matrix](crate::Matrix) decoded into Arrow.
+#[derive(Clone, Debug)]
+pub struct ArrowMatrix {
+    pub globals: ArrowComponent,
+    pub cols: ArrowComponent,
+    pub rows: ArrowComponent,
+    pub entries: ArrowComponent,
+    pub metadata: MatrixMetadata,
+}
+
+/// Like [load::component](crate::load::component), but each partition is decoded into a
+/// [RecordBatch].
+pub fn component<T: AsRef<Path>>(path: T) -> Result<ArrowComponent> {
+    let path: &Path = path.as_ref();
+
+    let metadata = metadata::load_component_metadata(path)?;
+    let schema = Arc::new(row_schema(&metadata.virtual_type)?);
+    let data_path = path.join("parts");
+
+    let batches = parallel::map_partitions(&metadata.part_files, |part_name| {
+        decode_part(&data_path.join(part_name), &metadata, &schema)
+    })?;
+
+    Ok(ArrowComponent {
+        schema,
+        batches,
+        metadata,
+    })
+}
+
+/// Decodes a single partition of the component at the given path into a [RecordBatch], so that
+/// partitions can be processed one at a time.
+pub fn partition<T: AsRef<Path>>(
+    path: T,
+    metadata: &ComponentMetadata,
+    index: usize,
+) -> Result<RecordBatch> {
+    let part_name = metadata
+        .part_files
+        .get(index)
+        .ok_or(Error::PartitionOutOfRange {
+            index,
+            partitions: metadata.part_files.len(),
+        })?;
+    let part_path = path.as_ref().join("parts").join(part_name);
+    let schema = Arc::new(row_schema(&metadata.virtual_type)?);
+
+    decode_part(&part_path, metadata, &schema)
+}
+
+/// Like [load::table](crate::load::table), but decoded into Arrow.
+pub fn table<T: AsRef<Path>>(path: T) -> Result<ArrowTable> {
+    let path: &Path = path.as_ref();
+
+    let metadata = metadata::load_table_metadata(path)?;
+
+    let components = &metadata.components;
+
+    let globals = component(path.join(&components.globals.rel_path))?;
+    let rows = component(path.join(&components.rows.rel_path))?;
+
+    Ok(ArrowTable {
+        globals,
+        rows,
+        metadata,
+    })
+}
+
+/// Like [load::matrix](crate::load::matrix), but decoded into Arrow.
+pub fn matrix<T: AsRef<Path>>(path: T) -> Result<ArrowMatrix> {
+    let path: &Path = path.as_ref();
+
+    let metadata = metadata::load_matrix_metadata(path)?;
+
+    let components = &metadata.components;
+
+    let globals = component(path.join(&components.globals.rel_path))?;
+    let cols = component(path.join(&components.cols.rel_path))?;
+    let rows = component(path.join(&components.rows.rel_path))?;
+    let entries = component(path.join(&components.entries.rel_path))?;
+
+    Ok(ArrowMatrix {
+        globals,
+        cols,
+        rows,
+        entries,
+        metadata,
+    })
+}
+
+/// Like [parse_rows](crate::parse::data::parse_rows), but the rows (encoded as the encoded type)
+/// are decoded into a single [RecordBatch] with the columns of the virtual type.
+pub fn parse_record_batch<E: Encoding>(
+    i: &[u8],
+    virtual_type: &VType,
+    encoded_type: &EType,
+) -> Result<RecordBatch> {
+    let schema = Arc::new(row_schema(virtual_type)?);
+    let mut columns = row_columns(virtual_type, encoded_type)?;
+
+    let mut rest = i;
+    let mut row = 0;
+    loop {
+        match decode_next_row::<E>(rest, encoded_type, &mut columns) {
+            Ok((consumed, true)) => {
+                rest = &rest[consumed..];
+                row += 1;
+            }
+            Ok((_, false)) => break,
+            Err(e) => {
+                let kind = DecodeErrorKind::from_nom(&e);
+                let mut error =
+                    row_error::<E>(rest, row as u64, encoded_type, &Projection::All, kind);
+                error.offset += (i.len() - rest.len()) as u64;
+                return Err(error.into());
+            }
+        }
+    }
+
+    record_batch(schema, &mut columns, row)
+}
+
+fn decode_part(
+    part_path: &Path,
+    metadata: &ComponentMetadata,
+    schema: &SchemaRef,
+) -> Result<RecordBatch> {
+    match metadata.buffer_spec.uses_leb128() {
+        true => _decode_part::<UnsignedLEB128Encoder>(part_path, metadata, schema),
+        false => _decode_part::<StandardEncoder>(part_path, metadata, schema),
+    }
+}
+
+fn _decode_part<E: Encoding>(
+    part_path: &Path,
+    metadata: &ComponentMetadata,
+    schema: &SchemaRef,
+) -> Result<RecordBatch> {
+    let buffer_spec = &metadata.buffer_spec;
+    let row_type = &metadata.encoded_type;
+
+    if !buffer_spec.uses_compression() && !buffer_spec.appends_length() {
+        let file = map_file(part_path)?;
+        return parse_record_batch::<E>(&file, &metadata.virtual_type, row_type)
+            .map_err(|error| error.in_file(part_path));
+    }
+
+    let mut columns = row_columns(&metadata.virtual_type, row_type)?;
+    let mut decoder = PartDecoder::open(part_path, buffer_spec, 0, 0)?;
+    let mut rows = 0;
+    while decoder
+        .decode_row_with(row_type, &Projection::All, |input| {
+            let (consumed, is_row) = decode_next_row::<E>(input, row_type, &mut columns)
+                .map_err(|e| e.map(|e| e.code))?;
+            Ok((consumed, Some(()).filter(|_| is_row)))
+        })?
+        .is_some()
+    {
+        rows += 1;
+    }
+
+    record_batch(schema.clone(), &mut columns, rows)
+}
+
+/// The row count is given explicitly, as rows without fields make batches without columns.
+fn record_batch(schema: SchemaRef, columns: &mut StructColumn, rows: usize) -> Result<RecordBatch> {
+    let options = RecordBatchOptions::new().with_row_count(Some(rows));
+    let batch = RecordBatch::try_new_with_options(schema, columns.finish_columns()?, &options)?;
+    Ok(batch)
+}
+
+/// Decodes the next row into the columns, returning the number of bytes consumed and whether
+/// there was a row (rather than the end-of-partition marker).
+///
+/// The builders cannot take back the values appended to them, so the row is first skipped to
+/// check that it can be decoded in full: when streaming, the rest of the row may only be in the
+/// next block. Once skipped, the row is all there, so errors are failures that are not retried.
+fn decode_next_row<'i, E: Encoding>(
+    i: &'i [u8],
+    row_type: &EType,
+    columns: &mut StructColumn,
+) -> Result<(usize, bool), nom::Err<nom::error::Error<&'i [u8]>>> {
+    let (rest, is_row) = E::bool(i)?;
+    if !is_row {
+        return Ok((i.len() - rest.len(), false));
+    }
+    row_type.skip_from::<E>(rest)?;
+    let (rest, ()) = columns.decode::<E>(rest).map_err(|e| match e {
+        nom::Err::Error(e) => nom::Err::Failure(e),
+        e => e,
+    })?;
+    Ok((i.len() - rest.len(), true))
+}
+
+fn row_schema(virtual_type: &VType) -> Result<Schema> {
+    schema(virtual_type).ok_or_else(|| {
+        Error::from(ArrowError::SchemaError(format!(
+            "the rows must be structs, found {}",
+            virtual_type
+        )))
+    })
+}
+
+fn row_columns(virtual_type: &VType, encoded_type: &EType) -> Result<StructColumn> {
+    match (&virtual_type.shape, &encoded_type.shape) {
+        (VTypeShape::Struct(fields), ETypeShape::BaseStruct(encoded)) => {
+            Ok(StructColumn::by_name(fields, encoded)?)
+        }
+        _ => Err(ArrowError::SchemaError(format!(
+            "the rows must be structs, found {} encoded as {}",
+            virtual_type, encoded_type
+        ))
+        .into()),
+    }
+}
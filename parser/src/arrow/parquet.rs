@@ -0,0 +1,168 @@
+//! Writing of components into Parquet files, behind the `parquet` feature.
+//!
+//! The rows are decoded into Arrow (see [arrow](super)) one partition at a time, and each
+//! partition becomes a row group. The Hail row type and key are kept in the key-value metadata of
+//! the file (see [SCHEMA_KEY] and [KEY_KEY]), and the key columns are recorded as the sort order of
+//! the row groups, up to the first one Parquet may not order as Hail does (a locus or a string, for
+//! example).
+use std::{fs::File, path::Path};
+
+use arrow::datatypes::{DataType, Schema};
+use parquet::{
+    arrow::ArrowWriter,
+    file::properties::WriterProperties,
+    format::{FileMetaData, KeyValue, SortingColumn},
+};
+
+use crate::{
+    error::{Error, Result},
+    load::metadata,
+    types::{metadata::ComponentMetadata, VType, VTypeShape},
+};
+
+/// The key-value metadata entry holding the Hail row type, as found in the Hail metadata.
+pub const SCHEMA_KEY: &str = "hail.schema";
+/// The key-value metadata entry holding the key fields of the rows, as a JSON array.
+pub const KEY_KEY: &str = "hail.key";
+
+/// Writes the rows of the component at `path` into a Parquet file, with a row group for each
+/// (non-empty) partition, and returns the metadata of the written file.
+///
+/// Parquet files have no room for the partition bounds, but as Hail partitions are sorted and
+/// follow each other, so do the row groups.
+pub fn write_component<T, U>(path: T, output: U) -> Result<FileMetaData>
+where
+    T: AsRef<Path>,
+    U: AsRef<Path>,
+{
+    let path: &Path = path.as_ref();
+    let output: &Path = output.as_ref();
+
+    let metadata = metadata::load_component_metadata(path)?;
+    let schema = super::row_schema(&metadata.virtual_type)?;
+
+    let file = File::create(output).map_err(|source| Error::Io {
+        path: Some(output.to_owned()),
+        source,
+    })?;
+    let properties = writer_properties(&metadata, &schema);
+    let mut writer = ArrowWriter::try_new(file, schema.into(), Some(properties))?;
+
+    for index in 0..metadata.part_files.len() {
+        let batch = super::partition(path, &metadata, index)?;
+        if batch.num_rows() == 0 {
+            continue;
+        }
+        writer.write(&batch)?;
+        // Ends the row group, so that it matches the partition.
+        writer.flush()?;
+    }
+
+    Ok(writer.close()?)
+}
+
+/// Writes the rows of the table at `path` into a Parquet file (see [write_component]).
+pub fn write_table<T, U>(path: T, output: U) -> Result<FileMetaData>
+where
+    T: AsRef<Path>,
+    U: AsRef<Path>,
+{
+    let path: &Path = path.as_ref();
+
+    let metadata = metadata::load_table_metadata(path)?;
+
+    write_component(path.join(&metadata.components.rows.rel_path), output)
+}
+
+fn writer_properties(metadata: &ComponentMetadata, schema: &Schema) -> WriterProperties {
+    let key_value_metadata = vec![
+        KeyValue::new(SCHEMA_KEY.to_owned(), metadata.virtual_type.to_string()),
+        KeyValue::new(
+            KEY_KEY.to_owned(),
+            serde_json::json!(metadata.key).to_string(),
+        ),
+    ];
+
+    WriterProperties::builder()
+        // Row groups are only ended by the end of a partition.
+        .set_max_row_group_size(usize::MAX)
+        .set_key_value_metadata(Some(key_value_metadata))
+        .set_sorting_columns(sorting_columns(metadata, schema))
+        .build()
+}
+
+/// The leaf columns of the key fields, in the order of the key, as long as the rows sorted as Hail
+/// sorts them (with missing values last) are also sorted by the values of the columns.
+///
+/// This stops at the first list, as the order of a list cannot be described by a single column,
+/// at the first locus, as loci are sorted by the index of their contig in the reference genome
+/// rather than by its name (`2` comes before `10`), and at the first string or binary, as the order
+/// of byte arrays in Parquet (left to readers and to the logical type) is not guaranteed to be the
+/// one Hail sorted them by.
+fn sorting_columns(metadata: &ComponentMetadata, schema: &Schema) -> Option<Vec<SortingColumn>> {
+    let mut columns = Vec::new();
+    for (name, v_type) in metadata.key_type() {
+        let index = match schema.index_of(&name) {
+            Ok(index) => index,
+            Err(_) => break,
+        };
+        let first_leaf: usize = schema.fields()[..index]
+            .iter()
+            .map(|field| leaf_count(field.data_type()))
+            .sum();
+        let (sorted, all_sorted) = sorted_leaves(&v_type);
+        columns.extend(
+            (first_leaf..first_leaf + sorted)
+                .map(|column| SortingColumn::new(column as i32, false, false)),
+        );
+        if !all_sorted {
+            break;
+        }
+    }
+    match columns.is_empty() {
+        true => None,
+        false => Some(columns),
+    }
+}
+
+/// The number of leading leaf columns of a value that are sorted (in ascending order) when the
+/// values are, and whether all of them are.
+fn sorted_leaves(v_type: &VType) -> (usize, bool) {
+    match &v_type.shape {
+        VTypeShape::Struct(fields) => sorted_prefix(fields.iter().map(|(_, v_type)| v_type)),
+        VTypeShape::Tuple(types) => sorted_prefix(types.iter()),
+        // Intervals are sorted by their start, but then an included start comes first.
+        VTypeShape::Interval(point) => (sorted_leaves(point).0, false),
+        VTypeShape::Locus(_)
+        | VTypeShape::String
+        | VTypeShape::Binary
+        | VTypeShape::Array(_)
+        | VTypeShape::Set(_)
+        | VTypeShape::Dict(_, _)
+        | VTypeShape::NDArray(_, _) => (0, false),
+        _ => (1, true),
+    }
+}
+
+fn sorted_prefix<'a>(types: impl Iterator<Item = &'a VType>) -> (usize, bool) {
+    let mut count = 0;
+    for v_type in types {
+        let (sorted, all_sorted) = sorted_leaves(v_type);
+        count += sorted;
+        if !all_sorted {
+            return (count, false);
+        }
+    }
+    (count, true)
+}
+
+/// The number of Parquet (leaf) columns a value is stored into.
+fn leaf_count(data_type: &DataType) -> usize {
+    match data_type {
+        DataType::Struct(fields) => fields.iter().map(|f| leaf_count(f.data_type())).sum(),
+        DataType::List(item) | DataType::FixedSizeList(item, _) | DataType::Map(item, _) => {
+            leaf_count(item.data_type())
+        }
+        _ => 1,
+    }
+}
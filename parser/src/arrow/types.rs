@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use arrow::datatypes::{DataType, Field, Fields, Schema};
+
+use crate::types::{VType, VTypeShape};
+
+/// The name Arrow gives to the elements of a list.
+pub(super) const ITEM: &str = "item";
+/// The name of the struct holding the keys and values of a map.
+pub(super) const ENTRIES: &str = "entries";
+
+/// Maps a [VType] to the Arrow [DataType] its values are decoded into.
+///
+/// - Structs, tuples (with fields named `0`, `1`, ...), loci (`contig` and `position`), and
+///   intervals (`start`, `end`, `includesStart`, and `includesEnd`) are structs.
+/// - Arrays and sets are lists, dicts are (unsorted) maps.
+/// - NDArrays are structs of their `shape` (a fixed size list of [DataType::Int64]) and their
+///   `data` (a list of the elements, in column major order), as their shape is not known upfront.
+/// - Calls are [DataType::Int32], as encoded by Hail.
+///
+/// Arrow does not allow missing map keys, so the keys of a dict are never nullable.
+pub fn data_type(v_type: &VType) -> DataType {
+    match &v_type.shape {
+        VTypeShape::Struct(fields) => DataType::Struct(fields_of(fields)),
+        VTypeShape::Tuple(types) => DataType::Struct(fields_of(&tuple_fields(types))),
+        VTypeShape::Locus(_) => DataType::Struct(fields_of(&locus_fields())),
+        VTypeShape::Interval(point) => DataType::Struct(fields_of(&interval_fields(point))),
+
+        VTypeShape::Array(element) | VTypeShape::Set(element) => {
+            DataType::List(Arc::new(field(ITEM, element)))
+        }
+        VTypeShape::Dict(key, value) => {
+            let entries = DataType::Struct(fields_of(&entry_fields(key, value)));
+            DataType::Map(Arc::new(Field::new(ENTRIES, entries, false)), false)
+        }
+        VTypeShape::NDArray(element, n) => DataType::Struct(ndarray_fields(element, *n)),
+
+        VTypeShape::String => DataType::Utf8,
+        VTypeShape::Binary => DataType::Binary,
+        VTypeShape::Float32 => DataType::Float32,
+        VTypeShape::Float64 => DataType::Float64,
+        VTypeShape::Int32 | VTypeShape::Call => DataType::Int32,
+        VTypeShape::Int64 => DataType::Int64,
+        VTypeShape::Boolean => DataType::Boolean,
+    }
+}
+
+/// A named [Field], nullable unless the type is required.
+pub fn field(name: &str, v_type: &VType) -> Field {
+    Field::new(name, data_type(v_type), !v_type.required)
+}
+
+/// The Arrow [Schema] of the rows of a component, with a column for each field of the row type.
+///
+/// `None` is returned if the row type is not a struct.
+pub fn schema(row_type: &VType) -> Option<Schema> {
+    match &row_type.shape {
+        VTypeShape::Struct(fields) => Some(Schema::new(fields_of(fields))),
+        _ => None,
+    }
+}
+
+pub(super) fn fields_of(fields: &[(String, VType)]) -> Fields {
+    fields
+        .iter()
+        .map(|(name, v_type)| field(name, v_type))
+        .collect()
+}
+
+/// The fields of a tuple, named after their position.
+pub(super) fn tuple_fields(types: &[VType]) -> Vec<(String, VType)> {
+    types
+        .iter()
+        .enumerate()
+        .map(|(index, v_type)| (index.to_string(), v_type.clone()))
+        .collect()
+}
+
+pub(super) fn locus_fields() -> Vec<(String, VType)> {
+    vec![
+        ("contig".to_owned(), required(VTypeShape::String)),
+        ("position".to_owned(), required(VTypeShape::Int32)),
+    ]
+}
+
+pub(super) fn interval_fields(point: &VType) -> Vec<(String, VType)> {
+    vec![
+        ("start".to_owned(), point.clone()),
+        ("end".to_owned(), point.clone()),
+        ("includesStart".to_owned(), required(VTypeShape::Boolean)),
+        ("includesEnd".to_owned(), required(VTypeShape::Boolean)),
+    ]
+}
+
+/// The key and value of a map entry, the key is always required.
+pub(super) fn entry_fields(key: &VType, value: &VType) -> Vec<(String, VType)> {
+    let mut key = key.clone();
+    key.required = true;
+    vec![("key".to_owned(), key), ("value".to_owned(), value.clone())]
+}
+
+pub(super) fn ndarray_fields(element: &VType, n: u32) -> Fields {
+    let dimension = Arc::new(Field::new(ITEM, DataType::Int64, false));
+    // The elements of a NDArray are always present.
+    let element = Arc::new(Field::new(ITEM, data_type(element), false));
+    Fields::from(vec![
+        Field::new("shape", DataType::FixedSizeList(dimension, n as i32), false),
+        Field::new("data", DataType::List(element), false),
+    ])
+}
+
+fn required(shape: VTypeShape) -> VType {
+    VType {
+        shape,
+        required: true,
+    }
+}
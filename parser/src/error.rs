@@ -0,0 +1,311 @@
+use std::{
+    fmt::{self, Display},
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::types::EType;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The errors returned when loading, looking up (see [index](crate::index)), and writing (see
+/// [write](crate::write)) components, tables, and matrices (see [load](crate::load)).
+///
+/// File paths are optional because some of the lower level functions work on bytes, they are
+/// filled in by the loaders.
+#[derive(Debug)]
+pub enum Error {
+    /// A file could not be opened or read.
+    Io {
+        path: Option<PathBuf>,
+        source: io::Error,
+    },
+    /// The metadata could not be read, or does not describe a valid component, table, or matrix.
+    Metadata { path: PathBuf, message: String },
+    /// The metadata is valid, but describes something else (a table where a matrix was expected,
+    /// for example).
+    UnexpectedMetadata {
+        path: PathBuf,
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// A block of a part (or index) file is malformed, or could not be decompressed.
+    Block {
+        path: Option<PathBuf>,
+        message: String,
+    },
+    /// A value could not be decoded, see [DecodeError].
+    Decode(Box<DecodeError>),
+    /// A partition was requested that the component does not have.
+    PartitionOutOfRange { index: usize, partitions: usize },
+    /// The component has no index to look up its rows by key.
+    NotIndexed { path: PathBuf },
+    /// The index of a part file is malformed, or does not match the part file.
+    Index { path: PathBuf, message: String },
+    /// Some of the selected fields do not exist, or cannot be selected (see
+    /// [Projection](crate::types::Projection)).
+    Projection(String),
+    /// The thread pool of the `parallel` feature could not be built.
+    ThreadPool(String),
+    /// A value does not match the encoded type it is written as.
+    Encode(String),
+    /// The rows of a keyed component being written are not sorted by key: the given row (of the
+    /// given partition) comes before the previous one.
+    UnsortedRows { partition: usize, row: usize },
+    /// An empty partition of a keyed component is being written, but its range bounds are not
+    /// known (see [write::component](crate::write::component)).
+    EmptyPartitionBounds { partition: usize },
+    /// The rows and entries of a matrix being written are not partitioned in the same way, given
+    /// the number of rows of each of their partitions.
+    MismatchedPartitions { rows: Vec<u32>, entries: Vec<u32> },
+    /// A file could not be written.
+    Write { path: PathBuf, source: io::Error },
+    /// The error returned by the visitor of
+    /// [component_for_each](crate::load::component_for_each).
+    Visit(Box<dyn std::error::Error + Send + Sync>),
+    /// The values could not be put into Arrow arrays (see [arrow](crate::arrow)).
+    #[cfg(feature = "arrow")]
+    Arrow(arrow::error::ArrowError),
+    /// The Parquet file could not be written (see [parquet](crate::arrow::parquet)).
+    #[cfg(feature = "parquet")]
+    Parquet(parquet::errors::ParquetError),
+}
+
+/// Where (and why) a value could not be decoded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodeError {
+    /// The part (or index) file being decoded, if any.
+    pub part_file: Option<PathBuf>,
+    /// The index of the row within the part file, starting from where decoding started.
+    pub row: u64,
+    /// The position of the value that could not be decoded, in bytes, within the decompressed
+    /// data of the part file.
+    pub offset: u64,
+    /// The path to the value within the row, like `info.AC[3]`, empty for the row itself.
+    pub field: String,
+    /// The encoded type of the value, if known.
+    pub expected: Option<EType>,
+    pub kind: DecodeErrorKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecodeErrorKind {
+    /// The data ends in the middle of the value.
+    UnexpectedEnd,
+    /// The data does not match the expected type, the [nom] error kind tells which check failed.
+    Invalid(nom::error::ErrorKind),
+}
+
+impl DecodeErrorKind {
+    pub(crate) fn from_nom<I>(error: &nom::Err<nom::error::Error<I>>) -> Self {
+        match error {
+            nom::Err::Incomplete(_) => DecodeErrorKind::UnexpectedEnd,
+            nom::Err::Error(e) | nom::Err::Failure(e) => Self::from_code(e.code),
+        }
+    }
+
+    pub(crate) fn from_code(code: nom::error::ErrorKind) -> Self {
+        match code {
+            nom::error::ErrorKind::Eof => DecodeErrorKind::UnexpectedEnd,
+            code => DecodeErrorKind::Invalid(code),
+        }
+    }
+}
+
+impl Error {
+    /// Attributes the error to a file, unless it already names one.
+    pub(crate) fn in_file(self, file: &Path) -> Self {
+        match self {
+            Error::Io { path: None, source } => Error::Io {
+                path: Some(file.to_owned()),
+                source,
+            },
+            Error::Block {
+                path: None,
+                message,
+            } => Error::Block {
+                path: Some(file.to_owned()),
+                message,
+            },
+            Error::Decode(mut error) if error.part_file.is_none() => {
+                error.part_file = Some(file.to_owned());
+                Error::Decode(error)
+            }
+            error => error,
+        }
+    }
+
+    pub(crate) fn write(path: &Path, source: io::Error) -> Self {
+        Error::Write {
+            path: path.to_owned(),
+            source,
+        }
+    }
+
+    pub(crate) fn block<T: Display>(message: T) -> Self {
+        Error::Block {
+            path: None,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(source: io::Error) -> Self {
+        Error::Io { path: None, source }
+    }
+}
+
+// The conversions used while reading blocks.
+impl From<std::array::TryFromSliceError> for Error {
+    fn from(error: std::array::TryFromSliceError) -> Self {
+        Error::block(error)
+    }
+}
+
+impl From<std::num::TryFromIntError> for Error {
+    fn from(error: std::num::TryFromIntError) -> Self {
+        Error::block(error)
+    }
+}
+
+impl From<lzzzz::Error> for Error {
+    fn from(error: lzzzz::Error) -> Self {
+        Error::block(format!("unable to decompress: {}", error))
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl From<arrow::error::ArrowError> for Error {
+    fn from(error: arrow::error::ArrowError) -> Self {
+        Error::Arrow(error)
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<parquet::errors::ParquetError> for Error {
+    fn from(error: parquet::errors::ParquetError) -> Self {
+        Error::Parquet(error)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(error: DecodeError) -> Self {
+        Error::Decode(Box::new(error))
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io {
+                path: Some(path),
+                source,
+            } => write!(f, "unable to read {}: {}", path.display(), source),
+            Error::Io { path: None, source } => write!(f, "unable to read the data: {}", source),
+            Error::Metadata { path, message } => {
+                write!(f, "invalid metadata in {}: {}", path.display(), message)
+            }
+            Error::UnexpectedMetadata {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected a {}, found a {} in {}",
+                expected,
+                found,
+                path.display()
+            ),
+            Error::Block {
+                path: Some(path),
+                message,
+            } => write!(f, "malformed block in {}: {}", path.display(), message),
+            Error::Block {
+                path: None,
+                message,
+            } => write!(f, "malformed block: {}", message),
+            Error::Decode(error) => error.fmt(f),
+            Error::PartitionOutOfRange { index, partitions } => write!(
+                f,
+                "partition {} is out of range, the component has {} partitions",
+                index, partitions
+            ),
+            Error::NotIndexed { path } => {
+                write!(f, "the component {} is not indexed", path.display())
+            }
+            Error::Index { path, message } => {
+                write!(f, "invalid index for {}: {}", path.display(), message)
+            }
+            Error::Projection(message) => f.write_str(message),
+            Error::ThreadPool(message) => write!(f, "unable to build the thread pool: {}", message),
+            Error::Encode(message) => write!(f, "unable to encode: {}", message),
+            Error::UnsortedRows { partition, row } => write!(
+                f,
+                "the rows are not sorted by key: row {} of partition {} comes before the previous \
+                row",
+                row, partition
+            ),
+            Error::EmptyPartitionBounds { partition } => write!(
+                f,
+                "partition {} is empty, and the metadata has no range bounds for it",
+                partition
+            ),
+            Error::MismatchedPartitions { rows, entries } => write!(
+                f,
+                "the rows and entries are not partitioned in the same way ({:?} and {:?} rows)",
+                rows, entries
+            ),
+            Error::Write { path, source } => {
+                write!(f, "unable to write {}: {}", path.display(), source)
+            }
+            Error::Visit(error) => error.fmt(f),
+            #[cfg(feature = "arrow")]
+            Error::Arrow(error) => write!(f, "unable to build the Arrow arrays: {}", error),
+            #[cfg(feature = "parquet")]
+            Error::Parquet(error) => write!(f, "unable to write the Parquet file: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } | Error::Write { source, .. } => Some(source),
+            Error::Visit(error) => Some(error.as_ref()),
+            #[cfg(feature = "arrow")]
+            Error::Arrow(error) => Some(error),
+            #[cfg(feature = "parquet")]
+            Error::Parquet(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unable to decode row {}", self.row)?;
+        if let Some(part_file) = &self.part_file {
+            write!(f, " of {}", part_file.display())?;
+        }
+        if !self.field.is_empty() {
+            write!(f, " at `{}`", self.field)?;
+        }
+        write!(f, " (byte {}", self.offset)?;
+        if let Some(expected) = &self.expected {
+            write!(f, ", expected {}", expected)?;
+        }
+        write!(f, "): {}", self.kind)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl Display for DecodeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeErrorKind::UnexpectedEnd => f.write_str("unexpected end of data"),
+            DecodeErrorKind::Invalid(code) => write!(f, "invalid data ({:?})", code),
+        }
+    }
+}
@@ -0,0 +1,333 @@
+mod node;
+
+use std::{
+    cmp::Ordering,
+    ops::{Bound, RangeBounds},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::{Error, Result},
+    load::{
+        self,
+        stream::{row_decoder, split_offset, PartDecoder, RowDecoder},
+    },
+    types::{
+        key::compare_key,
+        metadata::{component::ComponentIndexSpec, ComponentMetadata, IndexMetadata},
+        HailOrdering, HailValue, VType, VTypeShape,
+    },
+};
+
+pub use node::{IndexNode, InternalChild, LeafEntry};
+
+use node::{as_u64, node_decoder, NodeDecoder};
+
+/// The index of a single part file, a B-tree mapping the key of each row to its offset in the
+/// part file.
+///
+/// Nodes are read from disk as needed, so a search only touches the nodes on the path from the
+/// root to the relevant leaf.
+pub struct Index {
+    pub metadata: IndexMetadata,
+    pub spec: ComponentIndexSpec,
+    index_path: PathBuf,
+    decode_node: NodeDecoder,
+    /// The names and types of the fields of the key, in key order.
+    key_fields: Vec<(String, VType)>,
+    ordering: HailOrdering,
+}
+
+/// A run of consecutive rows in a part file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RowSpan {
+    /// The index of the first row in the part file.
+    pub first_idx: u64,
+    /// The offset of the first row in the part file.
+    pub offset: u64,
+    pub count: u64,
+}
+
+impl Index {
+    /// Opens the index stored in the given `.idx` folder.
+    pub fn open<T: AsRef<Path>>(path: T, spec: &ComponentIndexSpec) -> Result<Index> {
+        let path: &Path = path.as_ref();
+
+        let metadata = load::metadata::load_index_metadata(path)?;
+        let index_path = path.join(&metadata.index_path);
+
+        let key_fields = match &spec.key_type.shape {
+            VTypeShape::Struct(fields) => fields.clone(),
+            shape => {
+                return Err(Error::Index {
+                    path: path.to_owned(),
+                    message: format!("expected a struct as the index key, found {:?}", shape),
+                })
+            }
+        };
+
+        Ok(Index {
+            metadata,
+            spec: spec.clone(),
+            index_path,
+            decode_node: node_decoder(spec),
+            key_fields,
+            ordering: HailOrdering::default(),
+        })
+    }
+
+    pub fn root(&self) -> Result<IndexNode> {
+        self.read_node(self.metadata.root_offset)
+    }
+
+    /// Reads the node at the given position in the index file.
+    ///
+    /// Each node is written in its own block(s), so node offsets are plain file positions.
+    pub fn read_node(&self, offset: u64) -> Result<IndexNode> {
+        let mut decoder = PartDecoder::open(&self.index_path, &self.spec.buffer_spec, offset, 0)?;
+        decoder.decode(|i| (self.decode_node)(i, &self.spec))
+    }
+
+    /// Finds the rows whose key falls in the given range, see [Index::compare_key] for how keys
+    /// are compared.
+    pub fn span<R: RangeBounds<HailValue>>(&self, range: &R) -> Result<Option<RowSpan>> {
+        let start = match range.start_bound() {
+            Bound::Included(key) => self.find(|k| self.compare_key(k, key) != Ordering::Less)?,
+            Bound::Excluded(key) => self.find(|k| self.compare_key(k, key) == Ordering::Greater)?,
+            Bound::Unbounded => self.find(|_| true)?,
+        };
+        let (first_idx, offset) = match start {
+            Some(start) => start,
+            None => return Ok(None),
+        };
+
+        let end_idx = match range.end_bound() {
+            Bound::Included(key) => self.find(|k| self.compare_key(k, key) == Ordering::Greater)?,
+            Bound::Excluded(key) => self.find(|k| self.compare_key(k, key) != Ordering::Less)?,
+            Bound::Unbounded => None,
+        }
+        .map_or(self.metadata.n_keys, |(idx, _)| idx);
+
+        match end_idx > first_idx {
+            true => Ok(Some(RowSpan {
+                first_idx,
+                offset,
+                count: end_idx - first_idx,
+            })),
+            false => Ok(None),
+        }
+    }
+
+    /// Compares the key of an entry with a (possibly partial) key, see [compare_key].
+    ///
+    /// Loci are placed with the built-in reference genomes, unless another ordering is set (see
+    /// [Index::set_ordering]).
+    pub fn compare_key(&self, entry_key: &HailValue, key: &HailValue) -> Ordering {
+        compare_key(&self.key_fields, &self.ordering, entry_key, key)
+    }
+
+    /// Sets the ordering of the keys, for keys holding loci of custom reference genomes.
+    pub fn set_ordering(&mut self, ordering: HailOrdering) {
+        self.ordering = ordering;
+    }
+
+    /// Finds the first entry whose key is after the searched position, returning its index and
+    /// record offset.
+    ///
+    /// `is_after` must be monotonic over the (sorted) keys of the index.
+    fn find<F: Fn(&HailValue) -> bool>(&self, is_after: F) -> Result<Option<(u64, u64)>> {
+        self._find(self.metadata.root_offset, &is_after)
+    }
+
+    fn _find(
+        &self,
+        node_offset: u64,
+        is_after: &dyn Fn(&HailValue) -> bool,
+    ) -> Result<Option<(u64, u64)>> {
+        match self.read_node(node_offset)? {
+            IndexNode::Leaf { first_idx, entries } => entries
+                .iter()
+                .zip(first_idx..)
+                .find(|(entry, _)| is_after(&entry.key))
+                .map(|(entry, idx)| Ok((idx, self.record_offset(entry.offset, &entry.annotation)?)))
+                .transpose(),
+            IndexNode::Internal { children } => {
+                let first_of = |child: &InternalChild| -> Result<(u64, u64)> {
+                    let offset =
+                        self.record_offset(child.first_record_offset, &child.first_annotation)?;
+                    Ok((child.first_idx, offset))
+                };
+
+                // The first child that starts after the searched position, the result is either
+                // in the child before it or it is its first entry.
+                let position = children
+                    .iter()
+                    .position(|child| is_after(&child.first_key))
+                    .unwrap_or(children.len());
+
+                if position == 0 {
+                    return children.first().map(first_of).transpose();
+                }
+
+                match self._find(children[position - 1].index_file_offset, is_after)? {
+                    Some(found) => Ok(Some(found)),
+                    None => children.get(position).map(first_of).transpose(),
+                }
+            }
+        }
+    }
+
+    /// The offset of the record in the part file, which is stored in the annotation when the
+    /// index belongs to a different component (see [ComponentIndexSpec::offset_field]).
+    fn record_offset(&self, offset: u64, annotation: &HailValue) -> Result<u64> {
+        let field = match &self.spec.offset_field {
+            Some(field) => field,
+            None => return Ok(offset),
+        };
+
+        match annotation {
+            HailValue::Struct(fields) => fields.get(field).cloned().and_then(as_u64),
+            _ => None,
+        }
+        .ok_or_else(|| Error::Index {
+            path: self.index_path.clone(),
+            message: format!(
+                "missing offset field {} in annotation {:?}",
+                field, annotation
+            ),
+        })
+    }
+}
+
+/// A component whose rows can be looked up by key, using its index to seek straight to the
+/// relevant rows in each part file.
+pub struct IndexedComponent {
+    pub metadata: ComponentMetadata,
+    /// One index per part file.
+    pub indexes: Vec<Index>,
+    data_path: PathBuf,
+    decode_row: RowDecoder,
+    ordering: HailOrdering,
+}
+
+impl IndexedComponent {
+    pub fn open<T: AsRef<Path>>(path: T) -> Result<IndexedComponent> {
+        let path: &Path = path.as_ref();
+
+        let metadata = load::metadata::load_component_metadata(path)?;
+        let spec = metadata
+            .index_spec
+            .as_ref()
+            .ok_or_else(|| Error::NotIndexed {
+                path: path.to_owned(),
+            })?;
+
+        let index_folder = path.join(&spec.rel_path);
+        let indexes = metadata
+            .part_files
+            .iter()
+            .map(|part_file| Index::open(index_folder.join(format!("{}.idx", part_file)), spec))
+            .collect::<Result<Vec<Index>>>()?;
+
+        let decode_row = row_decoder(&metadata.buffer_spec);
+
+        Ok(IndexedComponent {
+            metadata,
+            indexes,
+            data_path: path.join("parts"),
+            decode_row,
+            ordering: HailOrdering::default(),
+        })
+    }
+
+    /// Sets the ordering of the keys (see [Index::set_ordering]), like the ordering with the
+    /// reference genomes of the table holding the component.
+    pub fn with_ordering(mut self, ordering: HailOrdering) -> Self {
+        for index in &mut self.indexes {
+            index.set_ordering(ordering.clone());
+        }
+        self.ordering = ordering;
+        self
+    }
+
+    /// Returns all the rows with the given (possibly partial) key, see [Index::compare_key].
+    pub fn lookup(&self, key: &HailValue) -> Result<Vec<HailValue>> {
+        self.range::<HailValue, _>((Bound::Included(key), Bound::Included(key)))
+    }
+
+    /// Returns all the rows whose key falls in the given range, in order.
+    ///
+    /// Ranges can be built from either owned values (`start..end`) or references
+    /// (`(Bound::Included(&start), Bound::Unbounded)`).
+    pub fn range<K, R>(&self, range: R) -> Result<Vec<HailValue>>
+    where
+        K: std::borrow::Borrow<HailValue>,
+        R: RangeBounds<K>,
+    {
+        let range = (
+            as_ref_bound(range.start_bound()),
+            as_ref_bound(range.end_bound()),
+        );
+
+        let mut rows = vec![];
+        // The range bounds of the partitions allow skipping most indexes without reading them.
+        for part in self
+            .metadata
+            .overlapping_partitions_with::<HailValue, _>(&self.ordering, range)
+        {
+            if let Some(span) = self.indexes[part].span(&range)? {
+                rows.extend(self.rows_at(part, span.offset, span.count)?);
+            }
+        }
+
+        Ok(rows)
+    }
+
+    /// Decodes `count` consecutive rows of a part file, starting from the given offset (as found
+    /// in the index).
+    pub fn rows_at(&self, part: usize, offset: u64, count: u64) -> Result<Vec<HailValue>> {
+        let part_file =
+            self.metadata
+                .part_files
+                .get(part)
+                .ok_or_else(|| Error::PartitionOutOfRange {
+                    index: part,
+                    partitions: self.metadata.part_files.len(),
+                })?;
+
+        let (file_position, block_position) = split_offset(&self.metadata.buffer_spec, offset);
+        let mut decoder = PartDecoder::open(
+            &self.data_path.join(part_file),
+            &self.metadata.buffer_spec,
+            file_position,
+            block_position,
+        )?;
+
+        let mut rows = vec![];
+        while (rows.len() as u64) < count {
+            match decoder.next_row(self.decode_row, &self.metadata.encoded_type)? {
+                Some(row) => rows.push(row),
+                None => {
+                    return Err(Error::Index {
+                        path: self.data_path.join(part_file),
+                        message: format!(
+                            "the part file ended after {} of the {} rows requested",
+                            rows.len(),
+                            count
+                        ),
+                    })
+                }
+            }
+        }
+
+        Ok(rows)
+    }
+}
+
+fn as_ref_bound<K: std::borrow::Borrow<HailValue>>(bound: Bound<&K>) -> Bound<&HailValue> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key.borrow()),
+        Bound::Excluded(key) => Bound::Excluded(key.borrow()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
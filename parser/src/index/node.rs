@@ -0,0 +1,146 @@
+use std::{collections::BTreeMap, convert::TryFrom};
+
+use nom::IResult;
+
+use crate::{
+    parse::{Encoding, StandardEncoder, UnsignedLEB128Encoder},
+    types::{metadata::component::ComponentIndexSpec, HailValue},
+};
+
+/// A node of the B-tree stored in an index file.
+///
+/// Leaves hold one entry per row of the indexed part file, in order, while internal nodes hold
+/// a summary of the first entry of each of their children.
+#[derive(Clone, Debug)]
+pub enum IndexNode {
+    Internal {
+        children: Vec<InternalChild>,
+    },
+    Leaf {
+        /// The index of the first entry of the leaf among all the entries of the index.
+        first_idx: u64,
+        entries: Vec<LeafEntry>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct InternalChild {
+    /// The position of the child node in the index file.
+    pub index_file_offset: u64,
+    pub first_idx: u64,
+    pub first_key: HailValue,
+    pub first_record_offset: u64,
+    pub first_annotation: HailValue,
+}
+
+#[derive(Clone, Debug)]
+pub struct LeafEntry {
+    pub key: HailValue,
+    /// The offset of the row in the part file.
+    pub offset: u64,
+    pub annotation: HailValue,
+}
+
+/// A function that decodes an index node, see [parse_node].
+pub(crate) type NodeDecoder =
+    for<'i> fn(&'i [u8], &ComponentIndexSpec) -> IResult<&'i [u8], IndexNode>;
+
+/// Picks the [NodeDecoder] matching the encoding of the index.
+pub(crate) fn node_decoder(spec: &ComponentIndexSpec) -> NodeDecoder {
+    match spec.buffer_spec.uses_leb128() {
+        true => parse_node::<UnsignedLEB128Encoder>,
+        false => parse_node::<StandardEncoder>,
+    }
+}
+
+/// Each node starts with a byte indicating its kind (`1u8` for internal nodes, `0u8` for leaves),
+/// followed by the node itself, encoded as a struct.
+pub(crate) fn parse_node<'i, E: Encoding>(
+    i: &'i [u8],
+    spec: &ComponentIndexSpec,
+) -> IResult<&'i [u8], IndexNode> {
+    let (rest, kind) = E::u8(i)?;
+
+    let (rest, node) = match kind {
+        0 => {
+            let (rest, value) = spec.leaf_encoded_type.decode_from::<E>(rest)?;
+            (rest, leaf_from_value(value))
+        }
+        1 => {
+            let (rest, value) = spec.internal_node_encoded_type.decode_from::<E>(rest)?;
+            (rest, internal_node_from_value(value))
+        }
+        _ => (rest, None),
+    };
+
+    match node {
+        Some(node) => Ok((rest, node)),
+        None => Err(nom::Err::Failure(nom::error::Error::new(
+            i,
+            nom::error::ErrorKind::Verify,
+        ))),
+    }
+}
+
+fn leaf_from_value(value: HailValue) -> Option<IndexNode> {
+    let mut fields = into_fields(value)?;
+
+    let first_idx = as_u64(fields.remove("first_idx")?)?;
+    let entries = match fields.remove("keys")? {
+        HailValue::Array(entries) => entries,
+        _ => return None,
+    };
+
+    let entries = entries
+        .into_iter()
+        .map(|entry| {
+            let mut fields = into_fields(entry)?;
+            Some(LeafEntry {
+                key: fields.remove("key")?,
+                offset: as_u64(fields.remove("offset")?)?,
+                annotation: fields.remove("annotation")?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(IndexNode::Leaf { first_idx, entries })
+}
+
+fn internal_node_from_value(value: HailValue) -> Option<IndexNode> {
+    let mut fields = into_fields(value)?;
+
+    let children = match fields.remove("children")? {
+        HailValue::Array(children) => children,
+        _ => return None,
+    };
+
+    let children = children
+        .into_iter()
+        .map(|child| {
+            let mut fields = into_fields(child)?;
+            Some(InternalChild {
+                index_file_offset: as_u64(fields.remove("index_file_offset")?)?,
+                first_idx: as_u64(fields.remove("first_idx")?)?,
+                first_key: fields.remove("first_key")?,
+                first_record_offset: as_u64(fields.remove("first_record_offset")?)?,
+                first_annotation: fields.remove("first_annotation")?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(IndexNode::Internal { children })
+}
+
+fn into_fields(value: HailValue) -> Option<BTreeMap<String, HailValue>> {
+    match value {
+        HailValue::Struct(fields) => Some(fields),
+        _ => None,
+    }
+}
+
+pub(crate) fn as_u64(value: HailValue) -> Option<u64> {
+    match value {
+        HailValue::Int64(value) => u64::try_from(value).ok(),
+        _ => None,
+    }
+}
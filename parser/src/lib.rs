@@ -1,6 +1,14 @@
-#![forbid(unsafe_code)]
+// The only exception is the memory mapping of part files, see `load::helpers::map_file`.
+#![deny(unsafe_code)]
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod error;
+pub mod index;
 pub mod load;
 pub mod parse;
 pub mod types;
+pub mod validate;
+pub mod write;
 
-pub use types::{Component, HailValue, Matrix, Table};
+pub use error::{Error, Result};
+pub use types::{Component, HailValue, HailValueRef, Matrix, Table};
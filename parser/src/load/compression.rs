@@ -1,48 +1,198 @@
-use std::convert::TryInto;
+use std::{convert::TryInto, io::Read};
 
-use anyhow::Result;
+use crate::error::{Error, Result};
 
+/// LZ4 cannot expand the data by more than this ratio (a match encodes at most 255 extra bytes
+/// per byte of its length), so larger original sizes are refused before allocating anything.
+const MAX_DECOMPRESSION_RATIO: usize = 255;
+
+/// Decompresses a whole compressed part file, which is a sequence of blocks as described in
+/// [BlockReader].
 pub fn decompress_part_file(raw: &[u8]) -> Result<Vec<u8>> {
-    let final_size = get_total_original_size(raw)?;
-    let mut decompressed = vec![0; final_size];
+    let mut blocks = BlockReader::new(raw, true, true);
+    let mut decompressed = Vec::with_capacity(raw.len());
+    while blocks.read_block_into(&mut decompressed)? {}
+    Ok(decompressed)
+}
+
+/// Joins the blocks of an uncompressed (but blocked) part file.
+pub fn concatenate_blocks(raw: &[u8]) -> Result<Vec<u8>> {
+    let mut blocks = BlockReader::new(raw, false, true);
+    let mut data = Vec::with_capacity(raw.len());
+    while blocks.read_block_into(&mut data)? {}
+    Ok(data)
+}
 
-    let mut compressed_cursor = 0;
-    let mut decompressed_cursor = 0;
+/// Reads a part file one block at a time, so that only a single (decompressed) block needs to be
+/// held in memory.
+///
+/// Blocked part files are a sequence of blocks, each prefixed with its length (a u32).
+/// When compressed, the block itself starts with the original size (a u32) followed by the LZ4
+/// compressed data, exactly as expected by [decompress_part_file].
+/// Part files without blocks are simply read in chunks of fixed size.
+pub struct BlockReader<R: Read> {
+    reader: R,
+    is_compressed: bool,
+    is_blocked: bool,
+    done: bool,
+    /// The number of bytes read from the reader so far.
+    consumed: u64,
+    /// The compressed bytes of the last block, kept around to reuse the allocation.
+    compressed: Vec<u8>,
+}
+
+/// The size of the chunks read from part files that are neither compressed nor blocked.
+const UNBLOCKED_CHUNK_SIZE: usize = 64 * 1024;
 
-    while compressed_cursor < raw.len() {
-        let compressed_block_size: usize =
-            u32::from_le_bytes(raw[compressed_cursor..(compressed_cursor + 4)].try_into()?)
-                .try_into()?;
-        let original_block_size: usize =
-            u32::from_le_bytes(raw[(compressed_cursor + 4)..(compressed_cursor + 8)].try_into()?)
-                .try_into()?;
-        if original_block_size == 0 {
-            break;
+impl<R: Read> BlockReader<R> {
+    pub fn new(reader: R, is_compressed: bool, has_appended_length: bool) -> Self {
+        BlockReader {
+            reader,
+            is_compressed,
+            // Compressed part files are always blocked.
+            is_blocked: is_compressed || has_appended_length,
+            done: false,
+            consumed: 0,
+            compressed: vec![],
         }
-        let block = &raw[(compressed_cursor + 8)..(compressed_cursor + 4 + compressed_block_size)];
-        let destination_subslice =
-            &mut decompressed[decompressed_cursor..(decompressed_cursor + original_block_size)];
-        let _ = lzzzz::lz4::decompress_partial(block, destination_subslice, original_block_size)?;
+    }
 
-        compressed_cursor += compressed_block_size + 4;
-        decompressed_cursor += original_block_size;
+    /// The number of bytes read from the reader so far.
+    pub fn consumed(&self) -> u64 {
+        self.consumed
     }
 
-    Ok(decompressed)
+    /// The most data that `remaining` bytes of the reader can hold once decompressed.
+    pub fn max_data_size(&self, remaining: u64) -> u64 {
+        match self.is_compressed {
+            true => remaining.saturating_mul(MAX_DECOMPRESSION_RATIO as u64),
+            false => remaining,
+        }
+    }
+
+    /// Returns the next block of data, or `None` once the end of the file has been reached.
+    pub fn next_block(&mut self) -> Result<Option<Vec<u8>>> {
+        let mut block = vec![];
+        match self.read_block_into(&mut block)? {
+            true => Ok(Some(block)),
+            false => Ok(None),
+        }
+    }
+
+    /// Appends the next block of data to the buffer, returning `false` (and leaving the buffer
+    /// untouched) once the end of the file has been reached.
+    ///
+    /// Unlike [BlockReader::next_block], nothing is allocated once the buffer (and the internal
+    /// buffer used for the compressed data) are large enough to hold a block.
+    pub fn read_block_into(&mut self, buffer: &mut Vec<u8>) -> Result<bool> {
+        if self.done {
+            return Ok(false);
+        }
+
+        let has_block = match self.is_blocked {
+            true => self.read_framed_block(buffer)?,
+            false => self.read_chunk(buffer)?,
+        };
+
+        if !has_block {
+            self.done = true;
+        }
+
+        Ok(has_block)
+    }
+
+    fn read_framed_block(&mut self, buffer: &mut Vec<u8>) -> Result<bool> {
+        let block_size: usize = match read_u32_or_eof(&mut self.reader)? {
+            Some(size) => size.try_into()?,
+            None => return Ok(false),
+        };
+        self.consumed += 4;
+
+        if !self.is_compressed {
+            read_exactly(&mut self.reader, block_size, buffer)?;
+            self.consumed += block_size as u64;
+            return Ok(true);
+        }
+
+        let block = &mut self.compressed;
+        block.clear();
+        read_exactly(&mut self.reader, block_size, block)?;
+        self.consumed += block_size as u64;
+
+        if block.len() < 4 {
+            return Err(Error::block(format!(
+                "compressed block is too short to hold its original size ({} bytes)",
+                block.len()
+            )));
+        }
+        let original_size: usize = u32::from_le_bytes(block[..4].try_into()?).try_into()?;
+        if original_size == 0 {
+            return Ok(false);
+        }
+        let compressed_size = block.len() - 4;
+        if original_size / MAX_DECOMPRESSION_RATIO > compressed_size {
+            return Err(Error::block(format!(
+                "{} compressed bytes cannot hold a block of {} bytes",
+                compressed_size, original_size
+            )));
+        }
+
+        let start = buffer.len();
+        buffer.resize(start + original_size, 0);
+        let decompressed =
+            lzzzz::lz4::decompress_partial(&block[4..], &mut buffer[start..], original_size)?;
+        if decompressed != original_size {
+            buffer.truncate(start + decompressed);
+            return Err(Error::block(format!(
+                "block decompressed to {} bytes instead of {}",
+                decompressed, original_size
+            )));
+        }
+
+        Ok(true)
+    }
+
+    fn read_chunk(&mut self, buffer: &mut Vec<u8>) -> Result<bool> {
+        let read = (&mut self.reader)
+            .take(UNBLOCKED_CHUNK_SIZE as u64)
+            .read_to_end(buffer)?;
+        self.consumed += read as u64;
+
+        Ok(read > 0)
+    }
 }
 
-fn get_total_original_size(raw: &[u8]) -> Result<usize> {
-    let mut total = 0;
-    let mut cursor = 0;
+/// Appends exactly `length` bytes from the reader to the buffer.
+///
+/// The length comes from the file itself, so the buffer only grows as the data is actually read
+/// rather than being allocated upfront.
+fn read_exactly<R: Read>(reader: &mut R, length: usize, buffer: &mut Vec<u8>) -> Result<()> {
+    let read = reader.take(length as u64).read_to_end(buffer)?;
+    if read < length {
+        return Err(Error::block(format!(
+            "unexpected end of file in a block of {} bytes ({} available)",
+            length, read
+        )));
+    }
+    Ok(())
+}
 
-    while cursor < raw.len() {
-        let block_size: usize =
-            u32::from_le_bytes(raw[cursor..(cursor + 4)].try_into()?).try_into()?;
-        let original_size: usize =
-            u32::from_le_bytes(raw[(cursor + 4)..(cursor + 8)].try_into()?).try_into()?;
-        total += original_size;
-        cursor += block_size + 4
+/// Reads a little endian u32, returning `None` if the reader is already exhausted.
+fn read_u32_or_eof<R: Read>(reader: &mut R) -> Result<Option<u32>> {
+    let mut buffer = [0u8; 4];
+    let mut filled = 0;
+
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..])? {
+            0 if filled == 0 => return Ok(None),
+            0 => {
+                return Err(Error::block(
+                    "unexpected end of file while reading a block length",
+                ))
+            }
+            n => filled += n,
+        }
     }
 
-    Ok(total)
+    Ok(Some(u32::from_le_bytes(buffer)))
 }
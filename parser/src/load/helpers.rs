@@ -1,24 +1,35 @@
-use std::path::Path;
+use std::{fs::File, ops::Deref, path::Path};
 
-use anyhow::Result;
+use memmap2::Mmap;
 
 use crate::{
-    parse::{parse_rows, Encoding, StandardEncoder, UnsignedLEB128Encoder},
-    types::{metadata::shared::BufferSpec, EType, HailValue, Metadata},
+    error::{Error, Result},
+    parse::{
+        parse_next_projected_row, parse_next_row_ref, parse_projected_rows, Encoding,
+        StandardEncoder, UnsignedLEB128Encoder,
+    },
+    types::{
+        metadata::{shared::BufferSpec, ComponentMetadata},
+        EType, HailValue, HailValueRef, Partition, Projection,
+    },
 };
 
-use super::compression;
+use super::{parallel, stream::PartDecoder};
 
-pub fn load_metadata_in<T: AsRef<Path>>(path: T) -> Result<Metadata> {
-    let metadata_path = path.as_ref().join("metadata.json.gz");
-    let metadata_decoder = flate2::read::GzDecoder::new(std::fs::File::open(metadata_path)?);
-    let metadata: Metadata = serde_json::from_reader(metadata_decoder)?;
-    Ok(metadata)
+pub fn load_component_data<T: AsRef<Path>>(
+    part_files: &[String],
+    row_schema: &EType,
+    buffer_spec: &BufferSpec,
+    path: T,
+) -> Result<Vec<Vec<HailValue>>> {
+    load_projected_component_data(part_files, row_schema, &Projection::All, buffer_spec, path)
 }
 
-pub fn load_component_data<T: AsRef<Path>>(
+/// Like [load_component_data], but only the selected fields of each row are decoded.
+pub fn load_projected_component_data<T: AsRef<Path>>(
     part_files: &[String],
     row_schema: &EType,
+    projection: &Projection,
     buffer_spec: &BufferSpec,
     path: T,
 ) -> Result<Vec<Vec<HailValue>>> {
@@ -28,46 +39,195 @@ pub fn load_component_data<T: AsRef<Path>>(
         true => _load_data::<UnsignedLEB128Encoder>(
             part_files,
             row_schema,
-            buffer_spec.uses_compression(),
-            buffer_spec.appends_length(),
-            path,
-        ),
-        false => _load_data::<StandardEncoder>(
-            part_files,
-            row_schema,
-            buffer_spec.uses_compression(),
-            buffer_spec.appends_length(),
+            projection,
+            buffer_spec,
             path,
         ),
+        false => {
+            _load_data::<StandardEncoder>(part_files, row_schema, projection, buffer_spec, path)
+        }
     }?;
 
     Ok(data)
 }
 
+/// Loads only the selected partitions of a component, in the order they are given.
+///
+/// If the partition counts are provided, they are used to compute the row offset of each
+/// partition.
+pub fn load_partitions<T: AsRef<Path>>(
+    metadata: &ComponentMetadata,
+    partitions: &[usize],
+    partition_counts: Option<&[u32]>,
+    path: T,
+) -> Result<Vec<Partition>> {
+    let part_files = partitions
+        .iter()
+        .map(|&index| {
+            metadata
+                .part_files
+                .get(index)
+                .cloned()
+                .ok_or(Error::PartitionOutOfRange {
+                    index,
+                    partitions: metadata.part_files.len(),
+                })
+        })
+        .collect::<Result<Vec<String>>>()?;
+
+    let data = load_component_data(
+        &part_files,
+        &metadata.encoded_type,
+        &metadata.buffer_spec,
+        path,
+    )?;
+
+    let row_offset = |index: usize| {
+        partition_counts.map(|counts| counts.iter().take(index).map(|&c| u64::from(c)).sum())
+    };
+
+    Ok(partitions
+        .iter()
+        .zip(data)
+        .map(|(&index, rows)| Partition {
+            index,
+            row_offset: row_offset(index),
+            rows,
+        })
+        .collect())
+}
+
+/// Part files are never read in full: unblocked data is parsed straight from the mapped file, while
+/// blocked (and possibly compressed) data is decoded one block at a time into a reused buffer, so
+/// that only a block needs to be held in memory on top of the decoded rows.
 fn _load_data<E: Encoding>(
     part_files: &[String],
     row_schema: &EType,
-    is_compressed: bool,
-    has_appended_length: bool,
+    projection: &Projection,
+    buffer_spec: &BufferSpec,
     path: &Path,
 ) -> Result<Vec<Vec<HailValue>>> {
-    let mut data = Vec::new();
-
-    for part_name in part_files {
-        let part_data = {
-            let part_path = path.join(part_name);
-            let file = std::fs::read(&part_path)?;
-            match is_compressed {
-                true => parse_rows::<E>(&compression::decompress_part_file(&file)?, row_schema)?,
-                false => match has_appended_length {
-                    true => parse_rows::<E>(&file[4..], row_schema)?,
-                    false => parse_rows::<E>(&file, row_schema)?,
-                },
-            }
-        };
-
-        data.push(part_data);
+    parallel::map_partitions(part_files, |part_name| {
+        let part_path = path.join(part_name);
+
+        if !buffer_spec.uses_compression() && !buffer_spec.appends_length() {
+            let file = map_file(&part_path)?;
+            return parse_projected_rows::<E>(&file, row_schema, projection)
+                .map_err(|error| error.in_file(&part_path));
+        }
+
+        let mut decoder = PartDecoder::open(&part_path, buffer_spec, 0, 0)?;
+        let mut rows = Vec::new();
+        while let Some(row) = decoder.decode_row(row_schema, projection, |i| {
+            parse_next_projected_row::<E>(i, row_schema, projection)
+        })? {
+            rows.push(row);
+        }
+        Ok(rows)
+    })
+}
+
+/// Decodes the rows of a component one at a time, as [HailValueRef]s borrowing from the current
+/// block, and passes them to `visit`.
+pub fn for_each_row<E, F>(metadata: &ComponentMetadata, path: &Path, mut visit: F) -> Result<()>
+where
+    E: Encoding,
+    F: FnMut(HailValueRef<'_>) -> Result<(), Box<dyn std::error::Error + Send + Sync>>,
+{
+    for part_name in &metadata.part_files {
+        for_each_part_row::<E, _>(metadata, &path.join(part_name), &mut visit)?;
     }
 
-    Ok(data)
+    Ok(())
+}
+
+/// Like [for_each_row], for a single part file.
+pub fn for_each_part_row<E, F>(
+    metadata: &ComponentMetadata,
+    file: &Path,
+    mut visit: F,
+) -> Result<()>
+where
+    E: Encoding,
+    F: FnMut(HailValueRef<'_>) -> Result<(), Box<dyn std::error::Error + Send + Sync>>,
+{
+    let row_type = &metadata.encoded_type;
+
+    let mut decoder = PartDecoder::open(file, &metadata.buffer_spec, 0, 0)?;
+    loop {
+        let visited = decoder.decode_row_with(row_type, &Projection::All, |input| {
+            let (rest, row) =
+                parse_next_row_ref::<E>(input, row_type).map_err(|e| e.map(|e| e.code))?;
+            Ok((input.len() - rest.len(), row.map(&mut visit)))
+        })?;
+        match visited {
+            Some(result) => result.map_err(Error::Visit)?,
+            None => return Ok(()),
+        }
+    }
+}
+
+/// Counts the rows of each part file of a component, skipping over them without decoding them.
+pub fn count_rows<E: Encoding>(metadata: &ComponentMetadata, path: &Path) -> Result<Vec<u64>> {
+    let row_type = &metadata.encoded_type;
+
+    parallel::map_partitions(&metadata.part_files, |part_name| {
+        let mut decoder = PartDecoder::open(&path.join(part_name), &metadata.buffer_spec, 0, 0)?;
+        let mut rows = 0;
+        while decoder
+            .decode_row(row_type, &Projection::All, |i| {
+                let (rest, is_row) = E::bool(i)?;
+                match is_row {
+                    true => row_type
+                        .skip_from::<E>(rest)
+                        .map(|(rest, ())| (rest, Some(()))),
+                    false => Ok((rest, None)),
+                }
+            })?
+            .is_some()
+        {
+            rows += 1;
+        }
+        Ok(rows)
+    })
+}
+
+/// Maps a part file in memory, so that it can be parsed without copying it.
+///
+/// Empty files cannot be mapped, so they are returned as an empty buffer instead.
+pub(crate) fn map_file(path: &Path) -> Result<MappedFile> {
+    _map_file(path).map_err(|source| Error::Io {
+        path: Some(path.to_owned()),
+        source,
+    })
+}
+
+fn _map_file(path: &Path) -> std::io::Result<MappedFile> {
+    let file = File::open(path)?;
+    if file.metadata()?.len() == 0 {
+        return Ok(MappedFile::Empty);
+    }
+
+    // SAFETY: the mapping is only sound as long as the file is not modified (or truncated) while
+    // it is mapped. Hail never modifies part files once they have been written, so this only
+    // assumes that no other process is rewriting the table while it is being loaded.
+    #[allow(unsafe_code)]
+    let map = unsafe { Mmap::map(&file)? };
+    Ok(MappedFile::Mapped(map))
+}
+
+pub(crate) enum MappedFile {
+    Mapped(Mmap),
+    Empty,
+}
+
+impl Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            MappedFile::Mapped(map) => map,
+            MappedFile::Empty => &[],
+        }
+    }
 }
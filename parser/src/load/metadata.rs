@@ -1,88 +1,215 @@
-use std::path::Path;
+use std::{
+    collections::BTreeMap,
+    convert::TryInto,
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
 
-use anyhow::Result;
+use serde::de::DeserializeOwned;
 
-use crate::types::{
-    metadata::{ComponentMetadata, MatrixMetadata, TableMetadata},
-    Metadata,
+use crate::{
+    error::{Error, Result},
+    types::{
+        metadata::{ComponentMetadata, IndexMetadata, MatrixMetadata, TableMetadata},
+        reference::locus_references,
+        Metadata, ReferenceGenome, VType,
+    },
+    validate::check_types,
 };
 
-use crate::load;
+/// The metadata of a matrix, a table, or a component (see [load_any_metadata]).
+#[derive(Clone, Debug)]
+pub enum AnyMetadata {
+    Matrix(MatrixMetadata),
+    Table(TableMetadata),
+    Component(ComponentMetadata),
+}
 
 pub fn load_component_metadata<T: AsRef<Path>>(path: T) -> Result<ComponentMetadata> {
-    match load::helpers::load_metadata_in(path)? {
-        Metadata::OrderedRVDSpec(metadata) => Ok(metadata.into()),
-        Metadata::IndexedRVDSpec(metadata) => Ok(metadata.into()),
-        Metadata::UnpartitionedRVDSpec(metadata) => Ok(metadata.into()),
-        Metadata::OrderedRVDSpec2(metadata) => Ok(metadata.into()),
-        Metadata::IndexedRVDSpec2(metadata) => Ok(metadata.into()),
-        Metadata::TableSpec(ref table_metadata) => Err(anyhow::anyhow!(format!(
-            "Expected a component, found a table {:?}",
-            table_metadata
-        ))),
-        Metadata::MatrixTableSpec(ref matrix_metadata) => Err(anyhow::anyhow!(format!(
-            "Expected a component, found a matrix {:?}",
-            matrix_metadata
-        ))),
-    }
+    let path: &Path = path.as_ref();
+    component_metadata(path, read_metadata_file(path)?)
 }
 
 pub fn load_table_metadata<T: AsRef<Path>>(path: T) -> Result<TableMetadata> {
-    match load::helpers::load_metadata_in(path)? {
-        Metadata::TableSpec(metadata) => Ok(metadata),
-        Metadata::OrderedRVDSpec(ref comp_metadata) => Err(anyhow::anyhow!(format!(
-            "Expected a table, found an ordered component {:?}",
-            comp_metadata
-        ))),
-        Metadata::IndexedRVDSpec(ref comp_metadata) => Err(anyhow::anyhow!(format!(
-            "Expected a table, found an indexed component {:?}",
-            comp_metadata
-        ))),
-        Metadata::UnpartitionedRVDSpec(ref comp_metadata) => Err(anyhow::anyhow!(format!(
-            "Expected a table, found an unpartitioned component {:?}",
-            comp_metadata
-        ))),
-        Metadata::OrderedRVDSpec2(ref comp_metadata) => Err(anyhow::anyhow!(format!(
-            "Expected a table, found an ordered component (version 2) {:?}",
-            comp_metadata
-        ))),
-        Metadata::IndexedRVDSpec2(ref comp_metadata) => Err(anyhow::anyhow!(format!(
-            "Expected a table, found an indexed component (version 2) {:?}",
-            comp_metadata
-        ))),
-        Metadata::MatrixTableSpec(ref matrix_metadata) => Err(anyhow::anyhow!(format!(
-            "Expected a table, found a matrix {:?}",
-            matrix_metadata
-        ))),
+    let path: &Path = path.as_ref();
+    match read_metadata_file(path)? {
+        Metadata::TableSpec(metadata) => with_table_references(path, metadata),
+        metadata => Err(unexpected(path, "table", &metadata)),
     }
 }
 
 pub fn load_matrix_metadata<T: AsRef<Path>>(path: T) -> Result<MatrixMetadata> {
-    match load::helpers::load_metadata_in(path)? {
-        Metadata::MatrixTableSpec(metadata) => Ok(metadata),
-        Metadata::OrderedRVDSpec(ref comp_metadata) => Err(anyhow::anyhow!(format!(
-            "Expected a matrix, found an ordered component {:?}",
-            comp_metadata
-        ))),
-        Metadata::IndexedRVDSpec(ref comp_metadata) => Err(anyhow::anyhow!(format!(
-            "Expected a matrix, found an indexed component {:?}",
-            comp_metadata
-        ))),
-        Metadata::UnpartitionedRVDSpec(ref comp_metadata) => Err(anyhow::anyhow!(format!(
-            "Expected a matrix, found an unpartitioned component {:?}",
-            comp_metadata
-        ))),
-        Metadata::OrderedRVDSpec2(ref comp_metadata) => Err(anyhow::anyhow!(format!(
-            "Expected a matrix, found an ordered component (version 2) {:?}",
-            comp_metadata
-        ))),
-        Metadata::IndexedRVDSpec2(ref comp_metadata) => Err(anyhow::anyhow!(format!(
-            "Expected a matrix, found an indexed component (version 2) {:?}",
-            comp_metadata
-        ))),
-        Metadata::TableSpec(ref table_metadata) => Err(anyhow::anyhow!(format!(
-            "Expected a matrix, found a table {:?}",
-            table_metadata
-        ))),
+    let path: &Path = path.as_ref();
+    match read_metadata_file(path)? {
+        Metadata::MatrixTableSpec(metadata) => with_matrix_references(path, metadata),
+        metadata => Err(unexpected(path, "matrix", &metadata)),
+    }
+}
+
+/// Loads the metadata of whatever the path holds, for when it is not known upfront.
+pub fn load_any_metadata<T: AsRef<Path>>(path: T) -> Result<AnyMetadata> {
+    let path: &Path = path.as_ref();
+    match read_metadata_file(path)? {
+        Metadata::MatrixTableSpec(metadata) => {
+            with_matrix_references(path, metadata).map(AnyMetadata::Matrix)
+        }
+        Metadata::TableSpec(metadata) => {
+            with_table_references(path, metadata).map(AnyMetadata::Table)
+        }
+        metadata => component_metadata(path, metadata).map(AnyMetadata::Component),
+    }
+}
+
+/// Index metadata files have no "name" field, so they are loaded separately from the others.
+pub fn load_index_metadata<T: AsRef<Path>>(path: T) -> Result<IndexMetadata> {
+    read_metadata_file(path.as_ref())
+}
+
+/// Loads a reference genome from a JSON file, as written by Hail (gzipped if its name ends with
+/// `.gz`).
+pub fn load_reference_genome<T: AsRef<Path>>(file: T) -> Result<ReferenceGenome> {
+    let file: &Path = file.as_ref();
+    let reader = File::open(file).map_err(|source| Error::Io {
+        path: Some(file.to_owned()),
+        source,
+    })?;
+    let reader: Box<dyn Read> = match file.extension() {
+        Some(extension) if extension == "gz" => Box::new(flate2::read::GzDecoder::new(reader)),
+        _ => Box::new(BufReader::new(reader)),
+    };
+    serde_json::from_reader(reader).map_err(|error| Error::Metadata {
+        path: file.to_owned(),
+        message: error.to_string(),
+    })
+}
+
+/// Loads the reference genomes of the loci of the given types: from the references folder (as
+/// `<name>.json.gz` or `<name>.json`), or else from the [built-in](ReferenceGenome::builtin)
+/// genomes.
+///
+/// Genomes that are neither are left out rather than failing the load: their loci are not checked,
+/// and their contigs are ordered by name.
+pub fn load_references<'a, T, I>(
+    references_path: T,
+    types: I,
+) -> Result<BTreeMap<String, ReferenceGenome>>
+where
+    T: AsRef<Path>,
+    I: IntoIterator<Item = &'a VType>,
+{
+    let references_path: &Path = references_path.as_ref();
+
+    let mut references = BTreeMap::new();
+    for name in types.into_iter().flat_map(locus_references) {
+        if references.contains_key(&name) {
+            continue;
+        }
+        let files = [format!("{}.json.gz", name), format!("{}.json", name)];
+        let genome = match files
+            .iter()
+            .map(|file| references_path.join(file))
+            .find(|file| file.is_file())
+        {
+            Some(file) => {
+                let genome = load_reference_genome(&file)?;
+                if genome.name() != name {
+                    return Err(Error::Metadata {
+                        path: file,
+                        message: format!(
+                            "expected the reference genome {}, found {}",
+                            name,
+                            genome.name()
+                        ),
+                    });
+                }
+                genome
+            }
+            None => match ReferenceGenome::builtin(&name) {
+                Some(genome) => genome,
+                None => continue,
+            },
+        };
+        references.insert(name, genome);
+    }
+    Ok(references)
+}
+
+fn with_table_references(path: &Path, mut metadata: TableMetadata) -> Result<TableMetadata> {
+    let schema = &metadata.table_type;
+    metadata.references = load_references(
+        path.join(&metadata.references_rel_path),
+        vec![&schema.global_schema, &schema.row_schema],
+    )?;
+    Ok(metadata)
+}
+
+fn with_matrix_references(path: &Path, mut metadata: MatrixMetadata) -> Result<MatrixMetadata> {
+    let schema = &metadata.matrix_type;
+    metadata.references = load_references(
+        path.join(&metadata.references_rel_path),
+        vec![
+            &schema.global_schema,
+            &schema.col_schema,
+            &schema.row_schema,
+            &schema.entry_schema,
+        ],
+    )?;
+    Ok(metadata)
+}
+
+fn component_metadata(path: &Path, metadata: Metadata) -> Result<ComponentMetadata> {
+    let invalid = |error: crate::parse::SchemaParsingError| Error::Metadata {
+        path: path.to_owned(),
+        message: error.to_string(),
+    };
+
+    let metadata: ComponentMetadata = match metadata {
+        Metadata::OrderedRVDSpec(metadata) => metadata.try_into().map_err(invalid)?,
+        Metadata::IndexedRVDSpec(metadata) => metadata.try_into().map_err(invalid)?,
+        Metadata::UnpartitionedRVDSpec(metadata) => metadata.into(),
+        Metadata::OrderedRVDSpec2(metadata) => metadata.try_into().map_err(invalid)?,
+        Metadata::IndexedRVDSpec2(metadata) => metadata.try_into().map_err(invalid)?,
+        metadata => return Err(unexpected(path, "component", &metadata)),
+    };
+
+    // Otherwise the values would be decoded with the wrong types.
+    let violations = check_types(&metadata.virtual_type, &metadata.encoded_type);
+    match violations.first() {
+        Some(violation) => Err(Error::Metadata {
+            path: path.to_owned(),
+            message: violation.to_string(),
+        }),
+        None => Ok(metadata),
+    }
+}
+
+fn read_metadata_file<M: DeserializeOwned>(path: &Path) -> Result<M> {
+    let metadata_path = path.join("metadata.json.gz");
+    let file = File::open(&metadata_path).map_err(|source| Error::Io {
+        path: Some(metadata_path.clone()),
+        source,
+    })?;
+    let metadata_decoder = flate2::read::GzDecoder::new(file);
+    serde_json::from_reader(metadata_decoder).map_err(|error| Error::Metadata {
+        path: metadata_path,
+        message: error.to_string(),
+    })
+}
+
+fn unexpected(path: &Path, expected: &'static str, found: &Metadata) -> Error {
+    let found = match found {
+        Metadata::MatrixTableSpec(_) => "matrix",
+        Metadata::TableSpec(_) => "table",
+        Metadata::OrderedRVDSpec(_) => "ordered component",
+        Metadata::IndexedRVDSpec(_) => "indexed component",
+        Metadata::UnpartitionedRVDSpec(_) => "unpartitioned component",
+        Metadata::OrderedRVDSpec2(_) => "ordered component (version 2)",
+        Metadata::IndexedRVDSpec2(_) => "indexed component (version 2)",
+    };
+    Error::UnexpectedMetadata {
+        path: path.to_owned(),
+        expected,
+        found,
     }
 }
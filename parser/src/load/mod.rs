@@ -1,13 +1,20 @@
-mod helpers;
+pub(crate) mod helpers;
 
 pub mod compression;
 pub mod metadata;
+pub mod parallel;
+pub mod stream;
 
-use std::path::Path;
+use std::{borrow::Borrow, ops::RangeBounds, path::Path};
 
-use anyhow::Result;
+use crate::{
+    error::Result,
+    parse::{StandardEncoder, UnsignedLEB128Encoder},
+    types::{HailOrdering, PartialComponent, PartialMatrix, PartialTable, Projection},
+    Component, HailValue, HailValueRef, Matrix, Table,
+};
 
-use crate::{Component, Matrix, Table};
+use stream::{ComponentIter, MatrixIter, TableIter};
 
 pub fn component<T: AsRef<Path>>(path: T) -> Result<Component> {
     let path: &Path = path.as_ref();
@@ -25,6 +32,36 @@ pub fn component<T: AsRef<Path>>(path: T) -> Result<Component> {
     Ok(Component { data, metadata })
 }
 
+/// Like [component], but only the selected fields of each row are decoded, while the others are
+/// skipped without being allocated.
+///
+/// Fields are selected by path, so nested fields can be picked out of a struct
+/// (`&["locus", "info.AF"]`, see [Projection::from_paths]).
+/// The metadata of the returned component describes the projected rows
+/// (see [ComponentMetadata::project](crate::types::metadata::ComponentMetadata::project)).
+pub fn component_with_projection<T, S>(path: T, fields: &[S]) -> Result<Component>
+where
+    T: AsRef<Path>,
+    S: AsRef<str>,
+{
+    let path: &Path = path.as_ref();
+
+    let full_metadata = metadata::load_component_metadata(path)?;
+    let projection = Projection::from_paths(fields);
+    let metadata = full_metadata.project(&projection)?;
+    let data_path = path.join("parts");
+
+    let data = helpers::load_projected_component_data(
+        &full_metadata.part_files,
+        &full_metadata.encoded_type,
+        &projection,
+        &full_metadata.buffer_spec,
+        &data_path,
+    )?;
+
+    Ok(Component { data, metadata })
+}
+
 pub fn table<T: AsRef<Path>>(path: T) -> Result<Table> {
     let path: &Path = path.as_ref();
 
@@ -62,3 +99,239 @@ pub fn matrix<T: AsRef<Path>>(path: T) -> Result<Matrix> {
         metadata,
     })
 }
+
+/// Loads only the selected partitions of a component, in the order they are given.
+///
+/// Partitions can be selected by index (`vec![0, 3]`) or by range (`2..5`).
+/// Bare components do not know their partition counts, so the row offsets are not available.
+pub fn component_partitions<T, I>(path: T, partitions: I) -> Result<PartialComponent>
+where
+    T: AsRef<Path>,
+    I: IntoIterator<Item = usize>,
+{
+    let partitions: Vec<usize> = partitions.into_iter().collect();
+    _component_partitions(path.as_ref(), &partitions, None)
+}
+
+/// Like [table], but only the selected row partitions are loaded (see [component_partitions]).
+pub fn table_partitions<T, I>(path: T, partitions: I) -> Result<PartialTable>
+where
+    T: AsRef<Path>,
+    I: IntoIterator<Item = usize>,
+{
+    let path: &Path = path.as_ref();
+    let partitions: Vec<usize> = partitions.into_iter().collect();
+
+    let metadata = metadata::load_table_metadata(path)?;
+
+    let components = &metadata.components;
+    let counts = &components.partition_counts.counts;
+
+    let globals = component(path.join(&components.globals.rel_path))?;
+    let rows = _component_partitions(
+        &path.join(&components.rows.rel_path),
+        &partitions,
+        Some(counts),
+    )?;
+
+    Ok(PartialTable {
+        globals,
+        rows,
+        metadata,
+    })
+}
+
+/// Like [matrix], but only the selected row partitions are loaded (see [component_partitions]).
+/// The same partitions are loaded for both the rows and the entries, while the columns are
+/// always loaded in full.
+pub fn matrix_partitions<T, I>(path: T, partitions: I) -> Result<PartialMatrix>
+where
+    T: AsRef<Path>,
+    I: IntoIterator<Item = usize>,
+{
+    let path: &Path = path.as_ref();
+    let partitions: Vec<usize> = partitions.into_iter().collect();
+
+    let metadata = metadata::load_matrix_metadata(path)?;
+
+    let components = &metadata.components;
+    let counts = &components.partition_counts.counts;
+
+    let globals = component(path.join(&components.globals.rel_path))?;
+    let cols = component(path.join(&components.cols.rel_path))?;
+    let rows = _component_partitions(
+        &path.join(&components.rows.rel_path),
+        &partitions,
+        Some(counts),
+    )?;
+    let entries = _component_partitions(
+        &path.join(&components.entries.rel_path),
+        &partitions,
+        Some(counts),
+    )?;
+
+    Ok(PartialMatrix {
+        globals,
+        cols,
+        rows,
+        entries,
+        metadata,
+    })
+}
+
+/// Like [table_partitions], but only the partitions that might hold keys in the given range are
+/// loaded (see [overlapping_partitions]).
+///
+/// Whole partitions are loaded, so rows outside of the range can still be present.
+///
+/// [overlapping_partitions]: crate::types::metadata::ComponentMetadata::overlapping_partitions
+pub fn table_partitions_overlapping<T, K, R>(path: T, range: R) -> Result<PartialTable>
+where
+    T: AsRef<Path>,
+    K: Borrow<HailValue>,
+    R: RangeBounds<K>,
+{
+    let path: &Path = path.as_ref();
+
+    let metadata = metadata::load_table_metadata(path)?;
+    let rows_metadata =
+        metadata::load_component_metadata(path.join(&metadata.components.rows.rel_path))?;
+    let ordering = HailOrdering::new(metadata.references);
+
+    table_partitions(
+        path,
+        rows_metadata.overlapping_partitions_with(&ordering, range),
+    )
+}
+
+/// Like [matrix_partitions], but only the partitions that might hold keys in the given range are
+/// loaded (see [overlapping_partitions]).
+///
+/// Whole partitions are loaded, so rows outside of the range can still be present.
+///
+/// [overlapping_partitions]: crate::types::metadata::ComponentMetadata::overlapping_partitions
+pub fn matrix_partitions_overlapping<T, K, R>(path: T, range: R) -> Result<PartialMatrix>
+where
+    T: AsRef<Path>,
+    K: Borrow<HailValue>,
+    R: RangeBounds<K>,
+{
+    let path: &Path = path.as_ref();
+
+    let metadata = metadata::load_matrix_metadata(path)?;
+    let rows_metadata =
+        metadata::load_component_metadata(path.join(&metadata.components.rows.rel_path))?;
+    let ordering = HailOrdering::new(metadata.references);
+
+    matrix_partitions(
+        path,
+        rows_metadata.overlapping_partitions_with(&ordering, range),
+    )
+}
+
+fn _component_partitions(
+    path: &Path,
+    partitions: &[usize],
+    partition_counts: Option<&[u32]>,
+) -> Result<PartialComponent> {
+    let metadata = metadata::load_component_metadata(path)?;
+    let data_path = path.join("parts");
+
+    let partitions = helpers::load_partitions(&metadata, partitions, partition_counts, &data_path)?;
+
+    Ok(PartialComponent {
+        partitions,
+        metadata,
+    })
+}
+
+/// Like [component], but the rows are decoded lazily, one at a time, as the iterator is advanced.
+pub fn component_iter<T: AsRef<Path>>(path: T) -> Result<ComponentIter> {
+    let path: &Path = path.as_ref();
+
+    let metadata = metadata::load_component_metadata(path)?;
+    let data_path = path.join("parts");
+
+    Ok(ComponentIter::new(metadata, data_path))
+}
+
+/// Visits the rows of a component in order, as [HailValueRef]s that borrow their strings from the
+/// decoded data instead of allocating them, which makes read-only scans much cheaper.
+///
+/// As with [component_iter], the part files are decoded one block at a time.
+/// Each row only lives for the duration of the call, [HailValueRef::to_owned] can be used to keep
+/// it around. The first error returned by `visit` stops the scan, and is returned as
+/// [Error::Visit](crate::Error::Visit).
+pub fn component_for_each<T, F>(path: T, visit: F) -> Result<()>
+where
+    T: AsRef<Path>,
+    F: FnMut(HailValueRef<'_>) -> Result<(), Box<dyn std::error::Error + Send + Sync>>,
+{
+    let path: &Path = path.as_ref();
+
+    let metadata = metadata::load_component_metadata(path)?;
+    let data_path = path.join("parts");
+
+    match metadata.buffer_spec.uses_leb128() {
+        true => helpers::for_each_row::<UnsignedLEB128Encoder, _>(&metadata, &data_path, visit),
+        false => helpers::for_each_row::<StandardEncoder, _>(&metadata, &data_path, visit),
+    }
+}
+
+/// Counts the rows of each partition of a component, skipping over the rows without decoding
+/// them, which is much faster than loading them.
+///
+/// Tables and matrices already record the number of rows of each partition in their metadata
+/// (see [PartitionCounts](crate::types::metadata::shared::PartitionCounts)), bare components
+/// have to be scanned.
+pub fn component_partition_counts<T: AsRef<Path>>(path: T) -> Result<Vec<u64>> {
+    let path: &Path = path.as_ref();
+
+    let metadata = metadata::load_component_metadata(path)?;
+    let data_path = path.join("parts");
+
+    match metadata.buffer_spec.uses_leb128() {
+        true => helpers::count_rows::<UnsignedLEB128Encoder>(&metadata, &data_path),
+        false => helpers::count_rows::<StandardEncoder>(&metadata, &data_path),
+    }
+}
+
+/// Like [table], but the rows are decoded lazily (see [component_iter]).
+pub fn table_iter<T: AsRef<Path>>(path: T) -> Result<TableIter> {
+    let path: &Path = path.as_ref();
+
+    let metadata = metadata::load_table_metadata(path)?;
+
+    let components = &metadata.components;
+
+    let globals = component(path.join(&components.globals.rel_path))?;
+    let rows = component_iter(path.join(&components.rows.rel_path))?;
+
+    Ok(TableIter {
+        globals,
+        rows,
+        metadata,
+    })
+}
+
+/// Like [matrix], but the columns, rows, and entries are decoded lazily (see [component_iter]).
+pub fn matrix_iter<T: AsRef<Path>>(path: T) -> Result<MatrixIter> {
+    let path: &Path = path.as_ref();
+
+    let metadata = metadata::load_matrix_metadata(path)?;
+
+    let components = &metadata.components;
+
+    let globals = component(path.join(&components.globals.rel_path))?;
+    let cols = component_iter(path.join(&components.cols.rel_path))?;
+    let rows = component_iter(path.join(&components.rows.rel_path))?;
+    let entries = component_iter(path.join(&components.entries.rel_path))?;
+
+    Ok(MatrixIter {
+        globals,
+        cols,
+        rows,
+        entries,
+        metadata,
+    })
+}
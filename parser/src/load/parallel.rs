@@ -0,0 +1,78 @@
+//! Decoding of the partitions of a component, concurrently when the `parallel` feature is enabled.
+//!
+//! With the feature, the part files are read, decompressed, and parsed on a [rayon] thread pool,
+//! while the order of the partitions is preserved.
+//! By default the global pool is used (one thread per core), see [set_threads] to change it.
+
+/// Applies `f` to each partition, keeping the results in the same order.
+///
+/// The error type is left to the caller (so that other crates can use their own).
+/// The bounds are the same with or without the feature, so that enabling it never breaks a build.
+/// If several partitions fail, which of the errors is returned is unspecified when running in
+/// parallel.
+#[cfg(feature = "parallel")]
+pub fn map_partitions<I, T, E, F>(partitions: &[I], f: F) -> Result<Vec<T>, E>
+where
+    I: Sync,
+    T: Send,
+    E: Send,
+    F: Fn(&I) -> Result<T, E> + Sync,
+{
+    use rayon::prelude::*;
+
+    let run = || partitions.par_iter().map(&f).collect::<Result<Vec<T>, E>>();
+    match pool::current() {
+        Some(pool) => pool.install(run),
+        None => run(),
+    }
+}
+
+/// Applies `f` to each partition, keeping the results in the same order.
+#[cfg(not(feature = "parallel"))]
+pub fn map_partitions<I, T, E, F>(partitions: &[I], f: F) -> Result<Vec<T>, E>
+where
+    I: Sync,
+    T: Send,
+    E: Send,
+    F: Fn(&I) -> Result<T, E> + Sync,
+{
+    partitions.iter().map(f).collect()
+}
+
+#[cfg(feature = "parallel")]
+pub use pool::set_threads;
+
+#[cfg(feature = "parallel")]
+mod pool {
+    use std::sync::{Arc, Mutex};
+
+    use rayon::{ThreadPool, ThreadPoolBuilder};
+
+    use crate::error::{Error, Result};
+
+    static POOL: Mutex<Option<Arc<ThreadPool>>> = Mutex::new(None);
+
+    /// Sets the number of threads used to decode partitions, for all the loaders.
+    ///
+    /// `0` goes back to the default: the global [rayon] pool, with one thread per core.
+    pub fn set_threads(threads: usize) -> Result<()> {
+        let pool = match threads {
+            0 => None,
+            _ => Some(Arc::new(
+                ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .thread_name(|index| format!("hail-parser-{}", index))
+                    .build()
+                    .map_err(|error| Error::ThreadPool(error.to_string()))?,
+            )),
+        };
+        *POOL
+            .lock()
+            .map_err(|_| Error::ThreadPool("the thread pool lock is poisoned".to_owned()))? = pool;
+        Ok(())
+    }
+
+    pub(super) fn current() -> Option<Arc<ThreadPool>> {
+        POOL.lock().ok().and_then(|pool| pool.clone())
+    }
+}
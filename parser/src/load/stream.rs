@@ -0,0 +1,419 @@
+use std::{
+    fs::File,
+    io::{BufReader, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use nom::IResult;
+
+use crate::{
+    error::{DecodeError, DecodeErrorKind, Error, Result},
+    parse::{data::locate_failure, parse_next_row, StandardEncoder, UnsignedLEB128Encoder},
+    types::{
+        metadata::{shared::BufferSpec, ComponentMetadata, MatrixMetadata, TableMetadata},
+        EType, HailValue, Projection,
+    },
+    Component,
+};
+
+use super::compression::BlockReader;
+
+/// A function that decodes the next row (or the end-of-partition marker) from the input.
+/// Picking it once, based on the [BufferSpec](crate::types::metadata::shared::BufferSpec),
+/// keeps the [Encoding](crate::parse::Encoding) monomorphised without leaking the generic into the iterator type.
+pub(crate) type RowDecoder = for<'i> fn(&'i [u8], &EType) -> IResult<&'i [u8], Option<HailValue>>;
+
+/// A streaming version of [Component] which decodes one row at a time.
+///
+/// Part files are read and decompressed one block at a time, so memory usage is bounded by the
+/// block size (and the size of a single row) instead of the size of the component.
+/// Rows are yielded in order, partition after partition.
+pub struct ComponentIter {
+    pub metadata: ComponentMetadata,
+    data_path: PathBuf,
+    decode_row: RowDecoder,
+    /// The index of the next part file to open.
+    next_part: usize,
+    current_part: Option<PartDecoder>,
+    /// Once an error has been returned the iterator is exhausted.
+    failed: bool,
+}
+
+/// A streaming version of [Table](crate::Table), the (small) globals are loaded eagerly.
+pub struct TableIter {
+    pub globals: Component,
+    pub rows: ComponentIter,
+    pub metadata: TableMetadata,
+}
+
+/// A streaming version of [Matrix](crate::Matrix), the (small) globals are loaded eagerly.
+pub struct MatrixIter {
+    pub globals: Component,
+    pub cols: ComponentIter,
+    pub rows: ComponentIter,
+    pub entries: ComponentIter,
+    pub metadata: MatrixMetadata,
+}
+
+/// Picks the [RowDecoder] matching the encoding of the buffer spec.
+pub(crate) fn row_decoder(buffer_spec: &BufferSpec) -> RowDecoder {
+    match buffer_spec.uses_leb128() {
+        true => parse_next_row::<UnsignedLEB128Encoder>,
+        false => parse_next_row::<StandardEncoder>,
+    }
+}
+
+/// Splits an offset into the position of its block in the file and its position within the
+/// (decompressed) block.
+///
+/// For blocked buffer specs, offsets are 'virtual' offsets as written by Hail: the file position
+/// of the block in the upper 48 bits, and the position within the block in the lower 16 bits.
+/// Otherwise, they are simply positions in the file.
+pub(crate) fn split_offset(buffer_spec: &BufferSpec, offset: u64) -> (u64, usize) {
+    match buffer_spec.uses_compression() || buffer_spec.appends_length() {
+        true => (offset >> 16, (offset & 0xFFFF) as usize),
+        false => (offset, 0),
+    }
+}
+
+impl ComponentIter {
+    pub(crate) fn new(metadata: ComponentMetadata, data_path: PathBuf) -> Self {
+        let decode_row = row_decoder(&metadata.buffer_spec);
+
+        ComponentIter {
+            metadata,
+            data_path,
+            decode_row,
+            next_part: 0,
+            current_part: None,
+            failed: false,
+        }
+    }
+
+    fn next_row(&mut self) -> Result<Option<HailValue>> {
+        loop {
+            if let Some(part) = &mut self.current_part {
+                match part.next_row(self.decode_row, &self.metadata.encoded_type)? {
+                    Some(row) => return Ok(Some(row)),
+                    None => self.current_part = None,
+                }
+            }
+
+            let part_name = match self.metadata.part_files.get(self.next_part) {
+                Some(part_name) => part_name,
+                None => return Ok(None),
+            };
+            self.next_part += 1;
+
+            self.current_part = Some(PartDecoder::open(
+                &self.data_path.join(part_name),
+                &self.metadata.buffer_spec,
+                0,
+                0,
+            )?);
+        }
+    }
+}
+
+impl Iterator for ComponentIter {
+    type Item = Result<HailValue>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        match self.next_row() {
+            Ok(row) => row.map(Ok),
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Decodes the rows of a part file one at a time with `decode`, which returns the number of bytes
+/// it consumed along with the row, or `None` at the end-of-partition marker.
+///
+/// The file is read and decompressed one block at a time into a reused buffer, so only the blocks
+/// holding the row being decoded are kept in memory, on top of the decoded rows. When the buffer
+/// ends in the middle of a row, `decode` should fail with [nom::Err::Incomplete]: the next blocks
+/// are then appended to the buffer and the row is decoded again from its start. Any other error
+/// is returned as it is.
+pub fn decode_part_rows<T, E, F>(
+    path: &Path,
+    buffer_spec: &BufferSpec,
+    mut decode: F,
+) -> std::result::Result<Vec<T>, E>
+where
+    E: From<Error>,
+    F: FnMut(&[u8]) -> std::result::Result<(usize, Option<T>), nom::Err<E>>,
+{
+    let mut decoder = PartDecoder::open(path, buffer_spec, 0, 0)?;
+    let mut rows = Vec::new();
+    while let Some(row) = decoder.try_decode(&mut decode)? {
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Why [PartDecoder::decode_with] failed: invalid data, to be located in the file, or an error
+/// reading the file.
+enum Invalid {
+    Data(DecodeErrorKind),
+    File(Error),
+}
+
+impl From<Error> for Invalid {
+    fn from(error: Error) -> Self {
+        Invalid::File(error)
+    }
+}
+
+/// Decodes the contents of a single (part or index) file, keeping only the undecoded bytes in
+/// memory.
+pub(crate) struct PartDecoder {
+    path: PathBuf,
+    uses_leb128: bool,
+    blocks: BlockReader<BufReader<File>>,
+    /// The size of the file from where decoding started, to tell when a value cannot fit in the
+    /// rest of the file.
+    file_size: u64,
+    buffer: Vec<u8>,
+    /// The position of the first byte in the buffer that has not been decoded yet.
+    cursor: usize,
+    /// The number of decoded bytes that have been discarded from the buffer.
+    discarded: u64,
+    /// The number of rows decoded so far, see [PartDecoder::decode_row].
+    rows: u64,
+}
+
+impl PartDecoder {
+    /// Opens a file to decode its contents starting from the block at the given file position,
+    /// skipping the first `block_position` bytes of the block (see [split_offset]).
+    pub(crate) fn open(
+        path: &Path,
+        buffer_spec: &BufferSpec,
+        file_position: u64,
+        block_position: usize,
+    ) -> Result<Self> {
+        Self::_open(path, buffer_spec, file_position, block_position)
+            .map_err(|error| error.in_file(path))
+    }
+
+    fn _open(
+        path: &Path,
+        buffer_spec: &BufferSpec,
+        file_position: u64,
+        block_position: usize,
+    ) -> Result<Self> {
+        let mut file = File::open(path)?;
+        let file_size = file.metadata()?.len().saturating_sub(file_position);
+        file.seek(SeekFrom::Start(file_position))?;
+
+        let mut decoder = PartDecoder {
+            path: path.to_owned(),
+            uses_leb128: buffer_spec.uses_leb128(),
+            blocks: BlockReader::new(
+                BufReader::new(file),
+                buffer_spec.uses_compression(),
+                buffer_spec.appends_length(),
+            ),
+            file_size,
+            buffer: vec![],
+            cursor: 0,
+            discarded: 0,
+            rows: 0,
+        };
+
+        if block_position > 0 {
+            decoder.blocks.read_block_into(&mut decoder.buffer)?;
+            if block_position > decoder.buffer.len() {
+                return Err(Error::block(format!(
+                    "position {} is past the end of its block ({} bytes)",
+                    block_position,
+                    decoder.buffer.len()
+                )));
+            }
+            decoder.cursor = block_position;
+        }
+
+        Ok(decoder)
+    }
+
+    /// Decodes the next row, or returns `None` at the end of the file.
+    pub(crate) fn next_row(
+        &mut self,
+        decode_row: RowDecoder,
+        row_type: &EType,
+    ) -> Result<Option<HailValue>> {
+        self.decode_row(row_type, &Projection::All, |i| decode_row(i, row_type))
+    }
+
+    /// Decodes the next row (or the end-of-partition marker), counting the rows so that errors
+    /// can point to the value that could not be decoded (see [locate_failure]).
+    pub(crate) fn decode_row<T, F>(
+        &mut self,
+        row_type: &EType,
+        projection: &Projection,
+        decode: F,
+    ) -> Result<Option<T>>
+    where
+        F: for<'i> Fn(&'i [u8]) -> IResult<&'i [u8], Option<T>>,
+    {
+        self.decode_row_with(row_type, projection, |input| match decode(input) {
+            Ok((rest, value)) => Ok((input.len() - rest.len(), value)),
+            Err(e) => Err(e.map(|e| e.code)),
+        })
+    }
+
+    /// Like [PartDecoder::decode_row], but the row can borrow from the buffer (see
+    /// [PartDecoder::decode_with]).
+    pub(crate) fn decode_row_with<T, F>(
+        &mut self,
+        row_type: &EType,
+        projection: &Projection,
+        decode: F,
+    ) -> Result<Option<T>>
+    where
+        F: FnMut(&[u8]) -> Result<(usize, Option<T>), nom::Err<nom::error::ErrorKind>>,
+    {
+        match self.decode_with(decode) {
+            Ok(Some(row)) => {
+                self.rows += 1;
+                Ok(Some(row))
+            }
+            Ok(None) => Ok(None),
+            Err(Error::Decode(mut error)) => {
+                let input = &self.buffer[self.cursor..];
+                let location = match self.uses_leb128 {
+                    true => locate_failure::<UnsignedLEB128Encoder>(input, row_type, projection),
+                    false => locate_failure::<StandardEncoder>(input, row_type, projection),
+                };
+                match location {
+                    Some(location) => error.locate(location),
+                    None => error.expected = Some(row_type.clone()),
+                }
+                Err(Error::Decode(error))
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Values can straddle block boundaries, so when decoding fails for lack of input
+    /// ([nom::Err::Incomplete]) the next block is appended to the buffer and decoding is attempted
+    /// again (with at least twice as much input each time). Any other error is returned at once.
+    pub(crate) fn decode<T, F>(&mut self, decode: F) -> Result<T>
+    where
+        F: for<'i> Fn(&'i [u8]) -> IResult<&'i [u8], T>,
+    {
+        self.decode_with(|input| match decode(input) {
+            Ok((rest, value)) => Ok((input.len() - rest.len(), value)),
+            Err(e) => Err(e.map(|e| e.code)),
+        })
+    }
+
+    /// Like [PartDecoder::decode], but the decoded value can borrow from the buffer as long as it
+    /// does not escape `decode`, which returns the number of bytes it consumed along with its
+    /// result.
+    ///
+    /// As decoding can be attempted more than once, side effects should only happen once a value
+    /// has been decoded successfully.
+    ///
+    /// Errors only point to the start of the value, see [PartDecoder::decode_row_with] for rows.
+    pub(crate) fn decode_with<T, F>(&mut self, mut decode: F) -> Result<T>
+    where
+        F: FnMut(&[u8]) -> Result<(usize, T), nom::Err<nom::error::ErrorKind>>,
+    {
+        let decoded = self.try_decode(|input| {
+            decode(input).map_err(|e| e.map(|code| Invalid::Data(DecodeErrorKind::from_code(code))))
+        });
+        decoded.map_err(|invalid| match invalid {
+            Invalid::Data(kind) => self.error(kind),
+            Invalid::File(error) => error,
+        })
+    }
+
+    /// Like [PartDecoder::decode_with], but the errors of `decode` other than a lack of input are
+    /// returned as they are, whatever their type.
+    pub(crate) fn try_decode<T, E, F>(&mut self, mut decode: F) -> std::result::Result<T, E>
+    where
+        E: From<Error>,
+        F: FnMut(&[u8]) -> std::result::Result<(usize, T), nom::Err<E>>,
+    {
+        loop {
+            let input = &self.buffer[self.cursor..];
+
+            let needed = match decode(input) {
+                Ok((consumed, value)) => {
+                    self.cursor += consumed;
+                    return Ok(value);
+                }
+                // Only a lack of input is worth retrying, invalid data stays invalid however much
+                // of the file is read.
+                Err(nom::Err::Incomplete(needed)) => needed,
+                Err(nom::Err::Error(error)) | Err(nom::Err::Failure(error)) => return Err(error),
+            };
+            self.read_more(needed)?;
+        }
+    }
+
+    /// Reads enough blocks for the buffer to hold `needed` more bytes, and at least twice as many
+    /// as it holds now.
+    fn read_more(&mut self, needed: nom::Needed) -> Result<()> {
+        // Neither is a value longer than the rest of the file can hold (a corrupted length, for
+        // example) worth retrying.
+        if let nom::Needed::Size(needed) = needed {
+            if needed.get() as u64 > self.max_data_left() {
+                return Err(self.error(DecodeErrorKind::UnexpectedEnd));
+            }
+        }
+
+        // Discard the bytes that have already been decoded before growing the buffer, the
+        // allocation is reused from one block to the next.
+        self.buffer.drain(..self.cursor);
+        self.discarded += self.cursor as u64;
+        self.cursor = 0;
+
+        // Decoding starts over from the start of the value, so the input is (at least) doubled
+        // before each attempt, for values spanning many blocks to be decoded in linear time.
+        let available = self.buffer.len();
+        let needed = match needed {
+            nom::Needed::Size(size) => size.get(),
+            nom::Needed::Unknown => 1,
+        };
+        let target = available.saturating_add(needed.max(available));
+        let mut has_block = false;
+        while self.buffer.len() < target
+            && self
+                .blocks
+                .read_block_into(&mut self.buffer)
+                .map_err(|error| error.in_file(&self.path))?
+        {
+            has_block = true;
+        }
+        match has_block {
+            true => Ok(()),
+            false => Err(self.error(DecodeErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    /// The most data that the rest of the file can hold, on top of the buffer.
+    fn max_data_left(&self) -> u64 {
+        let remaining = self.file_size.saturating_sub(self.blocks.consumed());
+        self.blocks.max_data_size(remaining)
+    }
+
+    /// An error for the value at the cursor.
+    fn error(&self, kind: DecodeErrorKind) -> Error {
+        Error::from(DecodeError {
+            part_file: Some(self.path.clone()),
+            row: self.rows,
+            offset: self.discarded + self.cursor as u64,
+            field: String::new(),
+            expected: None,
+            kind,
+        })
+    }
+}
@@ -64,6 +64,12 @@ impl EType {
                 virtual_hint: Some(VirtualHint::String),
                 ..
             } => parse::string::<E>(i),
+            // [Binary]
+            EType {
+                shape: ETypeShape::Binary,
+                virtual_hint: None,
+                ..
+            } => parse::binary::<E>(i),
 
             // [Float32]
             EType {
@@ -84,7 +90,7 @@ impl EType {
                 shape: ETypeShape::Int32,
                 virtual_hint: None,
                 ..
-            } => parse::u32::<E>(i),
+            } => parse::i32::<E>(i),
 
             // [Int64]
             EType {
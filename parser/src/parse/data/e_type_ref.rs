@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+
+use nom::IResult;
+
+use ndarray::{ArrayD, IxDyn, ShapeBuilder};
+
+use crate::types::{encoding::VirtualHint, EType, ETypeShape, HailValueRef};
+
+use super::{encoders::Encoding, helpers};
+
+impl EType {
+    /// Like [EType::decode_from], but the value borrows its strings from the input (and its field
+    /// names from the schema) instead of allocating them, see [HailValueRef].
+    ///
+    /// The layout of the data is the same, the comments in the [parse](super::parse) module
+    /// describe how each value is encoded.
+    pub fn decode_ref_from<'a, E: Encoding>(
+        &'a self,
+        i: &'a [u8],
+    ) -> IResult<&'a [u8], HailValueRef<'a>> {
+        match (&self.shape, &self.virtual_hint) {
+            (ETypeShape::BaseStruct(mapping), None) => {
+                let (rest, fields) = fields::<E>(i, mapping)?;
+                Ok((rest, HailValueRef::Struct(fields.into_iter().collect())))
+            }
+            (ETypeShape::BaseStruct(_), Some(VirtualHint::Locus(reference))) => {
+                let (rest, contig) = E::str(i)?;
+                let (rest, position) = E::u32(rest)?;
+                let locus = HailValueRef::Locus {
+                    contig,
+                    position,
+                    reference,
+                };
+                Ok((rest, locus))
+            }
+            (ETypeShape::BaseStruct(mapping), Some(VirtualHint::Interval)) => {
+                let (rest, fields) = fields::<E>(i, mapping)?;
+                let mut fields: BTreeMap<_, _> = fields.into_iter().collect();
+                let mut take = |name| fields.remove(name);
+                match (
+                    take("start"),
+                    take("end"),
+                    take("includesStart"),
+                    take("includesEnd"),
+                ) {
+                    (
+                        Some(start),
+                        Some(end),
+                        Some(HailValueRef::Boolean(includes_start)),
+                        Some(HailValueRef::Boolean(includes_end)),
+                    ) => Ok((
+                        rest,
+                        HailValueRef::Interval {
+                            start: Box::new(start),
+                            end: Box::new(end),
+                            includes_start,
+                            includes_end,
+                        },
+                    )),
+                    _ => Err(failure(i, nom::error::ErrorKind::NoneOf)),
+                }
+            }
+            (ETypeShape::BaseStruct(mapping), Some(VirtualHint::Tuple)) => {
+                let (rest, fields) = fields::<E>(i, mapping)?;
+                let values = fields.into_iter().map(|(_, value)| value).collect();
+                Ok((rest, HailValueRef::Tuple(values)))
+            }
+
+            (ETypeShape::Array(inner_type), None) => {
+                let (rest, len) = E::u32(i)?;
+                let (rest, values) = sequence::<E>(rest, inner_type, len as usize)?;
+                Ok((rest, HailValueRef::Array(values)))
+            }
+            (ETypeShape::Array(inner_type), Some(VirtualHint::Set)) => {
+                let (rest, len) = E::u32(i)?;
+                let (rest, values) = sequence::<E>(rest, inner_type, len as usize)?;
+                Ok((rest, HailValueRef::Set(values)))
+            }
+            (ETypeShape::Array(inner_type), Some(VirtualHint::Dict)) => {
+                let (rest, len) = E::u32(i)?;
+                let (rest, values) = sequence::<E>(rest, inner_type, len as usize)?;
+                let entries = values
+                    .into_iter()
+                    .map(|entry| match entry {
+                        HailValueRef::Struct(mut fields) => {
+                            match (fields.remove("key"), fields.remove("value")) {
+                                (Some(key), Some(value)) => Ok((key, value)),
+                                _ => Err(failure(i, nom::error::ErrorKind::NoneOf)),
+                            }
+                        }
+                        _ => Err(failure(i, nom::error::ErrorKind::ParseTo)),
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok((rest, HailValueRef::Dict(entries)))
+            }
+
+            (ETypeShape::NdArrayColumnMajor(inner_type, n), None) => {
+                let (mut rest, (dims, number_of_elements)) = helpers::ndarray_shape::<E>(i, *n)?;
+
+                // As when decoding owned values, the elements are assumed to be required.
+                helpers::check_length(rest, inner_type, number_of_elements)?;
+                let mut elements = Vec::new();
+                for _ in 0..number_of_elements {
+                    let (inner_rest, element) = inner_type.decode_ref_from::<E>(rest)?;
+                    rest = inner_rest;
+                    elements.push(element);
+                }
+
+                match ArrayD::from_shape_vec(IxDyn(&dims).f(), elements) {
+                    Ok(array) => Ok((rest, HailValueRef::NDArray(array))),
+                    Err(_) => Err(failure(i, nom::error::ErrorKind::ParseTo)),
+                }
+            }
+
+            (ETypeShape::Binary, Some(VirtualHint::String)) => {
+                let (rest, string) = E::str(i)?;
+                Ok((rest, HailValueRef::String(string)))
+            }
+            (ETypeShape::Binary, None) => {
+                let (rest, bytes) = E::bytes(i)?;
+                Ok((rest, HailValueRef::Binary(bytes)))
+            }
+            (ETypeShape::Float32, None) => {
+                let (rest, float) = E::f32(i)?;
+                match float.is_nan() {
+                    true => Ok((rest, HailValueRef::Missing)),
+                    false => Ok((rest, HailValueRef::Float32(float))),
+                }
+            }
+            (ETypeShape::Float64, None) => {
+                let (rest, float) = E::f64(i)?;
+                match float.is_nan() {
+                    true => Ok((rest, HailValueRef::Missing)),
+                    false => Ok((rest, HailValueRef::Float64(float))),
+                }
+            }
+            (ETypeShape::Int32, None) => {
+                let (rest, int) = E::i32(i)?;
+                Ok((rest, HailValueRef::Int32(int)))
+            }
+            (ETypeShape::Int32, Some(VirtualHint::Call)) => {
+                let (rest, call) = E::u32(i)?;
+                Ok((rest, HailValueRef::Call(call)))
+            }
+            (ETypeShape::Int64, None) => {
+                let (rest, int) = E::i64(i)?;
+                Ok((rest, HailValueRef::Int64(int)))
+            }
+            (ETypeShape::Boolean, None) => {
+                let (rest, flag) = E::bool(i)?;
+                Ok((rest, HailValueRef::Boolean(flag)))
+            }
+
+            // Anything else
+            _ => Err(failure(i, nom::error::ErrorKind::ParseTo)),
+        }
+    }
+}
+
+/// Decodes the fields of a struct, in order, preceded by the presence flags of the optional ones.
+fn fields<'a, E: Encoding>(
+    i: &'a [u8],
+    mapping: &'a [(String, EType)],
+) -> IResult<&'a [u8], Vec<(&'a str, HailValueRef<'a>)>> {
+    let number_of_optional_fields = mapping.iter().filter(|(_, t)| !t.required).count();
+    let (mut rest, are_present) = helpers::presence_array(i, number_of_optional_fields)?;
+    let mut presence_iter = are_present.into_iter();
+
+    let mut output = Vec::with_capacity(mapping.len());
+
+    for (field_name, field_type) in mapping {
+        if !field_type.required {
+            match presence_iter.next() {
+                Some(false) => {
+                    output.push((field_name.as_str(), HailValueRef::Missing));
+                    continue;
+                }
+                None => return Err(failure(i, nom::error::ErrorKind::TagBits)),
+                Some(true) => {} // pass: decode the value ↓
+            }
+        }
+        let (inner_rest, decoded) = field_type.decode_ref_from::<E>(rest)?;
+        rest = inner_rest;
+        output.push((field_name.as_str(), decoded));
+    }
+
+    Ok((rest, output))
+}
+
+/// Decodes `len` elements, preceded by their presence flags if they are optional.
+fn sequence<'a, E: Encoding>(
+    i: &'a [u8],
+    inner_type: &'a EType,
+    len: usize,
+) -> IResult<&'a [u8], Vec<HailValueRef<'a>>> {
+    let (mut rest, are_present) = match inner_type.required {
+        true => {
+            helpers::check_length(i, inner_type, len)?;
+            (i, vec![])
+        }
+        false => helpers::presence_array(i, len)?,
+    };
+
+    let mut output = Vec::new();
+    for index in 0..len {
+        // Required elements have no presence flags, they are all present.
+        if are_present.get(index) == Some(&false) {
+            output.push(HailValueRef::Missing);
+            continue;
+        }
+        let (inner_rest, decoded) = inner_type.decode_ref_from::<E>(rest)?;
+        rest = inner_rest;
+        output.push(decoded);
+    }
+
+    Ok((rest, output))
+}
+
+fn failure(i: &[u8], kind: nom::error::ErrorKind) -> nom::Err<nom::error::Error<&[u8]>> {
+    nom::Err::Failure(nom::error::Error::new(i, kind))
+}
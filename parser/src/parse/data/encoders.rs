@@ -1,14 +1,19 @@
-use nom::bytes::complete as bytes;
+use nom::bytes::streaming as bytes;
 use nom::{named, take, IResult};
 
 /// This trait defines the physical encodying of the primitive data types
 /// (numbers, booleans, and string).
+/// Each primitive can be both decoded (from a slice) and encoded (appended to a buffer).
 ///
 /// The trait exists to allow for different encodings (like using LEB128 instead of u32) to be
 /// monomorphised at compile time.
 /// By default all numbers use their standard little endian encoding,
 /// booleans are encoded as a byte (`0u8`/`1u8`), and strings as UTF-8 byte arrays with length
 /// prepended (a u32).
+///
+/// Running out of input is reported as [nom::Err::Incomplete], so that part files decoded one
+/// block at a time only read the next block when a value is cut short, and fail right away on
+/// invalid data.
 pub trait Encoding {
     fn u8(i: &[u8]) -> IResult<&[u8], u8> {
         let (rest, value) = take1(i)?;
@@ -16,27 +21,27 @@ pub trait Encoding {
     }
 
     fn u32(i: &[u8]) -> IResult<&[u8], u32> {
-        nom::number::complete::le_u32::<&[u8], nom::error::Error<&[u8]>>(i)
+        nom::number::streaming::le_u32::<&[u8], nom::error::Error<&[u8]>>(i)
     }
 
     fn u64(i: &[u8]) -> IResult<&[u8], u64> {
-        nom::number::complete::le_u64::<&[u8], nom::error::Error<&[u8]>>(i)
+        nom::number::streaming::le_u64::<&[u8], nom::error::Error<&[u8]>>(i)
     }
 
     fn i32(i: &[u8]) -> IResult<&[u8], i32> {
-        nom::number::complete::le_i32::<&[u8], nom::error::Error<&[u8]>>(i)
+        nom::number::streaming::le_i32::<&[u8], nom::error::Error<&[u8]>>(i)
     }
 
     fn i64(i: &[u8]) -> IResult<&[u8], i64> {
-        nom::number::complete::le_i64::<&[u8], nom::error::Error<&[u8]>>(i)
+        nom::number::streaming::le_i64::<&[u8], nom::error::Error<&[u8]>>(i)
     }
 
     fn f32(i: &[u8]) -> IResult<&[u8], f32> {
-        nom::number::complete::le_f32::<&[u8], nom::error::Error<&[u8]>>(i)
+        nom::number::streaming::le_f32::<&[u8], nom::error::Error<&[u8]>>(i)
     }
 
     fn f64(i: &[u8]) -> IResult<&[u8], f64> {
-        nom::number::complete::le_f64::<&[u8], nom::error::Error<&[u8]>>(i)
+        nom::number::streaming::le_f64::<&[u8], nom::error::Error<&[u8]>>(i)
     }
 
     fn bool(i: &[u8]) -> IResult<&[u8], bool> {
@@ -85,6 +90,43 @@ pub trait Encoding {
             ))),
         }
     }
+    fn write_u8(out: &mut Vec<u8>, value: u8) {
+        out.push(value);
+    }
+
+    fn write_u32(out: &mut Vec<u8>, value: u32) {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_u64(out: &mut Vec<u8>, value: u64) {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_i32(out: &mut Vec<u8>, value: i32) {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_i64(out: &mut Vec<u8>, value: i64) {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_f32(out: &mut Vec<u8>, value: f32) {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_f64(out: &mut Vec<u8>, value: f64) {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_bool(out: &mut Vec<u8>, value: bool) {
+        out.push(value as u8);
+    }
+
+    /// Bytes (and strings) are prefixed with their length.
+    fn write_bytes(out: &mut Vec<u8>, value: &[u8]) {
+        Self::write_u32(out, value.len() as u32);
+        out.extend_from_slice(value);
+    }
 }
 
 pub struct StandardEncoder;
@@ -100,12 +142,49 @@ impl Encoding for UnsignedLEB128Encoder {
         nom_leb128::leb128_u64::<&[u8], nom::error::Error<&[u8]>>(i)
     }
 
+    /// Hail writes the bits of signed integers as unsigned LEB128, not as signed LEB128: its
+    /// `LEB128OutputBuffer` writes the two's complement bits of an int without sign extension, so
+    /// -1 takes five bytes ending with `0x0f`. Decoding them as signed LEB128 reads positive
+    /// values wrongly whenever the last byte has its `0x40` bit set (64 is the single byte `0x40`,
+    /// which is -64 in signed LEB128).
     fn i32(i: &[u8]) -> IResult<&[u8], i32> {
-        nom_leb128::leb128_i32::<&[u8], nom::error::Error<&[u8]>>(i)
+        let (rest, value) = Self::u32(i)?;
+        Ok((rest, value as i32))
     }
 
+    /// See [UnsignedLEB128Encoder::i32].
     fn i64(i: &[u8]) -> IResult<&[u8], i64> {
-        nom_leb128::leb128_i64::<&[u8], nom::error::Error<&[u8]>>(i)
+        let (rest, value) = Self::u64(i)?;
+        Ok((rest, value as i64))
+    }
+
+    fn write_u32(out: &mut Vec<u8>, value: u32) {
+        write_leb128(out, value.into());
+    }
+
+    fn write_u64(out: &mut Vec<u8>, value: u64) {
+        write_leb128(out, value);
+    }
+
+    fn write_i32(out: &mut Vec<u8>, value: i32) {
+        write_leb128(out, (value as u32).into());
+    }
+
+    fn write_i64(out: &mut Vec<u8>, value: i64) {
+        write_leb128(out, value as u64);
+    }
+}
+
+/// Seven bits at a time, least significant first, with the high bit set on all but the last byte.
+fn write_leb128(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
     }
 }
 
@@ -1,11 +1,17 @@
-use nom::bytes::complete as bytes;
+use std::convert::TryFrom;
+
+use nom::bytes::streaming as bytes;
 use nom::IResult;
 
 use crate::{
     parse::Encoding,
-    types::{EType, HailValue},
+    types::{EType, ETypeShape, HailValue},
 };
 
+/// The number of elements allowed for arrays (and ndarrays) of values that take no space at all,
+/// like empty structs, whose length cannot be checked against the size of the data.
+pub const MAX_EMPTY_ELEMENTS: usize = 1 << 20;
+
 pub(crate) fn sequence<'i, E: Encoding>(
     i: &'i [u8],
     inner_type: &EType,
@@ -62,14 +68,15 @@ fn sequence_without_check<'i, E: Encoding>(
 ) -> IResult<&'i [u8], Vec<HailValue>> {
     let (rest, len) = E::u32(i)?;
 
-    sequence_with_given_length_without_check::<E>(rest, inner_type, len.into())
+    sequence_with_given_length_without_check::<E>(rest, inner_type, len as usize)
 }
 
 pub(crate) fn sequence_with_given_length_without_check<'i, E: Encoding>(
     i: &'i [u8],
     inner_type: &EType,
-    len: i64,
+    len: usize,
 ) -> IResult<&'i [u8], Vec<HailValue>> {
+    check_length(i, inner_type, len)?;
     let mut rest = i;
 
     let mut result: Vec<HailValue> = Vec::new();
@@ -83,6 +90,77 @@ pub(crate) fn sequence_with_given_length_without_check<'i, E: Encoding>(
     Ok((rest, result))
 }
 
+/// Reads the dimensions of an ndarray, returning them along with the number of elements.
+///
+/// Negative dimensions, and shapes with more elements than fit in a `usize`, are refused.
+pub(crate) fn ndarray_shape<E: Encoding>(i: &[u8], n: u32) -> IResult<&[u8], (Vec<usize>, usize)> {
+    let mut dims = Vec::new();
+    let mut number_of_elements = 1usize;
+    let mut rest = i;
+    for _ in 0..n {
+        let (inner_rest, d) = E::i64(rest)?;
+        let d = usize::try_from(d).map_err(|_| too_large(rest))?;
+        number_of_elements = number_of_elements
+            .checked_mul(d)
+            .ok_or_else(|| too_large(rest))?;
+        rest = inner_rest;
+        dims.push(d);
+    }
+    Ok((rest, (dims, number_of_elements)))
+}
+
+/// Checks that the data can hold `len` present values of a type, before decoding (and
+/// allocating) anything for them.
+///
+/// Lengths are read from the data itself, so a corrupted length would otherwise make decoding
+/// allocate (or loop) far beyond the size of the data.
+pub fn check_length<'i>(
+    i: &'i [u8],
+    e_type: &EType,
+    len: usize,
+) -> Result<(), nom::Err<nom::error::Error<&'i [u8]>>> {
+    match min_encoded_size(e_type) {
+        0 if len > MAX_EMPTY_ELEMENTS => Err(too_large(i)),
+        0 => Ok(()),
+        // Not a failure, as the rest of the value may only be missing from the input so far (when
+        // streaming a part file one block at a time).
+        size if len > i.len() / size => Err(nom::Err::Incomplete(nom::Needed::new(
+            len.saturating_mul(size) - i.len(),
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// The smallest number of bytes a present value of the type can take, with any encoding.
+fn min_encoded_size(e_type: &EType) -> usize {
+    match &e_type.shape {
+        ETypeShape::BaseStruct(fields) => {
+            let optional = fields.iter().filter(|(_, t)| !t.required).count();
+            let required: usize = fields
+                .iter()
+                .filter(|(_, t)| t.required)
+                .map(|(_, t)| min_encoded_size(t))
+                .sum();
+            optional.div_ceil(8) + required
+        }
+        // Every dimension takes at least a byte, a zero dimensional ndarray holds a single value.
+        ETypeShape::NdArrayColumnMajor(_, n) if *n > 0 => *n as usize,
+        ETypeShape::NdArrayColumnMajor(element, _) => min_encoded_size(element),
+        ETypeShape::Float32 => 4,
+        ETypeShape::Float64 => 8,
+        // Lengths and integers take at least a byte.
+        ETypeShape::Array(_)
+        | ETypeShape::Binary
+        | ETypeShape::Int32
+        | ETypeShape::Int64
+        | ETypeShape::Boolean => 1,
+    }
+}
+
+fn too_large(i: &[u8]) -> nom::Err<nom::error::Error<&[u8]>> {
+    nom::Err::Failure(nom::error::Error::new(i, nom::error::ErrorKind::TooLarge))
+}
+
 /// A presence array is represented as an array of bytes, long enough to have a bit for each
 /// field considered.
 ///
@@ -127,3 +205,23 @@ pub fn presence_array(i: &[u8], field_count: usize) -> IResult<&[u8], Vec<bool>>
 
     Ok((rest, are_present))
 }
+
+/// The inverse of [presence_array]: one bit per value, set when the value is missing.
+pub fn write_presence_array(out: &mut Vec<u8>, presence: impl Iterator<Item = bool>) {
+    let mut byte = 0u8;
+    let mut bit = 0;
+    for is_present in presence {
+        if !is_present {
+            byte |= 1 << bit;
+        }
+        bit += 1;
+        if bit == 8 {
+            out.push(byte);
+            byte = 0;
+            bit = 0;
+        }
+    }
+    if bit > 0 {
+        out.push(byte);
+    }
+}
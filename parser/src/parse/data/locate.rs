@@ -0,0 +1,200 @@
+use crate::{
+    error::{DecodeError, DecodeErrorKind},
+    types::{encoding::VirtualHint, EType, ETypeShape, Projection},
+};
+
+use super::{encoders::Encoding, helpers};
+
+/// Where decoding a row failed, relative to the start of the row (its marker).
+pub(crate) struct Location {
+    /// The path to the value, see [DecodeError::field].
+    pub field: String,
+    pub expected: EType,
+    pub offset: usize,
+    pub kind: DecodeErrorKind,
+}
+
+/// Decodes a row again (starting from its marker), keeping track of the path to each value, to
+/// find the value that cannot be decoded.
+///
+/// This is only done once decoding has failed, so that decoding itself does not pay for it.
+/// `None` is returned if the row can be decoded after all.
+pub(crate) fn locate_failure<E: Encoding>(
+    i: &[u8],
+    row_type: &EType,
+    projection: &Projection,
+) -> Option<Location> {
+    let marker = EType {
+        shape: ETypeShape::Boolean,
+        required: true,
+        virtual_hint: None,
+    };
+    let failure = match E::bool(i) {
+        Ok((rest, true)) => walk::<E>(rest, row_type, projection, &mut String::new()).err()?,
+        Ok((_, false)) => return None,
+        Err(error) => Failure::new(i, error, &marker, ""),
+    };
+
+    Some(Location {
+        field: failure.field,
+        expected: failure.expected,
+        offset: i.len() - failure.remaining,
+        kind: failure.kind,
+    })
+}
+
+/// The error for a row that cannot be decoded, `i` starting at the marker of the row.
+pub(crate) fn row_error<E: Encoding>(
+    i: &[u8],
+    row: u64,
+    row_type: &EType,
+    projection: &Projection,
+    kind: DecodeErrorKind,
+) -> DecodeError {
+    let mut error = DecodeError {
+        part_file: None,
+        row,
+        offset: 0,
+        field: String::new(),
+        expected: Some(row_type.clone()),
+        kind,
+    };
+    if let Some(location) = locate_failure::<E>(i, row_type, projection) {
+        error.locate(location);
+    }
+    error
+}
+
+impl DecodeError {
+    /// Moves the error from the start of the row to the value that cannot be decoded.
+    pub(crate) fn locate(&mut self, location: Location) {
+        self.offset += location.offset as u64;
+        self.field = location.field;
+        self.expected = Some(location.expected);
+        self.kind = location.kind;
+    }
+}
+
+struct Failure {
+    field: String,
+    expected: EType,
+    /// The length of the input left when decoding failed.
+    remaining: usize,
+    kind: DecodeErrorKind,
+}
+
+impl Failure {
+    fn new(
+        i: &[u8],
+        error: nom::Err<nom::error::Error<&[u8]>>,
+        expected: &EType,
+        field: &str,
+    ) -> Self {
+        let remaining = match &error {
+            nom::Err::Error(e) | nom::Err::Failure(e) => e.input.len(),
+            nom::Err::Incomplete(_) => i.len(),
+        };
+        Failure {
+            field: field.to_owned(),
+            expected: expected.clone(),
+            remaining,
+            kind: DecodeErrorKind::from_nom(&error),
+        }
+    }
+}
+
+/// Follows the same layout as [EType::decode_projected_from], down to the values that have no
+/// nested fields or elements, which are decoded as a whole.
+fn walk<'i, E: Encoding>(
+    i: &'i [u8],
+    e_type: &EType,
+    projection: &Projection,
+    path: &mut String,
+) -> Result<&'i [u8], Failure> {
+    let fail = |at: &[u8], error, path: &str| Failure::new(at, error, e_type, path);
+
+    match (&e_type.shape, &e_type.virtual_hint) {
+        (ETypeShape::BaseStruct(fields), None)
+        | (ETypeShape::BaseStruct(fields), Some(VirtualHint::Tuple)) => {
+            let number_of_optional_fields = fields.iter().filter(|(_, t)| !t.required).count();
+            let (mut rest, are_present) = helpers::presence_array(i, number_of_optional_fields)
+                .map_err(|e| fail(i, e, path))?;
+            let mut are_present = are_present.into_iter();
+
+            for (name, field) in fields {
+                if !field.required && are_present.next() != Some(true) {
+                    continue;
+                }
+                let parent = path.len();
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(name);
+
+                rest = match projection {
+                    Projection::All => walk::<E>(rest, field, projection, path)?,
+                    Projection::Fields(selected) => match selected.get(name) {
+                        Some(projection) => walk::<E>(rest, field, projection, path)?,
+                        None => match field.skip_from::<E>(rest) {
+                            Ok((rest, _)) => rest,
+                            Err(e) => return Err(Failure::new(rest, e, field, path)),
+                        },
+                    },
+                };
+                path.truncate(parent);
+            }
+            Ok(rest)
+        }
+
+        (ETypeShape::Array(element), _) => {
+            let (rest, len) = E::u32(i).map_err(|e| fail(i, e, path))?;
+            let len = len as usize;
+            let (rest, are_present) = match element.required {
+                true => (rest, vec![]),
+                false => helpers::presence_array(rest, len).map_err(|e| fail(rest, e, path))?,
+            };
+            elements::<E>(rest, element, len, &are_present, path)
+        }
+
+        (ETypeShape::NdArrayColumnMajor(element, n), _) => {
+            let (rest, (_, len)) =
+                helpers::ndarray_shape::<E>(i, *n).map_err(|e| fail(i, e, path))?;
+
+            // As when decoding, the elements are assumed to be required.
+            let mut element = element.as_ref().clone();
+            element.required = true;
+            elements::<E>(rest, &element, len, &[], path)
+        }
+
+        // Values without fields or elements of their own, and structs with a special meaning
+        // (like loci and intervals), are decoded as a whole.
+        _ => match e_type.decode_from::<E>(i) {
+            Ok((rest, _)) => Ok(rest),
+            Err(e) => Err(fail(i, e, path)),
+        },
+    }
+}
+
+/// Walks the elements of an array (or ndarray), with their presence flags if they are optional.
+fn elements<'i, E: Encoding>(
+    i: &'i [u8],
+    element: &EType,
+    len: usize,
+    are_present: &[bool],
+    path: &mut String,
+) -> Result<&'i [u8], Failure> {
+    if element.required {
+        helpers::check_length(i, element, len).map_err(|e| Failure::new(i, e, element, path))?;
+    }
+    let mut rest = i;
+    for index in 0..len {
+        if are_present.get(index) == Some(&false) {
+            continue;
+        }
+        let parent = path.len();
+        path.push_str(&format!("[{}]", index));
+        rest = walk::<E>(rest, element, &Projection::All, path)?;
+        path.truncate(parent);
+    }
+    Ok(rest)
+}
@@ -1,11 +1,18 @@
 mod e_type;
+mod e_type_ref;
 mod encoders;
-mod error;
+mod locate;
 mod parse;
 mod parse_rows;
+mod projection;
+mod skip;
 
 pub mod helpers;
 
 pub use encoders::{Encoding, StandardEncoder, UnsignedLEB128Encoder};
-pub use error::DataParsingError;
-pub use parse_rows::parse_rows;
+pub use parse_rows::{parse_projected_rows, parse_rows, parse_rows_ref};
+
+pub(crate) use locate::locate_failure;
+#[cfg(feature = "arrow")]
+pub(crate) use locate::row_error;
+pub(crate) use parse_rows::{parse_next_projected_row, parse_next_row, parse_next_row_ref};
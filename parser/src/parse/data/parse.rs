@@ -108,14 +108,14 @@ pub(crate) fn array<'i, E: Encoding>(
 }
 
 /// A [HailValue::Set] is essentially an Array, with the exception that the recovered values
-/// should be unique.
+/// should be unique (duplicates are merged).
 /// [HailValue::Missing] is also a valid value in the set.
 pub(crate) fn set<'i, E: Encoding>(
     i: &'i [u8],
     inner_type: &EType,
 ) -> IResult<&'i [u8], HailValue> {
     let (rest, sequence) = helpers::sequence::<E>(i, inner_type)?;
-    Ok((rest, HailValue::Set(sequence)))
+    Ok((rest, HailValue::Set(sequence.into_iter().collect())))
 }
 
 /// A [HailValue::Dict] is an array of
@@ -215,16 +215,7 @@ pub(crate) fn ndarray_column_major<'i, E: Encoding>(
     n: u32,
 ) -> IResult<&'i [u8], HailValue> {
     // Get the dimensions along each axis.
-    let mut dims = Vec::new();
-    let mut rest = i;
-    for _ in 0..n {
-        let (inner_rest, d) = E::i64(rest)?;
-        rest = inner_rest;
-        dims.push(d);
-    }
-
-    // Overflow will cause a panic in debug mode, only memory-safe in release mode.
-    let number_of_elements = dims.iter().product();
+    let (rest, (dims, number_of_elements)) = helpers::ndarray_shape::<E>(i, n)?;
 
     // Assumes the inner type is required.
     let (rest, elements) = helpers::sequence_with_given_length_without_check::<E>(
@@ -234,13 +225,6 @@ pub(crate) fn ndarray_column_major<'i, E: Encoding>(
     )?;
 
     let maybe_array = {
-        // This is safe, but will cause loss of data if any of the dimensions are more than the
-        // native usize.
-        // "The size of this primitive is how many bytes it takes to reference any location in
-        // memory. For example, on a 32 bit target, this is 4 bytes and on a 64 bit target,
-        // this is 8 bytes."
-        let size_dims: Vec<usize> = dims.iter().map(|d| *d as usize).collect();
-
         // Here we tell ndarray the shape of our data.
         // Note that by default ndarray uses a c-order (row-major) while Hail uses
         // a f-order (column-major), so we need to set it.
@@ -249,7 +233,7 @@ pub(crate) fn ndarray_column_major<'i, E: Encoding>(
         // [[1, 2, 3],     [[1, 4, 7],
         //  [4, 5, 6],      [2, 5, 8],
         //  [7, 8, 9]]      [3, 6, 9]]
-        let shape = IxDyn(size_dims.as_slice()).f();
+        let shape = IxDyn(dims.as_slice()).f();
 
         ArrayD::from_shape_vec(shape, elements)
     };
@@ -291,6 +275,13 @@ pub(crate) fn string<E: Encoding>(i: &[u8]) -> IResult<&[u8], HailValue> {
     Ok((rest, HailValue::String(s)))
 }
 
+/// A [HailValue::Binary] is encoded like a string (a length followed by the bytes), but the
+/// bytes are not required to be valid UTF-8.
+pub(crate) fn binary<E: Encoding>(i: &[u8]) -> IResult<&[u8], HailValue> {
+    let (rest, bytes) = E::byte_buf(i)?;
+    Ok((rest, HailValue::Binary(bytes)))
+}
+
 pub(crate) fn f32<E: Encoding>(i: &[u8]) -> IResult<&[u8], HailValue> {
     let (rest, float) = E::f32(i)?;
     if float.is_nan() {
@@ -309,8 +300,8 @@ pub(crate) fn f64<E: Encoding>(i: &[u8]) -> IResult<&[u8], HailValue> {
     }
 }
 
-pub(crate) fn u32<E: Encoding>(i: &[u8]) -> IResult<&[u8], HailValue> {
-    let (rest, int) = E::u32(i)?;
+pub(crate) fn i32<E: Encoding>(i: &[u8]) -> IResult<&[u8], HailValue> {
+    let (rest, int) = E::i32(i)?;
     Ok((rest, HailValue::Int32(int)))
 }
 
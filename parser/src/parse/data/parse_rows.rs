@@ -1,34 +1,110 @@
 use nom::IResult;
 
-use crate::types::{EType, HailValue};
+use crate::{
+    error::{DecodeErrorKind, Result},
+    types::{EType, HailValue, HailValueRef, Projection},
+};
 
-use super::{DataParsingError, Encoding};
+use super::{locate::row_error, Encoding};
 
 /// Decoding thedata means repeatedly decoding the value from the file until there are no elements
 /// left.
 ///
 /// Before each row, a boolean byte (`0u8`/`1u8`) indicates whether there is a new row or there are
 /// no rows left.
-pub fn parse_rows<E: Encoding>(
+///
+/// If a row cannot be decoded, the error points to the value that failed within the row (see
+/// [DecodeError](crate::error::DecodeError)).
+pub fn parse_rows<E: Encoding>(i: &[u8], row_type: &EType) -> Result<Vec<HailValue>> {
+    parse_projected_rows::<E>(i, row_type, &Projection::All)
+}
+
+/// Like [parse_rows], but only the selected fields of each row are decoded, the others are
+/// skipped (see [EType::decode_projected_from]).
+pub fn parse_projected_rows<E: Encoding>(
     i: &[u8],
     row_type: &EType,
-) -> Result<Vec<HailValue>, DataParsingError> {
-    match _parse_rows::<E>(i, row_type) {
-        Ok((_, data)) => Ok(data),
-        Err(e) => Err(DataParsingError::Generic(e.to_string())),
+    projection: &Projection,
+) -> Result<Vec<HailValue>> {
+    let mut result = Vec::new();
+    let mut rest = i;
+    loop {
+        match parse_next_projected_row::<E>(rest, row_type, projection) {
+            Ok((inner_rest, Some(row))) => {
+                rest = inner_rest;
+                result.push(row);
+            }
+            Ok((_, None)) => return Ok(result),
+            Err(e) => {
+                let kind = DecodeErrorKind::from_nom(&e);
+                let row = result.len() as u64;
+                let mut error = row_error::<E>(rest, row, row_type, projection, kind);
+                error.offset += (i.len() - rest.len()) as u64;
+                return Err(error.into());
+            }
+        }
     }
 }
 
-fn _parse_rows<'i, E: Encoding>(
+/// Decodes a single row, preceded by its boolean marker.
+/// `None` is returned when the marker indicates that there are no rows left.
+pub(crate) fn parse_next_row<'i, E: Encoding>(
     i: &'i [u8],
     row_type: &EType,
-) -> IResult<&'i [u8], Vec<HailValue>> {
+) -> IResult<&'i [u8], Option<HailValue>> {
+    parse_next_projected_row::<E>(i, row_type, &Projection::All)
+}
+
+/// Like [parse_next_row], but only the selected fields of the row are decoded.
+pub(crate) fn parse_next_projected_row<'i, E: Encoding>(
+    i: &'i [u8],
+    row_type: &EType,
+    projection: &Projection,
+) -> IResult<&'i [u8], Option<HailValue>> {
+    match E::bool(i)? {
+        (rest, true) => {
+            let (rest, parsed_value) = row_type.decode_projected_from::<E>(rest, projection)?;
+            Ok((rest, Some(parsed_value)))
+        }
+        (rest, false) => Ok((rest, None)),
+    }
+}
+
+/// Like [parse_rows], but the rows borrow from the input and the schema (see [HailValueRef]).
+pub fn parse_rows_ref<'a, E: Encoding>(
+    i: &'a [u8],
+    row_type: &'a EType,
+) -> Result<Vec<HailValueRef<'a>>> {
     let mut result = Vec::new();
     let mut rest = i;
-    while let (inner_rest, true) = E::bool(rest)? {
-        let (inner_rest, parsed_value) = row_type.decode_from::<E>(inner_rest)?;
-        rest = inner_rest;
-        result.push(parsed_value);
+    loop {
+        match parse_next_row_ref::<E>(rest, row_type) {
+            Ok((inner_rest, Some(row))) => {
+                rest = inner_rest;
+                result.push(row);
+            }
+            Ok((_, None)) => return Ok(result),
+            Err(e) => {
+                let kind = DecodeErrorKind::from_nom(&e);
+                let row = result.len() as u64;
+                let mut error = row_error::<E>(rest, row, row_type, &Projection::All, kind);
+                error.offset += (i.len() - rest.len()) as u64;
+                return Err(error.into());
+            }
+        }
+    }
+}
+
+/// Like [parse_next_row], but the row borrows from the input and the schema.
+pub(crate) fn parse_next_row_ref<'a, E: Encoding>(
+    i: &'a [u8],
+    row_type: &'a EType,
+) -> IResult<&'a [u8], Option<HailValueRef<'a>>> {
+    match E::bool(i)? {
+        (rest, true) => {
+            let (rest, row) = row_type.decode_ref_from::<E>(rest)?;
+            Ok((rest, Some(row)))
+        }
+        (rest, false) => Ok((rest, None)),
     }
-    Ok((rest, result))
 }
@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+
+use nom::IResult;
+
+use crate::types::{EType, ETypeShape, HailValue, Projection};
+
+use super::{encoders::Encoding, helpers};
+
+impl EType {
+    /// Decodes only the selected fields of a value, skipping the others with [EType::skip_from].
+    ///
+    /// The result is the value [EType::decode_from] would return for the pruned type given by
+    /// [Projection::encoded_type], so the projection is expected to have been checked against
+    /// this type beforehand.
+    pub fn decode_projected_from<'i, E: Encoding>(
+        &self,
+        i: &'i [u8],
+        projection: &Projection,
+    ) -> IResult<&'i [u8], HailValue> {
+        let selected = match projection {
+            Projection::All => return self.decode_from::<E>(i),
+            Projection::Fields(selected) => selected,
+        };
+        let mapping = match &self.shape {
+            ETypeShape::BaseStruct(mapping) if self.virtual_hint.is_none() => mapping,
+            _ => return Err(failure(i)),
+        };
+
+        // Same layout as a struct, see `parse::hail_struct`.
+        let number_of_optional_fields = mapping.iter().filter(|(_, t)| !t.required).count();
+        let (mut rest, are_present) = helpers::presence_array(i, number_of_optional_fields)?;
+        let mut presence_iter = are_present.into_iter();
+
+        let mut output = BTreeMap::<String, HailValue>::new();
+
+        for (field_name, field_type) in mapping {
+            let field_projection = selected.get(field_name);
+            if !field_type.required {
+                match presence_iter.next() {
+                    Some(false) => {
+                        if field_projection.is_some() {
+                            output.insert(field_name.to_owned(), HailValue::Missing);
+                        }
+                        continue;
+                    }
+                    None => return Err(failure(i)), // It should never run out of bits.
+                    Some(true) => {}                // pass: decode or skip the value ↓
+                }
+            }
+            rest = match field_projection {
+                Some(field_projection) => {
+                    let (inner_rest, decoded) =
+                        field_type.decode_projected_from::<E>(rest, field_projection)?;
+                    output.insert(field_name.to_owned(), decoded);
+                    inner_rest
+                }
+                None => field_type.skip_from::<E>(rest)?.0,
+            };
+        }
+
+        Ok((rest, HailValue::Struct(output)))
+    }
+}
+
+fn failure(i: &[u8]) -> nom::Err<nom::error::Error<&[u8]>> {
+    nom::Err::Failure(nom::error::Error::new(i, nom::error::ErrorKind::TagBits))
+}
@@ -0,0 +1,75 @@
+use std::iter;
+
+use nom::IResult;
+
+use crate::types::{EType, ETypeShape};
+
+use super::{encoders::Encoding, helpers};
+
+impl EType {
+    /// Advances past a (present) value without decoding it, following the same layout as
+    /// [EType::decode_from]: only the presence flags and the lengths are read, and nothing is
+    /// allocated for the skipped values.
+    ///
+    /// Virtual hints do not change the layout of the data, so they are ignored.
+    pub fn skip_from<'i, E: Encoding>(&self, i: &'i [u8]) -> IResult<&'i [u8], ()> {
+        match &self.shape {
+            ETypeShape::BaseStruct(mapping) => {
+                let number_of_optional_fields = mapping.iter().filter(|(_, t)| !t.required).count();
+                let (mut rest, are_present) =
+                    helpers::presence_array(i, number_of_optional_fields)?;
+                let mut presence_iter = are_present.into_iter();
+
+                for (_, field_type) in mapping {
+                    if !field_type.required && presence_iter.next() != Some(true) {
+                        continue;
+                    }
+                    rest = field_type.skip_from::<E>(rest)?.0;
+                }
+                Ok((rest, ()))
+            }
+            ETypeShape::Array(inner_type) => {
+                let (rest, len) = E::u32(i)?;
+                skip_sequence::<E>(rest, inner_type, len as usize)
+            }
+            ETypeShape::NdArrayColumnMajor(inner_type, n) => {
+                let (rest, (_, number_of_elements)) = helpers::ndarray_shape::<E>(i, *n)?;
+
+                // As when decoding, the elements are assumed to be required.
+                let mut required_type = inner_type.as_ref().clone();
+                required_type.required = true;
+                skip_sequence::<E>(rest, &required_type, number_of_elements)
+            }
+            ETypeShape::Binary => E::bytes(i).map(|(rest, _)| (rest, ())),
+            ETypeShape::Float32 => E::f32(i).map(|(rest, _)| (rest, ())),
+            ETypeShape::Float64 => E::f64(i).map(|(rest, _)| (rest, ())),
+            ETypeShape::Int32 => E::i32(i).map(|(rest, _)| (rest, ())),
+            ETypeShape::Int64 => E::i64(i).map(|(rest, _)| (rest, ())),
+            ETypeShape::Boolean => E::bool(i).map(|(rest, _)| (rest, ())),
+        }
+    }
+}
+
+/// Skips `len` elements, preceded by their presence flags if they are optional.
+fn skip_sequence<'i, E: Encoding>(
+    i: &'i [u8],
+    inner_type: &EType,
+    len: usize,
+) -> IResult<&'i [u8], ()> {
+    let (mut rest, are_present) = match inner_type.required {
+        true => {
+            helpers::check_length(i, inner_type, len)?;
+            (i, vec![])
+        }
+        false => helpers::presence_array(i, len)?,
+    };
+
+    // Required elements have no presence flags, they are all present.
+    let are_present = are_present.into_iter().chain(iter::repeat(true)).take(len);
+    for is_present in are_present {
+        if is_present {
+            rest = inner_type.skip_from::<E>(rest)?.0;
+        }
+    }
+    Ok((rest, ()))
+}
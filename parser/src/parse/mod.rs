@@ -1,9 +1,10 @@
 pub(crate) mod schema;
 
-pub(crate) use data::parse_rows;
+pub(crate) use data::{
+    parse_next_projected_row, parse_next_row, parse_next_row_ref, parse_projected_rows,
+};
 
 pub mod data;
 pub use data::{Encoding, StandardEncoder, UnsignedLEB128Encoder};
 
-pub use data::DataParsingError;
 pub use schema::SchemaParsingError;
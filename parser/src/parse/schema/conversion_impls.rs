@@ -1,12 +1,22 @@
-use crate::types::metadata::{component, component_1, component_2, ComponentMetadata};
+use std::convert::TryFrom;
 
-impl From<component_1::RvdMetadataV1> for ComponentMetadata {
-    fn from(original: component_1::RvdMetadataV1) -> Self {
+use crate::types::metadata::{
+    component, component_1, component_2, shared::BufferSpec, ComponentMetadata,
+};
+
+use super::{json_value, SchemaParsingError};
+
+/// The conversion fails if the range bounds do not match the key type.
+impl TryFrom<component_1::RvdMetadataV1> for ComponentMetadata {
+    type Error = SchemaParsingError;
+
+    fn try_from(original: component_1::RvdMetadataV1) -> Result<Self, Self::Error> {
         let component_1::RvdMetadataV1 {
             rvd_type,
             codec_spec,
+            index_spec,
             part_files,
-            ..
+            j_range_bounds,
         } = original;
 
         let component_1::RvdTypeSchema {
@@ -22,14 +32,21 @@ impl From<component_1::RvdMetadataV1> for ComponentMetadata {
             }
         };
 
-        ComponentMetadata {
+        let index_spec = index_spec.map(|spec| index_spec_from_v1(spec, &buffer_spec));
+
+        let key_type = json_value::key_type(&row_keys, &row_schema)?;
+        let range_bounds = json_value::range_bounds_to_intervals(&j_range_bounds, &key_type)?;
+
+        Ok(ComponentMetadata {
             key: row_keys,
             virtual_type: row_schema,
             encoded_type,
             codec_spec: std_codec_spec,
             buffer_spec,
             part_files,
-        }
+            index_spec,
+            range_bounds,
+        })
     }
 }
 
@@ -57,16 +74,23 @@ impl From<component_1::UnpartitionedRvdMetadataV1> for ComponentMetadata {
             codec_spec: std_codec_spec,
             buffer_spec,
             part_files,
+            index_spec: None,
+            range_bounds: vec![],
         }
     }
 }
 
-impl From<component_2::RVDMetadataV2> for ComponentMetadata {
-    fn from(original: component_2::RVDMetadataV2) -> Self {
+/// The conversion fails if the range bounds do not match the key type.
+impl TryFrom<component_2::RVDMetadataV2> for ComponentMetadata {
+    type Error = SchemaParsingError;
+
+    fn try_from(original: component_2::RVDMetadataV2) -> Result<Self, Self::Error> {
         let component_2::RVDMetadataV2 {
             key,
             codec_spec,
+            index_spec,
             part_files,
+            j_range_bounds,
         } = original;
 
         let (std_codec_spec, inner_buffer_spec) = match codec_spec {
@@ -86,13 +110,84 @@ impl From<component_2::RVDMetadataV2> for ComponentMetadata {
             Some(&virtual_type),
         );
 
-        ComponentMetadata {
+        let key_type = json_value::key_type(&key, &virtual_type)?;
+        let range_bounds = json_value::range_bounds_to_intervals(&j_range_bounds, &key_type)?;
+
+        Ok(ComponentMetadata {
             key,
             virtual_type,
             encoded_type,
             codec_spec: std_codec_spec,
             buffer_spec,
             part_files,
-        }
+            index_spec: index_spec.map(index_spec_from_v2),
+            range_bounds,
+        })
+    }
+}
+
+/// Older index specifications do not record the codec used for the index nodes, so the default
+/// encoded types are derived from the node layout, and the component's buffer spec is assumed.
+fn index_spec_from_v1(
+    original: component_1::IndexSpec,
+    buffer_spec: &BufferSpec,
+) -> component::ComponentIndexSpec {
+    let component_1::IndexSpec {
+        rel_path,
+        key_type,
+        annotation_type,
+        offset_field,
+    } = original;
+
+    let leaf_virtual_type = super::helpers::index_leaf_virtual_type(&key_type, &annotation_type);
+    let internal_node_virtual_type =
+        super::helpers::index_internal_node_virtual_type(&key_type, &annotation_type);
+
+    component::ComponentIndexSpec {
+        rel_path,
+        key_type,
+        annotation_type,
+        leaf_encoded_type: super::helpers::virtual_type_to_default_encoded_type(&leaf_virtual_type),
+        internal_node_encoded_type: super::helpers::virtual_type_to_default_encoded_type(
+            &internal_node_virtual_type,
+        ),
+        buffer_spec: buffer_spec.clone(),
+        offset_field,
+    }
+}
+
+fn index_spec_from_v2(original: component_2::IndexSpecV2) -> component::ComponentIndexSpec {
+    let component_2::IndexSpecV2 {
+        rel_path,
+        leaf_codec,
+        internal_node_codec,
+        key_type,
+        annotation_type,
+        offset_field,
+    } = original;
+
+    let component_2::ComponentCodecSpecV2::TypedCodecSpec(leaf_codec) = leaf_codec;
+    let component_2::ComponentCodecSpecV2::TypedCodecSpec(internal_node_codec) =
+        internal_node_codec;
+
+    let mut leaf_encoded_type = leaf_codec.encoded_type;
+    super::helpers::fill_encoded_type_with_virtual_hints(
+        &mut leaf_encoded_type,
+        Some(&leaf_codec.virtual_type),
+    );
+    let mut internal_node_encoded_type = internal_node_codec.encoded_type;
+    super::helpers::fill_encoded_type_with_virtual_hints(
+        &mut internal_node_encoded_type,
+        Some(&internal_node_codec.virtual_type),
+    );
+
+    component::ComponentIndexSpec {
+        rel_path,
+        key_type,
+        annotation_type,
+        leaf_encoded_type,
+        internal_node_encoded_type,
+        buffer_spec: leaf_codec.buffer_spec,
+        offset_field,
     }
 }
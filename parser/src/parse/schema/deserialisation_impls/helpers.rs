@@ -1,21 +1,20 @@
 use crate::types::VType;
 
-use crate::parse::schema::{error::SchemaParsingError, SchemaFromString};
+use crate::parse::schema::{error::SchemaParsingError, parse::parse_names, SchemaFromString};
 
 pub fn extract_keys_from_regex_match(
     raw_keys: &Option<regex::Match>,
     full_value: &str,
 ) -> Result<Vec<String>, SchemaParsingError> {
-    Ok(raw_keys
-        .ok_or(SchemaParsingError::Generic(format!(
-            "Unable to extract keys from the schema: {}",
-            full_value
-        )))?
+    let raw_keys = raw_keys
+        .ok_or_else(|| SchemaParsingError::Malformed {
+            schema: full_value.to_owned(),
+            part: "keys",
+        })?
         .as_str()
-        .trim_matches(&['[', ']'] as &[char])
-        .split(',')
-        .map(|s| s.to_owned())
-        .collect())
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    parse_names(raw_keys)
 }
 
 pub fn extract_field_from_regex_match(
@@ -24,10 +23,10 @@ pub fn extract_field_from_regex_match(
     full_value: &str,
 ) -> Result<VType, SchemaParsingError> {
     let raw_fields = raw_field
-        .ok_or(SchemaParsingError::Generic(format!(
-            "Unable to extract the fields from the schema: {}",
-            full_value
-        )))?
+        .ok_or_else(|| SchemaParsingError::Malformed {
+            schema: full_value.to_owned(),
+            part: "fields",
+        })?
         .as_str();
 
     let (found_field_name, field_schema) = VType::parse_named_type(raw_fields)?;
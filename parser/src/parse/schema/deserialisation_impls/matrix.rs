@@ -31,9 +31,13 @@ impl<'de> Deserialize<'de> for MatrixSchema {
 }
 
 fn string_to_matrix_schema(value: String) -> Result<MatrixSchema, SchemaParsingError> {
-    let captures: regex::Captures = MATRIX_SCHEMA_REGEX.captures(&value).ok_or_else(|| {
-        SchemaParsingError::Generic(format!("Unable to capture matrix schema in: {}", value))
-    })?;
+    let captures: regex::Captures =
+        MATRIX_SCHEMA_REGEX
+            .captures(&value)
+            .ok_or_else(|| SchemaParsingError::Malformed {
+                schema: value.clone(),
+                part: "matrix schema",
+            })?;
 
     let global_schema =
         helpers::extract_field_from_regex_match(&captures.get(1), "global", &value)?;
@@ -69,9 +73,6 @@ fn string_to_matrix_schema(value: String) -> Result<MatrixSchema, SchemaParsingE
                 entry_schema,
             })
         }
-        (_, _) => Err(SchemaParsingError::Generic(format!(
-            "Parsed matrix column or row schema is not a Struct: {}",
-            value
-        ))),
+        (_, _) => Err(SchemaParsingError::NotAStruct { schema: value }),
     }
 }
@@ -28,9 +28,13 @@ impl<'de> Deserialize<'de> for RvdTypeSchema {
 }
 
 fn string_to_rvd_type_schema(value: String) -> Result<RvdTypeSchema, SchemaParsingError> {
-    let captures: regex::Captures = COMPONENT_SCHEMA_REGEX.captures(&value).ok_or_else(|| {
-        SchemaParsingError::Generic(format!("Unable to capture component schema in: {}", value))
-    })?;
+    let captures: regex::Captures =
+        COMPONENT_SCHEMA_REGEX
+            .captures(&value)
+            .ok_or_else(|| SchemaParsingError::Malformed {
+                schema: value.clone(),
+                part: "component schema",
+            })?;
 
     let keys: Vec<String> = helpers::extract_keys_from_regex_match(&captures.get(1), &value)?;
 
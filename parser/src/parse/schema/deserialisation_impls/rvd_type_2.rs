@@ -1,8 +1,10 @@
+use std::str::FromStr;
+
 use serde::{Deserialize, Deserializer};
 
 use crate::types::{EType, VType};
 
-use crate::parse::schema::SchemaFromString;
+use crate::parse::schema::{SchemaFromString, SchemaParsingError};
 
 impl<'de> Deserialize<'de> for EType {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -25,3 +27,21 @@ impl<'de> Deserialize<'de> for VType {
         Self::parse_type(&intermediate).map_err(serde::de::Error::custom)
     }
 }
+
+/// Parses an encoded type as written in the metadata, like `+EBaseStruct{a:+EInt32}`.
+impl FromStr for EType {
+    type Err = SchemaParsingError;
+
+    fn from_str(schema: &str) -> Result<Self, Self::Err> {
+        Self::parse_type(schema)
+    }
+}
+
+/// Parses a virtual type as written in the metadata, like `Struct{a:Int32}`.
+impl FromStr for VType {
+    type Err = SchemaParsingError;
+
+    fn from_str(schema: &str) -> Result<Self, Self::Err> {
+        Self::parse_type(schema)
+    }
+}
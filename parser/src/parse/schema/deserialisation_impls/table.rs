@@ -28,9 +28,13 @@ impl<'de> Deserialize<'de> for TableSchema {
 }
 
 fn string_to_table_schema(value: String) -> Result<TableSchema, SchemaParsingError> {
-    let captures: regex::Captures = TABLE_SCHEMA_REGEX.captures(&value).ok_or_else(|| {
-        SchemaParsingError::Generic(format!("Unable to capture table schema in: {}", value))
-    })?;
+    let captures: regex::Captures =
+        TABLE_SCHEMA_REGEX
+            .captures(&value)
+            .ok_or_else(|| SchemaParsingError::Malformed {
+                schema: value.clone(),
+                part: "table schema",
+            })?;
 
     let global_schema =
         helpers::extract_field_from_regex_match(&captures.get(1), "global", &value)?;
@@ -52,9 +56,6 @@ fn string_to_table_schema(value: String) -> Result<TableSchema, SchemaParsingErr
                 row_keys,
             })
         }
-        _ => Err(SchemaParsingError::Generic(format!(
-            "Parsed table schema is not a Struct: {}",
-            value
-        ))),
+        _ => Err(SchemaParsingError::NotAStruct { schema: value }),
     }
 }
@@ -1,27 +1,90 @@
 use std::fmt::{self, Display};
 
-/// This is a bare-bones error implementation. Taken from the basic example in the docs.
+/// The reasons why a schema, or the metadata holding it, could not be parsed.
 #[derive(Clone, Debug, PartialEq)]
 pub enum SchemaParsingError {
-    Generic(String),
+    /// The type does not follow the syntax of Hail types, from the given byte offset on.
+    Syntax {
+        schema: String,
+        offset: usize,
+        kind: nom::error::ErrorKind,
+    },
+    /// The type is nested more deeply than the given limit.
+    TooDeep { limit: usize },
+    /// A part of a (matrix) table or component type, like its key, is missing or malformed.
+    Malformed { schema: String, part: &'static str },
+    /// The type is expected to be a struct, like the type of the rows.
+    NotAStruct { schema: String },
+    /// A field of the key is not a field of the rows.
+    MissingKeyField(String),
+    /// A value of the metadata (like the range bounds) cannot be converted from or to JSON as
+    /// the expected type.
+    Value { value: String, expected: String },
+    /// Any other error raised while (de)serializing the metadata.
+    Custom(String),
+}
+
+impl SchemaParsingError {
+    /// The error of a nom parser that stopped at `input`, a suffix of the whole `schema`.
+    pub(crate) fn syntax(schema: &str, error: nom::Err<nom::error::Error<&str>>) -> Self {
+        let (input, kind) = match error {
+            nom::Err::Error(error) | nom::Err::Failure(error) => (error.input, error.code),
+            nom::Err::Incomplete(_) => ("", nom::error::ErrorKind::Eof),
+        };
+        SchemaParsingError::Syntax {
+            schema: schema.to_owned(),
+            offset: schema.len().saturating_sub(input.len()),
+            kind,
+        }
+    }
 }
 
 impl serde::ser::Error for SchemaParsingError {
     fn custom<T: Display>(msg: T) -> Self {
-        SchemaParsingError::Generic(msg.to_string())
+        SchemaParsingError::Custom(msg.to_string())
     }
 }
 
 impl serde::de::Error for SchemaParsingError {
     fn custom<T: Display>(msg: T) -> Self {
-        SchemaParsingError::Generic(msg.to_string())
+        SchemaParsingError::Custom(msg.to_string())
     }
 }
 
 impl Display for SchemaParsingError {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            SchemaParsingError::Generic(msg) => formatter.write_str(msg),
+            SchemaParsingError::Syntax {
+                schema,
+                offset,
+                kind,
+            } => write!(
+                formatter,
+                "Unable to parse the schema: {}\nParsing error: {:?}\nLocation: {}",
+                schema,
+                kind,
+                schema.get(*offset..).unwrap_or_default()
+            ),
+            SchemaParsingError::TooDeep { limit } => write!(
+                formatter,
+                "Unable to parse the schema: types are nested more than {} levels deep",
+                limit
+            ),
+            SchemaParsingError::Malformed { schema, part } => {
+                write!(formatter, "Unable to extract the {} from: {}", part, schema)
+            }
+            SchemaParsingError::NotAStruct { schema } => {
+                write!(formatter, "Expected a Struct, found: {}", schema)
+            }
+            SchemaParsingError::MissingKeyField(name) => {
+                write!(formatter, "Key field {} not found in the rows", name)
+            }
+            SchemaParsingError::Value { value, expected } => write!(
+                formatter,
+                "Unable to convert the value {} to {}",
+                value, expected
+            ),
+            SchemaParsingError::Custom(msg) => formatter.write_str(msg),
         }
     }
 }
@@ -24,7 +24,7 @@ pub fn virtual_type_to_default_encoded_type(virtual_type: &VType) -> EType {
                 .zip(0u32..)
                 .map(|(v_type, index)| {
                     (
-                        format!("`{}`", index),
+                        index.to_string(),
                         virtual_type_to_default_encoded_type(v_type),
                     )
                 })
@@ -59,7 +59,7 @@ pub fn virtual_type_to_default_encoded_type(virtual_type: &VType) -> EType {
             *n,
         ),
 
-        VTypeShape::String => ETypeShape::Binary,
+        VTypeShape::String | VTypeShape::Binary => ETypeShape::Binary,
 
         VTypeShape::Float32 => ETypeShape::Float32,
         VTypeShape::Float64 => ETypeShape::Float64,
@@ -151,10 +151,24 @@ pub fn fill_encoded_type_with_virtual_hints(encoded: &mut EType, maybe_virtual:
             }
         }
         // Locus as Struct
-        // No special case necessary as the top-level hint is sufficient to direct to the
-        // Locus-specific parsing function.
-        // If we wanted to include it, then we would just need to make sure the contig string has
-        // the correct hint (String).
+        // The top-level hint is sufficient to direct to the Locus-specific parsing function, but
+        // the contig string is given the correct hint (String) for consumers that only follow the
+        // encoded type.
+        (ETypeShape::BaseStruct(e_inner_mapping), Some(VTypeShape::Locus(_))) => {
+            for (s, e) in e_inner_mapping.iter_mut() {
+                let inner_virtual = match s.as_str() {
+                    "contig" => VTypeShape::String,
+                    _ => VTypeShape::Int32,
+                };
+                fill_encoded_type_with_virtual_hints(
+                    e,
+                    Some(&VType {
+                        shape: inner_virtual,
+                        required: true,
+                    }),
+                )
+            }
+        }
 
         // [Array]
         // If the virtual type is also an array, then we need to pass the inner virtual type to
@@ -219,3 +233,61 @@ fn type_to_hint(maybe_virtual: Option<&VType>) -> Option<VirtualHint> {
         _ => None,
     }
 }
+
+/// The virtual type of an index leaf node, as written by Hail.
+pub fn index_leaf_virtual_type(key_type: &VType, annotation_type: &VType) -> VType {
+    let entry = VType {
+        shape: VTypeShape::Struct(vec![
+            ("key".to_owned(), key_type.clone()),
+            ("offset".to_owned(), required_int64()),
+            ("annotation".to_owned(), annotation_type.clone()),
+        ]),
+        required: true,
+    };
+
+    VType {
+        shape: VTypeShape::Struct(vec![
+            ("first_idx".to_owned(), required_int64()),
+            (
+                "keys".to_owned(),
+                VType {
+                    shape: VTypeShape::Array(Box::new(entry)),
+                    required: true,
+                },
+            ),
+        ]),
+        required: false,
+    }
+}
+
+/// The virtual type of an internal index node, as written by Hail.
+pub fn index_internal_node_virtual_type(key_type: &VType, annotation_type: &VType) -> VType {
+    let child = VType {
+        shape: VTypeShape::Struct(vec![
+            ("index_file_offset".to_owned(), required_int64()),
+            ("first_idx".to_owned(), required_int64()),
+            ("first_key".to_owned(), key_type.clone()),
+            ("first_record_offset".to_owned(), required_int64()),
+            ("first_annotation".to_owned(), annotation_type.clone()),
+        ]),
+        required: true,
+    };
+
+    VType {
+        shape: VTypeShape::Struct(vec![(
+            "children".to_owned(),
+            VType {
+                shape: VTypeShape::Array(Box::new(child)),
+                required: true,
+            },
+        )]),
+        required: false,
+    }
+}
+
+fn required_int64() -> VType {
+    VType {
+        shape: VTypeShape::Int64,
+        required: true,
+    }
+}
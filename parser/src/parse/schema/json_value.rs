@@ -0,0 +1,249 @@
+use std::{collections::BTreeMap, convert::TryFrom};
+
+use serde_json::Value;
+
+use crate::types::{metadata::shared::JRangeBound, HailValue, VType, VTypeShape};
+
+use super::SchemaParsingError;
+
+/// Converts the json representation of a value (as found in the metadata files) to a
+/// [HailValue], using its virtual type.
+///
+/// Only the types that can appear in keys are supported, which excludes calls and ndarrays.
+/// Binaries have no json representation in the metadata files, so they are not supported either.
+pub fn json_to_hail_value(
+    value: &Value,
+    virtual_type: &VType,
+) -> Result<HailValue, SchemaParsingError> {
+    let unexpected = || SchemaParsingError::Value {
+        value: value.to_string(),
+        expected: virtual_type.to_string(),
+    };
+
+    if value.is_null() {
+        return Ok(HailValue::Missing);
+    }
+
+    Ok(match &virtual_type.shape {
+        VTypeShape::Struct(fields) => {
+            let object = value.as_object().ok_or_else(unexpected)?;
+            let mut map = BTreeMap::new();
+            for (name, field_type) in fields {
+                let field = object.get(name).unwrap_or(&Value::Null);
+                map.insert(name.clone(), json_to_hail_value(field, field_type)?);
+            }
+            HailValue::Struct(map)
+        }
+        VTypeShape::Tuple(field_types) => {
+            let array = value.as_array().ok_or_else(unexpected)?;
+            if array.len() != field_types.len() {
+                return Err(unexpected());
+            }
+            HailValue::Tuple(
+                array
+                    .iter()
+                    .zip(field_types)
+                    .map(|(v, t)| json_to_hail_value(v, t))
+                    .collect::<Result<_, _>>()?,
+            )
+        }
+        VTypeShape::Array(inner) | VTypeShape::Set(inner) => {
+            let elements: Vec<HailValue> = value
+                .as_array()
+                .ok_or_else(unexpected)?
+                .iter()
+                .map(|v| json_to_hail_value(v, inner))
+                .collect::<Result<_, _>>()?;
+            match &virtual_type.shape {
+                VTypeShape::Set(_) => HailValue::Set(elements.into_iter().collect()),
+                _ => HailValue::Array(elements),
+            }
+        }
+        // Dictionaries are written as an array of key-value structs.
+        VTypeShape::Dict(key_type, value_type) => {
+            let mut map = BTreeMap::new();
+            for entry in value.as_array().ok_or_else(unexpected)? {
+                let entry = entry.as_object().ok_or_else(unexpected)?;
+                let key = entry.get("key").unwrap_or(&Value::Null);
+                let value = entry.get("value").unwrap_or(&Value::Null);
+                map.insert(
+                    json_to_hail_value(key, key_type)?,
+                    json_to_hail_value(value, value_type)?,
+                );
+            }
+            HailValue::Dict(map)
+        }
+        VTypeShape::Interval(point_type) => {
+            let object = value.as_object().ok_or_else(unexpected)?;
+            let flag = |name: &str| object.get(name).and_then(Value::as_bool);
+            HailValue::Interval {
+                start: Box::new(json_to_hail_value(
+                    object.get("start").unwrap_or(&Value::Null),
+                    point_type,
+                )?),
+                end: Box::new(json_to_hail_value(
+                    object.get("end").unwrap_or(&Value::Null),
+                    point_type,
+                )?),
+                includes_start: flag("includeStart").ok_or_else(unexpected)?,
+                includes_end: flag("includeEnd").ok_or_else(unexpected)?,
+            }
+        }
+        VTypeShape::String => HailValue::String(value.as_str().ok_or_else(unexpected)?.to_owned()),
+        // NaN values are not allowed, see [HailValue].
+        VTypeShape::Float32 => match value.as_f64() {
+            Some(float) => HailValue::Float32(float as f32),
+            None => HailValue::Missing,
+        },
+        VTypeShape::Float64 => match value.as_f64() {
+            Some(float) => HailValue::Float64(float),
+            None => HailValue::Missing,
+        },
+        VTypeShape::Int32 => HailValue::Int32(
+            value
+                .as_i64()
+                .and_then(|int| i32::try_from(int).ok())
+                .ok_or_else(unexpected)?,
+        ),
+        VTypeShape::Int64 => HailValue::Int64(value.as_i64().ok_or_else(unexpected)?),
+        VTypeShape::Boolean => HailValue::Boolean(value.as_bool().ok_or_else(unexpected)?),
+        VTypeShape::Locus(reference) => {
+            let object = value.as_object().ok_or_else(unexpected)?;
+            HailValue::Locus {
+                contig: object
+                    .get("contig")
+                    .and_then(Value::as_str)
+                    .ok_or_else(unexpected)?
+                    .to_owned(),
+                position: object
+                    .get("position")
+                    .and_then(Value::as_u64)
+                    .ok_or_else(unexpected)? as u32,
+                reference: reference.clone(),
+            }
+        }
+        VTypeShape::NDArray(_, _) | VTypeShape::Call | VTypeShape::Binary => {
+            return Err(unexpected())
+        }
+    })
+}
+
+/// The inverse of [json_to_hail_value], used when writing the metadata files.
+pub fn hail_value_to_json(value: &HailValue) -> Result<Value, SchemaParsingError> {
+    let unexpected = || SchemaParsingError::Value {
+        value: format!("{:?}", value),
+        expected: "json".to_owned(),
+    };
+
+    Ok(match value {
+        HailValue::Struct(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, field)| Ok((name.clone(), hail_value_to_json(field)?)))
+                .collect::<Result<_, SchemaParsingError>>()?,
+        ),
+        HailValue::Tuple(values) | HailValue::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(hail_value_to_json)
+                .collect::<Result<_, _>>()?,
+        ),
+        HailValue::Set(values) => Value::Array(
+            values
+                .iter()
+                .map(hail_value_to_json)
+                .collect::<Result<_, _>>()?,
+        ),
+        HailValue::Dict(map) => Value::Array(
+            map.iter()
+                .map(|(key, value)| {
+                    let mut entry = serde_json::Map::new();
+                    entry.insert("key".to_owned(), hail_value_to_json(key)?);
+                    entry.insert("value".to_owned(), hail_value_to_json(value)?);
+                    Ok(Value::Object(entry))
+                })
+                .collect::<Result<_, SchemaParsingError>>()?,
+        ),
+        HailValue::Interval {
+            start,
+            end,
+            includes_start,
+            includes_end,
+        } => {
+            let mut object = serde_json::Map::new();
+            object.insert("start".to_owned(), hail_value_to_json(start)?);
+            object.insert("end".to_owned(), hail_value_to_json(end)?);
+            object.insert("includeStart".to_owned(), Value::Bool(*includes_start));
+            object.insert("includeEnd".to_owned(), Value::Bool(*includes_end));
+            Value::Object(object)
+        }
+        HailValue::String(string) => Value::String(string.clone()),
+        HailValue::Float32(float) => serde_json::Number::from_f64(f64::from(*float))
+            .map(Value::Number)
+            .ok_or_else(unexpected)?,
+        HailValue::Float64(float) => serde_json::Number::from_f64(*float)
+            .map(Value::Number)
+            .ok_or_else(unexpected)?,
+        HailValue::Int32(int) => Value::from(*int),
+        HailValue::Int64(int) => Value::from(*int),
+        HailValue::Boolean(flag) => Value::Bool(*flag),
+        HailValue::Locus {
+            contig, position, ..
+        } => {
+            let mut object = serde_json::Map::new();
+            object.insert("contig".to_owned(), Value::String(contig.clone()));
+            object.insert("position".to_owned(), Value::from(*position as i32));
+            Value::Object(object)
+        }
+        HailValue::Missing => Value::Null,
+        HailValue::NDArray(_) | HailValue::Call(_) | HailValue::Binary(_) => {
+            return Err(unexpected())
+        }
+    })
+}
+
+/// Converts the raw range bounds of a component to [HailValue::Interval]s of key structs.
+pub fn range_bounds_to_intervals(
+    bounds: &[JRangeBound],
+    key_type: &VType,
+) -> Result<Vec<HailValue>, SchemaParsingError> {
+    bounds
+        .iter()
+        .map(|bound| {
+            Ok(HailValue::Interval {
+                start: Box::new(json_to_hail_value(&bound.start, key_type)?),
+                end: Box::new(json_to_hail_value(&bound.end, key_type)?),
+                includes_start: bound.include_start,
+                includes_end: bound.include_end,
+            })
+        })
+        .collect()
+}
+
+/// Builds the type of the key struct, with the fields in key order.
+pub fn key_type(key: &[String], row_type: &VType) -> Result<VType, SchemaParsingError> {
+    let fields = match &row_type.shape {
+        VTypeShape::Struct(fields) => fields,
+        _ => {
+            return Err(SchemaParsingError::NotAStruct {
+                schema: row_type.to_string(),
+            })
+        }
+    };
+
+    let key_fields = key
+        .iter()
+        .map(|name| {
+            fields
+                .iter()
+                .find(|(field_name, _)| field_name == name)
+                .cloned()
+                .ok_or_else(|| SchemaParsingError::MissingKeyField(name.clone()))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(VType {
+        shape: VTypeShape::Struct(key_fields),
+        required: true,
+    })
+}
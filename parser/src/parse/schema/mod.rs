@@ -2,8 +2,10 @@ mod conversion_impls;
 mod deserialisation_impls;
 mod error;
 mod helpers;
+mod json_value;
 
 mod parse;
 use parse::SchemaFromString;
 
 pub use error::SchemaParsingError;
+pub(crate) use json_value::hail_value_to_json;
@@ -12,46 +12,71 @@ use super::error::SchemaParsingError;
 /// It also handles error conversion from the nom error.
 pub trait SchemaFromString: Sized {
     fn parse_type(schema: &str) -> Result<Self, SchemaParsingError> {
+        check_nesting(schema)?;
         let result = Self::take_type(schema);
 
         match result {
-            Ok((_, schema)) => Ok(schema),
-            Err(nom::Err::Failure(nom::error::Error { input, code })) => {
-                Err(SchemaParsingError::Generic(format!(
-                    "Unable to parse the schema: {}\nParsing error: {:?}\nLocation: {}",
-                    &schema, code, input,
-                )))
-            }
-            Err(e) => Err(SchemaParsingError::Generic(format!(
-                "Unable to parse the schema: {}\nParsing error: {}",
-                &schema,
-                e.to_string()
-            ))),
+            Ok((_, parsed)) => Ok(parsed),
+            Err(e) => Err(SchemaParsingError::syntax(schema, e)),
         }
     }
 
     fn parse_named_type(schema: &str) -> Result<(String, Self), SchemaParsingError> {
+        check_nesting(schema)?;
         let result = helpers::take_field::<Self>(schema);
 
         match result {
             Ok((_, named_field)) => Ok(named_field),
-            Err(nom::Err::Failure(nom::error::Error { input, code })) => {
-                Err(SchemaParsingError::Generic(format!(
-                    "Unable to parse the schema: {}\nParsing error: {:?}\nLocation: {}",
-                    &schema, code, input,
-                )))
-            }
-            Err(e) => Err(SchemaParsingError::Generic(format!(
-                "Unable to parse the schema: {}\nParsing error: {}",
-                &schema,
-                e.to_string()
-            ))),
+            Err(e) => Err(SchemaParsingError::syntax(schema, e)),
         }
     }
 
     fn take_type(i: &str) -> IResult<&str, Self>;
 }
 
+/// Parses a comma-separated list of field names, like the keys of a table type, where names may
+/// be escaped between backticks.
+pub fn parse_names(list: &str) -> Result<Vec<String>, SchemaParsingError> {
+    let mut names = vec![];
+    let mut rest = list;
+    while !rest.is_empty() {
+        let (after, name) = match rest.starts_with('`') {
+            true => {
+                helpers::take_escaped_name(rest).map_err(|e| SchemaParsingError::syntax(list, e))?
+            }
+            false => {
+                let end = rest.find(',').unwrap_or(rest.len());
+                (&rest[end..], rest[..end].to_owned())
+            }
+        };
+        names.push(name);
+        rest = after.strip_prefix(',').unwrap_or(after);
+    }
+    Ok(names)
+}
+
+/// Types can be nested at most this deep, well beyond anything Hail writes in practice.
+const MAX_SCHEMA_DEPTH: usize = 128;
+
+/// Schemas are parsed (and values decoded) recursively, so a schema nested too deeply would
+/// overflow the stack rather than fail.
+fn check_nesting(schema: &str) -> Result<(), SchemaParsingError> {
+    let mut depth = 0usize;
+    for c in schema.chars() {
+        match c {
+            '{' | '[' | '(' => depth += 1,
+            '}' | ']' | ')' => depth = depth.saturating_sub(1),
+            _ => continue,
+        }
+        if depth > MAX_SCHEMA_DEPTH {
+            return Err(SchemaParsingError::TooDeep {
+                limit: MAX_SCHEMA_DEPTH,
+            });
+        }
+    }
+    Ok(())
+}
+
 impl SchemaFromString for VType {
     /// Parses a [VType] recursively.
     fn take_type(i: &str) -> IResult<&str, Self> {
@@ -60,7 +85,7 @@ impl SchemaFromString for VType {
             alt!(
                 tag!("Struct") | tag!("Tuple") | tag!("Dict") | tag!("Interval") |
                 tag!("Array") | tag!("Set") | tag!("NDArray") |
-                tag!("String") | tag!("Boolean") |
+                tag!("String") | tag!("Binary") | tag!("Boolean") |
                 tag!("Float32") | tag!("Float64") | tag!("Int32") | tag!("Int64") |
                 tag!("Locus") | tag!("Call")
             )
@@ -90,7 +115,7 @@ impl SchemaFromString for VType {
                 let (inner_rest, element_type) =
                     helpers::take_single_type_in_square_brackets(rest)?;
                 rest = inner_rest;
-                VTypeShape::Array(Box::new(element_type))
+                VTypeShape::Set(Box::new(element_type))
             }
             "Dict" => {
                 let (inner_rest, (key_type, value_type)) = helpers::take_dict_types(rest)?;
@@ -110,6 +135,7 @@ impl SchemaFromString for VType {
                 VTypeShape::NDArray(Box::new(element_type), n)
             }
             "String" => VTypeShape::String,
+            "Binary" => VTypeShape::Binary,
             "Float32" => VTypeShape::Float32,
             "Float64" => VTypeShape::Float64,
             "Int32" => VTypeShape::Int32,
@@ -188,15 +214,53 @@ mod helpers {
 
     use super::SchemaFromString;
 
-    /// Takes a named type of the form "name:Type".
+    /// Takes a named type of the form "name:Type", where the name may be escaped between
+    /// backticks (see [escape_name](crate::types::encoding::escape_name)).
     pub fn take_field<T: SchemaFromString + Sized>(i: &str) -> IResult<&str, (String, T)> {
-        let (rest, name) = take_field_name(i)?;
+        let (rest, name) = match i.starts_with('`') {
+            true => take_escaped_name(i)?,
+            false => {
+                let (rest, name) = take_field_name(i)?;
+                (rest, name.to_owned())
+            }
+        };
 
         let (rest, _) = take_colon(rest)?; // :
 
         let (rest, encoded_type) = T::take_type(rest)?;
 
-        Ok((rest, (name.into(), encoded_type)))
+        Ok((rest, (name, encoded_type)))
+    }
+
+    /// Takes a name between backticks, unescaping it.
+    pub fn take_escaped_name(i: &str) -> IResult<&str, String> {
+        let failure =
+            || nom::Err::Failure(nom::error::Error::new(i, nom::error::ErrorKind::Escaped));
+        let mut name = String::new();
+        let mut chars = i.char_indices().skip(1);
+        while let Some((index, c)) = chars.next() {
+            let unescaped = match c {
+                '`' => return Ok((&i[index + 1..], name)),
+                '\\' => match chars.next().ok_or_else(failure)?.1 {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'u' => {
+                        let digits: String = (&mut chars).take(4).map(|(_, c)| c).collect();
+                        u32::from_str_radix(&digits, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(failure)?
+                    }
+                    escaped => escaped,
+                },
+                c => c,
+            };
+            name.push(unescaped);
+        }
+        Err(failure())
     }
 
     pub fn take_struct<T: SchemaFromString + Sized>(i: &str) -> IResult<&str, Vec<(String, T)>> {
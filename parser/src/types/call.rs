@@ -0,0 +1,284 @@
+//! Genotype calls, as packed by Hail into a single integer.
+//!
+//! A call is stored as `alleles << 3 | ploidy << 1 | phased`, where `alleles` depends on the
+//! ploidy:
+//! - no alleles (ploidy 0): 0.
+//! - a haploid call: the allele.
+//! - an unphased diploid call `j/k` (with `j <= k`): the triangular index `k * (k + 1) / 2 + j`, as
+//!   used to order the genotypes of the VCF `PL` and `GP` fields.
+//! - a phased diploid call `j|k`: the triangular index of `j` and `j + k`, which keeps the order of
+//!   the alleles.
+//!
+//! Hail does not support calls with a ploidy above 2.
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use super::{HailValue, HailValueRef};
+
+/// The largest value of the alleles that fits in a packed call (which is 29 bits).
+const MAX_ALLELES: u64 = (u32::MAX >> 3) as u64;
+
+/// A decoded genotype call (see the [module](self) documentation for its layout).
+///
+/// Calls are built from their packed form with [TryFrom] (which refuses ploidies above 2), or with
+/// [Call::empty], [Call::haploid], and [Call::diploid], and turn back into their packed form with
+/// [Call::packed].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Call(u32);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CallError {
+    /// The packed call has a ploidy of 3, which Hail does not support.
+    Ploidy(u32),
+    /// The alleles are too large to be packed into 32 bits.
+    Overflow,
+    /// The text is not a call like `0/1`, `0|1`, `0`, or `-`.
+    Parse(String),
+}
+
+impl Call {
+    /// A call with no alleles (written `-`).
+    pub fn empty(phased: bool) -> Self {
+        Call::pack(0, 0, phased)
+    }
+
+    pub fn haploid(allele: u32, phased: bool) -> Result<Self, CallError> {
+        if u64::from(allele) > MAX_ALLELES {
+            return Err(CallError::Overflow);
+        }
+        Ok(Call::pack(allele, 1, phased))
+    }
+
+    /// A diploid call, the alleles of unphased calls are sorted (so `1/0` is `0/1`).
+    pub fn diploid(first: u32, second: u32, phased: bool) -> Result<Self, CallError> {
+        let alleles = if phased {
+            triangular_index(first, u64::from(first) + u64::from(second))
+        } else {
+            triangular_index(first.min(second), u64::from(first.max(second)))
+        };
+        if alleles > MAX_ALLELES {
+            return Err(CallError::Overflow);
+        }
+        Ok(Call::pack(alleles as u32, 2, phased))
+    }
+
+    fn pack(alleles: u32, ploidy: u32, phased: bool) -> Self {
+        Call(alleles << 3 | ploidy << 1 | phased as u32)
+    }
+
+    /// The call as stored by Hail.
+    pub fn packed(self) -> u32 {
+        self.0
+    }
+
+    pub fn ploidy(self) -> usize {
+        ((self.0 >> 1) & 0b11) as usize
+    }
+
+    pub fn is_phased(self) -> bool {
+        self.0 & 1 == 1
+    }
+
+    /// The allele indices, in order for phased calls, and sorted for unphased ones.
+    pub fn alleles(self) -> Vec<u32> {
+        self.pair()[..self.ploidy()].to_vec()
+    }
+
+    /// The allele at `index` (0 or 1), if the call has that many.
+    pub fn allele(self, index: usize) -> Option<u32> {
+        if index < self.ploidy() {
+            Some(self.pair()[index])
+        } else {
+            None
+        }
+    }
+
+    /// The alleles, padded with zeros up to a diploid call.
+    fn pair(self) -> [u32; 2] {
+        let alleles = self.0 >> 3;
+        match self.ploidy() {
+            0 => [0, 0],
+            1 => [alleles, 0],
+            _ => {
+                let (j, k) = inverse_triangular_index(alleles);
+                if self.is_phased() {
+                    [j, k - j]
+                } else {
+                    [j, k]
+                }
+            }
+        }
+    }
+
+    /// Whether every allele is the reference (and there is at least one).
+    pub fn is_hom_ref(self) -> bool {
+        self.ploidy() > 0 && self.alleles().iter().all(|&allele| allele == 0)
+    }
+
+    /// Whether the call is diploid with two different alleles.
+    pub fn is_het(self) -> bool {
+        let [first, second] = self.pair();
+        self.ploidy() == 2 && first != second
+    }
+
+    /// Whether every allele is the same alternate allele (and there is at least one).
+    pub fn is_hom_var(self) -> bool {
+        let alleles = self.alleles();
+        !alleles.is_empty() && alleles[0] != 0 && alleles.iter().all(|&a| a == alleles[0])
+    }
+
+    /// The number of alleles that are not the reference.
+    pub fn n_alt_alleles(self) -> usize {
+        self.alleles().iter().filter(|&&allele| allele != 0).count()
+    }
+
+    /// The index of the genotype among the unphased diploid genotypes (`0/0`, `0/1`, `1/1`, `0/2`,
+    /// ...), the order of the VCF `PL` and `GP` fields. Phased calls are indexed as if unphased.
+    ///
+    /// Only diploid calls have an index.
+    pub fn unphased_diploid_gt_index(self) -> Option<u32> {
+        if self.ploidy() != 2 {
+            return None;
+        }
+        if !self.is_phased() {
+            return Some(self.0 >> 3);
+        }
+        let [first, second] = self.pair();
+        u32::try_from(triangular_index(
+            first.min(second),
+            u64::from(first.max(second)),
+        ))
+        .ok()
+    }
+}
+
+/// The index of the pair `(j, k)`, with `j <= k`, in the order `(0, 0), (0, 1), (1, 1), (0, 2)...`
+fn triangular_index(j: u32, k: u64) -> u64 {
+    // Saturates rather than overflows, as anything this large cannot be packed anyway.
+    k.saturating_mul(k + 1) / 2 + u64::from(j)
+}
+
+/// The pair `(j, k)`, with `j <= k`, of a [triangular_index].
+fn inverse_triangular_index(index: u32) -> (u32, u32) {
+    let index = u64::from(index);
+    // An estimate, corrected for the rounding of the square root.
+    let mut k = (((8 * index + 1) as f64).sqrt() as u64).saturating_sub(1) / 2;
+    while k * (k + 1) / 2 > index {
+        k -= 1;
+    }
+    while (k + 1) * (k + 2) / 2 <= index {
+        k += 1;
+    }
+    ((index - k * (k + 1) / 2) as u32, k as u32)
+}
+
+impl TryFrom<u32> for Call {
+    type Error = CallError;
+
+    fn try_from(packed: u32) -> Result<Self, Self::Error> {
+        let call = Call(packed);
+        if call.ploidy() > 2 {
+            return Err(CallError::Ploidy(packed));
+        }
+        Ok(call)
+    }
+}
+
+impl From<Call> for u32 {
+    fn from(call: Call) -> Self {
+        call.0
+    }
+}
+
+impl From<Call> for HailValue {
+    fn from(call: Call) -> Self {
+        HailValue::Call(call.0)
+    }
+}
+
+impl HailValue {
+    /// The decoded call, if the value is a call (of a supported ploidy).
+    pub fn as_call(&self) -> Option<Call> {
+        match self {
+            HailValue::Call(packed) => Call::try_from(*packed).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl HailValueRef<'_> {
+    /// The decoded call, if the value is a call (of a supported ploidy).
+    pub fn as_call(&self) -> Option<Call> {
+        match self {
+            HailValueRef::Call(packed) => Call::try_from(*packed).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Writes the call as in a VCF: `0/1` or `0|1`, with a leading `|` for phased haploid calls, and
+/// `-` for a call with no alleles (as Hail does).
+impl Display for Call {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let separator = if self.is_phased() { "|" } else { "/" };
+        match self.ploidy() {
+            0 if self.is_phased() => write!(f, "|-"),
+            0 => write!(f, "-"),
+            1 if self.is_phased() => write!(f, "|{}", self.pair()[0]),
+            1 => write!(f, "{}", self.pair()[0]),
+            _ => {
+                let [first, second] = self.pair();
+                write!(f, "{}{}{}", first, separator, second)
+            }
+        }
+    }
+}
+
+/// The inverse of [Display].
+impl FromStr for Call {
+    type Err = CallError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || CallError::Parse(s.to_owned());
+        let allele = |allele: &str| {
+            if allele.is_empty() || !allele.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            allele.parse::<u32>().map_err(|_| CallError::Overflow)
+        };
+
+        let (phased, rest) = match s.strip_prefix('|') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        if rest == "-" {
+            return Ok(Call::empty(phased));
+        }
+        if let Some((first, second)) = rest.split_once(['/', '|']) {
+            if phased {
+                return Err(invalid());
+            }
+            return Call::diploid(allele(first)?, allele(second)?, rest.contains('|'));
+        }
+        Call::haploid(allele(rest)?, phased)
+    }
+}
+
+impl Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CallError::Ploidy(packed) => write!(
+                f,
+                "the call {} has a ploidy of 3, only ploidies up to 2 are supported",
+                packed
+            ),
+            CallError::Overflow => write!(f, "the alleles are too large for a call"),
+            CallError::Parse(text) => write!(f, "invalid call: {:?}", text),
+        }
+    }
+}
+
+impl std::error::Error for CallError {}
@@ -32,7 +32,8 @@ pub enum ETypeShape {
     Binary,
     Float32,
     Float64,
-    /// All physical representations for this are *unsigned* (either u32le or +LEB128).
+    /// Signed, but written as the bits of its two's complement: either i32le, or those same bits
+    /// as unsigned LEB128 (so negative values always take five bytes).
     Int32,
     Int64,
     Boolean,
@@ -54,7 +55,7 @@ pub enum VirtualHint {
     /// [ETypeShape::BaseStruct] → [HailValue::Tuple]
     Tuple,
     /// [ETypeShape::Binary] → [HailValue::String]
-    /// Currently this is the only use of the Binary variant.
+    /// Without this hint, binaries are raw bytes ([HailValue::Binary]).
     String,
     /// [ETypeShape::BaseStruct] with fields "contig" and "position" → [HailValue::Locus]
     /// The inner string is the reference genome.
@@ -88,6 +89,7 @@ pub enum VTypeShape {
     Interval(Box<VType>),
 
     String,
+    Binary,
 
     Float32,
     Float64,
@@ -99,3 +101,119 @@ pub enum VTypeShape {
     Locus(String),
     Call,
 }
+
+/// A field name as written in the schemas: identifiers as they are, and other names between
+/// backticks, with backticks, backslashes and control characters escaped, as Hail does.
+pub fn escape_name(name: &str) -> std::borrow::Cow<'_, str> {
+    let mut chars = name.chars();
+    let is_identifier = chars
+        .next()
+        .is_some_and(|first| first.is_alphabetic() || first == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_');
+    if is_identifier {
+        return name.into();
+    }
+
+    let mut escaped = String::with_capacity(name.len() + 2);
+    escaped.push('`');
+    for c in name.chars() {
+        match c {
+            '`' => escaped.push_str("\\`"),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('`');
+    escaped.into()
+}
+
+/// Both schemas are displayed in the same format used by Hail in the metadata files, so that the
+/// output can be parsed back (by this crate or by Hail).
+mod schema_display {
+    use std::fmt::{self, Display, Formatter};
+
+    use super::{EType, ETypeShape, VType, VTypeShape};
+
+    impl Display for VType {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            if self.required {
+                write!(f, "+")?;
+            }
+            match &self.shape {
+                VTypeShape::Struct(fields) => {
+                    write!(f, "Struct")?;
+                    write_fields(f, fields)
+                }
+                VTypeShape::Tuple(types) => {
+                    write!(f, "Tuple[")?;
+                    write_sequence(f, types)?;
+                    write!(f, "]")
+                }
+                VTypeShape::Array(inner) => write!(f, "Array[{}]", inner),
+                VTypeShape::Set(inner) => write!(f, "Set[{}]", inner),
+                VTypeShape::Dict(key, value) => write!(f, "Dict[{},{}]", key, value),
+                VTypeShape::NDArray(inner, n) => write!(f, "NDArray[{},{}]", inner, n),
+                VTypeShape::Interval(inner) => write!(f, "Interval[{}]", inner),
+                VTypeShape::String => write!(f, "String"),
+                VTypeShape::Binary => write!(f, "Binary"),
+                VTypeShape::Float32 => write!(f, "Float32"),
+                VTypeShape::Float64 => write!(f, "Float64"),
+                VTypeShape::Int32 => write!(f, "Int32"),
+                VTypeShape::Int64 => write!(f, "Int64"),
+                VTypeShape::Boolean => write!(f, "Boolean"),
+                VTypeShape::Locus(reference) => write!(f, "Locus({})", reference),
+                VTypeShape::Call => write!(f, "Call"),
+            }
+        }
+    }
+
+    /// The virtual hints are not part of the encoded schema, so they are not displayed.
+    impl Display for EType {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            if self.required {
+                write!(f, "+")?;
+            }
+            match &self.shape {
+                ETypeShape::BaseStruct(fields) => {
+                    write!(f, "EBaseStruct")?;
+                    write_fields(f, fields)
+                }
+                ETypeShape::Array(inner) => write!(f, "EArray[{}]", inner),
+                ETypeShape::NdArrayColumnMajor(inner, n) => {
+                    write!(f, "ENDArrayColumnMajor[{},{}]", inner, n)
+                }
+                ETypeShape::Binary => write!(f, "EBinary"),
+                ETypeShape::Float32 => write!(f, "EFloat32"),
+                ETypeShape::Float64 => write!(f, "EFloat64"),
+                ETypeShape::Int32 => write!(f, "EInt32"),
+                ETypeShape::Int64 => write!(f, "EInt64"),
+                ETypeShape::Boolean => write!(f, "EBoolean"),
+            }
+        }
+    }
+
+    fn write_fields<T: Display>(f: &mut Formatter<'_>, fields: &[(String, T)]) -> fmt::Result {
+        write!(f, "{{")?;
+        for (index, (name, field_type)) in fields.iter().enumerate() {
+            if index > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}:{}", super::escape_name(name), field_type)?;
+        }
+        write!(f, "}}")
+    }
+
+    fn write_sequence<T: Display>(f: &mut Formatter<'_>, types: &[T]) -> fmt::Result {
+        for (index, inner) in types.iter().enumerate() {
+            if index > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", inner)?;
+        }
+        Ok(())
+    }
+}
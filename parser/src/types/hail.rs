@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use crate::types::metadata::{MatrixMetadata, TableMetadata};
 
@@ -42,6 +42,43 @@ pub struct Component {
     // index: Option<Index>,
 }
 
+/// A subset of the partitions of a [Component], see [Partition].
+#[derive(Clone, Debug)]
+pub struct PartialComponent {
+    pub partitions: Vec<Partition>,
+    pub metadata: ComponentMetadata,
+}
+
+/// A single partition (part file) of a component, with its position in the full component.
+#[derive(Clone, Debug)]
+pub struct Partition {
+    /// The index of the partition in the component's part files.
+    pub index: usize,
+    /// The number of rows that precede this partition in the full component.
+    /// It is only known when the partition counts are available (that is, for (matrix) tables).
+    pub row_offset: Option<u64>,
+    pub rows: Vec<HailValue>,
+}
+
+/// A variant of [Table] where only some of the row partitions have been loaded.
+#[derive(Clone, Debug)]
+pub struct PartialTable {
+    pub globals: Component,
+    pub rows: PartialComponent,
+    pub metadata: TableMetadata,
+}
+
+/// A variant of [Matrix] where only some of the row partitions have been loaded.
+/// The rows and entries share the same partitioning, so the same partitions are loaded for both.
+#[derive(Clone, Debug)]
+pub struct PartialMatrix {
+    pub globals: Component,
+    pub cols: Component,
+    pub rows: PartialComponent,
+    pub entries: PartialComponent,
+    pub metadata: MatrixMetadata,
+}
+
 /// HailValue is an enum that represents any possible value value that data in Hail (matrix) tables
 /// may take.
 ///
@@ -55,7 +92,7 @@ pub enum HailValue {
     Tuple(Vec<HailValue>),
 
     Array(Vec<HailValue>),
-    Set(Vec<HailValue>),
+    Set(BTreeSet<HailValue>),
     Dict(BTreeMap<HailValue, HailValue>),
     NDArray(ndarray::ArrayD<HailValue>),
     Interval {
@@ -66,10 +103,12 @@ pub enum HailValue {
     },
 
     String(String),
+    /// Raw bytes, for binaries that are not strings.
+    Binary(Vec<u8>),
 
     Float32(f32),
     Float64(f64),
-    Int32(u32),
+    Int32(i32),
     Int64(i64),
 
     Boolean(bool),
@@ -83,7 +122,8 @@ pub enum HailValue {
     Missing,
 }
 
-/// These traits are implemented manually because of the missing implementations on [f32] and [f64].
+/// These traits (and [Hash](std::hash::Hash)) are implemented manually because of the missing
+/// implementations on [f32] and [f64].
 /// Those implementations are missing because the NaN value in floats is not equal to itself,
 /// meaning that equality is not Reflexive, and unambiguous ordering is impossible as per the spec
 /// (IEEE 754-2008 section 5.11).
@@ -92,8 +132,17 @@ pub enum HailValue {
 /// [HailValue::Missing] (the same value as for missing structs/array elements).
 /// Order-wise, [HailValue::Missing] will always be the 'smallest' value and equal to itself.
 ///
+/// This order is total, so that values can be used in sorted collections, but it is not the order
+/// of Hail (which sorts missing values last, loci by the contigs of their reference genome, and
+/// struct fields in the order of their type): see [HailOrdering](crate::types::HailOrdering).
+///
 /// More: <https://doc.rust-lang.org/std/cmp/trait.PartialOrd.html#how-can-i-implement-partialord>
 mod hail_value_comparisons {
+    use std::{
+        cmp::Ordering,
+        hash::{Hash, Hasher},
+    };
+
     use super::HailValue;
 
     /// [Eq] is a marker trait indicating that the [PartialEq] implementation is Reflexive
@@ -104,62 +153,78 @@ mod hail_value_comparisons {
     /// Note that [PartialEq] already guarantees the equality is Symmetric and Transitive.
     impl Eq for HailValue {}
 
-    /// Ordering of instances of [HailValue] is possible because of two project-level invariants:
-    /// - Any NaN value in floats is replaced by [HailValue::Missing] which is always the 'smallest'
-    ///   number in any category.
-    /// - There is no meaningless comparisons across categories. This mean that in this current
-    ///   form we do not support mappings where the key can have different types.
-    ///   Both [HailValue::Struct] and [HailValue::Dict] respect this invariant.
-    ///   Note: if we wished to support that use-case in the future, we would need to
-    ///    use a HashMap and carefully implement the [Hash](core::hash::Hash) trait to ensure it
-    ///    remains aligned with [PartialEq] and [Ord]
+    /// Ordering of instances of [HailValue] is possible because any NaN value in floats is replaced
+    /// by [HailValue::Missing], which is always the 'smallest' value in any category.
+    ///
+    /// Values of the same variant are compared by their contents:
+    /// - loci on the same contig (of the same reference) by position, and otherwise by reference,
+    ///   contig name, and position.
+    /// - intervals by start, then by end, with an included start before an excluded one and an
+    ///   excluded end before an included one.
+    /// - ndarrays by shape, then by their elements in row major order.
     ///
-    /// Given these invariants, any illegal ordering will be turned into a
-    /// [Ordering::Less](std::cmp::Ordering::Less).
+    /// Values of different variants (which do not appear in the same column, and so are not
+    /// compared in practice) are ordered by variant, so that the order remains total.
     impl Ord for HailValue {
-        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-            self.partial_cmp(other).unwrap_or(std::cmp::Ordering::Less)
-        }
-    }
-
-    /// Comparisons make sense only among instances of the same [HailValue] variant.
-    /// Comparisons between the different numeric types are intentionally left out for now,
-    /// but might be implemented in the future for convenience.
-    impl PartialOrd for HailValue {
-        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        fn cmp(&self, other: &Self) -> Ordering {
             match (self, other) {
                 (HailValue::Struct(self_map), HailValue::Struct(other_map)) => {
-                    self_map.partial_cmp(other_map)
-                }
-                (HailValue::Array(self_vec), HailValue::Array(other_vec)) => {
-                    self_vec.partial_cmp(other_vec)
+                    self_map.cmp(other_map)
                 }
-                (HailValue::Set(self_vec), HailValue::Set(other_vec)) => {
-                    self_vec.partial_cmp(other_vec)
+                (HailValue::Tuple(self_vec), HailValue::Tuple(other_vec)) => {
+                    self_vec.cmp(other_vec)
                 }
-                (HailValue::Dict(self_map), HailValue::Dict(other_map)) => {
-                    self_map.partial_cmp(other_map)
+                (HailValue::Array(self_vec), HailValue::Array(other_vec)) => {
+                    self_vec.cmp(other_vec)
                 }
+                (HailValue::Set(self_set), HailValue::Set(other_set)) => self_set.cmp(other_set),
+                (HailValue::Dict(self_map), HailValue::Dict(other_map)) => self_map.cmp(other_map),
+                (HailValue::NDArray(self_array), HailValue::NDArray(other_array)) => self_array
+                    .shape()
+                    .cmp(other_array.shape())
+                    .then_with(|| self_array.iter().cmp(other_array.iter())),
+                (
+                    HailValue::Interval {
+                        start: self_start,
+                        end: self_end,
+                        includes_start: self_includes_start,
+                        includes_end: self_includes_end,
+                    },
+                    HailValue::Interval {
+                        start: other_start,
+                        end: other_end,
+                        includes_start: other_includes_start,
+                        includes_end: other_includes_end,
+                    },
+                ) => self_start
+                    .cmp(other_start)
+                    .then_with(|| other_includes_start.cmp(self_includes_start))
+                    .then_with(|| self_end.cmp(other_end))
+                    .then_with(|| self_includes_end.cmp(other_includes_end)),
 
                 (HailValue::String(self_inner), HailValue::String(other_inner)) => {
-                    self_inner.partial_cmp(other_inner)
+                    self_inner.cmp(other_inner)
                 }
-
-                (HailValue::Float32(self_inner), HailValue::Float32(other_inner)) => {
-                    self_inner.partial_cmp(other_inner)
-                }
-                (HailValue::Float64(self_inner), HailValue::Float64(other_inner)) => {
-                    self_inner.partial_cmp(other_inner)
+                (HailValue::Binary(self_inner), HailValue::Binary(other_inner)) => {
+                    self_inner.cmp(other_inner)
                 }
+
+                // Floats are never NaN (see above), the fallback only keeps the order total.
+                (HailValue::Float32(self_inner), HailValue::Float32(other_inner)) => self_inner
+                    .partial_cmp(other_inner)
+                    .unwrap_or_else(|| self_inner.total_cmp(other_inner)),
+                (HailValue::Float64(self_inner), HailValue::Float64(other_inner)) => self_inner
+                    .partial_cmp(other_inner)
+                    .unwrap_or_else(|| self_inner.total_cmp(other_inner)),
                 (HailValue::Int32(self_inner), HailValue::Int32(other_inner)) => {
-                    self_inner.partial_cmp(other_inner)
+                    self_inner.cmp(other_inner)
                 }
                 (HailValue::Int64(self_inner), HailValue::Int64(other_inner)) => {
-                    self_inner.partial_cmp(other_inner)
+                    self_inner.cmp(other_inner)
                 }
 
                 (HailValue::Boolean(self_inner), HailValue::Boolean(other_inner)) => {
-                    self_inner.partial_cmp(other_inner)
+                    self_inner.cmp(other_inner)
                 }
 
                 (
@@ -173,30 +238,31 @@ mod hail_value_comparisons {
                         position: other_position,
                         reference: other_reference,
                     },
-                ) => {
-                    // Do contigs have an ordering?
-                    if self_contig == other_contig && self_reference == other_reference {
-                        self_position.partial_cmp(other_position)
-                    } else {
-                        None
-                    }
-                }
+                ) => (self_reference, self_contig, self_position).cmp(&(
+                    other_reference,
+                    other_contig,
+                    other_position,
+                )),
                 (HailValue::Call(self_inner), HailValue::Call(other_inner)) => {
-                    self_inner.partial_cmp(other_inner)
+                    self_inner.cmp(other_inner)
                 }
 
                 // A missing value is always the 'smallest' value.
-                (HailValue::Missing, HailValue::Missing) => Some(std::cmp::Ordering::Equal),
-                (HailValue::Missing, _) => Some(std::cmp::Ordering::Less),
-                (_, HailValue::Missing) => Some(std::cmp::Ordering::Greater),
+                (HailValue::Missing, HailValue::Missing) => Ordering::Equal,
+                (HailValue::Missing, _) => Ordering::Less,
+                (_, HailValue::Missing) => Ordering::Greater,
 
-                // UNDEFINED BEHAVIOUR
-                // If the two are not the same, then there is no point comparing.
-                (_, _) => None,
+                (_, _) => self.variant_index().cmp(&other.variant_index()),
             }
         }
     }
 
+    impl PartialOrd for HailValue {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
     /// In the current implementation each [HailValue] variant is equal only to instances of the
     /// same type when the contents are also the same.
     /// This means that direct equality between different numbers variants will always return false
@@ -204,66 +270,95 @@ mod hail_value_comparisons {
     ///
     /// [HailValue::Missing] is equal to itself and different from any other value.
     ///
-    /// We are implementing [PartialEq] manually because it is not reccomended to implement some of
-    /// these comparison trait manually and some by derivation.
+    /// Equality agrees with [Ord]: two values are equal when neither is before the other.
     impl PartialEq for HailValue {
         fn eq(&self, other: &Self) -> bool {
-            match (self, other) {
-                (HailValue::Struct(self_map), HailValue::Struct(other_map)) => {
-                    self_map.eq(other_map)
-                }
-                (HailValue::Array(self_vec), HailValue::Array(other_vec)) => self_vec.eq(other_vec),
-                (HailValue::Set(self_vec), HailValue::Set(other_vec)) => self_vec.eq(other_vec),
-                (HailValue::Dict(self_map), HailValue::Dict(other_map)) => self_map.eq(other_map),
-
-                (HailValue::String(self_inner), HailValue::String(other_inner)) => {
-                    self_inner.eq(other_inner)
-                }
+            self.cmp(other) == Ordering::Equal
+        }
+    }
 
-                (HailValue::Float32(self_inner), HailValue::Float32(other_inner)) => {
-                    self_inner.eq(other_inner)
-                }
-                (HailValue::Float64(self_inner), HailValue::Float64(other_inner)) => {
-                    self_inner.eq(other_inner)
-                }
-                (HailValue::Int32(self_inner), HailValue::Int32(other_inner)) => {
-                    self_inner.eq(other_inner)
+    /// Hashing agrees with [Eq]: equal values have the same hash, so that values can be used in
+    /// hash maps and sets (for hash joins, or distinct counts, without sorting).
+    ///
+    /// The variant is hashed before the contents, and `-0.0` is hashed as `0.0`, as the two are
+    /// equal. Like [Ord], this relies on floats never being NaN.
+    impl Hash for HailValue {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.variant_index().hash(state);
+            match self {
+                HailValue::Struct(fields) => fields.hash(state),
+                HailValue::Tuple(values) | HailValue::Array(values) => values.hash(state),
+                HailValue::Set(values) => values.hash(state),
+                HailValue::Dict(entries) => entries.hash(state),
+                HailValue::NDArray(array) => {
+                    // The shape gives the number of elements.
+                    array.shape().hash(state);
+                    array.iter().for_each(|element| element.hash(state));
                 }
-                (HailValue::Int64(self_inner), HailValue::Int64(other_inner)) => {
-                    self_inner.eq(other_inner)
+                HailValue::Interval {
+                    start,
+                    end,
+                    includes_start,
+                    includes_end,
+                } => {
+                    start.hash(state);
+                    end.hash(state);
+                    includes_start.hash(state);
+                    includes_end.hash(state);
                 }
 
-                (HailValue::Boolean(self_inner), HailValue::Boolean(other_inner)) => {
-                    self_inner.eq(other_inner)
-                }
+                HailValue::String(inner) => inner.hash(state),
+                HailValue::Binary(inner) => inner.hash(state),
 
-                (
-                    HailValue::Locus {
-                        contig: self_contig,
-                        position: self_position,
-                        reference: self_reference,
-                    },
-                    HailValue::Locus {
-                        contig: other_contig,
-                        position: other_position,
-                        reference: other_reference,
-                    },
-                ) => {
-                    self_contig.eq(other_contig)
-                        && self_position.eq(other_position)
-                        && self_reference.eq(other_reference)
+                HailValue::Float32(inner) => {
+                    let inner = if *inner == 0.0 { 0.0 } else { *inner };
+                    inner.to_bits().hash(state)
                 }
-                (HailValue::Call(self_inner), HailValue::Call(other_inner)) => {
-                    self_inner.eq(other_inner)
+                HailValue::Float64(inner) => {
+                    let inner = if *inner == 0.0 { 0.0 } else { *inner };
+                    inner.to_bits().hash(state)
                 }
+                HailValue::Int32(inner) => inner.hash(state),
+                HailValue::Int64(inner) => inner.hash(state),
+
+                HailValue::Boolean(inner) => inner.hash(state),
 
-                // A missing value is oly equal to itself
-                (HailValue::Missing, HailValue::Missing) => true,
-                (HailValue::Missing, _) => false,
-                (_, HailValue::Missing) => false,
+                HailValue::Locus {
+                    contig,
+                    position,
+                    reference,
+                } => {
+                    contig.hash(state);
+                    position.hash(state);
+                    reference.hash(state);
+                }
+                HailValue::Call(inner) => inner.hash(state),
+                HailValue::Missing => (),
+            }
+        }
+    }
 
-                // Currently, different HailValue variants are always different.
-                (_, _) => false,
+    impl HailValue {
+        /// The position of the variant in the declaration of [HailValue].
+        fn variant_index(&self) -> u8 {
+            match self {
+                HailValue::Struct(_) => 0,
+                HailValue::Tuple(_) => 1,
+                HailValue::Array(_) => 2,
+                HailValue::Set(_) => 3,
+                HailValue::Dict(_) => 4,
+                HailValue::NDArray(_) => 5,
+                HailValue::Interval { .. } => 6,
+                HailValue::String(_) => 7,
+                HailValue::Binary(_) => 8,
+                HailValue::Float32(_) => 9,
+                HailValue::Float64(_) => 10,
+                HailValue::Int32(_) => 11,
+                HailValue::Int64(_) => 12,
+                HailValue::Boolean(_) => 13,
+                HailValue::Locus { .. } => 14,
+                HailValue::Call(_) => 15,
+                HailValue::Missing => 16,
             }
         }
     }
@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+
+use super::HailValue;
+
+/// A borrowed version of [HailValue], decoded with
+/// [EType::decode_ref_from](crate::types::EType::decode_ref_from).
+///
+/// Strings (including the contigs of loci) and binaries point straight into the decoded data, and field names
+/// and reference genomes into the schema, so nothing is allocated for them.
+/// This makes read-only scans much cheaper, while [HailValueRef::to_owned] recovers a [HailValue]
+/// for the values that need to be kept around.
+///
+/// As with [HailValue], NaN floats are decoded as [HailValueRef::Missing].
+#[derive(Clone, Debug, PartialEq)]
+pub enum HailValueRef<'a> {
+    Struct(BTreeMap<&'a str, HailValueRef<'a>>),
+    Tuple(Vec<HailValueRef<'a>>),
+
+    Array(Vec<HailValueRef<'a>>),
+    Set(Vec<HailValueRef<'a>>),
+    /// The entries of the dict, in the order they were encoded (that is, sorted by key).
+    Dict(Vec<(HailValueRef<'a>, HailValueRef<'a>)>),
+    NDArray(ndarray::ArrayD<HailValueRef<'a>>),
+    Interval {
+        start: Box<HailValueRef<'a>>,
+        end: Box<HailValueRef<'a>>,
+        includes_start: bool,
+        includes_end: bool,
+    },
+
+    String(&'a str),
+    Binary(&'a [u8]),
+
+    Float32(f32),
+    Float64(f64),
+    Int32(i32),
+    Int64(i64),
+
+    Boolean(bool),
+
+    Locus {
+        contig: &'a str,
+        position: u32,
+        reference: &'a str,
+    },
+    Call(u32),
+    Missing,
+}
+
+impl HailValueRef<'_> {
+    /// Copies the value (and everything it borrows) into a [HailValue].
+    pub fn to_owned(&self) -> HailValue {
+        match self {
+            HailValueRef::Struct(fields) => HailValue::Struct(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.to_owned()))
+                    .collect(),
+            ),
+            HailValueRef::Tuple(values) => HailValue::Tuple(to_owned_values(values)),
+            HailValueRef::Array(values) => HailValue::Array(to_owned_values(values)),
+            HailValueRef::Set(values) => {
+                HailValue::Set(values.iter().map(HailValueRef::to_owned).collect())
+            }
+            HailValueRef::Dict(entries) => HailValue::Dict(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                    .collect(),
+            ),
+            HailValueRef::NDArray(array) => HailValue::NDArray(array.map(HailValueRef::to_owned)),
+            HailValueRef::Interval {
+                start,
+                end,
+                includes_start,
+                includes_end,
+            } => HailValue::Interval {
+                start: Box::new(HailValueRef::to_owned(start)),
+                end: Box::new(HailValueRef::to_owned(end)),
+                includes_start: *includes_start,
+                includes_end: *includes_end,
+            },
+            HailValueRef::String(string) => HailValue::String(string.to_string()),
+            HailValueRef::Binary(bytes) => HailValue::Binary(bytes.to_vec()),
+            HailValueRef::Float32(float) => HailValue::Float32(*float),
+            HailValueRef::Float64(float) => HailValue::Float64(*float),
+            HailValueRef::Int32(int) => HailValue::Int32(*int),
+            HailValueRef::Int64(int) => HailValue::Int64(*int),
+            HailValueRef::Boolean(flag) => HailValue::Boolean(*flag),
+            HailValueRef::Locus {
+                contig,
+                position,
+                reference,
+            } => HailValue::Locus {
+                contig: contig.to_string(),
+                position: *position,
+                reference: reference.to_string(),
+            },
+            HailValueRef::Call(call) => HailValue::Call(*call),
+            HailValueRef::Missing => HailValue::Missing,
+        }
+    }
+}
+
+fn to_owned_values(values: &[HailValueRef<'_>]) -> Vec<HailValue> {
+    values.iter().map(HailValueRef::to_owned).collect()
+}
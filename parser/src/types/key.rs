@@ -0,0 +1,73 @@
+use std::cmp::Ordering;
+
+use super::{HailOrdering, HailValue, VType};
+
+/// Compares a key with a (possibly partial) searched key, in the order of Hail (see
+/// [HailOrdering]), given the name and type of each field of the key.
+///
+/// Keys are compared field by field, in key order, up to the first field missing from the
+/// searched key, so a prefix of the key (`{x: 1}` for a key `{x, y, z}`) is equal to all the keys
+/// starting with it.
+/// A value that is not a struct stands for the first field of the key.
+pub fn compare_key(
+    key_fields: &[(String, VType)],
+    ordering: &HailOrdering,
+    key: &HailValue,
+    searched: &HailValue,
+) -> Ordering {
+    let missing = HailValue::Missing;
+    for (position, (name, v_type)) in key_fields.iter().enumerate() {
+        let searched_value = match field(searched, position, name) {
+            Some(searched_value) => searched_value,
+            None => break,
+        };
+        let value = field(key, position, name).unwrap_or(&missing);
+        let compared = ordering.compare(v_type, value, searched_value);
+        if compared != Ordering::Equal {
+            return compared;
+        }
+    }
+
+    Ordering::Equal
+}
+
+/// Compares two endpoints of intervals of (possibly partial) keys, as Hail compares its
+/// `IntervalEndpoint`s: an endpoint sits just before all the keys starting with its key, or just
+/// after them when `after` is set.
+///
+/// The keys are compared on the fields they both set, then the endpoint with the shorter key
+/// sits before or after all the keys starting with the longer one.
+/// The start of an interval is after its key when excluded, and its end when included.
+pub fn compare_endpoints(
+    key_fields: &[(String, VType)],
+    ordering: &HailOrdering,
+    (key, after): (&HailValue, bool),
+    (other, other_after): (&HailValue, bool),
+) -> Ordering {
+    for (position, (name, v_type)) in key_fields.iter().enumerate() {
+        match (field(key, position, name), field(other, position, name)) {
+            (Some(value), Some(other_value)) => {
+                let compared = ordering.compare(v_type, value, other_value);
+                if compared != Ordering::Equal {
+                    return compared;
+                }
+            }
+            (None, Some(_)) if after => return Ordering::Greater,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) if other_after => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (None, None) => break,
+        }
+    }
+
+    after.cmp(&other_after)
+}
+
+/// The field `name` of a (possibly partial) key, at the given position in the key.
+fn field<'a>(key: &'a HailValue, position: usize, name: &str) -> Option<&'a HailValue> {
+    match key {
+        HailValue::Struct(fields) => fields.get(name),
+        value if position == 0 => Some(value),
+        _ => None,
+    }
+}
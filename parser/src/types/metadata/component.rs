@@ -1,4 +1,11 @@
-use crate::types::{EType, VType};
+use std::ops::{Bound, RangeBounds};
+
+use crate::{
+    error::Result,
+    types::{
+        key::compare_endpoints, EType, HailOrdering, HailValue, Projection, VType, VTypeShape,
+    },
+};
 
 #[derive(Clone, Debug)]
 pub struct ComponentMetadata {
@@ -8,6 +15,132 @@ pub struct ComponentMetadata {
     pub codec_spec: CodecSpec,
     pub buffer_spec: super::shared::BufferSpec,
     pub part_files: Vec<String>,
+    pub index_spec: Option<ComponentIndexSpec>,
+    /// The interval of keys covered by each partition, as a [HailValue::Interval] of key structs.
+    /// It is empty for unpartitioned components.
+    pub range_bounds: Vec<HailValue>,
+}
+
+impl ComponentMetadata {
+    /// The metadata of the component as seen through a projection: the schemas only hold the
+    /// selected fields, and the key is truncated to its longest prefix of fully selected fields.
+    ///
+    /// The part files, buffer spec, and index are unchanged, as they describe the files on disk.
+    pub fn project(&self, projection: &Projection) -> Result<ComponentMetadata> {
+        let key = match projection {
+            Projection::All => self.key.clone(),
+            Projection::Fields(selected) => self
+                .key
+                .iter()
+                .take_while(|field| selected.get(*field).is_some_and(Projection::is_all))
+                .cloned()
+                .collect(),
+        };
+
+        Ok(ComponentMetadata {
+            key,
+            virtual_type: projection.virtual_type(&self.virtual_type)?,
+            encoded_type: projection.encoded_type(&self.encoded_type)?,
+            ..self.clone()
+        })
+    }
+
+    /// The names and types of the fields of the key, in key order.
+    pub fn key_type(&self) -> Vec<(String, VType)> {
+        let fields = match &self.virtual_type.shape {
+            VTypeShape::Struct(fields) => fields,
+            _ => return vec![],
+        };
+        self.key
+            .iter()
+            .filter_map(|name| fields.iter().find(|(field, _)| field == name))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the partitions whose keys might fall in the given range, using the range bounds of
+    /// each partition (so no data needs to be read).
+    ///
+    /// The bounds of the range and of the partitions are compared as in [compare_endpoints], so
+    /// they can be expressed in terms of a prefix of the key (for example, a locus range on a
+    /// `[locus, alleles]` key).
+    /// If the component has no key, or the range bounds are not known, all partitions are
+    /// returned.
+    ///
+    /// Loci are placed with the built-in reference genomes, see [overlapping_partitions_with] for
+    /// custom genomes.
+    ///
+    /// [overlapping_partitions_with]: ComponentMetadata::overlapping_partitions_with
+    pub fn overlapping_partitions<K, R>(&self, range: R) -> Vec<usize>
+    where
+        K: std::borrow::Borrow<HailValue>,
+        R: RangeBounds<K>,
+    {
+        self.overlapping_partitions_with(&HailOrdering::default(), range)
+    }
+
+    /// Like [overlapping_partitions](ComponentMetadata::overlapping_partitions), with the keys
+    /// compared in the given ordering (which knows the reference genomes of the loci).
+    pub fn overlapping_partitions_with<K, R>(&self, ordering: &HailOrdering, range: R) -> Vec<usize>
+    where
+        K: std::borrow::Borrow<HailValue>,
+        R: RangeBounds<K>,
+    {
+        if self.key.is_empty() || self.range_bounds.len() != self.part_files.len() {
+            return (0..self.part_files.len()).collect();
+        }
+
+        let key_type = self.key_type();
+        self.range_bounds
+            .iter()
+            .enumerate()
+            .filter(|(_, bounds)| may_overlap(&key_type, ordering, bounds, &range))
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+fn may_overlap<K, R>(
+    key: &[(String, VType)],
+    ordering: &HailOrdering,
+    bounds: &HailValue,
+    range: &R,
+) -> bool
+where
+    K: std::borrow::Borrow<HailValue>,
+    R: RangeBounds<K>,
+{
+    let (start, end, includes_start, includes_end) = match bounds {
+        HailValue::Interval {
+            start,
+            end,
+            includes_start,
+            includes_end,
+        } => (start, end, *includes_start, *includes_end),
+        _ => return true,
+    };
+
+    // The endpoints of the partition and of the range, compared as in [compare_endpoints]: they
+    // overlap when each starts before the other ends.
+    let compare = |endpoint, other| compare_endpoints(key, ordering, endpoint, other);
+    let partition_start = (start.as_ref(), !includes_start);
+    let partition_end = (end.as_ref(), includes_end);
+
+    // The partition ends before the start of the range.
+    let ends_before = match range.start_bound() {
+        Bound::Included(searched) => compare(partition_end, (searched.borrow(), false)).is_le(),
+        Bound::Excluded(searched) => compare(partition_end, (searched.borrow(), true)).is_le(),
+        Bound::Unbounded => false,
+    };
+
+    // The partition starts after the end of the range.
+    let starts_after = match range.end_bound() {
+        Bound::Included(searched) => compare(partition_start, (searched.borrow(), true)).is_ge(),
+        Bound::Excluded(searched) => compare(partition_start, (searched.borrow(), false)).is_ge(),
+        Bound::Unbounded => false,
+    };
+
+    !ends_before && !starts_after
 }
 
 #[derive(Clone, Debug)]
@@ -15,3 +148,19 @@ pub enum CodecSpec {
     TypedCodecSpec,
     PackCodecSpec,
 }
+
+/// A harmonised version of the index specifications, with fleshed out encoded types for both
+/// kinds of index node.
+#[derive(Clone, Debug)]
+pub struct ComponentIndexSpec {
+    /// The path of the folder holding the index files, relative to the component.
+    pub rel_path: String,
+    pub key_type: VType,
+    pub annotation_type: VType,
+    pub leaf_encoded_type: EType,
+    pub internal_node_encoded_type: EType,
+    pub buffer_spec: super::shared::BufferSpec,
+    /// When set, the offset of a record is the value of this annotation field rather than the
+    /// offset of the index entry (entries components share the index of the rows this way).
+    pub offset_field: Option<String>,
+}
@@ -12,7 +12,7 @@ pub struct RvdMetadataV1 {
     pub codec_spec: ComponentCodecSpec,
     pub index_spec: Option<IndexSpec>,
     pub part_files: Vec<String>,
-    pub j_range_bounds: Vec<JRangeBound>,
+    pub j_range_bounds: Vec<shared::JRangeBound>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -39,15 +39,7 @@ pub enum ComponentCodecSpec {
 #[serde(rename_all = "camelCase")]
 pub struct IndexSpec {
     pub rel_path: String,
-    pub key_type: String,
-    pub annotation_type: String,
-}
-
-#[derive(Deserialize, Clone, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct JRangeBound {
-    // start: HailValue,
-    // end: HailValue,
-    include_start: bool,
-    include_end: bool,
+    pub key_type: VType,
+    pub annotation_type: VType,
+    pub offset_field: Option<String>,
 }
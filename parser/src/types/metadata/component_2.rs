@@ -10,10 +10,12 @@ pub struct RVDMetadataV2 {
     pub key: Vec<String>,
     #[serde(rename = "_codecSpec")]
     pub codec_spec: ComponentCodecSpecV2,
-    // pub index_spec: Option<Something>,
+    #[serde(rename = "_indexSpec")]
+    pub index_spec: Option<IndexSpecV2>,
     #[serde(rename = "_partFiles")]
     pub part_files: Vec<String>,
-    // pub j_range_bounds: Vec<Something>,
+    #[serde(rename = "_jRangeBounds", default)]
+    pub j_range_bounds: Vec<shared::JRangeBound>,
     // pub attrs: Something,
 }
 
@@ -32,3 +34,19 @@ pub struct TypedCodecSpec {
     #[serde(rename = "_bufferSpec")]
     pub buffer_spec: shared::BufferSpec,
 }
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct IndexSpecV2 {
+    #[serde(rename = "_relPath")]
+    pub rel_path: String,
+    #[serde(rename = "_leafCodec")]
+    pub leaf_codec: ComponentCodecSpecV2,
+    #[serde(rename = "_internalNodeCodec")]
+    pub internal_node_codec: ComponentCodecSpecV2,
+    #[serde(rename = "_keyType")]
+    pub key_type: VType,
+    #[serde(rename = "_annotationType")]
+    pub annotation_type: VType,
+    #[serde(rename = "_offsetField")]
+    pub offset_field: Option<String>,
+}
@@ -0,0 +1,20 @@
+use serde::Deserialize;
+
+use crate::types::VType;
+
+/// The metadata of a single index file (the `metadata.json.gz` inside each `.idx` folder).
+///
+/// The index itself is a B-tree stored in the file at `index_path`, with the root node found at
+/// `root_offset`.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexMetadata {
+    pub file_version: u32,
+    pub branching_factor: u32,
+    pub height: u32,
+    pub key_type: VType,
+    pub annotation_type: VType,
+    pub n_keys: u64,
+    pub index_path: String,
+    pub root_offset: u64,
+}
@@ -1,6 +1,8 @@
+use std::collections::BTreeMap;
+
 use serde::Deserialize;
 
-use crate::types::VType;
+use crate::types::{ReferenceGenome, VType};
 
 use super::shared;
 
@@ -11,6 +13,12 @@ pub struct MatrixMetadata {
     pub references_rel_path: String,
     pub matrix_type: MatrixSchema,
     pub components: MatrixComponents,
+    /// The reference genomes of the loci of the matrix, by name. They are not part of the metadata
+    /// file, they are attached by the loaders (see [load_matrix_metadata]).
+    ///
+    /// [load_matrix_metadata]: crate::load::metadata::load_matrix_metadata
+    #[serde(skip)]
+    pub references: BTreeMap<String, ReferenceGenome>,
 }
 
 #[derive(Clone, Debug)]
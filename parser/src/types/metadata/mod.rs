@@ -3,11 +3,13 @@ use serde::Deserialize;
 pub mod component;
 pub mod component_1;
 pub mod component_2;
+pub mod index;
 pub mod matrix;
 pub mod shared;
 pub mod table;
 
 pub use component::ComponentMetadata;
+pub use index::IndexMetadata;
 pub use matrix::MatrixMetadata;
 pub use table::TableMetadata;
 
@@ -21,7 +23,9 @@ pub use table::TableMetadata;
 /// parsed along with the document.
 /// Note 2: A generalised [ComponentMetadata] type is also present to harmonise the various
 /// component metadata formats. The other component metadata types implement
-/// [Into]<ComponentMetadata>.
+/// [TryInto]<ComponentMetadata> (or [Into]<ComponentMetadata> when there is nothing to validate).
+///
+/// [TryInto]: std::convert::TryInto
 #[derive(Deserialize, Clone, Debug)]
 #[serde(tag = "name")]
 pub enum Metadata {
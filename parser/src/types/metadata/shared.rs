@@ -1,6 +1,6 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(tag = "name")]
 pub enum BufferSpec {
     LEB128BufferSpec {
@@ -66,6 +66,33 @@ impl BufferSpec {
             BufferSpec::StreamBufferSpec => false,
         }
     }
+
+    /// The size of the blocks the (uncompressed) data is split into, if it is split at all.
+    pub fn block_size(&self) -> Option<u32> {
+        match self {
+            BufferSpec::LEB128BufferSpec { child } => child.block_size(),
+            BufferSpec::BlockingBufferSpec { block_size, .. } => Some(*block_size),
+            BufferSpec::LZ4BlockBufferSpec { block_size, .. } => Some(*block_size),
+            BufferSpec::LZ4HCBlockBufferSpec { block_size, .. } => Some(*block_size),
+            BufferSpec::LZ4FastBlockBufferSpec { block_size, .. } => Some(*block_size),
+            BufferSpec::StreamBlockBufferSpec => None,
+            BufferSpec::StreamBufferSpec => None,
+        }
+    }
+
+    /// Whether the blocks are compressed with the slower, high compression, variant of LZ4.
+    /// It makes no difference when decompressing.
+    pub fn uses_high_compression(&self) -> bool {
+        match self {
+            BufferSpec::LEB128BufferSpec { child } => child.uses_high_compression(),
+            BufferSpec::BlockingBufferSpec { child, .. } => child.uses_high_compression(),
+            BufferSpec::LZ4BlockBufferSpec { .. } => false,
+            BufferSpec::LZ4HCBlockBufferSpec { .. } => true,
+            BufferSpec::LZ4FastBlockBufferSpec { .. } => false,
+            BufferSpec::StreamBlockBufferSpec => false,
+            BufferSpec::StreamBufferSpec => false,
+        }
+    }
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -79,3 +106,16 @@ pub struct PartitionCounts {
     pub name: String, // PartitionCountsComponentSpec
     pub counts: Vec<u32>,
 }
+
+/// The interval of keys covered by a partition.
+///
+/// The bounds are kept as raw json until the key type is known, they are then converted to a
+/// [HailValue::Interval](crate::HailValue::Interval) (see [ComponentMetadata::range_bounds](super::ComponentMetadata::range_bounds)).
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JRangeBound {
+    pub start: serde_json::Value,
+    pub end: serde_json::Value,
+    pub include_start: bool,
+    pub include_end: bool,
+}
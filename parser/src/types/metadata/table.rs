@@ -1,6 +1,8 @@
+use std::collections::BTreeMap;
+
 use serde::Deserialize;
 
-use crate::types::VType;
+use crate::types::{ReferenceGenome, VType};
 
 use super::shared;
 
@@ -11,6 +13,12 @@ pub struct TableMetadata {
     pub references_rel_path: String,
     pub table_type: TableSchema,
     pub components: TableComponents,
+    /// The reference genomes of the loci of the table, by name. They are not part of the metadata
+    /// file, they are attached by the loaders (see [load_table_metadata]).
+    ///
+    /// [load_table_metadata]: crate::load::metadata::load_table_metadata
+    #[serde(skip)]
+    pub references: BTreeMap<String, ReferenceGenome>,
 }
 
 #[derive(Clone, Debug)]
@@ -1,10 +1,22 @@
+pub mod call;
 pub mod encoding;
 pub mod hail;
+pub mod hail_ref;
+pub mod key;
 pub mod metadata;
+pub mod ordering;
+pub mod projection;
+pub mod reference;
 
+pub use call::Call;
 pub use encoding::{EType, ETypeShape, VType, VTypeShape};
 pub use hail::Component;
 pub use hail::HailValue;
 pub use hail::Matrix;
 pub use hail::Table;
+pub use hail::{PartialComponent, PartialMatrix, PartialTable, Partition};
+pub use hail_ref::HailValueRef;
 pub use metadata::Metadata;
+pub use ordering::HailOrdering;
+pub use projection::Projection;
+pub use reference::ReferenceGenome;
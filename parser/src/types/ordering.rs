@@ -0,0 +1,201 @@
+//! The ordering of values in Hail, which sorts the keys of (matrix) tables and their partitions.
+//!
+//! It differs from the [Ord] of [HailValue], which does not know the types or the reference genomes
+//! of the values:
+//! - missing values come last, rather than first.
+//! - loci are ordered by the index of their contig in the reference genome, then by position.
+//! - the fields of structs are compared in the order of the type, rather than by name.
+//! - the elements of sets and the entries of dicts are compared in this ordering (of their keys).
+//! - floats are ordered as in Java, with `-0.0` before `0.0`.
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+};
+
+use super::{reference::builtin_references, HailValue, ReferenceGenome, VType, VTypeShape};
+
+/// Compares values as Hail does, given their type (see the [module](self) documentation).
+///
+/// The loci are placed using the given reference genomes, or else the
+/// [built-in](ReferenceGenome::builtin) genomes of the same name. Loci on a contig that is not in
+/// their genome are placed after all the others, ordered by contig name.
+#[derive(Clone, Debug, Default)]
+pub struct HailOrdering {
+    references: BTreeMap<String, ReferenceGenome>,
+}
+
+impl HailOrdering {
+    /// An ordering using the given reference genomes, like the ones attached to the metadata of a
+    /// table (see [TableMetadata::references](super::metadata::TableMetadata::references)).
+    pub fn new(references: BTreeMap<String, ReferenceGenome>) -> Self {
+        HailOrdering { references }
+    }
+
+    pub fn compare(&self, v_type: &VType, value: &HailValue, other: &HailValue) -> Ordering {
+        match (value, other) {
+            (HailValue::Missing, HailValue::Missing) => return Ordering::Equal,
+            (HailValue::Missing, _) => return Ordering::Greater,
+            (_, HailValue::Missing) => return Ordering::Less,
+            _ => (),
+        }
+
+        match (&v_type.shape, value, other) {
+            (VTypeShape::Struct(types), HailValue::Struct(fields), HailValue::Struct(others)) => {
+                let missing = HailValue::Missing;
+                types
+                    .iter()
+                    .map(|(name, v_type)| {
+                        let field = fields.get(name).unwrap_or(&missing);
+                        let other = others.get(name).unwrap_or(&missing);
+                        self.compare(v_type, field, other)
+                    })
+                    .find(|ordering| *ordering != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            }
+            (VTypeShape::Tuple(types), HailValue::Tuple(values), HailValue::Tuple(others)) => types
+                .iter()
+                .zip(values.iter().zip(others))
+                .map(|(v_type, (value, other))| self.compare(v_type, value, other))
+                .find(|ordering| *ordering != Ordering::Equal)
+                .unwrap_or_else(|| values.len().cmp(&others.len())),
+            (VTypeShape::Array(element), HailValue::Array(values), HailValue::Array(others)) => {
+                self.compare_sequences(element, values.iter(), others.iter())
+            }
+            (VTypeShape::Set(element), HailValue::Set(values), HailValue::Set(others)) => {
+                let values = self.sorted_elements(element, values);
+                let others = self.sorted_elements(element, others);
+                self.compare_sequences(element, values.into_iter(), others.into_iter())
+            }
+            (VTypeShape::Dict(key, value), HailValue::Dict(entries), HailValue::Dict(others)) => {
+                let entries = self.sorted_entries(key, entries);
+                let others = self.sorted_entries(key, others);
+                entries
+                    .iter()
+                    .zip(&others)
+                    .map(|((entry_key, entry), (other_key, other))| {
+                        self.compare(key, entry_key, other_key)
+                            .then_with(|| self.compare(value, entry, other))
+                    })
+                    .find(|ordering| *ordering != Ordering::Equal)
+                    .unwrap_or_else(|| entries.len().cmp(&others.len()))
+            }
+            (
+                VTypeShape::NDArray(element, _),
+                HailValue::NDArray(array),
+                HailValue::NDArray(other),
+            ) => {
+                array.shape().cmp(other.shape()).then_with(|| {
+                    // The shapes are the same, so are the numbers of elements.
+                    array
+                        .iter()
+                        .zip(other.iter())
+                        .map(|(value, other)| self.compare(element, value, other))
+                        .find(|ordering| *ordering != Ordering::Equal)
+                        .unwrap_or(Ordering::Equal)
+                })
+            }
+            (
+                VTypeShape::Interval(point),
+                HailValue::Interval {
+                    start,
+                    end,
+                    includes_start,
+                    includes_end,
+                },
+                HailValue::Interval {
+                    start: other_start,
+                    end: other_end,
+                    includes_start: other_includes_start,
+                    includes_end: other_includes_end,
+                },
+            ) => self
+                .compare(point, start, other_start)
+                // An included start is before an excluded one, at the same point.
+                .then_with(|| other_includes_start.cmp(includes_start))
+                .then_with(|| self.compare(point, end, other_end))
+                // And an excluded end is before an included one.
+                .then_with(|| includes_end.cmp(other_includes_end)),
+            (
+                _,
+                HailValue::Locus {
+                    contig,
+                    position,
+                    reference,
+                },
+                HailValue::Locus {
+                    contig: other_contig,
+                    position: other_position,
+                    ..
+                },
+            ) => self
+                .compare_contigs(reference, contig, other_contig)
+                .then_with(|| position.cmp(other_position)),
+            (_, HailValue::Float32(float), HailValue::Float32(other)) => float.total_cmp(other),
+            (_, HailValue::Float64(float), HailValue::Float64(other)) => float.total_cmp(other),
+            // Calls are ordered as the integers they are stored as.
+            (_, HailValue::Call(call), HailValue::Call(other)) => {
+                (*call as i32).cmp(&(*other as i32))
+            }
+            // The other values are ordered in the same way as by [Ord].
+            _ => value.cmp(other),
+        }
+    }
+
+    /// Sorts values of the given type.
+    pub fn sort(&self, v_type: &VType, values: &mut [HailValue]) {
+        values.sort_by(|value, other| self.compare(v_type, value, other));
+    }
+
+    fn compare_sequences<'a>(
+        &self,
+        element: &VType,
+        values: impl ExactSizeIterator<Item = &'a HailValue>,
+        others: impl ExactSizeIterator<Item = &'a HailValue>,
+    ) -> Ordering {
+        let lengths = values.len().cmp(&others.len());
+        values
+            .zip(others)
+            .map(|(value, other)| self.compare(element, value, other))
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or(lengths)
+    }
+
+    /// The elements of a set, sorted in this ordering.
+    pub(crate) fn sorted_elements<'a>(
+        &self,
+        element: &VType,
+        values: &'a BTreeSet<HailValue>,
+    ) -> Vec<&'a HailValue> {
+        let mut values: Vec<_> = values.iter().collect();
+        values.sort_by(|value, other| self.compare(element, value, other));
+        values
+    }
+
+    /// The entries of a dict, sorted by key in this ordering.
+    pub(crate) fn sorted_entries<'a>(
+        &self,
+        key: &VType,
+        entries: &'a BTreeMap<HailValue, HailValue>,
+    ) -> Vec<(&'a HailValue, &'a HailValue)> {
+        let mut entries: Vec<_> = entries.iter().collect();
+        entries.sort_by(|(entry, _), (other, _)| self.compare(key, entry, other));
+        entries
+    }
+
+    fn compare_contigs(&self, reference: &str, contig: &str, other: &str) -> Ordering {
+        if contig == other {
+            return Ordering::Equal;
+        }
+        let genome = self
+            .references
+            .get(reference)
+            .or_else(|| builtin_references().get(reference));
+        let index = |contig| genome.and_then(|genome| genome.contig_index(contig));
+        match (index(contig), index(other)) {
+            (Some(index), Some(other_index)) => index.cmp(&other_index),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => contig.cmp(other),
+        }
+    }
+}
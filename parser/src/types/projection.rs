@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+
+use crate::error::{Error, Result};
+
+use super::{EType, ETypeShape, VType, VTypeShape};
+
+/// A selection of (possibly nested) fields of a struct, usually built from dotted paths with
+/// [Projection::from_paths].
+///
+/// Only plain structs can be projected: loci, intervals, tuples, and the elements of arrays are
+/// always selected (or skipped) as a whole.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Projection {
+    /// The whole value is selected.
+    All,
+    /// Only the listed fields of the struct are selected, each with its own projection.
+    Fields(BTreeMap<String, Projection>),
+}
+
+impl Projection {
+    /// Builds a projection from paths such as `"locus"` or `"info.AF"`.
+    ///
+    /// Selecting a field as a whole takes precedence over selecting some of its sub-fields, so
+    /// `["info", "info.AF"]` selects all of `info`.
+    pub fn from_paths<S: AsRef<str>>(paths: &[S]) -> Projection {
+        let mut projection = Projection::Fields(BTreeMap::new());
+        for path in paths {
+            projection.insert(path.as_ref().split('.'));
+        }
+        projection
+    }
+
+    fn insert<'p, I: Iterator<Item = &'p str>>(&mut self, mut path: I) {
+        let fields = match self {
+            Projection::All => return,
+            Projection::Fields(fields) => fields,
+        };
+        match path.next() {
+            Some(name) => fields
+                .entry(name.to_owned())
+                .or_insert_with(|| Projection::Fields(BTreeMap::new()))
+                .insert(path),
+            None => *self = Projection::All,
+        }
+    }
+
+    /// Whether the whole value is selected.
+    pub fn is_all(&self) -> bool {
+        matches!(self, Projection::All)
+    }
+
+    /// The pruned encoded type, holding only the selected fields (in their original order).
+    ///
+    /// This is the type of the values decoded with
+    /// [EType::decode_projected_from](crate::types::EType::decode_projected_from).
+    pub fn encoded_type(&self, e_type: &EType) -> Result<EType> {
+        let selected = match self {
+            Projection::All => return Ok(e_type.clone()),
+            Projection::Fields(selected) => selected,
+        };
+        let fields = match &e_type.shape {
+            ETypeShape::BaseStruct(fields) if e_type.virtual_hint.is_none() => fields,
+            _ => {
+                return Err(Error::Projection(format!(
+                    "cannot select fields of {}",
+                    e_type
+                )))
+            }
+        };
+
+        check_selected(selected, fields)?;
+        let fields = fields
+            .iter()
+            .filter_map(|(name, field)| selected.get(name).map(|p| (name, field, p)))
+            .map(|(name, field, projection)| Ok((name.clone(), projection.encoded_type(field)?)))
+            .collect::<Result<_>>()?;
+
+        Ok(EType {
+            shape: ETypeShape::BaseStruct(fields),
+            required: e_type.required,
+            virtual_hint: None,
+        })
+    }
+
+    /// The pruned virtual type, holding only the selected fields (in their original order).
+    pub fn virtual_type(&self, v_type: &VType) -> Result<VType> {
+        let selected = match self {
+            Projection::All => return Ok(v_type.clone()),
+            Projection::Fields(selected) => selected,
+        };
+        let fields = match &v_type.shape {
+            VTypeShape::Struct(fields) => fields,
+            _ => {
+                return Err(Error::Projection(format!(
+                    "cannot select fields of {}",
+                    v_type
+                )))
+            }
+        };
+
+        check_selected(selected, fields)?;
+        let fields = fields
+            .iter()
+            .filter_map(|(name, field)| selected.get(name).map(|p| (name, field, p)))
+            .map(|(name, field, projection)| Ok((name.clone(), projection.virtual_type(field)?)))
+            .collect::<Result<_>>()?;
+
+        Ok(VType {
+            shape: VTypeShape::Struct(fields),
+            required: v_type.required,
+        })
+    }
+}
+
+/// Makes sure that every selected field exists.
+fn check_selected<T>(
+    selected: &BTreeMap<String, Projection>,
+    fields: &[(String, T)],
+) -> Result<()> {
+    for name in selected.keys() {
+        if !fields.iter().any(|(field, _)| field == name) {
+            let available: Vec<&str> = fields.iter().map(|(field, _)| field.as_str()).collect();
+            return Err(Error::Projection(format!(
+                "no field `{}`, the available fields are: {}",
+                name,
+                available.join(", ")
+            )));
+        }
+    }
+    Ok(())
+}
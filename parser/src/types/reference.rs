@@ -0,0 +1,357 @@
+//! Reference genomes: the contigs (and their lengths) that the loci of a table are placed on.
+//!
+//! Hail writes the custom reference genomes used by a (matrix) table as JSON files in its
+//! `references_rel_path` folder, one per genome (`references/<name>.json.gz`). The built-in genomes
+//! (see [ReferenceGenome::builtin]) are not written, they are known by name.
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    convert::TryFrom,
+    fmt::{self, Display},
+    sync::OnceLock,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{VType, VTypeShape};
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Contig {
+    pub name: String,
+    pub length: u32,
+}
+
+/// A pseudoautosomal region, from `start` (included) to `end` (excluded) on a sex chromosome.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Par {
+    pub contig: String,
+    pub start: u32,
+    pub end: u32,
+}
+
+/// A reference genome, in the layout of the JSON files of Hail.
+///
+/// Loci are placed on a contig, at a position between 1 and the length of the contig (both
+/// included), and are ordered by the index of their contig, then by position.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "JReferenceGenome", into = "JReferenceGenome")]
+pub struct ReferenceGenome {
+    name: String,
+    contigs: Vec<Contig>,
+    x_contigs: Vec<String>,
+    y_contigs: Vec<String>,
+    mt_contigs: Vec<String>,
+    par: Vec<Par>,
+    /// The index of each contig, by name.
+    indices: HashMap<String, usize>,
+}
+
+/// Why a locus does not fit in its reference genome (see [ReferenceGenome::check_locus]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LocusError {
+    UnknownContig {
+        reference: String,
+        contig: String,
+    },
+    PositionOutOfRange {
+        reference: String,
+        contig: String,
+        position: u32,
+        length: u32,
+    },
+}
+
+impl ReferenceGenome {
+    pub fn new(
+        name: String,
+        contigs: Vec<Contig>,
+        x_contigs: Vec<String>,
+        y_contigs: Vec<String>,
+        mt_contigs: Vec<String>,
+        par: Vec<Par>,
+    ) -> Result<Self, String> {
+        let mut indices = HashMap::new();
+        for (index, contig) in contigs.iter().enumerate() {
+            if indices.insert(contig.name.clone(), index).is_some() {
+                return Err(format!(
+                    "the contig {} of {} is repeated",
+                    contig.name, name
+                ));
+            }
+        }
+
+        let genome = ReferenceGenome {
+            name,
+            contigs,
+            x_contigs,
+            y_contigs,
+            mt_contigs,
+            par,
+            indices,
+        };
+        let named = genome
+            .x_contigs
+            .iter()
+            .chain(&genome.y_contigs)
+            .chain(&genome.mt_contigs)
+            .chain(genome.par.iter().map(|par| &par.contig));
+        for contig in named {
+            if genome.contig_index(contig).is_none() {
+                return Err(format!("{} has no contig {}", genome.name, contig));
+            }
+        }
+        for par in &genome.par {
+            if !genome.is_x(&par.contig) && !genome.is_y(&par.contig) {
+                return Err(format!(
+                    "the pseudoautosomal region on {} of {} is not on a sex chromosome",
+                    par.contig, genome.name
+                ));
+            }
+        }
+        Ok(genome)
+    }
+
+    /// The genomes known to Hail by name, rather than written with the tables using them:
+    /// `GRCh37`, `GRCh38`, `GRCm38`, and `CanFam3`.
+    ///
+    /// The contigs of `GRCh38` stop after `chrEBV`, and those of `CanFam3` after `chrM`: the
+    /// alternate loci, decoys, and unplaced contigs that follow them in Hail are left out, and
+    /// order after the listed contigs (see [HailOrdering](super::HailOrdering)). Loci on those
+    /// can use the full genome, as a JSON file of the same name in the references folder of the
+    /// table (which takes precedence).
+    pub fn builtin(name: &str) -> Option<Self> {
+        builtin_references().get(name).cloned()
+    }
+
+    /// Whether the genome is one of the [built-in](ReferenceGenome::builtin) genomes, which are
+    /// not written with the tables.
+    pub fn is_builtin(&self) -> bool {
+        builtin_references().get(&self.name) == Some(self)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The contigs, in order.
+    pub fn contigs(&self) -> &[Contig] {
+        &self.contigs
+    }
+
+    pub fn contig(&self, name: &str) -> Option<&Contig> {
+        self.contig_index(name).map(|index| &self.contigs[index])
+    }
+
+    pub fn contig_index(&self, name: &str) -> Option<usize> {
+        self.indices.get(name).copied()
+    }
+
+    pub fn par(&self) -> &[Par] {
+        &self.par
+    }
+
+    pub fn is_x(&self, contig: &str) -> bool {
+        self.x_contigs.iter().any(|x| x == contig)
+    }
+
+    pub fn is_y(&self, contig: &str) -> bool {
+        self.y_contigs.iter().any(|y| y == contig)
+    }
+
+    pub fn is_mitochondrial(&self, contig: &str) -> bool {
+        self.mt_contigs.iter().any(|mt| mt == contig)
+    }
+
+    /// Whether the locus is in one of the pseudoautosomal regions.
+    pub fn in_par(&self, contig: &str, position: u32) -> bool {
+        self.par
+            .iter()
+            .any(|par| par.contig == contig && par.start <= position && position < par.end)
+    }
+
+    /// Checks that the contig of a locus exists, and that its position is within the contig.
+    pub fn check_locus(&self, contig: &str, position: u32) -> Result<(), LocusError> {
+        let length = match self.contig(contig) {
+            Some(contig) => contig.length,
+            None => {
+                return Err(LocusError::UnknownContig {
+                    reference: self.name.clone(),
+                    contig: contig.to_owned(),
+                })
+            }
+        };
+        if position == 0 || position > length {
+            return Err(LocusError::PositionOutOfRange {
+                reference: self.name.clone(),
+                contig: contig.to_owned(),
+                position,
+                length,
+            });
+        }
+        Ok(())
+    }
+
+    /// Compares two loci by the index of their contig, then by position, or `None` if a contig is
+    /// not in the genome.
+    pub fn compare_loci(&self, locus: (&str, u32), other: (&str, u32)) -> Option<Ordering> {
+        let index = self.contig_index(locus.0)?;
+        let other_index = self.contig_index(other.0)?;
+        Some(index.cmp(&other_index).then(locus.1.cmp(&other.1)))
+    }
+}
+
+/// The names of the reference genomes of the loci in a type.
+pub fn locus_references(v_type: &VType) -> BTreeSet<String> {
+    fn collect(v_type: &VType, names: &mut BTreeSet<String>) {
+        match &v_type.shape {
+            VTypeShape::Struct(fields) => fields.iter().for_each(|(_, f)| collect(f, names)),
+            VTypeShape::Tuple(types) => types.iter().for_each(|t| collect(t, names)),
+            VTypeShape::Array(inner)
+            | VTypeShape::Set(inner)
+            | VTypeShape::NDArray(inner, _)
+            | VTypeShape::Interval(inner) => collect(inner, names),
+            VTypeShape::Dict(key, value) => {
+                collect(key, names);
+                collect(value, names);
+            }
+            VTypeShape::Locus(reference) => {
+                names.insert(reference.clone());
+            }
+            _ => (),
+        }
+    }
+
+    let mut names = BTreeSet::new();
+    collect(v_type, &mut names);
+    names
+}
+
+/// The JSON files of the built-in genomes, in the layout of Hail.
+const BUILTIN_JSON: &[&str] = &[
+    include_str!("references/GRCh37.json"),
+    include_str!("references/GRCh38.json"),
+    include_str!("references/GRCm38.json"),
+    include_str!("references/CanFam3.json"),
+];
+
+/// The built-in genomes, built once.
+pub(crate) fn builtin_references() -> &'static BTreeMap<String, ReferenceGenome> {
+    static REFERENCES: OnceLock<BTreeMap<String, ReferenceGenome>> = OnceLock::new();
+    REFERENCES.get_or_init(|| {
+        BUILTIN_JSON
+            .iter()
+            // The built-in genomes are valid.
+            .filter_map(|json| serde_json::from_str::<ReferenceGenome>(json).ok())
+            .map(|genome| (genome.name().to_owned(), genome))
+            .collect()
+    })
+}
+
+impl Display for LocusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LocusError::UnknownContig { reference, contig } => {
+                write!(f, "{} has no contig {}", reference, contig)
+            }
+            LocusError::PositionOutOfRange {
+                reference,
+                contig,
+                position,
+                length,
+            } => write!(
+                f,
+                "the position {} is outside of the contig {} of {} (from 1 to {})",
+                position, contig, reference, length
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LocusError {}
+
+/// The JSON representation of a [ReferenceGenome].
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JReferenceGenome {
+    name: String,
+    contigs: Vec<Contig>,
+    #[serde(default)]
+    x_contigs: Vec<String>,
+    #[serde(default)]
+    y_contigs: Vec<String>,
+    #[serde(default)]
+    mt_contigs: Vec<String>,
+    #[serde(default)]
+    par: Vec<JPar>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+struct JPar {
+    start: JLocus,
+    end: JLocus,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+struct JLocus {
+    contig: String,
+    position: u32,
+}
+
+impl TryFrom<JReferenceGenome> for ReferenceGenome {
+    type Error = String;
+
+    fn try_from(genome: JReferenceGenome) -> Result<Self, Self::Error> {
+        let par = genome
+            .par
+            .into_iter()
+            .map(|JPar { start, end }| {
+                if start.contig != end.contig {
+                    return Err(format!(
+                        "the pseudoautosomal region from {} to {} spans several contigs",
+                        start.contig, end.contig
+                    ));
+                }
+                Ok(Par {
+                    contig: start.contig,
+                    start: start.position,
+                    end: end.position,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        ReferenceGenome::new(
+            genome.name,
+            genome.contigs,
+            genome.x_contigs,
+            genome.y_contigs,
+            genome.mt_contigs,
+            par,
+        )
+    }
+}
+
+impl From<ReferenceGenome> for JReferenceGenome {
+    fn from(genome: ReferenceGenome) -> Self {
+        JReferenceGenome {
+            name: genome.name,
+            contigs: genome.contigs,
+            x_contigs: genome.x_contigs,
+            y_contigs: genome.y_contigs,
+            mt_contigs: genome.mt_contigs,
+            par: genome
+                .par
+                .into_iter()
+                .map(|par| JPar {
+                    start: JLocus {
+                        contig: par.contig.clone(),
+                        position: par.start,
+                    },
+                    end: JLocus {
+                        contig: par.contig,
+                        position: par.end,
+                    },
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! Checks that components conform to their schema, to audit the (matrix) tables received from
+//! elsewhere.
+//!
+//! Two checks are available:
+//! - [check_types]: whether the encoded type of a component can be read as its virtual type. This
+//!   is done whenever the metadata of a component is loaded, as the values would otherwise be
+//!   decoded with the wrong types.
+//! - [component] (and [table], [matrix]): whether every row conforms to the virtual type: required
+//!   values are present, the elements of sets and the keys of dicts are unique, loci are within
+//!   their contigs, and the rows are sorted by key (see [ViolationKind]).
+//!
+//! The rows are checked as [HailValueRef](crate::HailValueRef)s, which keep the elements of sets and the entries of
+//! dicts as they were encoded ([HailValue::Set] and [HailValue::Dict] merge the duplicates).
+//! Every violation is reported, with the path to the value, rather than only the first one.
+mod rows;
+mod types;
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    path::Path,
+};
+
+use crate::{
+    error::Result,
+    load::{
+        metadata::{load_component_metadata, load_matrix_metadata, load_table_metadata},
+        parallel,
+    },
+    parse::{StandardEncoder, UnsignedLEB128Encoder},
+    types::{key::compare_key, reference::LocusError, EType, ReferenceGenome, VType},
+    HailValue,
+};
+
+pub use types::check_types;
+
+/// A row (or type) that does not conform to the schema of its component.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    /// The index of the partition (part file) holding the row, for the violations in rows.
+    pub partition: Option<usize>,
+    /// The index of the row within its partition, for the violations in rows.
+    pub row: Option<u64>,
+    /// The path to the value within the row (or to the type within the schema), like
+    /// `info.AC[3]`, empty for the row itself. The elements of types are written `[]`.
+    pub field: String,
+    pub kind: ViolationKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ViolationKind {
+    /// The encoded type cannot be read as the virtual type: the shapes differ, the fields of the
+    /// structs differ, or the virtual type is required but the encoded one is not.
+    IncompatibleTypes {
+        virtual_type: VType,
+        encoded_type: EType,
+    },
+    /// A required value is missing (missing floats are not reported, as NaN is decoded as
+    /// missing).
+    MissingRequired,
+    /// The value is not of the expected type.
+    UnexpectedValue { expected: VType },
+    /// A struct has a field that is not in its type.
+    UnexpectedField,
+    /// The element of a set is equal to one of the previous elements.
+    DuplicateElement,
+    /// The key of a dict entry is equal to the key of one of the previous entries.
+    DuplicateKey,
+    /// The locus is not within its reference genome. Unknown contigs are only reported for the
+    /// custom genomes, as the [built-in](ReferenceGenome::builtin) ones only list the assembled
+    /// chromosomes.
+    Locus(LocusError),
+    /// The key of the row is before the key of the previous row, in the order of Hail (see
+    /// [HailOrdering](crate::types::HailOrdering)).
+    UnsortedKey,
+}
+
+/// Checks every row of a component, placing the loci with the built-in reference genomes (see
+/// [component_with] for custom genomes).
+pub fn component<T: AsRef<Path>>(path: T) -> Result<Vec<Violation>> {
+    component_with(path, &BTreeMap::new())
+}
+
+/// Checks every row of a component, placing the loci with the given reference genomes (or else
+/// the built-in ones), like the ones attached to the metadata of a table.
+///
+/// The partitions are checked concurrently with the `parallel` feature. A row that cannot be
+/// decoded stops the check with an error.
+pub fn component_with<T: AsRef<Path>>(
+    path: T,
+    references: &BTreeMap<String, ReferenceGenome>,
+) -> Result<Vec<Violation>> {
+    let path: &Path = path.as_ref();
+
+    let metadata = load_component_metadata(path)?;
+    let data_path = path.join("parts");
+    let validator = rows::Validator::new(&metadata, references.clone());
+
+    let partitions: Vec<_> = metadata.part_files.iter().enumerate().collect();
+    let scans = parallel::map_partitions(&partitions, |(index, part_name)| {
+        let file = data_path.join(part_name);
+        match metadata.buffer_spec.uses_leb128() {
+            true => validator.partition::<UnsignedLEB128Encoder>(&metadata, *index, &file),
+            false => validator.partition::<StandardEncoder>(&metadata, *index, &file),
+        }
+    })?;
+
+    // The rows are sorted within each partition, the partitions also need to be sorted.
+    let key_type = metadata.key_type();
+    let mut violations = vec![];
+    let mut last_key: Option<HailValue> = None;
+    for (index, scan) in scans.into_iter().enumerate() {
+        if let (Some(last_key), Some(first_key)) = (&last_key, &scan.first_key) {
+            if compare_key(&key_type, validator.ordering(), last_key, first_key).is_gt() {
+                violations.push(Violation {
+                    partition: Some(index),
+                    row: Some(0),
+                    field: String::new(),
+                    kind: ViolationKind::UnsortedKey,
+                });
+            }
+        }
+        violations.extend(scan.violations);
+        last_key = scan.last_key.or(last_key);
+    }
+    violations.sort_by_key(|violation| (violation.partition, violation.row));
+    Ok(violations)
+}
+
+/// Checks the globals and rows of a table, with its reference genomes.
+pub fn table<T: AsRef<Path>>(path: T) -> Result<Vec<(&'static str, Vec<Violation>)>> {
+    let path: &Path = path.as_ref();
+    let metadata = load_table_metadata(path)?;
+    let components = &metadata.components;
+
+    [("globals", &components.globals), ("rows", &components.rows)]
+        .iter()
+        .map(|(name, component)| {
+            let violations = component_with(path.join(&component.rel_path), &metadata.references)?;
+            Ok((*name, violations))
+        })
+        .collect()
+}
+
+/// Checks the globals, columns, rows, and entries of a matrix table, with its reference genomes.
+pub fn matrix<T: AsRef<Path>>(path: T) -> Result<Vec<(&'static str, Vec<Violation>)>> {
+    let path: &Path = path.as_ref();
+    let metadata = load_matrix_metadata(path)?;
+    let components = &metadata.components;
+
+    [
+        ("globals", &components.globals),
+        ("cols", &components.cols),
+        ("rows", &components.rows),
+        ("entries", &components.entries),
+    ]
+    .iter()
+    .map(|(name, component)| {
+        let violations = component_with(path.join(&component.rel_path), &metadata.references)?;
+        Ok((*name, violations))
+    })
+    .collect()
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(row) = self.row {
+            write!(f, "row {}", row)?;
+            if let Some(partition) = self.partition {
+                write!(f, " of partition {}", partition)?;
+            }
+            if !self.field.is_empty() {
+                write!(f, " at `{}`", self.field)?;
+            }
+            write!(f, ": ")?;
+        } else if !self.field.is_empty() {
+            write!(f, "at `{}`: ", self.field)?;
+        }
+        self.kind.fmt(f)
+    }
+}
+
+impl std::error::Error for Violation {}
+
+impl Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ViolationKind::IncompatibleTypes {
+                virtual_type,
+                encoded_type,
+            } => write!(
+                f,
+                "the encoded type {} cannot be read as {}",
+                encoded_type, virtual_type
+            ),
+            ViolationKind::MissingRequired => f.write_str("a required value is missing"),
+            ViolationKind::UnexpectedValue { expected } => {
+                write!(f, "expected a value of type {}", expected)
+            }
+            ViolationKind::UnexpectedField => f.write_str("the field is not in the schema"),
+            ViolationKind::DuplicateElement => f.write_str("the element is already in the set"),
+            ViolationKind::DuplicateKey => f.write_str("the key is already in the dict"),
+            ViolationKind::Locus(error) => error.fmt(f),
+            ViolationKind::UnsortedKey => {
+                f.write_str("the key is before the key of the previous row")
+            }
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::Path,
+};
+
+use ndarray::Dimension;
+
+use crate::{
+    error::Result,
+    load::helpers,
+    parse::Encoding,
+    types::{
+        key::compare_key,
+        metadata::ComponentMetadata,
+        reference::{builtin_references, LocusError},
+        HailOrdering, ReferenceGenome, VType, VTypeShape,
+    },
+    HailValue, HailValueRef,
+};
+
+use super::{Violation, ViolationKind};
+
+/// Checks the rows of a component against its virtual type.
+pub(super) struct Validator {
+    row_type: VType,
+    key_type: Vec<(String, VType)>,
+    references: BTreeMap<String, ReferenceGenome>,
+    ordering: HailOrdering,
+}
+
+/// The violations in the rows of a partition, and the keys of its first and last rows (to check
+/// the order of the partitions).
+pub(super) struct PartitionScan {
+    pub violations: Vec<Violation>,
+    pub first_key: Option<HailValue>,
+    pub last_key: Option<HailValue>,
+}
+
+impl Validator {
+    pub fn new(
+        metadata: &ComponentMetadata,
+        references: BTreeMap<String, ReferenceGenome>,
+    ) -> Self {
+        Validator {
+            row_type: metadata.virtual_type.clone(),
+            key_type: metadata.key_type(),
+            ordering: HailOrdering::new(references.clone()),
+            references,
+        }
+    }
+
+    pub fn ordering(&self) -> &HailOrdering {
+        &self.ordering
+    }
+
+    /// Checks the rows of a part file, in order.
+    pub fn partition<E: Encoding>(
+        &self,
+        metadata: &ComponentMetadata,
+        partition: usize,
+        file: &Path,
+    ) -> Result<PartitionScan> {
+        let mut scan = PartitionScan {
+            violations: vec![],
+            first_key: None,
+            last_key: None,
+        };
+        let mut row = 0;
+
+        helpers::for_each_part_row::<E, _>(metadata, file, |value| {
+            let start = scan.violations.len();
+            self.value(
+                &self.row_type,
+                &value,
+                &mut String::new(),
+                &mut scan.violations,
+            );
+
+            if !self.key_type.is_empty() {
+                let key = self.key(&value);
+                if let Some(last_key) = &scan.last_key {
+                    if compare_key(&self.key_type, &self.ordering, last_key, &key).is_gt() {
+                        scan.violations
+                            .push(violation(String::new(), ViolationKind::UnsortedKey));
+                    }
+                }
+                if scan.first_key.is_none() {
+                    scan.first_key = Some(key.clone());
+                }
+                scan.last_key = Some(key);
+            }
+
+            for violation in &mut scan.violations[start..] {
+                violation.partition = Some(partition);
+                violation.row = Some(row);
+            }
+            row += 1;
+            Ok(())
+        })?;
+
+        Ok(scan)
+    }
+
+    /// The key of a row, as a struct of its key fields.
+    fn key(&self, row: &HailValueRef<'_>) -> HailValue {
+        let fields = match row {
+            HailValueRef::Struct(fields) => fields,
+            _ => return HailValue::Missing,
+        };
+        HailValue::Struct(
+            self.key_type
+                .iter()
+                .map(|(name, _)| {
+                    let value = fields.get(name.as_str()).map(HailValueRef::to_owned);
+                    (name.clone(), value.unwrap_or(HailValue::Missing))
+                })
+                .collect(),
+        )
+    }
+
+    /// Checks a value, and the values inside it, `field` being the path to the value.
+    fn value(
+        &self,
+        v_type: &VType,
+        value: &HailValueRef<'_>,
+        field: &mut String,
+        out: &mut Vec<Violation>,
+    ) {
+        let missing = HailValueRef::Missing;
+
+        match (&v_type.shape, value) {
+            // NaN floats are decoded as missing values, which is fine for required floats.
+            (VTypeShape::Float32, HailValueRef::Missing)
+            | (VTypeShape::Float64, HailValueRef::Missing) => (),
+            (_, HailValueRef::Missing) => {
+                if v_type.required {
+                    out.push(violation(field.clone(), ViolationKind::MissingRequired));
+                }
+            }
+
+            (VTypeShape::Struct(types), HailValueRef::Struct(fields)) => {
+                for (name, v_type) in types {
+                    let value = fields.get(name.as_str()).unwrap_or(&missing);
+                    self.inner(name, v_type, value, field, out);
+                }
+                for name in fields.keys() {
+                    if !types.iter().any(|(field, _)| field == name) {
+                        let path = nested(field, name);
+                        out.push(violation(path, ViolationKind::UnexpectedField));
+                    }
+                }
+            }
+            (VTypeShape::Tuple(types), HailValueRef::Tuple(values))
+                if types.len() == values.len() =>
+            {
+                for (index, (v_type, value)) in types.iter().zip(values).enumerate() {
+                    self.inner(&index.to_string(), v_type, value, field, out);
+                }
+            }
+            (VTypeShape::Array(element), HailValueRef::Array(values)) => {
+                for (index, value) in values.iter().enumerate() {
+                    self.inner(&format!("[{}]", index), element, value, field, out);
+                }
+            }
+            (VTypeShape::Set(element), HailValueRef::Set(values)) => {
+                let mut seen = HashSet::new();
+                for (index, value) in values.iter().enumerate() {
+                    let name = format!("[{}]", index);
+                    self.inner(&name, element, value, field, out);
+                    if !seen.insert(value.to_owned()) {
+                        out.push(violation(
+                            nested(field, &name),
+                            ViolationKind::DuplicateElement,
+                        ));
+                    }
+                }
+            }
+            (VTypeShape::Dict(key_type, value_type), HailValueRef::Dict(entries)) => {
+                let mut seen = HashSet::new();
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    let name = format!("[{}].key", index);
+                    self.inner(&name, key_type, key, field, out);
+                    self.inner(&format!("[{}].value", index), value_type, value, field, out);
+                    if !seen.insert(key.to_owned()) {
+                        out.push(violation(nested(field, &name), ViolationKind::DuplicateKey));
+                    }
+                }
+            }
+            (VTypeShape::NDArray(element, n), HailValueRef::NDArray(array))
+                if array.ndim() == *n as usize =>
+            {
+                for (index, value) in array.indexed_iter() {
+                    let name = format!("{:?}", index.slice());
+                    self.inner(&name, element, value, field, out);
+                }
+            }
+            (VTypeShape::Interval(point), HailValueRef::Interval { start, end, .. }) => {
+                self.inner("start", point, start, field, out);
+                self.inner("end", point, end, field, out);
+            }
+
+            (
+                VTypeShape::Locus(reference),
+                HailValueRef::Locus {
+                    contig, position, ..
+                },
+            ) => {
+                if let Err(error) = self.check_locus(reference, contig, *position) {
+                    out.push(violation(field.clone(), ViolationKind::Locus(error)));
+                }
+            }
+
+            (VTypeShape::String, HailValueRef::String(_))
+            | (VTypeShape::Binary, HailValueRef::Binary(_))
+            | (VTypeShape::Float32, HailValueRef::Float32(_))
+            | (VTypeShape::Float64, HailValueRef::Float64(_))
+            | (VTypeShape::Int32, HailValueRef::Int32(_))
+            | (VTypeShape::Int64, HailValueRef::Int64(_))
+            | (VTypeShape::Boolean, HailValueRef::Boolean(_))
+            | (VTypeShape::Call, HailValueRef::Call(_)) => (),
+
+            _ => out.push(violation(
+                field.clone(),
+                ViolationKind::UnexpectedValue {
+                    expected: v_type.clone(),
+                },
+            )),
+        }
+    }
+
+    /// Checks a value inside another one, `name` being its field (or `[index]`) in the outer
+    /// value.
+    fn inner(
+        &self,
+        name: &str,
+        v_type: &VType,
+        value: &HailValueRef<'_>,
+        field: &mut String,
+        out: &mut Vec<Violation>,
+    ) {
+        let parent = field.len();
+        *field = nested(field, name);
+        self.value(v_type, value, field, out);
+        field.truncate(parent);
+    }
+
+    /// Checks that a locus is within its genome, when the genome is known.
+    fn check_locus(&self, reference: &str, contig: &str, position: u32) -> Result<(), LocusError> {
+        let genome = match self
+            .references
+            .get(reference)
+            .or_else(|| builtin_references().get(reference))
+        {
+            Some(genome) => genome,
+            None => return Ok(()),
+        };
+        match genome.check_locus(contig, position) {
+            // Some built-in genomes leave out the alternate loci and decoys of Hail.
+            Err(LocusError::UnknownContig { .. }) if genome.is_builtin() => Ok(()),
+            checked => checked,
+        }
+    }
+}
+
+/// The path to a field (or `[index]`) of the value at `field`.
+fn nested(field: &str, name: &str) -> String {
+    match field.is_empty() || name.starts_with('[') {
+        true => format!("{}{}", field, name),
+        false => format!("{}.{}", field, name),
+    }
+}
+
+fn violation(field: String, kind: ViolationKind) -> Violation {
+    Violation {
+        partition: None,
+        row: None,
+        field,
+        kind,
+    }
+}
@@ -0,0 +1,142 @@
+use crate::types::{EType, ETypeShape, VType, VTypeShape};
+
+use super::{Violation, ViolationKind};
+
+/// Checks that the encoded type can be read as the virtual type, the way the decoder reads it:
+/// - structs have the same fields, in the same order.
+/// - tuples have as many fields as the tuple has types.
+/// - sets and dicts are arrays (of `key` and `value` structs, for dicts), ndarrays have the same
+///   number of dimensions.
+/// - intervals and loci are structs of the expected fields (a required binary `contig` and a
+///   required Int32 `position`, for loci).
+/// - calls are Int32s, strings are binaries, and the other primitives are the same.
+/// - a required virtual type has a required encoded type (the reverse is fine).
+///
+/// Each incompatible type is reported once, with its path, without checking the types inside it.
+pub fn check_types(virtual_type: &VType, encoded_type: &EType) -> Vec<Violation> {
+    let mut violations = vec![];
+    check(
+        virtual_type,
+        encoded_type,
+        &mut String::new(),
+        &mut violations,
+    );
+    violations
+}
+
+fn check(virtual_type: &VType, encoded_type: &EType, field: &mut String, out: &mut Vec<Violation>) {
+    let compatible = (encoded_type.required || !virtual_type.required)
+        && match (&virtual_type.shape, &encoded_type.shape) {
+            (VTypeShape::Struct(types), ETypeShape::BaseStruct(fields)) => {
+                types.len() == fields.len()
+                    && types
+                        .iter()
+                        .zip(fields)
+                        .all(|((name, _), (other, _))| name == other)
+            }
+            (VTypeShape::Tuple(types), ETypeShape::BaseStruct(fields)) => {
+                types.len() == fields.len()
+            }
+            (VTypeShape::Array(_), ETypeShape::Array(_))
+            | (VTypeShape::Set(_), ETypeShape::Array(_)) => true,
+            (VTypeShape::Dict(_, _), ETypeShape::Array(entry)) => match &entry.shape {
+                ETypeShape::BaseStruct(fields) => {
+                    let names: Vec<_> = fields.iter().map(|(name, _)| name.as_str()).collect();
+                    names == ["key", "value"]
+                }
+                _ => false,
+            },
+            (VTypeShape::NDArray(_, n), ETypeShape::NdArrayColumnMajor(_, m)) => n == m,
+            (VTypeShape::Interval(_), ETypeShape::BaseStruct(fields)) => {
+                let mut names: Vec<_> = fields.iter().map(|(name, _)| name.as_str()).collect();
+                names.sort_unstable();
+                names == ["end", "includesEnd", "includesStart", "start"]
+            }
+            // The contig and position are read in this order, without presence flags.
+            (VTypeShape::Locus(_), ETypeShape::BaseStruct(fields)) => match fields.as_slice() {
+                [(contig, contig_type), (position, position_type)] => {
+                    contig == "contig"
+                        && position == "position"
+                        && contig_type.shape == ETypeShape::Binary
+                        && position_type.shape == ETypeShape::Int32
+                        && contig_type.required
+                        && position_type.required
+                }
+                _ => false,
+            },
+            (VTypeShape::String, ETypeShape::Binary)
+            | (VTypeShape::Binary, ETypeShape::Binary)
+            | (VTypeShape::Float32, ETypeShape::Float32)
+            | (VTypeShape::Float64, ETypeShape::Float64)
+            | (VTypeShape::Int32, ETypeShape::Int32)
+            | (VTypeShape::Int64, ETypeShape::Int64)
+            | (VTypeShape::Boolean, ETypeShape::Boolean)
+            | (VTypeShape::Call, ETypeShape::Int32) => true,
+            _ => false,
+        };
+
+    if !compatible {
+        out.push(Violation {
+            partition: None,
+            row: None,
+            field: field.clone(),
+            kind: ViolationKind::IncompatibleTypes {
+                virtual_type: virtual_type.clone(),
+                encoded_type: encoded_type.clone(),
+            },
+        });
+        return;
+    }
+
+    let mut inner = |name: &str, virtual_type: &VType, encoded_type: &EType| {
+        let parent = field.len();
+        if !field.is_empty() && !name.starts_with('[') {
+            field.push('.');
+        }
+        field.push_str(name);
+        check(virtual_type, encoded_type, field, out);
+        field.truncate(parent);
+    };
+
+    match (&virtual_type.shape, &encoded_type.shape) {
+        (VTypeShape::Struct(types), ETypeShape::BaseStruct(fields)) => {
+            for ((name, virtual_type), (_, encoded_type)) in types.iter().zip(fields) {
+                inner(name, virtual_type, encoded_type);
+            }
+        }
+        (VTypeShape::Tuple(types), ETypeShape::BaseStruct(fields)) => {
+            for (index, (virtual_type, (_, encoded_type))) in types.iter().zip(fields).enumerate() {
+                inner(&index.to_string(), virtual_type, encoded_type);
+            }
+        }
+        (VTypeShape::Array(element), ETypeShape::Array(encoded))
+        | (VTypeShape::Set(element), ETypeShape::Array(encoded)) => {
+            inner("[]", element, encoded);
+        }
+        (VTypeShape::Dict(key, value), ETypeShape::Array(entry)) => {
+            if let ETypeShape::BaseStruct(fields) = &entry.shape {
+                inner("[].key", key, &fields[0].1);
+                inner("[].value", value, &fields[1].1);
+            }
+        }
+        (VTypeShape::NDArray(element, _), ETypeShape::NdArrayColumnMajor(encoded, _)) => {
+            // As when decoding, the elements are assumed to be required.
+            let mut encoded = encoded.as_ref().clone();
+            encoded.required = true;
+            inner("[]", element, &encoded);
+        }
+        (VTypeShape::Interval(point), ETypeShape::BaseStruct(fields)) => {
+            for (name, encoded_type) in fields {
+                let virtual_type = match name.as_str() {
+                    "start" | "end" => point.as_ref().clone(),
+                    _ => VType {
+                        shape: VTypeShape::Boolean,
+                        required: true,
+                    },
+                };
+                inner(name, &virtual_type, encoded_type);
+            }
+        }
+        _ => (),
+    }
+}
@@ -0,0 +1,74 @@
+use std::{
+    convert::TryFrom,
+    io::{self, Write},
+};
+
+use crate::types::metadata::shared::BufferSpec;
+
+/// The block size used for blocked buffer specs that do not specify one.
+const DEFAULT_BLOCK_SIZE: usize = 32 * 1024;
+
+/// Writes the encoded contents of a part file, framed (and compressed) as described by the buffer
+/// spec.
+///
+/// This is the inverse of [BlockReader](crate::load::compression::BlockReader): blocked data is
+/// split into blocks prefixed with their length and, when compressed, each block starts with its
+/// original size followed by the LZ4 compressed data.
+pub(crate) fn write_blocks<W: Write>(
+    data: &[u8],
+    buffer_spec: &BufferSpec,
+    out: &mut W,
+) -> io::Result<()> {
+    let is_compressed = buffer_spec.uses_compression();
+    if !is_compressed && !buffer_spec.appends_length() {
+        out.write_all(data)?;
+        return Ok(());
+    }
+
+    let block_size = buffer_spec
+        .block_size()
+        .map_or(Ok(DEFAULT_BLOCK_SIZE), usize::try_from)
+        .map_err(invalid_input)?;
+
+    let mut compressed = Vec::new();
+    for block in data.chunks(block_size) {
+        match is_compressed {
+            true => {
+                compressed.clear();
+                match buffer_spec.uses_high_compression() {
+                    true => lzzzz::lz4_hc::compress_to_vec(
+                        block,
+                        &mut compressed,
+                        lzzzz::lz4_hc::CLEVEL_DEFAULT,
+                    ),
+                    false => lzzzz::lz4::compress_to_vec(
+                        block,
+                        &mut compressed,
+                        lzzzz::lz4::ACC_LEVEL_DEFAULT,
+                    ),
+                }
+                .map_err(invalid_input)?;
+                out.write_all(&block_length(compressed.len() + 4)?)?;
+                out.write_all(&block_length(block.len())?)?;
+                out.write_all(&compressed)?;
+            }
+            false => {
+                out.write_all(&block_length(block.len())?)?;
+                out.write_all(block)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The little-endian length prefix of a block.
+fn block_length(length: usize) -> io::Result<[u8; 4]> {
+    u32::try_from(length)
+        .map(u32::to_le_bytes)
+        .map_err(invalid_input)
+}
+
+fn invalid_input<E: std::error::Error + Send + Sync + 'static>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, error)
+}
@@ -0,0 +1,325 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    error::{Error, Result},
+    parse::{data::helpers::write_presence_array, Encoding},
+    types::{encoding::VirtualHint, EType, ETypeShape, HailOrdering, HailValue, VType, VTypeShape},
+};
+
+/// Encodes a single row, preceded by its boolean marker.
+/// This is the inverse of [parse_rows](crate::parse::data::parse_rows), which also expects a
+/// final `false` marker (see [encode_end_of_rows]).
+///
+/// The elements of sets and the entries of dicts are written sorted in the given ordering, as Hail
+/// expects them to be.
+pub(crate) fn encode_row<E: Encoding>(
+    row_type: &EType,
+    row: &HailValue,
+    ordering: &HailOrdering,
+    out: &mut Vec<u8>,
+) -> Result<()> {
+    E::write_bool(out, true);
+    encode::<E>(row_type, row, ordering, out)
+}
+
+pub(crate) fn encode_end_of_rows<E: Encoding>(out: &mut Vec<u8>) {
+    E::write_bool(out, false);
+}
+
+/// Encodes a (present) value, this mirrors [EType::decode_from].
+///
+/// Missing values are never encoded directly, they are marked as such in the presence bit-flags
+/// of the enclosing struct or array.
+pub(crate) fn encode<E: Encoding>(
+    e_type: &EType,
+    value: &HailValue,
+    ordering: &HailOrdering,
+    out: &mut Vec<u8>,
+) -> Result<()> {
+    match (&e_type.shape, &e_type.virtual_hint, value) {
+        // [Struct]
+        (ETypeShape::BaseStruct(mapping), None, HailValue::Struct(fields)) => {
+            encode_struct::<E>(mapping, |name| fields.get(name), ordering, out)
+        }
+        // Locus as Struct
+        (
+            ETypeShape::BaseStruct(_),
+            Some(VirtualHint::Locus(_)),
+            HailValue::Locus {
+                contig, position, ..
+            },
+        ) => {
+            E::write_bytes(out, contig.as_bytes());
+            E::write_u32(out, *position);
+            Ok(())
+        }
+        // Interval as Struct
+        (
+            ETypeShape::BaseStruct(mapping),
+            Some(VirtualHint::Interval),
+            HailValue::Interval {
+                start,
+                end,
+                includes_start,
+                includes_end,
+            },
+        ) => {
+            let includes_start = HailValue::Boolean(*includes_start);
+            let includes_end = HailValue::Boolean(*includes_end);
+            let field = |name: &str| match name {
+                "start" => Some(start.as_ref()),
+                "end" => Some(end.as_ref()),
+                "includesStart" => Some(&includes_start),
+                "includesEnd" => Some(&includes_end),
+                _ => None,
+            };
+            encode_struct::<E>(mapping, field, ordering, out)
+        }
+        // Tuple as Struct
+        (ETypeShape::BaseStruct(mapping), Some(VirtualHint::Tuple), HailValue::Tuple(values)) => {
+            if mapping.len() != values.len() {
+                return Err(Error::Encode(format!(
+                    "expected a tuple of {} values, found {}",
+                    mapping.len(),
+                    values.len()
+                )));
+            }
+            let mut values = values.iter();
+            encode_struct::<E>(mapping, |_| values.next(), ordering, out)
+        }
+
+        // [Array]
+        (ETypeShape::Array(inner_type), None, HailValue::Array(values)) => {
+            encode_sequence::<E>(inner_type, values.iter(), values.len(), ordering, out)
+        }
+        // Set as Array, sorted as in Hail rather than as by [Ord]
+        (ETypeShape::Array(inner_type), Some(VirtualHint::Set), HailValue::Set(values)) => {
+            let values = ordering.sorted_elements(&virtual_type(inner_type), values);
+            encode_sequence::<E>(
+                inner_type,
+                values.iter().copied(),
+                values.len(),
+                ordering,
+                out,
+            )
+        }
+        // Dict as Array, sorted by key as in Hail rather than as by [Ord]
+        (ETypeShape::Array(inner_type), Some(VirtualHint::Dict), HailValue::Dict(map)) => {
+            let key_type = struct_field(inner_type, "key")
+                .map(virtual_type)
+                .ok_or_else(|| Error::Encode(format!("dict entries as {}", inner_type)))?;
+            let entries: Vec<HailValue> = ordering
+                .sorted_entries(&key_type, map)
+                .into_iter()
+                .map(|(key, value)| {
+                    let mut entry = BTreeMap::new();
+                    entry.insert("key".to_owned(), key.clone());
+                    entry.insert("value".to_owned(), value.clone());
+                    HailValue::Struct(entry)
+                })
+                .collect();
+            encode_sequence::<E>(inner_type, entries.iter(), entries.len(), ordering, out)
+        }
+
+        // NDArray as NDArrayColumnMajor
+        (ETypeShape::NdArrayColumnMajor(inner_type, n), None, HailValue::NDArray(array)) => {
+            if array.ndim() != *n as usize {
+                return Err(Error::Encode(format!(
+                    "expected an ndarray with {} dimensions, found {}",
+                    n,
+                    array.ndim()
+                )));
+            }
+            for &dimension in array.shape() {
+                E::write_i64(out, dimension as i64);
+            }
+            // Reversing the axes and iterating in the default (row-major) order yields the
+            // elements in column-major order.
+            for element in array.t().iter() {
+                encode::<E>(inner_type, element, ordering, out)?;
+            }
+            Ok(())
+        }
+
+        // String as Binary
+        (ETypeShape::Binary, Some(VirtualHint::String), HailValue::String(string)) => {
+            E::write_bytes(out, string.as_bytes());
+            Ok(())
+        }
+        (ETypeShape::Binary, None, HailValue::Binary(bytes)) => {
+            E::write_bytes(out, bytes);
+            Ok(())
+        }
+
+        (ETypeShape::Float32, None, HailValue::Float32(float)) => {
+            E::write_f32(out, *float);
+            Ok(())
+        }
+        (ETypeShape::Float64, None, HailValue::Float64(float)) => {
+            E::write_f64(out, *float);
+            Ok(())
+        }
+        (ETypeShape::Int32, None, HailValue::Int32(int)) => {
+            E::write_i32(out, *int);
+            Ok(())
+        }
+        (ETypeShape::Int64, None, HailValue::Int64(int)) => {
+            E::write_i64(out, *int);
+            Ok(())
+        }
+        // Int32 as Call
+        (ETypeShape::Int32, Some(VirtualHint::Call), HailValue::Call(call)) => {
+            E::write_u32(out, *call);
+            Ok(())
+        }
+        (ETypeShape::Boolean, None, HailValue::Boolean(flag)) => {
+            E::write_bool(out, *flag);
+            Ok(())
+        }
+
+        (_, _, HailValue::Missing) => Err(Error::Encode(format!(
+            "found a missing value for the required type {}",
+            e_type
+        ))),
+        _ => Err(Error::Encode(format!("{:?} as {}", value, e_type))),
+    }
+}
+
+/// The inverse of the struct decoding, the presence bit-flags of the optional
+/// fields are followed by the present values, in order.
+fn encode_struct<'v, E: Encoding>(
+    mapping: &[(String, EType)],
+    mut field: impl FnMut(&str) -> Option<&'v HailValue>,
+    ordering: &HailOrdering,
+    out: &mut Vec<u8>,
+) -> Result<()> {
+    let values: Vec<&HailValue> = mapping
+        .iter()
+        .map(|(name, _)| field(name).unwrap_or(&HailValue::Missing))
+        .collect();
+
+    let presence = mapping
+        .iter()
+        .zip(&values)
+        .filter(|((_, field_type), _)| !field_type.required)
+        .map(|(_, value)| !matches!(value, HailValue::Missing));
+    write_presence_array(out, presence);
+
+    for ((name, field_type), value) in mapping.iter().zip(values) {
+        if let HailValue::Missing = value {
+            match field_type.required {
+                true => {
+                    return Err(Error::Encode(format!(
+                        "the required field {} is missing",
+                        name
+                    )))
+                }
+                false => continue,
+            }
+        }
+        encode::<E>(field_type, value, ordering, out)?;
+    }
+
+    Ok(())
+}
+
+/// Sequences are prefixed by their length and, when their elements are optional, by the
+/// presence bit-flags of the elements.
+fn encode_sequence<'v, E: Encoding>(
+    inner_type: &EType,
+    values: impl Iterator<Item = &'v HailValue> + Clone,
+    len: usize,
+    ordering: &HailOrdering,
+    out: &mut Vec<u8>,
+) -> Result<()> {
+    E::write_u32(out, len as u32);
+
+    if !inner_type.required {
+        write_presence_array(
+            out,
+            values
+                .clone()
+                .map(|value| !matches!(value, HailValue::Missing)),
+        );
+    }
+
+    for value in values {
+        if let HailValue::Missing = value {
+            match inner_type.required {
+                true => {
+                    return Err(Error::Encode(
+                        "found a missing element in a required sequence".to_owned(),
+                    ))
+                }
+                false => continue,
+            }
+        }
+        encode::<E>(inner_type, value, ordering, out)?;
+    }
+
+    Ok(())
+}
+
+/// The virtual type an encoded type decodes to, given its hints, to order the elements of sets and
+/// the keys of dicts.
+fn virtual_type(e_type: &EType) -> VType {
+    let shape = match (&e_type.shape, &e_type.virtual_hint) {
+        (ETypeShape::BaseStruct(_), Some(VirtualHint::Locus(reference))) => {
+            VTypeShape::Locus(reference.clone())
+        }
+        (ETypeShape::BaseStruct(mapping), hint) => {
+            let fields = mapping.iter().map(|(_, e_type)| virtual_type(e_type));
+            match (hint, struct_field(e_type, "start")) {
+                (Some(VirtualHint::Interval), Some(point)) => {
+                    VTypeShape::Interval(Box::new(virtual_type(point)))
+                }
+                (Some(VirtualHint::Tuple), _) => VTypeShape::Tuple(fields.collect()),
+                _ => VTypeShape::Struct(
+                    mapping
+                        .iter()
+                        .map(|(name, _)| name.clone())
+                        .zip(fields)
+                        .collect(),
+                ),
+            }
+        }
+        (ETypeShape::Array(inner_type), Some(VirtualHint::Set)) => {
+            VTypeShape::Set(Box::new(virtual_type(inner_type)))
+        }
+        (ETypeShape::Array(inner_type), hint) => {
+            let field = |name| struct_field(inner_type, name).map(virtual_type);
+            match (hint, field("key"), field("value")) {
+                (Some(VirtualHint::Dict), Some(key), Some(value)) => {
+                    VTypeShape::Dict(Box::new(key), Box::new(value))
+                }
+                _ => VTypeShape::Array(Box::new(virtual_type(inner_type))),
+            }
+        }
+        (ETypeShape::NdArrayColumnMajor(inner_type, n), _) => {
+            VTypeShape::NDArray(Box::new(virtual_type(inner_type)), *n)
+        }
+        (ETypeShape::Binary, Some(VirtualHint::String)) => VTypeShape::String,
+        (ETypeShape::Binary, _) => VTypeShape::Binary,
+        (ETypeShape::Float32, _) => VTypeShape::Float32,
+        (ETypeShape::Float64, _) => VTypeShape::Float64,
+        (ETypeShape::Int32, Some(VirtualHint::Call)) => VTypeShape::Call,
+        (ETypeShape::Int32, _) => VTypeShape::Int32,
+        (ETypeShape::Int64, _) => VTypeShape::Int64,
+        (ETypeShape::Boolean, _) => VTypeShape::Boolean,
+    };
+    VType {
+        shape,
+        required: e_type.required,
+    }
+}
+
+/// The type of the field `name` of a struct.
+fn struct_field<'e>(e_type: &'e EType, name: &str) -> Option<&'e EType> {
+    match &e_type.shape {
+        ETypeShape::BaseStruct(mapping) => mapping
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, e_type)| e_type),
+        _ => None,
+    }
+}
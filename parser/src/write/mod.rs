@@ -0,0 +1,439 @@
+mod compression;
+mod encode;
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    error::{Error, Result},
+    parse::{schema::hail_value_to_json, Encoding, StandardEncoder, UnsignedLEB128Encoder},
+    types::{
+        encoding::escape_name, key::compare_key, metadata::ComponentMetadata, EType, ETypeShape,
+        HailOrdering, HailValue, ReferenceGenome, VType, VTypeShape,
+    },
+    Component, Matrix, Table,
+};
+
+use encode::{encode_end_of_rows, encode_row};
+
+/// Writes a component in the native Hail format, using the encoded type and buffer spec of its
+/// metadata: a `metadata.json.gz` file and one part file per partition in `parts/`.
+///
+/// Rows must already be sorted by key (in the order of [HailOrdering], with the built-in
+/// reference genomes), or an error is returned. The range bounds of the partitions are computed
+/// from their first and last rows. Empty partitions are kept, and take their range bounds from
+/// the metadata: writing an empty partition of a keyed component fails if the metadata does not
+/// have range bounds for each partition.
+/// Indexes are not written, so the component is always written as an `OrderedRVDSpec2`.
+pub fn component<T: AsRef<Path>>(component: &Component, path: T) -> Result<()> {
+    _component(component, path.as_ref(), &HailOrdering::default()).map(|_| ())
+}
+
+/// Writes a table in the native Hail format (see [component]), along with its `TableSpec`
+/// metadata, its custom reference genomes (see [ReferenceGenome::is_builtin]), and the `_SUCCESS`
+/// marker.
+///
+/// The partition counts are computed from the rows being written, and the loci of the keys are
+/// ordered with the reference genomes of the table.
+pub fn table<T: AsRef<Path>>(table: &Table, path: T) -> Result<()> {
+    let path: &Path = path.as_ref();
+    let ordering = HailOrdering::new(table.metadata.references.clone());
+
+    _component(&table.globals, &path.join("globals"), &ordering)?;
+    let counts = _component(&table.rows, &path.join("rows"), &ordering)?;
+    write_references(&table.metadata.references, &path.join(REFERENCES_REL_PATH))?;
+
+    let schema = &table.metadata.table_type;
+    let spec = TableSpec {
+        file_version: table.metadata.file_version,
+        hail_version: &table.metadata.hail_version,
+        references_rel_path: REFERENCES_REL_PATH,
+        global_schema: &schema.global_schema,
+        key: key_names(&schema.row_keys),
+        row_schema: &schema.row_schema,
+        globals_rel_path: "globals",
+        counts: &counts,
+    };
+
+    write_table_spec(&spec, path)
+}
+
+/// Writes a matrix table in the native Hail format, laid out as Hail does: each of the globals,
+/// columns, rows, and entries is a table of its own (see [table]), and they all share the
+/// globals table's rows as their globals. The custom reference genomes are written once, for all
+/// of them.
+///
+/// The rows and entries must have the same partitioning, the partition counts are computed from
+/// the rows being written.
+pub fn matrix<T: AsRef<Path>>(matrix: &Matrix, path: T) -> Result<()> {
+    let path: &Path = path.as_ref();
+    let metadata = &matrix.metadata;
+    let schema = &metadata.matrix_type;
+    let ordering = HailOrdering::new(metadata.references.clone());
+
+    let global_counts = _component(&matrix.globals, &path.join("globals/rows"), &ordering)?;
+    let col_counts = _component(&matrix.cols, &path.join("cols/rows"), &ordering)?;
+    let row_counts = _component(&matrix.rows, &path.join("rows/rows"), &ordering)?;
+    let entry_counts = _component(&matrix.entries, &path.join("entries/rows"), &ordering)?;
+
+    if row_counts != entry_counts {
+        return Err(Error::MismatchedPartitions {
+            rows: row_counts,
+            entries: entry_counts,
+        });
+    }
+    write_references(&metadata.references, &path.join(REFERENCES_REL_PATH))?;
+
+    // The globals of the globals table itself: a single empty struct.
+    _component(
+        &empty_globals(&matrix.globals.metadata),
+        &path.join("globals/globals"),
+        &ordering,
+    )?;
+
+    let empty_struct = VType {
+        shape: VTypeShape::Struct(vec![]),
+        required: false,
+    };
+    let table_spec = |global_schema, key, row_schema, globals_rel_path, counts| TableSpec {
+        file_version: metadata.file_version,
+        hail_version: &metadata.hail_version,
+        references_rel_path: "../references",
+        global_schema,
+        key,
+        row_schema,
+        globals_rel_path,
+        counts,
+    };
+
+    let globals = table_spec(
+        &empty_struct,
+        vec![],
+        &schema.global_schema,
+        "globals",
+        &global_counts,
+    );
+    let cols = table_spec(
+        &schema.global_schema,
+        key_names(&schema.col_keys),
+        &schema.col_schema,
+        "../globals/rows",
+        &col_counts,
+    );
+    let rows = table_spec(
+        &schema.global_schema,
+        key_names(&schema.row_keys),
+        &schema.row_schema,
+        "../globals/rows",
+        &row_counts,
+    );
+    let entries = table_spec(
+        &schema.global_schema,
+        vec![],
+        &matrix.entries.metadata.virtual_type,
+        "../globals/rows",
+        &entry_counts,
+    );
+
+    write_table_spec(&globals, &path.join("globals"))?;
+    write_table_spec(&cols, &path.join("cols"))?;
+    write_table_spec(&rows, &path.join("rows"))?;
+    write_table_spec(&entries, &path.join("entries"))?;
+
+    let matrix_metadata = json!({
+        "name": "MatrixTableSpec",
+        "file_version": metadata.file_version,
+        "hail_version": metadata.hail_version,
+        "references_rel_path": REFERENCES_REL_PATH,
+        "matrix_type": format!(
+            "Matrix{{global:{},col_key:[{}],col:{},row_key:[[{}]],row:{},entry:{}}}",
+            schema.global_schema,
+            key_list(&key_names(&schema.col_keys)),
+            schema.col_schema,
+            key_list(&key_names(&schema.row_keys)),
+            schema.row_schema,
+            schema.entry_schema
+        ),
+        "components": {
+            "globals": component_reference("globals/rows"),
+            "cols": component_reference("cols/rows"),
+            "rows": component_reference("rows/rows"),
+            "entries": component_reference("entries/rows"),
+            "partition_counts": partition_counts(&row_counts),
+        },
+    });
+
+    write_metadata(&matrix_metadata, path)?;
+    write_success(path)
+}
+
+/// Where the reference genomes are written, relative to the (matrix) table.
+const REFERENCES_REL_PATH: &str = "references";
+
+/// Writes the reference genomes that are not built into Hail, as Hail does: one gzipped JSON file
+/// per genome.
+fn write_references(references: &BTreeMap<String, ReferenceGenome>, path: &Path) -> Result<()> {
+    for genome in references.values().filter(|genome| !genome.is_builtin()) {
+        fs::create_dir_all(path).map_err(|error| Error::write(path, error))?;
+        write_json_gz(genome, &path.join(format!("{}.json.gz", genome.name())))?;
+    }
+    Ok(())
+}
+
+/// The contents of a `TableSpec` metadata file, see [write_table_spec].
+struct TableSpec<'a> {
+    file_version: u32,
+    hail_version: &'a str,
+    references_rel_path: &'a str,
+    global_schema: &'a VType,
+    key: Vec<&'a str>,
+    row_schema: &'a VType,
+    /// The path of the globals component, relative to the table (tables nested in a matrix table
+    /// share the globals of the matrix).
+    globals_rel_path: &'a str,
+    counts: &'a [u32],
+}
+
+/// Writes the `TableSpec` metadata of a table (whose components are already written) and its
+/// `_SUCCESS` marker.
+fn write_table_spec(spec: &TableSpec, path: &Path) -> Result<()> {
+    let metadata = json!({
+        "name": "TableSpec",
+        "file_version": spec.file_version,
+        "hail_version": spec.hail_version,
+        "references_rel_path": spec.references_rel_path,
+        "table_type": format!(
+            "Table{{global:{},key:[{}],row:{}}}",
+            spec.global_schema,
+            key_list(&spec.key),
+            spec.row_schema
+        ),
+        "components": {
+            "globals": component_reference(spec.globals_rel_path),
+            "rows": component_reference("rows"),
+            "partition_counts": partition_counts(spec.counts),
+        },
+    });
+
+    write_metadata(&metadata, path)?;
+    write_success(path)
+}
+
+/// Writes the component, returning the number of rows of each part file.
+fn _component(component: &Component, path: &Path, ordering: &HailOrdering) -> Result<Vec<u32>> {
+    let metadata = &component.metadata;
+    check_sorted(component, ordering)?;
+
+    let data_path = path.join("parts");
+    fs::create_dir_all(&data_path).map_err(|error| Error::write(&data_path, error))?;
+
+    // The range bounds of the metadata are only used for empty partitions, if they match the
+    // partitions being written.
+    let known_bounds = match metadata.range_bounds.len() == component.data.len() {
+        true => &metadata.range_bounds[..],
+        false => &[],
+    };
+
+    let mut part_files = vec![];
+    let mut range_bounds = vec![];
+    let mut counts = vec![];
+    for (index, rows) in component.data.iter().enumerate() {
+        let data = match metadata.buffer_spec.uses_leb128() {
+            true => {
+                encode_partition::<UnsignedLEB128Encoder>(rows, &metadata.encoded_type, ordering)
+            }
+            false => encode_partition::<StandardEncoder>(rows, &metadata.encoded_type, ordering),
+        }?;
+
+        let part_file = format!("part-{}", index);
+        let part_path = data_path.join(&part_file);
+        File::create(&part_path)
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                compression::write_blocks(&data, &metadata.buffer_spec, &mut writer)?;
+                writer.flush()
+            })
+            .map_err(|error| Error::write(&part_path, error))?;
+
+        range_bounds.push(range_bound(metadata, rows, known_bounds.get(index), index)?);
+        counts.push(rows.len() as u32);
+        part_files.push(part_file);
+    }
+
+    let component_metadata = json!({
+        "name": "OrderedRVDSpec2",
+        "_key": metadata.key,
+        "_codecSpec": {
+            "name": "TypedCodecSpec",
+            "_eType": metadata.encoded_type.to_string(),
+            "_vType": metadata.virtual_type.to_string(),
+            "_bufferSpec": metadata.buffer_spec,
+        },
+        "_partFiles": part_files,
+        "_jRangeBounds": range_bounds,
+        "_attrs": {},
+    });
+
+    write_metadata(&component_metadata, path)?;
+
+    Ok(counts)
+}
+
+fn encode_partition<E: Encoding>(
+    rows: &[HailValue],
+    row_type: &EType,
+    ordering: &HailOrdering,
+) -> Result<Vec<u8>> {
+    let mut data = vec![];
+    for row in rows {
+        encode_row::<E>(row_type, row, ordering, &mut data)?;
+    }
+    encode_end_of_rows::<E>(&mut data);
+    Ok(data)
+}
+
+/// Checks that the rows are sorted by key, within and across partitions.
+fn check_sorted(component: &Component, ordering: &HailOrdering) -> Result<()> {
+    let key_type = component.metadata.key_type();
+    if key_type.is_empty() {
+        return Ok(());
+    }
+
+    let mut previous: Option<&HailValue> = None;
+    for (partition, rows) in component.data.iter().enumerate() {
+        for (row, value) in rows.iter().enumerate() {
+            if let Some(previous) = previous {
+                if compare_key(&key_type, ordering, previous, value).is_gt() {
+                    return Err(Error::UnsortedRows { partition, row });
+                }
+            }
+            previous = Some(value);
+        }
+    }
+    Ok(())
+}
+
+/// The (inclusive) interval between the keys of the first and last rows of a partition.
+///
+/// An empty partition takes its known range bounds, if any: they cannot be computed from its
+/// rows, unless the component has no key.
+fn range_bound(
+    metadata: &ComponentMetadata,
+    rows: &[HailValue],
+    known: Option<&HailValue>,
+    partition: usize,
+) -> Result<Value> {
+    if rows.is_empty() && !metadata.key.is_empty() {
+        let known = known.ok_or(Error::EmptyPartitionBounds { partition })?;
+        return hail_value_to_json(known).map_err(|error| Error::Encode(error.to_string()));
+    }
+
+    let key_of = |row: Option<&HailValue>| -> Result<Value> {
+        let fields = match row {
+            Some(HailValue::Struct(fields)) => fields,
+            // The key of an unkeyed component is an empty struct.
+            None => return Ok(json!({})),
+            row => {
+                return Err(Error::Encode(format!(
+                    "expected the rows to be structs, found {:?}",
+                    row
+                )))
+            }
+        };
+
+        let key = metadata
+            .key
+            .iter()
+            .map(|name| {
+                let value = fields.get(name).cloned().unwrap_or(HailValue::Missing);
+                (name.clone(), value)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        hail_value_to_json(&HailValue::Struct(key))
+            .map_err(|error| Error::Encode(error.to_string()))
+    };
+
+    Ok(json!({
+        "start": key_of(rows.first())?,
+        "end": key_of(rows.last())?,
+        "includeStart": true,
+        "includeEnd": true,
+    }))
+}
+
+/// A component holding a single empty struct, encoded as the given component.
+fn empty_globals(like: &ComponentMetadata) -> Component {
+    let metadata = ComponentMetadata {
+        key: vec![],
+        virtual_type: VType {
+            shape: VTypeShape::Struct(vec![]),
+            required: true,
+        },
+        encoded_type: EType {
+            shape: ETypeShape::BaseStruct(vec![]),
+            required: true,
+            virtual_hint: None,
+        },
+        codec_spec: like.codec_spec.clone(),
+        buffer_spec: like.buffer_spec.clone(),
+        part_files: vec![],
+        index_spec: None,
+        range_bounds: vec![],
+    };
+
+    Component {
+        data: vec![vec![HailValue::Struct(BTreeMap::new())]],
+        metadata,
+    }
+}
+
+fn key_names(keys: &[(String, VType)]) -> Vec<&str> {
+    keys.iter().map(|(name, _)| name.as_str()).collect()
+}
+
+/// The key fields as listed in the schemas, escaped as the field names of their types.
+fn key_list(names: &[&str]) -> String {
+    let names: Vec<_> = names.iter().map(|name| escape_name(name)).collect();
+    names.join(",")
+}
+
+fn partition_counts(counts: &[u32]) -> Value {
+    json!({
+        "name": "PartitionCountsComponentSpec",
+        "counts": counts,
+    })
+}
+
+fn component_reference(rel_path: &str) -> Value {
+    json!({
+        "name": "RVDComponentSpec",
+        "rel_path": rel_path,
+    })
+}
+
+fn write_metadata(metadata: &Value, path: &Path) -> Result<()> {
+    write_json_gz(metadata, &path.join("metadata.json.gz"))
+}
+
+fn write_success(path: &Path) -> Result<()> {
+    let marker = path.join("_SUCCESS");
+    File::create(&marker).map_err(|error| Error::write(&marker, error))?;
+    Ok(())
+}
+
+fn write_json_gz<T: serde::Serialize>(value: &T, file: &Path) -> Result<()> {
+    File::create(file)
+        .and_then(|output| {
+            let mut encoder = flate2::write::GzEncoder::new(output, flate2::Compression::default());
+            serde_json::to_writer(&mut encoder, value)?;
+            encoder.finish().map(|_| ())
+        })
+        .map_err(|error| Error::write(file, error))
+}
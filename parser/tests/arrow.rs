@@ -0,0 +1,218 @@
+#![cfg(feature = "arrow")]
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use arrow::{
+    array::{Array, AsArray, StructArray},
+    datatypes::{DataType, Field, Fields, Float64Type, Int32Type, Int64Type},
+};
+use parser::{
+    arrow::{data_type, parse_record_batch},
+    parse::{Encoding, StandardEncoder},
+    types::{EType, VType},
+    HailValue,
+};
+
+use crate::common::resource;
+
+/// Every partition should become a batch with the same rows, and every column should have a null
+/// for each missing field.
+fn compare_component(file: &str) -> Result<()> {
+    let path = resource(file);
+    let loaded = parser::load::component(&path)?;
+    let exported = parser::arrow::component(&path)?;
+
+    assert_eq!(exported.batches.len(), loaded.data.len());
+    for (batch, rows) in exported.batches.iter().zip(&loaded.data) {
+        assert_eq!(batch.schema(), exported.schema);
+        assert_eq!(batch.num_rows(), rows.len());
+
+        for (field, column) in exported.schema.fields().iter().zip(batch.columns()) {
+            // Decoding into a HailValue turns NaN into a missing value, but not in Arrow.
+            if matches!(field.data_type(), DataType::Float32 | DataType::Float64) {
+                continue;
+            }
+            let missing = rows
+                .iter()
+                .filter(|row| match row {
+                    HailValue::Struct(fields) => {
+                        matches!(fields.get(field.name()), Some(HailValue::Missing))
+                    }
+                    _ => false,
+                })
+                .count();
+            assert_eq!(column.null_count(), missing, "{} in {}", field.name(), file);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn arrow_compat_150() -> Result<()> {
+    for index in 0..8 {
+        let path = format!("backward_compatability/1.5.0/table/{}.ht", index);
+        compare_component(&format!("{}/globals", path))?;
+        compare_component(&format!("{}/rows", path))?;
+    }
+    Ok(())
+}
+
+#[test]
+fn arrow_sample_vcf() -> Result<()> {
+    for component in &["globals", "cols", "rows", "entries"] {
+        compare_component(&format!("sample.vcf.mt/{}/rows", component))?;
+    }
+
+    let matrix = parser::arrow::matrix(resource("sample.vcf.mt"))?;
+    let loaded = parser::load::component(resource("sample.vcf.mt/rows/rows"))?;
+    let (batch, rows) = (&matrix.rows.batches[0], &loaded.data[0]);
+
+    let locus = batch.column_by_name("locus").unwrap().as_struct();
+    let contigs = locus.column_by_name("contig").unwrap().as_string::<i32>();
+    let positions = locus.column_by_name("position").unwrap();
+    let positions = positions.as_primitive::<Int32Type>();
+    for (index, row) in rows.iter().enumerate() {
+        match row {
+            HailValue::Struct(fields) => match &fields["locus"] {
+                HailValue::Locus {
+                    contig, position, ..
+                } => {
+                    assert_eq!(contigs.value(index), contig);
+                    assert_eq!(positions.value(index), *position as i32);
+                }
+                value => panic!("expected a locus, found {:?}", value),
+            },
+            value => panic!("expected a struct, found {:?}", value),
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn arrow_data_types() -> Result<()> {
+    let v_type: VType = "Struct{a:Array[Int32],b:Set[String],c:Dict[String,Float64],\
+        d:Locus(GRCh38),e:Interval[Int64],f:Tuple[Boolean,Binary],g:NDArray[Float64,2],h:Call}"
+        .parse()?;
+    let item = |data_type, nullable| Arc::new(Field::new("item", data_type, nullable));
+    let entries = Fields::from(vec![
+        Field::new("key", DataType::Utf8, false),
+        Field::new("value", DataType::Float64, true),
+    ]);
+    let expected = DataType::Struct(Fields::from(vec![
+        Field::new("a", DataType::List(item(DataType::Int32, true)), true),
+        Field::new("b", DataType::List(item(DataType::Utf8, true)), true),
+        Field::new(
+            "c",
+            DataType::Map(
+                Arc::new(Field::new("entries", DataType::Struct(entries), false)),
+                false,
+            ),
+            true,
+        ),
+        Field::new(
+            "d",
+            DataType::Struct(Fields::from(vec![
+                Field::new("contig", DataType::Utf8, false),
+                Field::new("position", DataType::Int32, false),
+            ])),
+            true,
+        ),
+        Field::new(
+            "e",
+            DataType::Struct(Fields::from(vec![
+                Field::new("start", DataType::Int64, true),
+                Field::new("end", DataType::Int64, true),
+                Field::new("includesStart", DataType::Boolean, false),
+                Field::new("includesEnd", DataType::Boolean, false),
+            ])),
+            true,
+        ),
+        Field::new(
+            "f",
+            DataType::Struct(Fields::from(vec![
+                Field::new("0", DataType::Boolean, true),
+                Field::new("1", DataType::Binary, true),
+            ])),
+            true,
+        ),
+        Field::new(
+            "g",
+            DataType::Struct(Fields::from(vec![
+                Field::new(
+                    "shape",
+                    DataType::FixedSizeList(item(DataType::Int64, false), 2),
+                    false,
+                ),
+                Field::new(
+                    "data",
+                    DataType::List(item(DataType::Float64, false)),
+                    false,
+                ),
+            ])),
+            true,
+        ),
+        Field::new("h", DataType::Int32, true),
+    ]));
+    assert_eq!(data_type(&v_type), expected);
+    Ok(())
+}
+
+#[test]
+fn arrow_nulls_and_values() -> Result<()> {
+    let v_type: VType =
+        "Struct{a:Int32,b:Array[String],c:Struct{x:Float64},d:NDArray[Int64,2]}".parse()?;
+    let e_type: EType = "+EBaseStruct{a:EInt32,b:EArray[EBinary],c:EBaseStruct{x:EFloat64},\
+        d:ENDArrayColumnMajor[+EInt64,2]}"
+        .parse()?;
+
+    let mut data = Vec::new();
+    // a, b ["x", missing], c {x: NaN}, d missing.
+    StandardEncoder::write_bool(&mut data, true);
+    data.push(0b1000);
+    StandardEncoder::write_i32(&mut data, 7);
+    StandardEncoder::write_u32(&mut data, 2);
+    data.push(0b10);
+    StandardEncoder::write_bytes(&mut data, b"x");
+    data.push(0b0);
+    StandardEncoder::write_f64(&mut data, f64::NAN);
+    // a and b missing, c {x: missing}, d [[1, 2, 3]].
+    StandardEncoder::write_bool(&mut data, true);
+    data.push(0b0011);
+    data.push(0b1);
+    StandardEncoder::write_i64(&mut data, 1);
+    StandardEncoder::write_i64(&mut data, 3);
+    for value in 1..=3 {
+        StandardEncoder::write_i64(&mut data, value);
+    }
+    StandardEncoder::write_bool(&mut data, false);
+
+    let batch = parse_record_batch::<StandardEncoder>(&data, &v_type, &e_type)?;
+    assert_eq!(batch.num_rows(), 2);
+
+    let a = batch.column(0).as_primitive::<Int32Type>();
+    assert_eq!((a.value(0), a.is_null(1)), (7, true));
+
+    let b = batch.column(1).as_list::<i32>();
+    assert!(b.is_null(1));
+    let strings = b.value(0);
+    let strings = strings.as_string::<i32>();
+    assert_eq!((strings.value(0), strings.is_null(1)), ("x", true));
+
+    let c: &StructArray = batch.column(2).as_struct();
+    let x = c.column(0).as_primitive::<Float64Type>();
+    assert!(c.is_valid(0) && c.is_valid(1));
+    assert!(x.value(0).is_nan() && x.is_null(1));
+
+    let d = batch.column(3).as_struct();
+    assert!(d.is_null(0) && d.is_valid(1));
+    let shape = d.column(0).as_fixed_size_list().value(1);
+    assert_eq!(shape.as_primitive::<Int64Type>().values(), &[1, 3]);
+    let elements = d.column(1).as_list::<i32>().value(1);
+    assert_eq!(elements.as_primitive::<Int64Type>().values(), &[1, 2, 3]);
+
+    // A truncated row is an error, rather than a partial row.
+    let error = parse_record_batch::<StandardEncoder>(&data[..data.len() - 4], &v_type, &e_type);
+    assert!(matches!(error, Err(parser::Error::Decode(_))));
+    Ok(())
+}
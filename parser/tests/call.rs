@@ -0,0 +1,124 @@
+use std::convert::TryFrom;
+
+use anyhow::Result;
+
+use parser::{
+    types::{call::CallError, Call},
+    HailValue,
+};
+
+use crate::common::resource;
+
+#[test]
+fn call_packing() -> Result<()> {
+    // The packed values written by Hail.
+    assert_eq!(Call::empty(false).packed(), 0);
+    assert_eq!(Call::haploid(1, false)?.packed(), 10);
+    assert_eq!(Call::diploid(0, 0, false)?.packed(), 4);
+    assert_eq!(Call::diploid(0, 1, false)?.packed(), 12);
+    assert_eq!(Call::diploid(1, 1, false)?.packed(), 20);
+    assert_eq!(Call::diploid(0, 1, true)?.packed(), 13);
+    assert_eq!(Call::diploid(1, 0, true)?.packed(), 21);
+
+    for phased in [false, true] {
+        for first in 0..50 {
+            let call = Call::haploid(first, phased)?;
+            assert_eq!(call.alleles(), vec![first]);
+            assert_eq!(Call::try_from(call.packed())?, call);
+
+            for second in 0..50 {
+                let call = Call::diploid(first, second, phased)?;
+                assert_eq!((call.ploidy(), call.is_phased()), (2, phased));
+                let expected = if phased || first <= second {
+                    vec![first, second]
+                } else {
+                    vec![second, first]
+                };
+                assert_eq!(call.alleles(), expected);
+                assert_eq!(call.to_string().parse::<Call>()?, call);
+            }
+        }
+    }
+
+    assert_eq!(Call::try_from(6), Err(CallError::Ploidy(6)));
+    assert_eq!(Call::haploid(u32::MAX, false), Err(CallError::Overflow));
+    assert_eq!(Call::diploid(0, 1 << 16, false), Err(CallError::Overflow));
+    assert_eq!(
+        Call::diploid(u32::MAX, u32::MAX, true),
+        Err(CallError::Overflow)
+    );
+    Ok(())
+}
+
+#[test]
+fn call_text() -> Result<()> {
+    for text in &["-", "|-", "0", "|2", "0/1", "1|0", "3/3"] {
+        assert_eq!(text.parse::<Call>()?.to_string(), *text);
+    }
+    assert_eq!("2/1".parse::<Call>()?.to_string(), "1/2");
+    for text in &["", "/", "0/", "|0/1", "a", "-1", "0/1/2", "+1"] {
+        assert!(text.parse::<Call>().is_err(), "{}", text);
+    }
+    Ok(())
+}
+
+#[test]
+fn call_genotypes() -> Result<()> {
+    let call = |text: &str| text.parse::<Call>().unwrap();
+
+    assert!(call("0/0").is_hom_ref() && call("0").is_hom_ref() && !call("-").is_hom_ref());
+    assert!(call("0/1").is_het() && call("2|1").is_het() && !call("1").is_het());
+    assert!(call("1/1").is_hom_var() && call("|2").is_hom_var() && !call("1/2").is_hom_var());
+    assert!(!call("0/0").is_het() && !call("0/0").is_hom_var());
+    assert_eq!(call("-").n_alt_alleles(), 0);
+    assert_eq!(call("0|2").n_alt_alleles(), 1);
+    assert_eq!(call("1/2").n_alt_alleles(), 2);
+
+    // The order of the PL field: 0/0, 0/1, 1/1, 0/2, 1/2, 2/2...
+    for (index, text) in ["0/0", "0/1", "1/1", "0/2", "1/2", "2/2"]
+        .iter()
+        .enumerate()
+    {
+        assert_eq!(call(text).unphased_diploid_gt_index(), Some(index as u32));
+    }
+    assert_eq!(call("2|1").unphased_diploid_gt_index(), Some(4));
+    assert_eq!(call("1").unphased_diploid_gt_index(), None);
+    assert_eq!(call("1").allele(1), None);
+    Ok(())
+}
+
+/// The called genotype should be the one with the best (zero) likelihood in `PL`.
+#[test]
+fn call_sample_vcf() -> Result<()> {
+    let component = parser::load::component(resource("sample.vcf.mt/entries/rows"))?;
+
+    let mut calls = 0;
+    for row in component.data.iter().flatten() {
+        let entries = match row {
+            // The entries are the only field, under a generated name.
+            HailValue::Struct(fields) => match fields.values().next() {
+                Some(HailValue::Array(entries)) => entries,
+                value => panic!("expected an array, found {:?}", value),
+            },
+            value => panic!("expected a struct, found {:?}", value),
+        };
+        for entry in entries {
+            let fields = match entry {
+                HailValue::Struct(fields) => fields,
+                _ => continue,
+            };
+            let call = match &fields["GT"] {
+                HailValue::Missing => continue,
+                value => value.as_call().unwrap(),
+            };
+            assert_eq!(HailValue::from(call), fields["GT"]);
+            if let HailValue::Array(likelihoods) = &fields["PL"] {
+                let index = call.unphased_diploid_gt_index().unwrap() as usize;
+                assert_eq!(likelihoods[index], HailValue::Int32(0));
+            }
+            calls += 1;
+        }
+    }
+    assert!(calls > 0);
+    Ok(())
+}
@@ -0,0 +1,17 @@
+//! Helpers shared by the integration tests.
+use std::path::PathBuf;
+
+/// A file or folder of the `resources` folder, at the root of the repository.
+pub fn resource(file: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../resources")
+        .join(file)
+}
+
+/// A fresh output file or folder for each test, as tests run concurrently.
+pub fn output(name: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&path);
+    let _ = std::fs::remove_file(&path);
+    path
+}
@@ -0,0 +1,256 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use parser::{
+    error::{DecodeError, DecodeErrorKind},
+    parse::{data::parse_rows, Encoding, StandardEncoder},
+    types::{encoding::VirtualHint, EType, ETypeShape, VType, VTypeShape},
+    Error, HailValue,
+};
+
+use crate::common::{output, resource};
+
+fn e_type(shape: ETypeShape, required: bool, virtual_hint: Option<VirtualHint>) -> EType {
+    EType {
+        shape,
+        required,
+        virtual_hint,
+    }
+}
+
+fn decode_error(error: Error) -> DecodeError {
+    match error {
+        Error::Decode(error) => *error,
+        error => panic!("expected a decoding error, found {:?}", error),
+    }
+}
+
+/// `+EBaseStruct{a:+EInt32,b:+EArray[EBinary]}`, where the binaries are strings.
+fn row_type() -> EType {
+    let string = e_type(ETypeShape::Binary, false, Some(VirtualHint::String));
+    e_type(
+        ETypeShape::BaseStruct(vec![
+            ("a".to_owned(), e_type(ETypeShape::Int32, true, None)),
+            (
+                "b".to_owned(),
+                e_type(ETypeShape::Array(Box::new(string)), true, None),
+            ),
+        ]),
+        true,
+        None,
+    )
+}
+
+fn encode_row(out: &mut Vec<u8>, a: i32, b: &[&[u8]]) {
+    StandardEncoder::write_bool(out, true);
+    StandardEncoder::write_i32(out, a);
+    StandardEncoder::write_u32(out, b.len() as u32);
+    out.push(0); // All the elements are present.
+    for bytes in b {
+        StandardEncoder::write_bytes(out, bytes);
+    }
+}
+
+#[test]
+fn decode_errors_point_to_the_field() {
+    let mut data = Vec::new();
+    encode_row(&mut data, 1, &[b"x"]);
+    let second_row = data.len();
+    encode_row(&mut data, 2, &[b"y", b"\xff"]);
+    StandardEncoder::write_bool(&mut data, false);
+
+    let error = decode_error(parse_rows::<StandardEncoder>(&data, &row_type()).unwrap_err());
+    assert_eq!(error.part_file, None);
+    assert_eq!(error.row, 1);
+    assert_eq!(error.field, "b[1]");
+    // The marker, `a`, the length and flags of `b`, and its first element come first.
+    assert_eq!(error.offset, (second_row + 1 + 4 + 4 + 1 + 5) as u64);
+    assert_eq!(
+        error.expected,
+        Some(e_type(ETypeShape::Binary, false, Some(VirtualHint::String)))
+    );
+    assert_eq!(
+        error.kind,
+        DecodeErrorKind::Invalid(nom::error::ErrorKind::ParseTo)
+    );
+    assert!(error.to_string().contains("`b[1]`"));
+}
+
+#[test]
+fn truncated_rows() {
+    let mut data = Vec::new();
+    encode_row(&mut data, 1, &[b"xyz"]);
+    data.truncate(data.len() - 1);
+
+    let error = decode_error(parse_rows::<StandardEncoder>(&data, &row_type()).unwrap_err());
+    assert_eq!(error.row, 0);
+    assert_eq!(error.field, "b[0]");
+    assert_eq!(error.kind, DecodeErrorKind::UnexpectedEnd);
+}
+
+#[test]
+fn decode_errors_name_the_part_file() -> Result<()> {
+    // The same rows are written twice, once with raw bytes and once with strings.
+    let mut component = parser::load::component(resource("small-pheno.t/rows"))?;
+    let write_with = |component: &mut parser::Component, shape, hint, name| -> Result<PathBuf> {
+        let metadata = &mut component.metadata;
+        match (
+            &mut metadata.virtual_type.shape,
+            &mut metadata.encoded_type.shape,
+        ) {
+            (VTypeShape::Struct(virtual_fields), ETypeShape::BaseStruct(encoded_fields)) => {
+                virtual_fields.retain(|(name, _)| name != "b");
+                encoded_fields.retain(|(name, _)| name != "b");
+                virtual_fields.push((
+                    "b".to_owned(),
+                    VType {
+                        shape,
+                        required: true,
+                    },
+                ));
+                encoded_fields.push(("b".to_owned(), e_type(ETypeShape::Binary, true, hint)));
+            }
+            _ => panic!("rows are structs"),
+        }
+        let path = output(name);
+        parser::write::component(component, &path)?;
+        Ok(path)
+    };
+    let set_b = |component: &mut parser::Component, value: &dyn Fn(usize) -> HailValue| {
+        for (index, row) in component.data.iter_mut().flatten().enumerate() {
+            match row {
+                HailValue::Struct(fields) => fields.insert("b".to_owned(), value(index)),
+                _ => panic!("rows are structs"),
+            };
+        }
+    };
+
+    set_b(&mut component, &|_| HailValue::String("b".to_owned()));
+    let strings = write_with(
+        &mut component,
+        VTypeShape::String,
+        Some(VirtualHint::String),
+        "errors.strings",
+    )?;
+    set_b(&mut component, &|index| match index {
+        2 => HailValue::Binary(vec![0xff]),
+        _ => HailValue::Binary(b"b".to_vec()),
+    });
+    let bytes = write_with(&mut component, VTypeShape::Binary, None, "errors.bytes")?;
+
+    // The bytes are then read as strings.
+    std::fs::copy(
+        strings.join("metadata.json.gz"),
+        bytes.join("metadata.json.gz"),
+    )?;
+    let error = decode_error(parser::load::component(&bytes).unwrap_err());
+
+    assert_eq!(error.part_file, Some(bytes.join("parts").join("part-0")));
+    assert_eq!(error.row, 2);
+    assert_eq!(error.field, "b");
+    assert_eq!(
+        error.kind,
+        DecodeErrorKind::Invalid(nom::error::ErrorKind::ParseTo)
+    );
+
+    // The streaming loaders point to the same value.
+    let streamed = parser::load::component_iter(&bytes)?
+        .find_map(|row| row.err())
+        .map(decode_error);
+    assert_eq!(streamed, Some(error));
+    Ok(())
+}
+
+#[test]
+fn metadata_errors() {
+    match parser::load::matrix(resource("three_key.ht")) {
+        Err(Error::UnexpectedMetadata {
+            expected, found, ..
+        }) => assert_eq!((expected, found), ("matrix", "table")),
+        result => panic!("expected a metadata error, found {:?}", result.map(|_| ())),
+    }
+
+    match parser::load::table(resource("not_a_table.ht")) {
+        Err(Error::Io {
+            path: Some(path), ..
+        }) => assert!(path.ends_with("not_a_table.ht/metadata.json.gz")),
+        result => panic!("expected an I/O error, found {:?}", result.map(|_| ())),
+    }
+}
+
+#[test]
+fn partition_errors() {
+    match parser::load::component_partitions(resource("three_key.ht/rows"), vec![100]) {
+        Err(Error::PartitionOutOfRange { index: 100, .. }) => {}
+        result => panic!("expected a partition error, found {:?}", result.map(|_| ())),
+    }
+}
+
+#[test]
+fn index_errors() -> Result<()> {
+    match parser::index::IndexedComponent::open(resource("small-pheno.t/rows")) {
+        Err(Error::NotIndexed { path }) => assert!(path.ends_with("small-pheno.t/rows")),
+        result => panic!("expected an index error, found {:?}", result.map(|_| ())),
+    }
+
+    let indexed = parser::index::IndexedComponent::open(resource("three_key.ht/rows"))?;
+    match indexed.rows_at(100, 0, 1) {
+        Err(Error::PartitionOutOfRange { index: 100, .. }) => {}
+        result => panic!("expected a partition error, found {:?}", result.map(|_| ())),
+    }
+    match indexed.rows_at(0, 0, 1_000_000) {
+        Err(Error::Index { path, .. }) => assert!(path.ends_with(&indexed.metadata.part_files[0])),
+        result => panic!("expected an index error, found {:?}", result.map(|_| ())),
+    }
+    Ok(())
+}
+
+#[test]
+fn write_errors() -> Result<()> {
+    let mut component = parser::load::component(resource("three_key.ht/rows"))?;
+    component.data[0].swap(0, 1);
+    match parser::write::component(&component, output("three_key.unsorted")) {
+        Err(Error::UnsortedRows {
+            partition: 0,
+            row: 1,
+        }) => {}
+        result => panic!("expected an unsorted rows error, found {:?}", result),
+    }
+
+    // Missing values sort last, so only the last row can miss a key field and stay sorted.
+    component.data[0].swap(0, 1);
+    match component.data.iter_mut().flatten().last() {
+        Some(HailValue::Struct(fields)) => fields.remove("z"),
+        _ => panic!("rows are structs"),
+    };
+    match parser::write::component(&component, output("three_key.unencodable")) {
+        Err(Error::Encode(message)) => assert!(message.contains("field z"), "{}", message),
+        result => panic!("expected an encoding error, found {:?}", result),
+    }
+
+    let mut component = parser::load::component(resource("three_key.ht/rows"))?;
+    component.data[0].clear();
+    component.metadata.range_bounds.clear();
+    match parser::write::component(&component, output("three_key.no_bounds")) {
+        Err(Error::EmptyPartitionBounds { partition: 0 }) => {}
+        result => panic!("expected a range bounds error, found {:?}", result),
+    }
+    Ok(())
+}
+
+#[test]
+fn schema_errors() {
+    use parser::parse::SchemaParsingError;
+
+    match "Struct{a:Int32,b:Foo}".parse::<VType>() {
+        Err(SchemaParsingError::Syntax { offset, .. }) => assert_eq!(offset, 17),
+        result => panic!("expected a syntax error, found {:?}", result),
+    }
+
+    let nested = format!("{}Int32{}", "Array[".repeat(200), "]".repeat(200));
+    match nested.parse::<VType>() {
+        Err(SchemaParsingError::TooDeep { limit }) => assert!(limit < 200),
+        result => panic!("expected a nesting error, found {:?}", result),
+    }
+}
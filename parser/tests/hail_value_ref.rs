@@ -0,0 +1,88 @@
+use anyhow::Result;
+
+use parser::{
+    parse::{data::parse_rows_ref, UnsignedLEB128Encoder},
+    HailValue, HailValueRef,
+};
+
+use crate::common::resource;
+
+/// The borrowed rows, once converted, should be exactly the rows loaded all at once.
+fn compare_component(file: &str) -> Result<()> {
+    let path = resource(file);
+    let loaded = parser::load::component(&path)?;
+
+    let mut visited = Vec::new();
+    parser::load::component_for_each(&path, |row| {
+        visited.push(row.to_owned());
+        Ok(())
+    })?;
+
+    let expected: Vec<HailValue> = loaded.data.into_iter().flatten().collect();
+    assert!(!expected.is_empty());
+    // Compared through Debug because HailValue equality does not cover every variant.
+    assert_eq!(format!("{:?}", expected), format!("{:?}", visited));
+    Ok(())
+}
+
+#[test]
+fn ref_sample_vcf() -> Result<()> {
+    compare_component("sample.vcf.mt/rows/rows")?;
+    compare_component("sample.vcf.mt/entries/rows")?;
+    compare_component("sample.vcf.mt/cols/rows")
+}
+
+#[test]
+fn ref_compat_150() -> Result<()> {
+    // Every kind of value, with and without LEB128.
+    for index in 0..8 {
+        compare_component(&format!(
+            "backward_compatability/1.5.0/table/{}.ht/rows",
+            index
+        ))?;
+    }
+    Ok(())
+}
+
+#[test]
+fn strings_borrow_from_the_input() -> Result<()> {
+    let path = resource("sample.vcf.mt/rows/rows");
+    let metadata = parser::load::metadata::load_component_metadata(&path)?;
+    assert!(metadata.buffer_spec.uses_leb128());
+
+    let raw = std::fs::read(path.join("parts").join(&metadata.part_files[0]))?;
+    let data = parser::load::compression::decompress_part_file(&raw)?;
+    let rows = parse_rows_ref::<UnsignedLEB128Encoder>(&data, &metadata.encoded_type)?;
+
+    assert!(!rows.is_empty());
+    let input = data.as_ptr_range();
+    for row in &rows {
+        let fields = match row {
+            HailValueRef::Struct(fields) => fields,
+            row => panic!("expected a struct, found {:?}", row),
+        };
+        match &fields["locus"] {
+            HailValueRef::Locus { contig, .. } => {
+                assert!(input.contains(&contig.as_ptr()));
+            }
+            locus => panic!("expected a locus, found {:?}", locus),
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn visit_errors_stop_the_scan() {
+    let mut visited = 0;
+    let error = parser::load::component_for_each(resource("sample.vcf.mt/rows/rows"), |_| {
+        visited += 1;
+        Err("stop".into())
+    })
+    .unwrap_err();
+
+    assert!(matches!(error, parser::Error::Visit(_)));
+    assert_eq!(error.to_string(), "stop");
+    let source = std::error::Error::source(&error).map(ToString::to_string);
+    assert_eq!(source.as_deref(), Some("stop"));
+    assert_eq!(visited, 1);
+}
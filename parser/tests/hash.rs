@@ -0,0 +1,95 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeSet, HashMap, HashSet},
+    hash::{Hash, Hasher},
+};
+
+use anyhow::Result;
+
+use parser::HailValue;
+
+use crate::common::resource;
+
+fn hash(value: &HailValue) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The key of a row, as a struct of the key fields.
+fn key(row: &HailValue, key: &[String]) -> HailValue {
+    match row {
+        HailValue::Struct(fields) => HailValue::Struct(
+            key.iter()
+                .map(|name| (name.clone(), fields[name].clone()))
+                .collect(),
+        ),
+        value => panic!("expected a struct, found {:?}", value),
+    }
+}
+
+#[test]
+fn hash_agrees_with_eq() {
+    let equal = [
+        (HailValue::Float64(-0.0), HailValue::Float64(0.0)),
+        (HailValue::Float32(-0.0), HailValue::Float32(0.0)),
+        (
+            HailValue::Set(
+                vec![HailValue::Int32(2), HailValue::Int32(1)]
+                    .into_iter()
+                    .collect(),
+            ),
+            HailValue::Set(
+                vec![
+                    HailValue::Int32(1),
+                    HailValue::Int32(2),
+                    HailValue::Int32(1),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+        ),
+        (
+            HailValue::Tuple(vec![HailValue::Missing, HailValue::Float64(-0.0)]),
+            HailValue::Tuple(vec![HailValue::Missing, HailValue::Float64(0.0)]),
+        ),
+    ];
+    for (value, other) in &equal {
+        assert_eq!(value, other);
+        assert_eq!(hash(value), hash(other), "{:?}", value);
+    }
+
+    // The same contents in different variants are different values.
+    let values: HashSet<_> = vec![
+        HailValue::Array(vec![HailValue::Int32(1)]),
+        HailValue::Tuple(vec![HailValue::Int32(1)]),
+        HailValue::Set(vec![HailValue::Int32(1)].into_iter().collect()),
+        HailValue::Int32(1),
+        HailValue::Int64(1),
+        HailValue::Call(1),
+        HailValue::Missing,
+        HailValue::Missing,
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(values.len(), 7);
+}
+
+/// Distinct counts and joins on keys, without sorting.
+#[test]
+fn hash_row_keys() -> Result<()> {
+    let matrix = parser::load::matrix(resource("sample.vcf.mt"))?;
+    let rows = matrix.rows.data.concat();
+    let key_fields = &matrix.rows.metadata.key;
+    let keys: Vec<_> = rows.iter().map(|row| key(row, key_fields)).collect();
+
+    let distinct: HashSet<_> = keys.iter().collect();
+    let sorted: BTreeSet<_> = keys.iter().collect();
+    assert_eq!(distinct.len(), sorted.len());
+
+    // Joins the rows with their entries, which are in the same order.
+    let by_key: HashMap<_, _> = keys.iter().zip(matrix.entries.data.concat()).collect();
+    for key in &keys {
+        assert!(by_key.contains_key(key));
+    }
+    Ok(())
+}
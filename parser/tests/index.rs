@@ -0,0 +1,132 @@
+use std::{collections::BTreeMap, ops::Bound};
+
+use anyhow::Result;
+use parser::{index::IndexedComponent, HailValue};
+
+use crate::common::resource;
+
+fn key_of(row: &HailValue, key_fields: &[&str]) -> HailValue {
+    match row {
+        HailValue::Struct(fields) => HailValue::Struct(
+            key_fields
+                .iter()
+                .map(|&f| (f.to_owned(), fields[f].clone()))
+                .collect::<BTreeMap<_, _>>(),
+        ),
+        _ => panic!("rows are structs"),
+    }
+}
+
+/// Every row can be found by its own key, and is returned along with its duplicates only.
+fn check_lookups(component: &str, key_fields: &[&str]) -> Result<()> {
+    let full = parser::load::component(resource(component))?;
+    let indexed = IndexedComponent::open(resource(component))?;
+
+    let rows: Vec<&HailValue> = full.data.iter().flatten().collect();
+    for row in &rows {
+        let key = key_of(row, key_fields);
+        let expected: Vec<String> = rows
+            .iter()
+            .filter(|r| key_of(r, key_fields) == key)
+            .map(|r| format!("{:?}", r))
+            .collect();
+
+        let found: Vec<String> = indexed
+            .lookup(&key)?
+            .iter()
+            .map(|r| format!("{:?}", r))
+            .collect();
+
+        assert_eq!(expected, found);
+    }
+    Ok(())
+}
+
+#[test]
+fn index_lookup_three_key() -> Result<()> {
+    check_lookups("three_key.ht/rows", &["x", "y", "z"])
+}
+
+#[test]
+fn index_lookup_v1_custom_references() -> Result<()> {
+    check_lookups("custom_references.t/rows", &["idx"])
+}
+
+#[test]
+fn index_lookup_sample_indexed() -> Result<()> {
+    check_lookups("sample-indexed-0.2.52.mt/rows/rows", &["locus", "alleles"])
+}
+
+#[test]
+fn index_lookup_missing_key() -> Result<()> {
+    let indexed = IndexedComponent::open(resource("custom_references.t/rows"))?;
+
+    let key = HailValue::Struct(
+        vec![("idx".to_owned(), HailValue::Int32(100))]
+            .into_iter()
+            .collect(),
+    );
+    assert!(indexed.lookup(&key)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn index_entries_share_the_rows_index() -> Result<()> {
+    let rows = parser::load::component(resource("sample-indexed-0.2.52.mt/rows/rows"))?;
+    let entries = parser::load::component(resource("sample-indexed-0.2.52.mt/entries/rows"))?;
+    let indexed = IndexedComponent::open(resource("sample-indexed-0.2.52.mt/entries/rows"))?;
+
+    for (row, entry) in rows.data[0].iter().zip(&entries.data[0]) {
+        let found = indexed.lookup(&key_of(row, &["locus", "alleles"]))?;
+        assert_eq!(format!("{:?}", vec![entry]), format!("{:?}", found));
+    }
+    Ok(())
+}
+
+#[test]
+fn index_range_three_key() -> Result<()> {
+    let full = parser::load::component(resource("three_key.ht/rows"))?;
+    let indexed = IndexedComponent::open(resource("three_key.ht/rows"))?;
+
+    let x = |x| {
+        HailValue::Struct(
+            vec![("x".to_owned(), HailValue::Int32(x))]
+                .into_iter()
+                .collect(),
+        )
+    };
+    let x_of = |row: &HailValue| match row {
+        HailValue::Struct(fields) => fields["x"].clone(),
+        _ => panic!("rows are structs"),
+    };
+
+    let all = indexed.range::<HailValue, _>(..)?;
+    assert_eq!(format!("{:?}", full.data.concat()), format!("{:?}", all));
+
+    let expected: Vec<&HailValue> = full
+        .data
+        .iter()
+        .flatten()
+        .filter(|row| x_of(row) >= HailValue::Int32(1) && x_of(row) < HailValue::Int32(3))
+        .collect();
+    assert!(!expected.is_empty());
+    assert_eq!(
+        format!("{:?}", expected),
+        format!("{:?}", indexed.range(x(1)..x(3))?)
+    );
+
+    let expected: Vec<&HailValue> = full
+        .data
+        .iter()
+        .flatten()
+        .filter(|row| x_of(row) > HailValue::Int32(2))
+        .collect();
+    assert_eq!(
+        format!("{:?}", expected),
+        format!(
+            "{:?}",
+            indexed.range::<HailValue, _>((Bound::Excluded(&x(2)), Bound::Unbounded))?
+        )
+    );
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use anyhow::Result;
+
+use parser::{
+    parse::{data::parse_rows, Encoding, StandardEncoder, UnsignedLEB128Encoder},
+    types::{EType, ETypeShape},
+    HailValue,
+};
+
+const INTS: [i32; 6] = [0, 1, -1, 127, i32::MIN, i32::MAX];
+
+fn int32() -> EType {
+    EType {
+        shape: ETypeShape::Int32,
+        required: true,
+        virtual_hint: None,
+    }
+}
+
+/// Encodes each value as a row of its own.
+fn encode<E: Encoding>(values: &[i32]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for value in values {
+        E::write_bool(&mut out, true);
+        E::write_i32(&mut out, *value);
+    }
+    E::write_bool(&mut out, false);
+    out
+}
+
+fn round_trip<E: Encoding>() -> Result<()> {
+    let rows = parse_rows::<E>(&encode::<E>(&INTS), &int32())?;
+    let expected: Vec<HailValue> = INTS.iter().copied().map(HailValue::Int32).collect();
+    assert_eq!(expected, rows);
+    Ok(())
+}
+
+#[test]
+fn round_trip_standard() -> Result<()> {
+    round_trip::<StandardEncoder>()
+}
+
+#[test]
+fn round_trip_leb128() -> Result<()> {
+    round_trip::<UnsignedLEB128Encoder>()
+}
+
+#[test]
+fn negative_values_as_written_by_hail() -> Result<()> {
+    // Hail writes the bits of the two's complement, so -1 is 0xFFFFFFFF.
+    let standard = [1, 0xff, 0xff, 0xff, 0xff, 0];
+    let leb128 = [1, 0xff, 0xff, 0xff, 0xff, 0x0f, 0];
+    let expected = vec![HailValue::Int32(-1)];
+
+    assert_eq!(
+        expected,
+        parse_rows::<StandardEncoder>(&standard, &int32())?
+    );
+    assert_eq!(
+        expected,
+        parse_rows::<UnsignedLEB128Encoder>(&leb128, &int32())?
+    );
+    assert_eq!(&leb128[..], &encode::<UnsignedLEB128Encoder>(&[-1])[..]);
+    Ok(())
+}
+
+#[test]
+fn negative_values_are_ordered() {
+    assert!(HailValue::Int32(-1) < HailValue::Int32(0));
+    assert!(HailValue::Int32(i32::MIN) < HailValue::Int32(i32::MAX));
+}
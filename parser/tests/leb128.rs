@@ -0,0 +1,49 @@
+use parser::parse::{Encoding, UnsignedLEB128Encoder};
+
+/// The bytes Hail writes for Int32 values: the unsigned LEB128 of their two's complement bits.
+#[test]
+fn leb128_int32_as_written_by_hail() {
+    for (value, bytes) in [
+        (64, &[0x40][..]),
+        (-64, &[0xc0, 0xff, 0xff, 0xff, 0x0f]),
+        (-1, &[0xff, 0xff, 0xff, 0xff, 0x0f]),
+        (i32::MIN, &[0x80, 0x80, 0x80, 0x80, 0x08]),
+        (i32::MAX, &[0xff, 0xff, 0xff, 0xff, 0x07]),
+    ] {
+        assert_eq!(
+            UnsignedLEB128Encoder::i32(bytes),
+            Ok((&[][..], value)),
+            "{:x?}",
+            bytes
+        );
+    }
+}
+
+/// The bytes Hail writes for Int64 values, as for Int32 values (see above).
+#[test]
+fn leb128_int64_as_written_by_hail() {
+    let mut minus_one = vec![0xff; 9];
+    minus_one.push(0x01);
+    let mut minus_sixty_four = vec![0xc0];
+    minus_sixty_four.extend([0xff; 8]);
+    minus_sixty_four.push(0x01);
+    let mut min = vec![0x80; 9];
+    min.push(0x01);
+    let mut max = vec![0xff; 8];
+    max.push(0x7f);
+
+    for (value, bytes) in [
+        (64, vec![0x40]),
+        (-64, minus_sixty_four),
+        (-1, minus_one),
+        (i64::MIN, min),
+        (i64::MAX, max),
+    ] {
+        assert_eq!(
+            UnsignedLEB128Encoder::i64(&bytes),
+            Ok((&[][..], value)),
+            "{:x?}",
+            bytes
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use anyhow::Result;
+
+use parser::{
+    error::{DecodeError, DecodeErrorKind},
+    load::compression::decompress_part_file,
+    parse::{
+        data::{parse_rows, parse_rows_ref},
+        Encoding, StandardEncoder, UnsignedLEB128Encoder,
+    },
+    types::{EType, VType},
+    Error,
+};
+
+use crate::common::resource;
+
+fn decode_error(error: Error) -> DecodeError {
+    match error {
+        Error::Decode(error) => *error,
+        error => panic!("expected a decoding error, found {:?}", error),
+    }
+}
+
+/// Decodes the data both as owned and as borrowed rows, returning whether it could be decoded.
+/// Malformed data must be refused with an error, never a panic.
+fn decodes<E: Encoding>(data: &[u8], row_type: &EType) -> bool {
+    let owned = parse_rows::<E>(data, row_type).is_ok();
+    let borrowed = parse_rows_ref::<E>(data, row_type).is_ok();
+    assert_eq!(owned, borrowed);
+    owned
+}
+
+/// Truncates (and corrupts) the first part file of a component in every way the size allows.
+fn mangle_component(file: &str) -> Result<()> {
+    let path = resource(file);
+    let metadata = parser::load::metadata::load_component_metadata(&path)?;
+    let raw = std::fs::read(path.join("parts").join(&metadata.part_files[0]))?;
+    let row_type = &metadata.encoded_type;
+    let leb128 = metadata.buffer_spec.uses_leb128();
+    let decode = |data: &[u8]| match leb128 {
+        true => decodes::<UnsignedLEB128Encoder>(data, row_type),
+        false => decodes::<StandardEncoder>(data, row_type),
+    };
+
+    let data = match (
+        metadata.buffer_spec.uses_compression(),
+        metadata.buffer_spec.appends_length(),
+    ) {
+        (true, _) => {
+            for end in 0..raw.len() {
+                let _ = decompress_part_file(&raw[..end]);
+            }
+            decompress_part_file(&raw)?
+        }
+        (false, true) => parser::load::compression::concatenate_blocks(&raw)?,
+        (false, false) => raw,
+    };
+
+    assert!(decode(&data));
+    for end in 0..data.len() {
+        assert!(!decode(&data[..end]), "{} bytes of {}", end, data.len());
+    }
+    let mut corrupted = data.clone();
+    for index in 0..data.len() {
+        for byte in [0x00, 0x7f, 0x80, 0xff].iter() {
+            corrupted[index] = *byte;
+            decode(&corrupted);
+        }
+        corrupted[index] = data[index];
+    }
+    Ok(())
+}
+
+#[test]
+fn malformed_compat_150() -> Result<()> {
+    // Every kind of value, with and without LEB128.
+    for index in 0..8 {
+        mangle_component(&format!(
+            "backward_compatability/1.5.0/table/{}.ht/rows",
+            index
+        ))?;
+    }
+    Ok(())
+}
+
+#[test]
+fn malformed_sample_vcf() -> Result<()> {
+    // The rows are much larger, and decoding every truncation of them would take a while.
+    mangle_component("sample.vcf.mt/cols/rows")
+}
+
+#[test]
+fn malformed_blocks() {
+    // A block shorter than its own length.
+    let mut raw = Vec::new();
+    raw.extend_from_slice(&100u32.to_le_bytes());
+    raw.extend_from_slice(&[1, 2, 3]);
+    assert!(matches!(
+        decompress_part_file(&raw),
+        Err(Error::Block { .. })
+    ));
+
+    // A few bytes claiming to decompress to 4GB.
+    let mut raw = Vec::new();
+    raw.extend_from_slice(&8u32.to_le_bytes());
+    raw.extend_from_slice(&u32::MAX.to_le_bytes());
+    raw.extend_from_slice(&[0x10, 0x00, 0x00, 0x00]);
+    assert!(matches!(
+        decompress_part_file(&raw),
+        Err(Error::Block { .. })
+    ));
+
+    // A valid LZ4 block of 4 literal bytes claiming to decompress to 8.
+    let mut raw = Vec::new();
+    raw.extend_from_slice(&9u32.to_le_bytes());
+    raw.extend_from_slice(&8u32.to_le_bytes());
+    raw.extend_from_slice(&[0x40, 1, 2, 3, 4]);
+    assert!(matches!(
+        decompress_part_file(&raw),
+        Err(Error::Block { .. })
+    ));
+
+    // A length cut short.
+    assert!(matches!(
+        decompress_part_file(&[1, 0]),
+        Err(Error::Block { .. })
+    ));
+}
+
+#[test]
+fn lengths_are_checked() -> Result<()> {
+    let row_type: EType = "+EBaseStruct{a:+EArray[+EInt64]}".parse()?;
+    let mut data = Vec::new();
+    StandardEncoder::write_bool(&mut data, true);
+    StandardEncoder::write_u32(&mut data, u32::MAX);
+    StandardEncoder::write_i64(&mut data, 1);
+
+    // A length beyond the data is read as the data ending early, as the rest of the array may
+    // only be in the next block when streaming.
+    let error = decode_error(parse_rows::<StandardEncoder>(&data, &row_type).unwrap_err());
+    assert_eq!(error.field, "a");
+    assert_eq!(error.kind, DecodeErrorKind::UnexpectedEnd);
+
+    // Empty structs take no space, so only their number is limited.
+    let row_type: EType = "+EBaseStruct{a:+EArray[+EBaseStruct{}]}".parse()?;
+    let mut data = Vec::new();
+    StandardEncoder::write_bool(&mut data, true);
+    StandardEncoder::write_u32(&mut data, u32::MAX);
+    StandardEncoder::write_bool(&mut data, false);
+    assert!(parse_rows::<StandardEncoder>(&data, &row_type).is_err());
+
+    let mut data = Vec::new();
+    StandardEncoder::write_bool(&mut data, true);
+    StandardEncoder::write_u32(&mut data, 3);
+    StandardEncoder::write_bool(&mut data, false);
+    assert_eq!(parse_rows::<StandardEncoder>(&data, &row_type)?.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn ndarray_shapes_are_checked() -> Result<()> {
+    let row_type: EType = "+EBaseStruct{a:+ENDArrayColumnMajor[+EFloat64,2]}".parse()?;
+    let too_large = DecodeErrorKind::Invalid(nom::error::ErrorKind::TooLarge);
+    let shapes = [
+        ([-1, 2], too_large),
+        ([i64::MAX, 3], too_large),
+        ([1 << 32, 1 << 32], too_large),
+        // The number of elements is fine, but the data ends long before them.
+        ([1 << 20, 1 << 20], DecodeErrorKind::UnexpectedEnd),
+    ];
+    for (shape, kind) in shapes.iter() {
+        let mut data = Vec::new();
+        StandardEncoder::write_bool(&mut data, true);
+        for d in shape.iter() {
+            StandardEncoder::write_i64(&mut data, *d);
+        }
+        StandardEncoder::write_f64(&mut data, 1.0);
+
+        let error = decode_error(parse_rows::<StandardEncoder>(&data, &row_type).unwrap_err());
+        assert_eq!(error.field, "a", "{:?}", shape);
+        assert_eq!(error.kind, *kind, "{:?}", shape);
+    }
+    Ok(())
+}
+
+#[test]
+fn malformed_schemas() {
+    let schema = "+EBaseStruct{a:EArray[+EBaseStruct{`0`:EInt32}],\
+        b:ENDArrayColumnMajor[+EFloat64,2],c:EBinary}";
+    assert!(schema.parse::<EType>().is_ok());
+    for (end, _) in schema.char_indices() {
+        assert!(
+            schema[..end].parse::<EType>().is_err(),
+            "{}",
+            &schema[..end]
+        );
+    }
+
+    let schema = "Struct{a:Array[Tuple[Int32,String]],b:Dict[String,Locus(GRCh38)]}";
+    assert!(schema.parse::<VType>().is_ok());
+    for (end, _) in schema.char_indices() {
+        let _ = schema[..end].parse::<VType>();
+    }
+
+    // Nesting deep enough to overflow the stack if it were parsed.
+    let deep = format!("{}Int32{}", "Array[".repeat(100_000), "]".repeat(100_000));
+    assert!(deep.parse::<VType>().is_err());
+    let deep = format!("{}EInt32{}", "EArray[".repeat(100_000), "]".repeat(100_000));
+    assert!(deep.parse::<EType>().is_err());
+    assert!("ENDArrayColumnMajor[EInt32,99999999999]"
+        .parse::<EType>()
+        .is_err());
+}
@@ -0,0 +1,218 @@
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use anyhow::Result;
+
+use parser::{
+    load::metadata::{load_any_metadata, AnyMetadata},
+    types::{key::compare_key, HailOrdering, VType},
+    HailValue,
+};
+
+use crate::common::resource;
+
+fn locus(contig: &str, position: u32, reference: &str) -> HailValue {
+    HailValue::Locus {
+        contig: contig.to_owned(),
+        position,
+        reference: reference.to_owned(),
+    }
+}
+
+fn fields(values: Vec<(&str, HailValue)>) -> HailValue {
+    HailValue::Struct(
+        values
+            .into_iter()
+            .map(|(name, value)| (name.to_owned(), value))
+            .collect(),
+    )
+}
+
+/// The rows written by Hail should be sorted by key, across partitions, in the Hail ordering.
+fn check_sorted(file: &str) -> Result<()> {
+    let path = resource(file);
+    let (rows_path, references) = match load_any_metadata(&path)? {
+        AnyMetadata::Matrix(metadata) => (
+            path.join(&metadata.components.rows.rel_path),
+            metadata.references,
+        ),
+        AnyMetadata::Table(metadata) => (
+            path.join(&metadata.components.rows.rel_path),
+            metadata.references,
+        ),
+        AnyMetadata::Component(_) => (path, BTreeMap::new()),
+    };
+    // Streamed, so that only the previous row is kept around.
+    let rows = parser::load::component_iter(rows_path)?;
+    let key_type = rows.metadata.key_type();
+    let ordering = HailOrdering::new(references);
+
+    let mut previous: Option<HailValue> = None;
+    for row in rows {
+        let row = row?;
+        if let Some(previous) = &previous {
+            let compared = compare_key(&key_type, &ordering, previous, &row);
+            assert_ne!(
+                compared,
+                Ordering::Greater,
+                "{}: {:?}",
+                file,
+                (previous, &row)
+            );
+        }
+        previous = Some(row);
+    }
+    Ok(())
+}
+
+#[test]
+fn ordering_of_written_keys() -> Result<()> {
+    for file in &[
+        "sample.vcf.mt",
+        "sample-indexed-0.2.52.mt",
+        "HG00096.g.vcf.gz.mt",
+        "ex.vcf.mt",
+        "three_key.ht",
+        "custom_references.t",
+        "custom_references.mt",
+        "small-pheno.t",
+    ] {
+        check_sorted(file)?;
+    }
+    for index in 0..8 {
+        check_sorted(&format!("backward_compatability/1.5.0/table/{}.ht", index))?;
+    }
+    Ok(())
+}
+
+#[test]
+fn ordering_missing_last() -> Result<()> {
+    let ordering = HailOrdering::default();
+    let v_type: VType = "Array[Int32]".parse()?;
+
+    let mut values = vec![
+        HailValue::Missing,
+        HailValue::Array(vec![HailValue::Int32(2)]),
+        HailValue::Array(vec![HailValue::Missing]),
+        HailValue::Array(vec![HailValue::Int32(1), HailValue::Int32(0)]),
+        HailValue::Array(vec![HailValue::Int32(1)]),
+    ];
+    ordering.sort(&v_type, &mut values);
+    assert_eq!(
+        values,
+        vec![
+            HailValue::Array(vec![HailValue::Int32(1)]),
+            HailValue::Array(vec![HailValue::Int32(1), HailValue::Int32(0)]),
+            HailValue::Array(vec![HailValue::Int32(2)]),
+            HailValue::Array(vec![HailValue::Missing]),
+            HailValue::Missing,
+        ]
+    );
+    // Unlike the default order.
+    assert!(HailValue::Missing < HailValue::Int32(0));
+    Ok(())
+}
+
+#[test]
+fn ordering_loci_by_contig() -> Result<()> {
+    let ordering = HailOrdering::default();
+    let v_type: VType = "Locus(GRCh38)".parse()?;
+
+    let mut values = vec![
+        locus("chrX", 1, "GRCh38"),
+        locus("chr10", 5, "GRCh38"),
+        locus("chr2", 7, "GRCh38"),
+        locus("chr_unknown", 1, "GRCh38"),
+        locus("chr2", 3, "GRCh38"),
+    ];
+    ordering.sort(&v_type, &mut values);
+    let contigs: Vec<_> = values
+        .iter()
+        .map(|value| match value {
+            HailValue::Locus {
+                contig, position, ..
+            } => format!("{}:{}", contig, position),
+            value => panic!("expected a locus, found {:?}", value),
+        })
+        .collect();
+    assert_eq!(
+        contigs,
+        vec!["chr2:3", "chr2:7", "chr10:5", "chrX:1", "chr_unknown:1"]
+    );
+
+    // The default order is by name, but still total.
+    let (chr2, chr10) = (locus("chr2", 1, "GRCh38"), locus("chr10", 1, "GRCh38"));
+    assert!(chr10 < chr2);
+    assert_eq!(chr2.cmp(&chr10), Ordering::Greater);
+    Ok(())
+}
+
+#[test]
+fn ordering_structs_by_type() -> Result<()> {
+    let ordering = HailOrdering::default();
+    // The fields are compared in the order of the type, not by name.
+    let v_type: VType = "Struct{b:Int32,a:Int32}".parse()?;
+    let first = fields(vec![("a", HailValue::Int32(2)), ("b", HailValue::Int32(1))]);
+    let second = fields(vec![("a", HailValue::Int32(1)), ("b", HailValue::Int32(2))]);
+
+    assert_eq!(ordering.compare(&v_type, &first, &second), Ordering::Less);
+    assert_eq!(first.cmp(&second), Ordering::Greater);
+    Ok(())
+}
+
+#[test]
+fn ordering_intervals_and_floats() -> Result<()> {
+    let ordering = HailOrdering::default();
+    let v_type: VType = "Interval[Int32]".parse()?;
+    let interval = |start, end, includes_start, includes_end| HailValue::Interval {
+        start: Box::new(HailValue::Int32(start)),
+        end: Box::new(HailValue::Int32(end)),
+        includes_start,
+        includes_end,
+    };
+
+    let mut values = vec![
+        interval(1, 5, false, true),
+        interval(1, 5, true, true),
+        interval(1, 4, false, true),
+        interval(1, 5, false, false),
+        interval(0, 9, false, false),
+    ];
+    let mut sorted = values.clone();
+    ordering.sort(&v_type, &mut values);
+    sorted.sort();
+    let expected = vec![
+        interval(0, 9, false, false),
+        interval(1, 5, true, true),
+        interval(1, 4, false, true),
+        interval(1, 5, false, false),
+        interval(1, 5, false, true),
+    ];
+    assert_eq!(values, expected);
+    assert_eq!(sorted, expected);
+
+    let v_type: VType = "Float64".parse()?;
+    let (negative, positive) = (HailValue::Float64(-0.0), HailValue::Float64(0.0));
+    assert_eq!(
+        ordering.compare(&v_type, &negative, &positive),
+        Ordering::Less
+    );
+    assert_eq!(negative, positive);
+    Ok(())
+}
+
+#[test]
+fn ordering_default_is_total() {
+    // Tuples, intervals, and ndarrays are equal to themselves.
+    let value = HailValue::Tuple(vec![HailValue::Int32(1), HailValue::String("a".to_owned())]);
+    assert_eq!(value, value.clone());
+    let array = HailValue::NDArray(
+        ndarray::ArrayD::from_shape_vec(vec![2], vec![HailValue::Int32(1), HailValue::Int32(2)])
+            .unwrap(),
+    );
+    assert_eq!(array, array.clone());
+
+    // Values of different variants are ordered, either way.
+    let (int, string) = (HailValue::Int32(1), HailValue::String("a".to_owned()));
+    assert_eq!(int.cmp(&string), string.cmp(&int).reverse());
+    assert_ne!(int.cmp(&string), Ordering::Equal);
+}
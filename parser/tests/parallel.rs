@@ -0,0 +1,29 @@
+#![cfg(feature = "parallel")]
+
+use anyhow::Result;
+
+use crate::common::resource;
+
+/// Whatever the number of threads, the partitions should come back in order, matching the rows
+/// decoded one at a time.
+#[test]
+fn partition_order_hg00096_entries() -> Result<()> {
+    let path = resource("HG00096.g.vcf.gz.mt/entries/rows");
+    let counts = parser::load::component_partition_counts(&path)?;
+
+    for &threads in &[1, 3, 0] {
+        parser::load::parallel::set_threads(threads)?;
+        let loaded = parser::load::component(&path)?;
+
+        let loaded_counts: Vec<u64> = loaded.data.iter().map(|rows| rows.len() as u64).collect();
+        assert_eq!(loaded_counts, counts);
+        // Compared value by value, without holding a second copy of the entries (let alone their
+        // Debug output, which would take gigabytes).
+        let mut streamed = parser::load::component_iter(&path)?;
+        for row in loaded.data.iter().flatten() {
+            assert_eq!(streamed.next().transpose()?.as_ref(), Some(row));
+        }
+        assert!(streamed.next().is_none());
+    }
+    Ok(())
+}
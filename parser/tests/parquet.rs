@@ -0,0 +1,183 @@
+#![cfg(feature = "parquet")]
+use std::{fs::File, path::PathBuf};
+
+use anyhow::Result;
+
+use parquet::{
+    arrow::arrow_reader::ParquetRecordBatchReaderBuilder,
+    file::{
+        metadata::ParquetMetaData, reader::FileReader, serialized_reader::SerializedFileReader,
+    },
+    format::SortingColumn,
+};
+use parser::arrow::parquet::{KEY_KEY, SCHEMA_KEY};
+
+use crate::common::{output, resource};
+
+fn key_value(metadata: &ParquetMetaData, key: &str) -> Option<String> {
+    metadata
+        .file_metadata()
+        .key_value_metadata()?
+        .iter()
+        .find(|entry| entry.key == key)?
+        .value
+        .clone()
+}
+
+fn sorted_by(metadata: &ParquetMetaData) -> Vec<i32> {
+    let columns = match metadata.row_group(0).sorting_columns() {
+        Some(columns) => columns,
+        None => return vec![],
+    };
+    assert!(columns
+        .iter()
+        .all(|column| !column.descending && !column.nulls_first));
+    columns
+        .iter()
+        .map(|SortingColumn { column_idx, .. }| *column_idx)
+        .collect()
+}
+
+/// The row groups should hold the same rows as the partitions, decoded into Arrow.
+fn compare_with_arrow(component: &str, path: &PathBuf) -> Result<ParquetMetaData> {
+    let exported = parser::arrow::component(resource(component))?;
+    let batches: Vec<_> = exported
+        .batches
+        .iter()
+        .filter(|batch| batch.num_rows() > 0)
+        .collect();
+
+    let reader = SerializedFileReader::new(File::open(path)?)?;
+    let metadata = reader.metadata().clone();
+    assert_eq!(metadata.num_row_groups(), batches.len());
+
+    for (index, batch) in batches.iter().enumerate() {
+        let row_group = metadata.row_group(index);
+        assert_eq!(row_group.num_rows(), batch.num_rows() as i64);
+
+        let mut read = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)?
+            .with_row_groups(vec![index])
+            .with_batch_size(batch.num_rows())
+            .build()?;
+        let read = read.next().unwrap()?;
+        assert_eq!(read.columns(), batch.columns());
+    }
+    Ok(metadata)
+}
+
+#[test]
+fn parquet_sample_vcf_rows() -> Result<()> {
+    let path = output("sample_vcf_rows.parquet");
+    parser::arrow::parquet::write_component(resource("sample.vcf.mt/rows/rows"), &path)?;
+    let metadata = compare_with_arrow("sample.vcf.mt/rows/rows", &path)?;
+
+    let component =
+        parser::load::metadata::load_component_metadata(resource("sample.vcf.mt/rows/rows"))?;
+    assert_eq!(
+        key_value(&metadata, SCHEMA_KEY),
+        Some(component.virtual_type.to_string())
+    );
+    assert_eq!(
+        key_value(&metadata, KEY_KEY).as_deref(),
+        Some(r#"["locus","alleles"]"#)
+    );
+    // Loci are sorted by the index of their contig, not by its name.
+    assert!(sorted_by(&metadata).is_empty());
+    Ok(())
+}
+
+#[test]
+fn parquet_three_key_table() -> Result<()> {
+    let path = output("three_key.parquet");
+    let written = parser::arrow::parquet::write_table(resource("three_key.ht"), &path)?;
+    assert_eq!(written.num_rows, 120);
+
+    let metadata = compare_with_arrow("three_key.ht/rows", &path)?;
+    assert_eq!(
+        key_value(&metadata, SCHEMA_KEY).as_deref(),
+        Some("Struct{x:Int32,y:Int32,z:Int32}")
+    );
+    assert_eq!(
+        key_value(&metadata, KEY_KEY).as_deref(),
+        Some(r#"["x","y","z"]"#)
+    );
+    assert_eq!(sorted_by(&metadata), vec![0, 1, 2]);
+    Ok(())
+}
+
+#[test]
+fn parquet_not_sorted_by_strings() -> Result<()> {
+    // The rows are keyed by the name of their sample.
+    let path = output("small-pheno.parquet");
+    parser::arrow::parquet::write_table(resource("small-pheno.t"), &path)?;
+    let metadata = compare_with_arrow("small-pheno.t/rows", &path)?;
+    assert_eq!(key_value(&metadata, KEY_KEY).as_deref(), Some(r#"["s"]"#));
+    assert!(sorted_by(&metadata).is_empty());
+    Ok(())
+}
+
+#[test]
+fn parquet_sorted_up_to_the_locus() -> Result<()> {
+    use parser::types::{encoding::VirtualHint, ETypeShape};
+    use parser::HailValue;
+
+    // The rows are keyed by their index, then by a locus.
+    let mut component = parser::load::component(resource("small-pheno.t/rows"))?;
+    component.metadata.key = vec!["index".to_owned(), "locus".to_owned()];
+    component.metadata.virtual_type =
+        "Struct{index:Int32,s:String,phenotype:Float64,locus:Locus(GRCh37)}".parse()?;
+    component.metadata.encoded_type = "+EBaseStruct{index:+EInt32,s:EBinary,phenotype:EFloat64,\
+        locus:EBaseStruct{contig:+EBinary,position:+EInt32}}"
+        .parse()?;
+    match &mut component.metadata.encoded_type.shape {
+        ETypeShape::BaseStruct(fields) => {
+            fields[1].1.virtual_hint = Some(VirtualHint::String);
+            fields[3].1.virtual_hint = Some(VirtualHint::Locus("GRCh37".to_owned()));
+        }
+        _ => panic!("rows are structs"),
+    }
+    component.metadata.range_bounds.clear();
+    for (index, row) in component.data.iter_mut().flatten().enumerate() {
+        match row {
+            HailValue::Struct(fields) => {
+                fields.insert("index".to_owned(), HailValue::Int32(index as i32));
+                fields.insert(
+                    "locus".to_owned(),
+                    HailValue::Locus {
+                        contig: ["2", "10"][index % 2].to_owned(),
+                        position: 1,
+                        reference: "GRCh37".to_owned(),
+                    },
+                )
+            }
+            _ => panic!("rows are structs"),
+        };
+    }
+    let rows = output("small-pheno.loci");
+    parser::write::component(&component, &rows)?;
+
+    let path = output("small-pheno.loci.parquet");
+    parser::arrow::parquet::write_component(&rows, &path)?;
+    let reader = SerializedFileReader::new(File::open(&path)?)?;
+    assert_eq!(sorted_by(reader.metadata()), vec![0]);
+    Ok(())
+}
+
+#[test]
+fn parquet_skips_empty_partitions() -> Result<()> {
+    let mut table = parser::load::table(resource("three_key.ht"))?;
+    // The empty partitions take the bounds of their neighbours.
+    let bounds = &mut table.rows.metadata.range_bounds;
+    bounds.insert(0, bounds[0].clone());
+    bounds.push(bounds[bounds.len() - 1].clone());
+    table.rows.data.insert(0, vec![]);
+    table.rows.data.push(vec![]);
+    let rows = output("three_key.empty_partitions");
+    parser::write::component(&table.rows, &rows)?;
+
+    let path = output("three_key.empty_partitions.parquet");
+    let written = parser::arrow::parquet::write_component(&rows, &path)?;
+    assert_eq!(written.num_rows, 120);
+    assert_eq!(written.row_groups.len(), table.rows.data.len() - 2);
+    Ok(())
+}
@@ -0,0 +1,258 @@
+use anyhow::Result;
+use parser::HailValue;
+
+use crate::common::resource;
+
+#[test]
+fn partitions_table_three_key() -> Result<()> {
+    let table = parser::load::table_partitions(resource("three_key.ht"), 1..3)?;
+
+    let partitions = &table.rows.partitions;
+    assert_eq!(
+        vec![1, 2],
+        partitions.iter().map(|p| p.index).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![Some(4), Some(56)],
+        partitions.iter().map(|p| p.row_offset).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![52, 64],
+        partitions.iter().map(|p| p.rows.len()).collect::<Vec<_>>()
+    );
+    Ok(())
+}
+
+#[test]
+fn partitions_matrix_custom_references() -> Result<()> {
+    let full = parser::load::matrix(resource("custom_references.mt"))?;
+    let matrix = parser::load::matrix_partitions(resource("custom_references.mt"), vec![6, 0])?;
+
+    for (rows, entries) in matrix
+        .rows
+        .partitions
+        .iter()
+        .zip(&matrix.entries.partitions)
+    {
+        assert_eq!(rows.index, entries.index);
+        assert_eq!(rows.row_offset, entries.row_offset);
+        assert_eq!(full.rows.data[rows.index], rows.rows);
+        assert_eq!(full.entries.data[entries.index], entries.rows);
+    }
+    assert_eq!(Some(12), matrix.rows.partitions[0].row_offset);
+    assert_eq!(Some(0), matrix.rows.partitions[1].row_offset);
+    Ok(())
+}
+
+#[test]
+fn partitions_component_without_offsets() -> Result<()> {
+    let component = parser::load::component_partitions(resource("sample.vcf.mt/rows/rows"), [1])?;
+
+    assert_eq!(1, component.partitions.len());
+    assert_eq!(None, component.partitions[0].row_offset);
+    assert_eq!(174, component.partitions[0].rows.len());
+    Ok(())
+}
+
+#[test]
+fn partitions_out_of_range() {
+    let result = parser::load::component_partitions(resource("sample.vcf.mt/rows/rows"), [2]);
+    assert!(result.is_err());
+}
+
+fn locus(position: u32) -> HailValue {
+    HailValue::Locus {
+        contig: "20".to_owned(),
+        position,
+        reference: "GRCh37".to_owned(),
+    }
+}
+
+#[test]
+fn partitions_range_bounds() -> Result<()> {
+    let metadata = parser::load::metadata::load_component_metadata(resource(
+        "sample-indexed-0.2.52.mt/rows/rows",
+    ))?;
+
+    assert_eq!(1, metadata.range_bounds.len());
+    match &metadata.range_bounds[0] {
+        HailValue::Interval {
+            start,
+            includes_start,
+            includes_end,
+            ..
+        } => {
+            match start.as_ref() {
+                HailValue::Struct(fields) => assert_eq!(locus(14370), fields["locus"]),
+                other => panic!("expected a struct, found {:?}", other),
+            }
+            assert!(includes_start);
+            assert!(includes_end);
+        }
+        other => panic!("expected an interval, found {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn partitions_overlapping_v1_exclusive_bounds() -> Result<()> {
+    let metadata =
+        parser::load::metadata::load_component_metadata(resource("custom_references.t/rows"))?;
+    let idx = |idx| {
+        HailValue::Struct(
+            vec![("idx".to_owned(), HailValue::Int32(idx))]
+                .into_iter()
+                .collect(),
+        )
+    };
+
+    assert_eq!(8, metadata.range_bounds.len());
+    assert_eq!(vec![1], metadata.overlapping_partitions(idx(2)..=idx(2)));
+    assert_eq!(vec![1, 2], metadata.overlapping_partitions(idx(3)..idx(6)));
+    assert_eq!(vec![6, 7], metadata.overlapping_partitions(idx(12)..));
+    assert!(metadata.overlapping_partitions(idx(20)..).is_empty());
+    Ok(())
+}
+
+#[test]
+fn partitions_overlapping_locus_range() -> Result<()> {
+    let full = parser::load::matrix(resource("sample.vcf.mt"))?;
+    let matrix = parser::load::matrix_partitions_overlapping(
+        resource("sample.vcf.mt"),
+        locus(14_000_000)..locus(15_000_000),
+    )?;
+
+    assert_eq!(
+        vec![1],
+        matrix
+            .rows
+            .partitions
+            .iter()
+            .map(|p| p.index)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(full.rows.data[1], matrix.rows.partitions[0].rows);
+
+    let table = parser::load::table_partitions_overlapping(
+        resource("three_key.ht"),
+        ..HailValue::Struct(
+            vec![("x".to_owned(), HailValue::Int32(0))]
+                .into_iter()
+                .collect(),
+        ),
+    )?;
+    assert!(table.rows.partitions.is_empty());
+    Ok(())
+}
+
+#[test]
+fn partitions_overlapping_prefix_bounds() -> Result<()> {
+    use std::collections::BTreeMap;
+
+    let mut metadata =
+        parser::load::metadata::load_component_metadata(resource("sample.vcf.mt/rows/rows"))?;
+    assert_eq!(metadata.key, vec!["locus", "alleles"]);
+    let prefix = |position| {
+        let fields: BTreeMap<_, _> = vec![("locus".to_owned(), locus(position))]
+            .into_iter()
+            .collect();
+        Box::new(HailValue::Struct(fields))
+    };
+    let key = |position| {
+        let alleles = vec!["A", "T"]
+            .into_iter()
+            .map(|allele| HailValue::String(allele.to_owned()))
+            .collect();
+        let fields: BTreeMap<_, _> = vec![
+            ("locus".to_owned(), locus(position)),
+            ("alleles".to_owned(), HailValue::Array(alleles)),
+        ]
+        .into_iter()
+        .collect();
+        HailValue::Struct(fields)
+    };
+    let interval = |start, end, includes_start| HailValue::Interval {
+        start: prefix(start),
+        end: prefix(end),
+        includes_start,
+        includes_end: true,
+    };
+
+    // The partitions are bounded by loci only, the second one starting at 20:200.
+    metadata.range_bounds = vec![interval(100, 200, true), interval(200, 300, true)];
+    assert_eq!(vec![0, 1], metadata.overlapping_partitions(..=key(200)));
+    assert_eq!(vec![0, 1], metadata.overlapping_partitions(key(200)..));
+    // The second partition may hold keys at 20:200 before this one.
+    assert_eq!(vec![0, 1], metadata.overlapping_partitions(..key(200)));
+    assert_eq!(vec![0], metadata.overlapping_partitions(..locus(200)));
+    assert_eq!(vec![1], metadata.overlapping_partitions(key(201)..));
+
+    // Excluding 20:200 excludes all the keys at that locus.
+    metadata.range_bounds = vec![interval(100, 200, true), interval(200, 300, false)];
+    assert_eq!(vec![0], metadata.overlapping_partitions(..=key(200)));
+    assert_eq!(vec![0], metadata.overlapping_partitions(..=locus(200)));
+    assert_eq!(vec![0, 1], metadata.overlapping_partitions(..=locus(201)));
+    Ok(())
+}
+
+#[test]
+fn partitions_compare_loci_by_contig_index() {
+    use parser::types::{key::compare_key, HailOrdering};
+
+    let locus = |contig: &str| HailValue::Locus {
+        contig: contig.to_owned(),
+        position: 1,
+        reference: "GRCh37".to_owned(),
+    };
+    let key_fields = vec![("locus".to_owned(), "Locus(GRCh37)".parse().unwrap())];
+    let ordering = HailOrdering::default();
+    assert!(compare_key(&key_fields, &ordering, &locus("2"), &locus("10")).is_lt());
+}
+
+#[test]
+fn partitions_counted_by_skipping() -> Result<()> {
+    // The matrix records the counts of its rows, which are those of the entries as well.
+    let matrix = parser::load::metadata::load_matrix_metadata(resource("sample.vcf.mt"))?;
+    let expected: Vec<u64> = matrix
+        .components
+        .partition_counts
+        .counts
+        .iter()
+        .map(|&count| count as u64)
+        .collect();
+    for component in &["rows", "entries"] {
+        let path = resource(&format!("sample.vcf.mt/{}/rows", component));
+        assert_eq!(parser::load::component_partition_counts(path)?, expected);
+    }
+
+    for index in 0..8 {
+        let path = resource(&format!(
+            "backward_compatability/1.5.0/table/{}.ht/rows",
+            index
+        ));
+        let loaded = parser::load::component(&path)?;
+        let counts: Vec<u64> = loaded.data.iter().map(|rows| rows.len() as u64).collect();
+        assert_eq!(parser::load::component_partition_counts(&path)?, counts);
+    }
+    Ok(())
+}
+
+#[test]
+fn partitions_any_metadata() -> Result<()> {
+    use parser::load::metadata::{load_any_metadata, AnyMetadata};
+
+    assert!(matches!(
+        load_any_metadata(resource("sample.vcf.mt"))?,
+        AnyMetadata::Matrix(_)
+    ));
+    assert!(matches!(
+        load_any_metadata(resource("three_key.ht"))?,
+        AnyMetadata::Table(_)
+    ));
+    assert!(matches!(
+        load_any_metadata(resource("three_key.ht/rows"))?,
+        AnyMetadata::Component(_)
+    ));
+    assert!(load_any_metadata(resource("three_key.ht/rows/parts")).is_err());
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use anyhow::Result;
+
+use parser::{types::Projection, HailValue};
+
+use crate::common::resource;
+
+/// Applies a projection to a fully decoded value.
+fn project(value: &HailValue, projection: &Projection) -> HailValue {
+    match (value, projection) {
+        (HailValue::Struct(fields), Projection::Fields(selected)) => HailValue::Struct(
+            fields
+                .iter()
+                .filter_map(|(name, value)| {
+                    let projection = selected.get(name)?;
+                    Some((name.clone(), project(value, projection)))
+                })
+                .collect(),
+        ),
+        (value, _) => value.clone(),
+    }
+}
+
+/// The projected rows should match the fully decoded rows, with the unselected fields removed.
+fn compare_with_full_load(file: &str, fields: &[&str]) -> Result<()> {
+    let path = resource(file);
+    let full = parser::load::component(&path)?;
+    let projected = parser::load::component_with_projection(&path, fields)?;
+
+    let projection = Projection::from_paths(fields);
+    let expected: Vec<Vec<HailValue>> = full
+        .data
+        .iter()
+        .map(|rows| rows.iter().map(|row| project(row, &projection)).collect())
+        .collect();
+
+    assert!(projected.data.iter().any(|rows| !rows.is_empty()));
+    assert_eq!(expected, projected.data);
+    assert_eq!(
+        projected.metadata.encoded_type,
+        projection.encoded_type(&full.metadata.encoded_type)?
+    );
+    Ok(())
+}
+
+#[test]
+fn sample_vcf_rows() -> Result<()> {
+    compare_with_full_load("sample.vcf.mt/rows/rows", &["locus", "info.AF"])?;
+    compare_with_full_load(
+        "sample.vcf.mt/rows/rows",
+        &["alleles", "info.DP", "info.MQ"],
+    )?;
+    compare_with_full_load("sample.vcf.mt/rows/rows", &["info", "info.AF", "qual"])
+}
+
+#[test]
+fn compat_150_tables() -> Result<()> {
+    for index in 0..8 {
+        let path = format!("backward_compatability/1.5.0/table/{}.ht/rows", index);
+        // The (unselected) ndarrays are skipped, as they cannot be compared.
+        compare_with_full_load(&path, &["idx", "astruct.b", "mstruct.y", "mc", "h38"])?;
+    }
+    Ok(())
+}
+
+#[test]
+fn no_fields() -> Result<()> {
+    let projected = parser::load::component_with_projection::<_, &str>(
+        resource("sample.vcf.mt/rows/rows"),
+        &[],
+    )?;
+    let rows: Vec<&HailValue> = projected.data.iter().flatten().collect();
+
+    assert!(!rows.is_empty());
+    assert!(rows
+        .iter()
+        .all(|row| **row == HailValue::Struct(Default::default())));
+    assert!(projected.metadata.key.is_empty());
+    Ok(())
+}
+
+#[test]
+fn projected_key() -> Result<()> {
+    let path = resource("sample.vcf.mt/rows/rows");
+    let full = parser::load::metadata::load_component_metadata(&path)?;
+    assert_eq!(full.key, vec!["locus", "alleles"]);
+
+    let projected = parser::load::component_with_projection(&path, &["locus", "qual"])?;
+    assert_eq!(projected.metadata.key, vec!["locus"]);
+    let projected = parser::load::component_with_projection(&path, &["alleles", "locus"])?;
+    assert_eq!(projected.metadata.key, full.key);
+    Ok(())
+}
+
+#[test]
+fn invalid_paths() {
+    let path = resource("sample.vcf.mt/rows/rows");
+
+    let error = parser::load::component_with_projection(&path, &["not_a_field"])
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("`not_a_field`"));
+    assert!(error.contains("alleles"));
+
+    // Loci cannot be projected.
+    assert!(parser::load::component_with_projection(&path, &["locus.contig"]).is_err());
+    assert!(parser::load::component_with_projection(&path, &["info.not_a_field"]).is_err());
+}
@@ -0,0 +1,168 @@
+use std::cmp::Ordering;
+
+use anyhow::Result;
+
+use parser::{
+    load::metadata::{load_matrix_metadata, load_reference_genome, load_table_metadata},
+    types::{
+        reference::{Contig, LocusError},
+        ReferenceGenome,
+    },
+    HailValue,
+};
+
+use crate::common::{output, resource};
+
+fn loci(rows: &[HailValue], field: &str) -> Vec<(String, u32)> {
+    rows.iter()
+        .map(|row| match row {
+            HailValue::Struct(fields) => match &fields[field] {
+                HailValue::Locus {
+                    contig, position, ..
+                } => (contig.clone(), *position),
+                value => panic!("expected a locus, found {:?}", value),
+            },
+            value => panic!("expected a struct, found {:?}", value),
+        })
+        .collect()
+}
+
+#[test]
+fn reference_builtin() -> Result<()> {
+    let matrix = parser::load::matrix(resource("sample.vcf.mt"))?;
+    let references: Vec<_> = matrix.metadata.references.keys().collect();
+    assert_eq!(references, vec!["GRCh37"]);
+
+    let grch37 = &matrix.metadata.references["GRCh37"];
+    assert!(grch37.is_builtin());
+    assert_eq!(grch37.contigs().len(), 86);
+    assert_eq!(grch37.contig_index("X"), Some(22));
+    assert!(grch37.is_x("X") && grch37.is_y("Y") && grch37.is_mitochondrial("MT"));
+    assert!(grch37.in_par("X", 60001) && !grch37.in_par("X", 2699521));
+
+    // The rows are sorted in the order of the genome.
+    let loci = loci(&matrix.rows.data.concat(), "locus");
+    assert!(!loci.is_empty());
+    for pair in loci.windows(2) {
+        let (locus, next) = (&pair[0], &pair[1]);
+        grch37.check_locus(&locus.0, locus.1)?;
+        let ordering = grch37.compare_loci((&locus.0, locus.1), (&next.0, next.1));
+        assert_ne!(ordering, Some(Ordering::Greater));
+    }
+
+    let grch38 = ReferenceGenome::builtin("GRCh38").unwrap();
+    assert_eq!(grch38.contig("chrM").map(|c| c.length), Some(16569));
+    assert_eq!(
+        grch38.compare_loci(("chr2", 1), ("chr10", 1)),
+        Some(Ordering::Less)
+    );
+    assert_eq!(grch38.compare_loci(("2", 1), ("chr10", 1)), None);
+    assert_eq!(grch38.contig_index("chrEBV"), Some(194));
+
+    // The unplaced contigs come after the chromosomes.
+    assert_eq!(
+        grch37.compare_loci(("MT", 1), ("GL000192.1", 1)),
+        Some(Ordering::Less)
+    );
+    assert_eq!(grch37.check_locus("hs37d5", 35477943), Ok(()));
+
+    let grcm38 = ReferenceGenome::builtin("GRCm38").unwrap();
+    assert_eq!(grcm38.contigs().len(), 66);
+    assert!(grcm38.is_y("Y") && grcm38.in_par("Y", 90745845));
+    assert_eq!(grcm38.contig("JH584304.1").map(|c| c.length), Some(114452));
+
+    let canfam3 = ReferenceGenome::builtin("CanFam3").unwrap();
+    assert_eq!(canfam3.contig_index("chrX"), Some(38));
+    assert!(canfam3.is_mitochondrial("chrM") && canfam3.par().is_empty());
+    assert!(ReferenceGenome::builtin("GRCh36").is_none());
+    Ok(())
+}
+
+#[test]
+fn reference_custom() -> Result<()> {
+    let metadata = load_table_metadata(resource("custom_references.t"))?;
+    let references: Vec<_> = metadata.references.keys().collect();
+    assert_eq!(references, vec!["test_rg_0", "test_rg_1"]);
+
+    let genome = &metadata.references["test_rg_0"];
+    assert!(!genome.is_builtin());
+    assert_eq!(
+        genome.contigs()[..2],
+        [
+            Contig {
+                name: "1".to_owned(),
+                length: 5
+            },
+            Contig {
+                name: "X".to_owned(),
+                length: 4
+            }
+        ]
+    );
+    assert!(genome.in_par("X", 3) && !genome.in_par("X", 4) && !genome.in_par("Y", 3));
+    assert_eq!(genome.check_locus("MT", 2), Ok(()));
+    assert!(matches!(
+        genome.check_locus("MT", 3),
+        Err(LocusError::PositionOutOfRange { length: 2, .. })
+    ));
+    assert!(matches!(
+        genome.check_locus("1", 0),
+        Err(LocusError::PositionOutOfRange { .. })
+    ));
+    assert!(matches!(
+        genome.check_locus("2", 1),
+        Err(LocusError::UnknownContig { .. })
+    ));
+
+    let file = resource("custom_references.t/references/test_rg_1.json.gz");
+    assert_eq!(
+        load_reference_genome(file)?,
+        metadata.references["test_rg_1"]
+    );
+
+    let matrix = load_matrix_metadata(resource("custom_references.mt"))?;
+    let references: Vec<_> = matrix.references.keys().collect();
+    assert_eq!(references, vec!["test_rg_0", "test_rg_2"]);
+    Ok(())
+}
+
+#[test]
+fn reference_invalid_json() {
+    let parse = |json: &str| serde_json::from_str::<ReferenceGenome>(json);
+
+    let valid = r#"{"name":"g","contigs":[{"name":"1","length":5}]}"#;
+    assert!(parse(valid).is_ok());
+    let repeated = r#"{"name":"g","contigs":[{"name":"1","length":5},{"name":"1","length":2}]}"#;
+    assert!(parse(repeated).is_err());
+    let unknown_x = r#"{"name":"g","contigs":[{"name":"1","length":5}],"xContigs":["X"]}"#;
+    assert!(parse(unknown_x).is_err());
+    let autosomal_par = r#"{"name":"g","contigs":[{"name":"1","length":5}],
+        "par":[{"start":{"contig":"1","position":1},"end":{"contig":"1","position":2}}]}"#;
+    assert!(parse(autosomal_par).is_err());
+}
+
+#[test]
+fn reference_written_with_tables() -> Result<()> {
+    let mut table = parser::load::table(resource("custom_references.t"))?;
+    let path = output("custom_references_written.t");
+    parser::write::table(&table, &path)?;
+
+    assert!(path.join("references/test_rg_0.json.gz").is_file());
+    let written = load_table_metadata(&path)?;
+    assert_eq!(written.references, table.metadata.references);
+
+    // Without the genomes, the table is still loaded, with its loci ordered by contig name.
+    table.metadata.references.clear();
+    let path = output("custom_references_missing.t");
+    parser::write::table(&table, &path)?;
+    let written = parser::load::table(&path)?;
+    assert!(written.metadata.references.is_empty());
+    assert_eq!(written.rows.data, table.rows.data);
+
+    let matrix = parser::load::matrix(resource("sample.vcf.mt"))?;
+    let path = output("sample_vcf_references.mt");
+    parser::write::matrix(&matrix, &path)?;
+    // The built-in genomes are not written.
+    assert!(!path.join("references").exists());
+    Ok(())
+}
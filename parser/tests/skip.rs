@@ -0,0 +1,76 @@
+use anyhow::{anyhow, Result};
+
+use parser::{
+    parse::{Encoding, StandardEncoder, UnsignedLEB128Encoder},
+    types::EType,
+};
+
+use crate::common::resource;
+
+/// Skipping each row should stop exactly where decoding it does.
+fn compare_component(file: &str) -> Result<()> {
+    let path = resource(file);
+    let metadata = parser::load::metadata::load_component_metadata(&path)?;
+
+    for part_file in &metadata.part_files {
+        let raw = std::fs::read(path.join("parts").join(part_file))?;
+        let buffer_spec = &metadata.buffer_spec;
+        let data = match (buffer_spec.uses_compression(), buffer_spec.appends_length()) {
+            (true, _) => parser::load::compression::decompress_part_file(&raw)?,
+            (false, true) => parser::load::compression::concatenate_blocks(&raw)?,
+            (false, false) => raw,
+        };
+
+        match metadata.buffer_spec.uses_leb128() {
+            true => compare_rows::<UnsignedLEB128Encoder>(&data, &metadata.encoded_type),
+            false => compare_rows::<StandardEncoder>(&data, &metadata.encoded_type),
+        }?;
+    }
+    Ok(())
+}
+
+fn compare_rows<E: Encoding>(data: &[u8], row_type: &EType) -> Result<()> {
+    let mut rest = data;
+    let mut rows = 0;
+    loop {
+        let (after_marker, is_row) = E::bool(rest).map_err(|e| anyhow!("{}", e))?;
+        if !is_row {
+            break;
+        }
+
+        let (decoded, _) = row_type
+            .decode_from::<E>(after_marker)
+            .map_err(|e| anyhow!("{}", e))?;
+        let (skipped, _) = row_type
+            .skip_from::<E>(after_marker)
+            .map_err(|e| anyhow!("{}", e))?;
+        assert_eq!(decoded.len(), skipped.len(), "row {}", rows);
+
+        rest = skipped;
+        rows += 1;
+    }
+    assert!(rows > 0);
+    Ok(())
+}
+
+#[test]
+fn skip_component_sample_rows() -> Result<()> {
+    compare_component("sample.vcf.mt/rows/rows")
+}
+
+#[test]
+fn skip_component_sample_entries() -> Result<()> {
+    compare_component("sample.vcf.mt/entries/rows")
+}
+
+#[test]
+fn skip_component_compat_150() -> Result<()> {
+    // Tables 0 and 2 do not use LEB128, the others do.
+    for index in 0..8 {
+        compare_component(&format!(
+            "backward_compatability/1.5.0/table/{}.ht/rows",
+            index
+        ))?;
+    }
+    Ok(())
+}
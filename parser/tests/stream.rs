@@ -0,0 +1,202 @@
+use anyhow::{Context, Result};
+
+use parser::HailValue;
+
+use crate::common::{output, resource};
+
+/// The streamed rows should be exactly the rows loaded all at once, flattened.
+fn compare_component(file: &str) -> Result<()> {
+    let path = resource(file);
+    let loaded = parser::load::component(&path)
+        .context(format!("Failed to load component in path: {:?}", path))?;
+    let mut streamed = parser::load::component_iter(&path)
+        .context(format!("Failed to open component in path: {:?}", path))?;
+
+    // Compared value by value, as the rows of the larger components would not fit in memory twice.
+    for row in loaded.data.iter().flatten() {
+        assert_eq!(streamed.next().transpose()?.as_ref(), Some(row));
+    }
+    assert!(streamed.next().is_none());
+    Ok(())
+}
+
+#[test]
+fn stream_component_sample_rows() -> Result<()> {
+    compare_component("sample.vcf.mt/rows/rows")
+}
+
+#[test]
+fn stream_component_sample_entries() -> Result<()> {
+    compare_component("sample.vcf.mt/entries/rows")
+}
+
+#[test]
+fn stream_component_hg00096_entries() -> Result<()> {
+    compare_component("HG00096.g.vcf.gz.mt/entries/rows")
+}
+
+#[test]
+fn stream_component_compat_120() -> Result<()> {
+    compare_component("backward_compatability/1.2.0/table/0.ht/rows")?;
+    compare_component("backward_compatability/1.2.0/matrix_table/0.hmt/entries/rows")
+}
+
+#[test]
+fn stream_table_three_key() -> Result<()> {
+    let table = parser::load::table_iter(resource("three_key.ht"))?;
+    let rows = table.rows.collect::<parser::Result<Vec<HailValue>>>()?;
+    assert_eq!(
+        table
+            .metadata
+            .components
+            .partition_counts
+            .counts
+            .iter()
+            .sum::<u32>() as usize,
+        rows.len()
+    );
+    Ok(())
+}
+
+#[test]
+fn stream_matrix_sample_vcf() -> Result<()> {
+    let matrix = parser::load::matrix_iter(resource("sample.vcf.mt"))?;
+    let counts = &matrix.metadata.components.partition_counts.counts;
+    assert_eq!(counts.iter().sum::<u32>() as usize, matrix.rows.count());
+    assert_eq!(counts.iter().sum::<u32>() as usize, matrix.entries.count());
+    Ok(())
+}
+
+#[test]
+fn blocks_read_into_reused_buffer() -> Result<()> {
+    let path = resource("HG00096.g.vcf.gz.mt/entries/rows");
+    let metadata = parser::load::metadata::load_component_metadata(&path)?;
+    assert!(metadata.buffer_spec.uses_compression());
+
+    for part_file in &metadata.part_files {
+        let raw = std::fs::read(path.join("parts").join(part_file))?;
+        let expected = parser::load::compression::decompress_part_file(&raw)?;
+
+        let mut blocks = parser::load::compression::BlockReader::new(&raw[..], true, true);
+        let mut buffer = Vec::new();
+        let mut decompressed = Vec::new();
+        while blocks.read_block_into(&mut buffer)? {
+            decompressed.extend_from_slice(&buffer);
+            buffer.clear();
+        }
+        assert_eq!(expected, decompressed);
+    }
+    Ok(())
+}
+
+#[test]
+fn stream_fails_at_invalid_data() -> Result<()> {
+    use parser::types::metadata::shared::BufferSpec;
+
+    // The rows are written in small uncompressed blocks.
+    let mut component = parser::load::component(resource("small-pheno.t/rows"))?;
+    component.metadata.buffer_spec = BufferSpec::BlockingBufferSpec {
+        block_size: 64,
+        child: Box::new(BufferSpec::StreamBlockBufferSpec),
+    };
+    let path = output("small-pheno.invalid");
+    parser::write::component(&component, &path)?;
+
+    // The first string claims to be longer than the file, and the last block is cut short.
+    let metadata = parser::load::metadata::load_component_metadata(&path)?;
+    let part_file = path.join("parts").join(&metadata.part_files[0]);
+    let mut raw = std::fs::read(&part_file)?;
+    // The block length, the row marker, and the presence flags come first.
+    raw[6..10].copy_from_slice(&u32::MAX.to_le_bytes());
+    raw.pop();
+    std::fs::write(&part_file, raw)?;
+
+    // Decoding fails at the string rather than reading on until the malformed block.
+    let error = parser::load::component_iter(&path)?
+        .find_map(|row| row.err())
+        .context("the rows are invalid")?;
+    match error {
+        parser::Error::Decode(error) => {
+            assert_eq!((error.row, error.field.as_str()), (0, "s"));
+            assert_eq!(error.kind, parser::error::DecodeErrorKind::UnexpectedEnd);
+        }
+        error => panic!("expected a decoding error, found {:?}", error),
+    }
+    Ok(())
+}
+
+#[test]
+fn stream_row_spanning_many_blocks() -> Result<()> {
+    use parser::types::{metadata::shared::BufferSpec, EType, ETypeShape, VType, VTypeShape};
+
+    // A single row holding an array much larger than the (compressed) blocks.
+    let mut component = parser::load::component(resource("small-pheno.t/rows"))?;
+    component.metadata.buffer_spec = BufferSpec::LEB128BufferSpec {
+        child: Box::new(BufferSpec::BlockingBufferSpec {
+            block_size: 1024,
+            child: Box::new(BufferSpec::LZ4FastBlockBufferSpec {
+                block_size: 1024,
+                child: Box::new(BufferSpec::StreamBlockBufferSpec),
+            }),
+        }),
+    };
+    match (
+        &mut component.metadata.virtual_type.shape,
+        &mut component.metadata.encoded_type.shape,
+    ) {
+        (VTypeShape::Struct(virtual_fields), ETypeShape::BaseStruct(encoded_fields)) => {
+            virtual_fields.push((
+                "values".to_owned(),
+                VType {
+                    shape: VTypeShape::Array(Box::new(VType {
+                        shape: VTypeShape::Int32,
+                        required: true,
+                    })),
+                    required: true,
+                },
+            ));
+            encoded_fields.push((
+                "values".to_owned(),
+                EType {
+                    shape: ETypeShape::Array(Box::new(EType {
+                        shape: ETypeShape::Int32,
+                        required: true,
+                        virtual_hint: None,
+                    })),
+                    required: true,
+                    virtual_hint: None,
+                },
+            ));
+        }
+        _ => panic!("rows are structs"),
+    }
+    let mut row = component.data[0][0].clone();
+    match &mut row {
+        HailValue::Struct(fields) => {
+            let values = (0..400_000).map(HailValue::Int32).collect();
+            fields.insert("values".to_owned(), HailValue::Array(values));
+        }
+        _ => panic!("rows are structs"),
+    }
+    component.data = vec![vec![row]];
+
+    let path = output("small-pheno.wide");
+    parser::write::component(&component, &path)?;
+
+    let streamed = parser::load::component_iter(&path)?.collect::<parser::Result<Vec<_>>>()?;
+    assert_eq!(streamed, component.data[0]);
+    let loaded = parser::load::component(&path)?;
+    assert_eq!(loaded.data, component.data);
+
+    let mut lengths = vec![];
+    parser::load::component_for_each(&path, |row| {
+        if let parser::HailValueRef::Struct(fields) = row {
+            if let Some(parser::HailValueRef::Array(values)) = fields.get("values") {
+                lengths.push(values.len());
+            }
+        }
+        Ok(())
+    })?;
+    assert_eq!(lengths, vec![400_000]);
+    Ok(())
+}
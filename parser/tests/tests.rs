@@ -1,3 +1,24 @@
+mod common;
+
+mod arrow;
+mod call;
 mod component;
+mod errors;
+mod hail_value_ref;
+mod hash;
+mod index;
+mod int32;
+mod leb128;
+mod malformed;
 mod matrix_tables;
+mod ordering;
+mod parallel;
+mod parquet;
+mod partitions;
+mod projection;
+mod reference;
+mod skip;
+mod stream;
 mod table;
+mod validate;
+mod write;
@@ -0,0 +1,261 @@
+use std::{collections::BTreeMap, fs::File, path::Path};
+
+use anyhow::Result;
+use serde_json::json;
+
+use parser::{
+    types::{encoding::VirtualHint, reference::LocusError, EType, ETypeShape, VType},
+    validate::{check_types, Violation, ViolationKind},
+    HailValue,
+};
+
+use crate::common::{output, resource};
+
+fn fields(values: Vec<(&str, HailValue)>) -> HailValue {
+    HailValue::Struct(
+        values
+            .into_iter()
+            .map(|(name, value)| (name.to_owned(), value))
+            .collect(),
+    )
+}
+
+fn binary(text: &str) -> HailValue {
+    HailValue::Binary(text.as_bytes().to_vec())
+}
+
+/// Keys a written component by the given field, with a single range bound for all partitions:
+/// the writer refuses unsorted keys, so the key is added after writing unkeyed rows.
+fn set_key(path: &Path, key: &str) -> Result<()> {
+    let file = path.join("metadata.json.gz");
+    let mut metadata: serde_json::Value =
+        serde_json::from_reader(flate2::read::GzDecoder::new(File::open(&file)?))?;
+    metadata["_key"] = json!([key]);
+    for bound in metadata["_jRangeBounds"].as_array_mut().unwrap() {
+        *bound = json!({
+            "start": {key: null},
+            "end": {key: null},
+            "includeStart": true,
+            "includeEnd": true,
+        });
+    }
+    let mut encoder =
+        flate2::write::GzEncoder::new(File::create(&file)?, flate2::Compression::default());
+    serde_json::to_writer(&mut encoder, &metadata)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// The fields and kinds of the violations, by partition and row.
+fn summary(violations: &[Violation]) -> Vec<(usize, u64, &str, &ViolationKind)> {
+    violations
+        .iter()
+        .map(|violation| {
+            (
+                violation.partition.unwrap(),
+                violation.row.unwrap(),
+                violation.field.as_str(),
+                &violation.kind,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn validate_resources() -> Result<()> {
+    for file in &[
+        "sample.vcf.mt",
+        "sample-indexed-0.2.52.mt",
+        "HG00096.g.vcf.gz.mt",
+        "custom_references.mt",
+        "required_globals.mt",
+    ] {
+        for (component, violations) in parser::validate::matrix(resource(file))? {
+            assert!(
+                violations.is_empty(),
+                "{} {}: {:?}",
+                file,
+                component,
+                violations
+            );
+        }
+    }
+    for file in &["three_key.ht", "small-pheno.t", "custom_references.t"] {
+        for (component, violations) in parser::validate::table(resource(file))? {
+            assert!(
+                violations.is_empty(),
+                "{} {}: {:?}",
+                file,
+                component,
+                violations
+            );
+        }
+    }
+    for index in 0..8 {
+        let file = format!("backward_compatability/1.5.0/table/{}.ht", index);
+        for (component, violations) in parser::validate::table(resource(&file))? {
+            assert!(
+                violations.is_empty(),
+                "{} {}: {:?}",
+                file,
+                component,
+                violations
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn validate_types() -> Result<()> {
+    let v_type: VType = "Struct{a:Int32,b:Array[String],c:Dict[String,Int64],d:Locus(GRCh37),\
+        e:Interval[Int32],f:Tuple[Call,Float64],g:NDArray[Float64,2],h:Set[Binary]}"
+        .parse()?;
+    let e_type: EType = "+EBaseStruct{a:+EInt32,b:EArray[EBinary],\
+        c:EArray[+EBaseStruct{key:+EBinary,value:EInt64}],\
+        d:EBaseStruct{contig:+EBinary,position:+EInt32},\
+        e:EBaseStruct{start:+EInt32,end:+EInt32,includesStart:+EBoolean,includesEnd:+EBoolean},\
+        f:EBaseStruct{`0`:EInt32,`1`:EFloat64},g:ENDArrayColumnMajor[EFloat64,2],\
+        h:EArray[EBinary]}"
+        .parse()?;
+    assert_eq!(check_types(&v_type, &e_type), vec![]);
+
+    let v_type: VType = "Struct{a:+Int32,b:Array[Int32],c:Struct{x:Int32},d:Locus(GRCh37),\
+        e:Dict[String,Int32]}"
+        .parse()?;
+    let e_type: EType = "+EBaseStruct{a:EInt32,b:EArray[EInt64],c:EBaseStruct{y:EInt32},\
+        d:EBaseStruct{contig:EBinary,position:+EInt32},\
+        e:EArray[+EBaseStruct{key:EBinary,value:EFloat32}]}"
+        .parse()?;
+    let violations = check_types(&v_type, &e_type);
+    let fields: Vec<_> = violations.iter().map(|v| v.field.as_str()).collect();
+    assert_eq!(fields, vec!["a", "b[]", "c", "d", "e[].value"]);
+    assert_eq!(
+        violations[1].to_string(),
+        "at `b[]`: the encoded type EInt64 cannot be read as Int32"
+    );
+    Ok(())
+}
+
+/// Incompatible types are refused when the metadata is loaded.
+#[test]
+fn validate_types_when_loading() -> Result<()> {
+    let mut component = parser::load::component(resource("small-pheno.t/rows"))?;
+    component.metadata.virtual_type = "Struct{s:String,phenotype:Int64}".parse()?;
+    let path = output("small-pheno.incompatible");
+    parser::write::component(&component, &path)?;
+
+    let error = parser::load::component(&path).unwrap_err();
+    assert!(matches!(error, parser::Error::Metadata { .. }), "{}", error);
+    assert!(error.to_string().contains("`phenotype`"), "{}", error);
+    Ok(())
+}
+
+/// Sets and dicts written as arrays, so that they can hold duplicates, with unsorted keys and a
+/// locus outside of its contig.
+#[test]
+fn validate_rows() -> Result<()> {
+    let mut component = parser::load::component(resource("small-pheno.t/rows"))?;
+    component.metadata.virtual_type =
+        "Struct{s:String,set:Set[Int32],dict:Dict[String,Int32],locus:Locus(GRCh37)}".parse()?;
+    component.metadata.encoded_type = "+EBaseStruct{s:EBinary,set:EArray[EInt32],\
+        dict:EArray[+EBaseStruct{key:+EBinary,value:EInt32}],\
+        locus:EBaseStruct{contig:+EBinary,position:+EInt32}}"
+        .parse()?;
+    // The key is written as a string.
+    match &mut component.metadata.encoded_type.shape {
+        ETypeShape::BaseStruct(fields) => fields[0].1.virtual_hint = Some(VirtualHint::String),
+        _ => panic!("rows are structs"),
+    }
+    component.metadata.key.clear();
+
+    let row = |s: &str, set: Vec<i32>, dict: Vec<(&str, i32)>, contig: &str, position| {
+        fields(vec![
+            ("s", HailValue::String(s.to_owned())),
+            (
+                "set",
+                HailValue::Array(set.into_iter().map(HailValue::Int32).collect()),
+            ),
+            (
+                "dict",
+                HailValue::Array(
+                    dict.into_iter()
+                        .map(|(key, value)| {
+                            fields(vec![
+                                ("key", binary(key)),
+                                ("value", HailValue::Int32(value)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "locus",
+                fields(vec![
+                    ("contig", binary(contig)),
+                    ("position", HailValue::Int32(position)),
+                ]),
+            ),
+        ])
+    };
+    component.data = vec![
+        vec![
+            row("b", vec![1, 2, 1], vec![("x", 1), ("x", 2)], "1", 10),
+            row("a", vec![], vec![], "1", 300_000_000),
+        ],
+        // The built-in genomes do not list the unplaced contigs.
+        vec![row("A", vec![3], vec![("y", 1)], "GL000192.1", 5)],
+    ];
+
+    let path = output("small-pheno.violations");
+    parser::write::component(&component, &path)?;
+    set_key(&path, "s")?;
+    let violations = parser::validate::component(&path)?;
+
+    let out_of_range = ViolationKind::Locus(LocusError::PositionOutOfRange {
+        reference: "GRCh37".to_owned(),
+        contig: "1".to_owned(),
+        position: 300_000_000,
+        length: 249250621,
+    });
+    assert_eq!(
+        summary(&violations),
+        vec![
+            (0, 0, "set[2]", &ViolationKind::DuplicateElement),
+            (0, 0, "dict[1].key", &ViolationKind::DuplicateKey),
+            (0, 1, "locus", &out_of_range),
+            (0, 1, "", &ViolationKind::UnsortedKey),
+            (1, 0, "", &ViolationKind::UnsortedKey),
+        ]
+    );
+    assert_eq!(
+        violations[0].to_string(),
+        "row 0 of partition 0 at `set[2]`: the element is already in the set"
+    );
+
+    // The duplicates are merged when loading.
+    let loaded = parser::load::component(&path)?;
+    match &loaded.data[0][0] {
+        HailValue::Struct(fields) => match (&fields["set"], &fields["dict"]) {
+            (HailValue::Set(set), HailValue::Dict(dict)) => {
+                assert_eq!((set.len(), dict.len()), (2, 1))
+            }
+            values => panic!("expected a set and a dict, found {:?}", values),
+        },
+        value => panic!("expected a struct, found {:?}", value),
+    }
+
+    // With a genome of the same name that lists the contig, it is out of range.
+    let genome: parser::types::ReferenceGenome = serde_json::from_str(
+        r#"{"name":"GRCh37","contigs":[{"name":"1","length":249250621},
+            {"name":"GL000192.1","length":4}]}"#,
+    )?;
+    let references: BTreeMap<_, _> = vec![("GRCh37".to_owned(), genome)].into_iter().collect();
+    let violations = parser::validate::component_with(&path, &references)?;
+    assert_eq!(violations.len(), 6);
+    assert!(matches!(
+        violations[5].kind,
+        ViolationKind::Locus(LocusError::PositionOutOfRange { length: 4, .. })
+    ));
+    Ok(())
+}
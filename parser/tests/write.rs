@@ -0,0 +1,508 @@
+use anyhow::Result;
+
+use crate::common::{output, resource};
+
+fn round_trip_table(file: &str, name: &str) -> Result<()> {
+    let table = parser::load::table(resource(file))?;
+    let path = output(name);
+    parser::write::table(&table, &path)?;
+    let written = parser::load::table(&path)?;
+
+    assert_eq!(table.globals.data, written.globals.data);
+    assert_eq!(table.rows.data, written.rows.data);
+    assert_eq!(
+        table.rows.metadata.key,
+        written
+            .metadata
+            .table_type
+            .row_keys
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>()
+    );
+
+    let counts: Vec<u32> = table
+        .rows
+        .data
+        .iter()
+        .map(|rows| rows.len() as u32)
+        .collect();
+    assert_eq!(counts, written.metadata.components.partition_counts.counts);
+    Ok(())
+}
+
+fn round_trip_component(file: &str, name: &str) -> Result<()> {
+    let component = parser::load::component(resource(file))?;
+    let path = output(name);
+    parser::write::component(&component, &path)?;
+    let written = parser::load::component(&path)?;
+
+    assert_eq!(component.data, written.data);
+    assert_eq!(component.metadata.key, written.metadata.key);
+    Ok(())
+}
+
+#[test]
+fn write_table_three_key() -> Result<()> {
+    round_trip_table("three_key.ht", "three_key.ht")
+}
+
+#[test]
+fn write_table_required_globals() -> Result<()> {
+    round_trip_table("required_globals.ht", "required_globals.ht")
+}
+
+#[test]
+fn write_table_small_pheno() -> Result<()> {
+    round_trip_table("small-pheno.t", "small-pheno.t")
+}
+
+#[test]
+fn write_table_custom_references() -> Result<()> {
+    round_trip_table("custom_references.t", "custom_references.t")
+}
+
+#[test]
+fn write_table_sample_vcf_rows() -> Result<()> {
+    round_trip_table("sample.vcf.mt/rows", "sample.vcf.rows.ht")
+}
+
+#[test]
+fn write_component_sample_vcf_entries() -> Result<()> {
+    round_trip_component("sample.vcf.mt/entries/rows", "sample.vcf.entries")
+}
+
+#[test]
+fn write_component_hg00096_entries() -> Result<()> {
+    round_trip_component("HG00096.g.vcf.gz.mt/entries/rows", "HG00096.entries")
+}
+
+#[test]
+fn write_keeps_the_range_bounds() -> Result<()> {
+    let table = parser::load::table(resource("three_key.ht"))?;
+    let path = output("three_key_bounds.ht");
+    parser::write::table(&table, &path)?;
+    let written = parser::load::table(&path)?;
+
+    // The written bounds are tight, so they fall within the original ones.
+    let original = &table.rows.metadata.range_bounds;
+    let bounds = &written.rows.metadata.range_bounds;
+    assert_eq!(original.len(), bounds.len());
+    for (original, bounds) in original.iter().zip(bounds) {
+        match (original, bounds) {
+            (
+                parser::HailValue::Interval {
+                    start: original_start,
+                    end: original_end,
+                    ..
+                },
+                parser::HailValue::Interval { start, end, .. },
+            ) => {
+                assert!(original_start <= start);
+                assert!(end <= original_end);
+            }
+            _ => panic!("range bounds are intervals"),
+        }
+    }
+    Ok(())
+}
+
+/// Empty partitions are written, with the range bounds they were loaded with.
+#[test]
+fn write_keeps_empty_partitions() -> Result<()> {
+    let mut table = parser::load::table(resource("three_key.ht"))?;
+    let partitions = table.rows.data.len();
+    table.rows.data[1].clear();
+
+    let path = output("three_key_empty.ht");
+    parser::write::table(&table, &path)?;
+    let written = parser::load::table(&path)?;
+
+    assert_eq!(written.rows.data.len(), partitions);
+    assert!(written.rows.data[1].is_empty());
+    assert_eq!(table.rows.data, written.rows.data);
+    assert_eq!(
+        table.rows.metadata.range_bounds[1],
+        written.rows.metadata.range_bounds[1]
+    );
+    assert_eq!(written.metadata.components.partition_counts.counts[1], 0);
+
+    // Without range bounds for each partition, those of the empty one are unknown.
+    table.rows.data.push(vec![]);
+    let error = parser::write::table(&table, output("three_key_unknown.ht")).unwrap_err();
+    assert!(error.to_string().contains("range bounds"), "{}", error);
+    Ok(())
+}
+
+/// Rows that are not sorted by key are refused, within or across partitions.
+#[test]
+fn write_checks_the_order_of_keys() -> Result<()> {
+    let mut component = parser::load::component(resource("three_key.ht/rows"))?;
+    component.data[0].swap(0, 1);
+    let error = parser::write::component(&component, output("three_key_unsorted")).unwrap_err();
+    assert!(error.to_string().contains("not sorted"), "{}", error);
+
+    let mut component = parser::load::component(resource("three_key.ht/rows"))?;
+    component.data.swap(0, 1);
+    let error = parser::write::component(&component, output("three_key_swapped")).unwrap_err();
+    assert!(error.to_string().contains("not sorted"), "{}", error);
+
+    // Unkeyed components are written in any order.
+    component.metadata.key.clear();
+    parser::write::component(&component, output("three_key_unkeyed"))?;
+    Ok(())
+}
+
+/// Field names that are not identifiers are escaped in the schemas, and read back as they were.
+#[test]
+fn write_escaped_field_names() -> Result<()> {
+    use parser::types::{ETypeShape, VType, VTypeShape};
+    use parser::HailValue;
+
+    let odd = "a:b,`c`\\d{e}";
+    let v_type: VType = "Struct{`my field`:Int32,`0`:Tuple[Int32],`a\\`b`:String}".parse()?;
+    assert_eq!(
+        v_type.to_string(),
+        "Struct{`my field`:Int32,`0`:Tuple[Int32],`a\\`b`:String}"
+    );
+    let names = |v_type: &VType| match &v_type.shape {
+        VTypeShape::Struct(fields) => fields.iter().map(|(name, _)| name.clone()).collect(),
+        _ => vec![],
+    };
+    assert_eq!(names(&v_type), vec!["my field", "0", "a`b"]);
+    assert_eq!(v_type.to_string().parse::<VType>()?, v_type);
+
+    // The key `s` is renamed to `sample id`, and the `phenotype` field to an odd name.
+    let mut table = parser::load::table(resource("small-pheno.t"))?;
+    let rename = |name: &mut String| match name.as_str() {
+        "s" => *name = "sample id".to_owned(),
+        "phenotype" => *name = odd.to_owned(),
+        _ => (),
+    };
+    let metadata = &mut table.rows.metadata;
+    match (
+        &mut metadata.virtual_type.shape,
+        &mut metadata.encoded_type.shape,
+        &mut table.metadata.table_type.row_schema.shape,
+    ) {
+        (VTypeShape::Struct(fields), ETypeShape::BaseStruct(encoded), VTypeShape::Struct(row)) => {
+            fields.iter_mut().for_each(|(name, _)| rename(name));
+            encoded.iter_mut().for_each(|(name, _)| rename(name));
+            row.iter_mut().for_each(|(name, _)| rename(name));
+        }
+        _ => panic!("rows are structs"),
+    }
+    metadata.key.iter_mut().for_each(rename);
+    table
+        .metadata
+        .table_type
+        .row_keys
+        .iter_mut()
+        .for_each(|(name, _)| rename(name));
+    for row in table.rows.data.iter_mut().flatten() {
+        match row {
+            HailValue::Struct(fields) => {
+                *fields = std::mem::take(fields)
+                    .into_iter()
+                    .map(|(mut name, value)| {
+                        rename(&mut name);
+                        (name, value)
+                    })
+                    .collect();
+            }
+            _ => panic!("rows are structs"),
+        }
+    }
+
+    let path = output("small-pheno.escaped.t");
+    parser::write::table(&table, &path)?;
+    let written = parser::load::table(&path)?;
+
+    assert_eq!(written.rows.metadata.key, vec!["sample id"]);
+    assert_eq!(
+        written.metadata.table_type.row_schema,
+        table.metadata.table_type.row_schema
+    );
+    assert_eq!(table.rows.data, written.rows.data);
+    Ok(())
+}
+
+#[test]
+fn write_component_with_binaries() -> Result<()> {
+    use parser::types::{EType, ETypeShape, VType, VTypeShape};
+    use parser::HailValue;
+
+    // Adds an optional binary field, which is not valid UTF-8, to every row.
+    let mut component = parser::load::component(resource("small-pheno.t/rows"))?;
+    match (
+        &mut component.metadata.virtual_type.shape,
+        &mut component.metadata.encoded_type.shape,
+    ) {
+        (VTypeShape::Struct(virtual_fields), ETypeShape::BaseStruct(encoded_fields)) => {
+            virtual_fields.push((
+                "b".to_owned(),
+                VType {
+                    shape: VTypeShape::Binary,
+                    required: false,
+                },
+            ));
+            encoded_fields.push((
+                "b".to_owned(),
+                EType {
+                    shape: ETypeShape::Binary,
+                    required: false,
+                    virtual_hint: None,
+                },
+            ));
+        }
+        _ => panic!("rows are structs"),
+    }
+    for (index, row) in component.data.iter_mut().flatten().enumerate() {
+        match row {
+            HailValue::Struct(fields) => {
+                let binary = match index % 3 {
+                    0 => HailValue::Missing,
+                    _ => HailValue::Binary(vec![0xff, index as u8, 0xc3]),
+                };
+                fields.insert("b".to_owned(), binary);
+            }
+            _ => panic!("rows are structs"),
+        }
+    }
+
+    let path = output("small-pheno.binaries");
+    parser::write::component(&component, &path)?;
+    let written = parser::load::component(&path)?;
+
+    assert_eq!(
+        component.metadata.virtual_type,
+        written.metadata.virtual_type
+    );
+    assert_eq!(component.data, written.data);
+    Ok(())
+}
+
+#[test]
+fn write_component_with_sets() -> Result<()> {
+    use parser::types::{encoding::VirtualHint, EType, ETypeShape, VType, VTypeShape};
+    use parser::HailValue;
+
+    // Adds an optional set field to every row, with a missing element in some of them.
+    let mut component = parser::load::component(resource("small-pheno.t/rows"))?;
+    match (
+        &mut component.metadata.virtual_type.shape,
+        &mut component.metadata.encoded_type.shape,
+    ) {
+        (VTypeShape::Struct(virtual_fields), ETypeShape::BaseStruct(encoded_fields)) => {
+            virtual_fields.push((
+                "set".to_owned(),
+                VType {
+                    shape: VTypeShape::Set(Box::new(VType {
+                        shape: VTypeShape::Int32,
+                        required: false,
+                    })),
+                    required: false,
+                },
+            ));
+            encoded_fields.push((
+                "set".to_owned(),
+                EType {
+                    shape: ETypeShape::Array(Box::new(EType {
+                        shape: ETypeShape::Int32,
+                        required: false,
+                        virtual_hint: None,
+                    })),
+                    required: false,
+                    virtual_hint: Some(VirtualHint::Set),
+                },
+            ));
+        }
+        _ => panic!("rows are structs"),
+    }
+    for (index, row) in component.data.iter_mut().flatten().enumerate() {
+        match row {
+            HailValue::Struct(fields) => {
+                let mut set = vec![HailValue::Int32(index as i32), HailValue::Int32(-1)];
+                if index % 2 == 0 {
+                    set.push(HailValue::Missing);
+                }
+                fields.insert("set".to_owned(), HailValue::Set(set.into_iter().collect()));
+            }
+            _ => panic!("rows are structs"),
+        }
+    }
+
+    let path = output("small-pheno.sets");
+    parser::write::component(&component, &path)?;
+    let written = parser::load::component(&path)?;
+
+    assert_eq!(component.data, written.data);
+    Ok(())
+}
+
+#[test]
+fn write_sets_and_dicts_in_hail_order() -> Result<()> {
+    use parser::load::compression::decompress_part_file;
+    use parser::parse::{data::parse_rows_ref, UnsignedLEB128Encoder};
+    use parser::types::{encoding::VirtualHint, EType, ETypeShape, VTypeShape};
+    use parser::{HailValue, HailValueRef};
+
+    // Adds a set of loci and a dict keyed by loci to every row.
+    let mut component = parser::load::component(resource("small-pheno.t/rows"))?;
+    let locus = || VirtualHint::Locus("GRCh38".to_owned());
+    let mut loci: EType = "EArray[EBaseStruct{contig:+EBinary,position:+EInt32}]".parse()?;
+    loci.virtual_hint = Some(VirtualHint::Set);
+    if let ETypeShape::Array(element) = &mut loci.shape {
+        element.virtual_hint = Some(locus());
+    }
+    let mut counts: EType =
+        "EArray[+EBaseStruct{key:+EBaseStruct{contig:+EBinary,position:+EInt32},value:+EInt32}]"
+            .parse()?;
+    counts.virtual_hint = Some(VirtualHint::Dict);
+    if let ETypeShape::Array(entry) = &mut counts.shape {
+        if let ETypeShape::BaseStruct(fields) = &mut entry.shape {
+            fields[0].1.virtual_hint = Some(locus());
+        }
+    }
+    match (
+        &mut component.metadata.virtual_type.shape,
+        &mut component.metadata.encoded_type.shape,
+    ) {
+        (VTypeShape::Struct(virtual_fields), ETypeShape::BaseStruct(encoded_fields)) => {
+            virtual_fields.push(("loci".to_owned(), "Set[Locus(GRCh38)]".parse()?));
+            virtual_fields.push(("counts".to_owned(), "Dict[Locus(GRCh38),Int32]".parse()?));
+            encoded_fields.push(("loci".to_owned(), loci));
+            encoded_fields.push(("counts".to_owned(), counts));
+        }
+        _ => panic!("rows are structs"),
+    }
+    let locus = |contig: &str| HailValue::Locus {
+        contig: contig.to_owned(),
+        position: 1,
+        reference: "GRCh38".to_owned(),
+    };
+    for row in component.data.iter_mut().flatten() {
+        match row {
+            HailValue::Struct(fields) => {
+                let loci = vec![locus("chr10"), HailValue::Missing, locus("chr2")];
+                fields.insert(
+                    "loci".to_owned(),
+                    HailValue::Set(loci.into_iter().collect()),
+                );
+                let counts = vec![(locus("chr10"), HailValue::Int32(10))];
+                let counts = counts
+                    .into_iter()
+                    .chain([(locus("chr2"), HailValue::Int32(2))]);
+                fields.insert("counts".to_owned(), HailValue::Dict(counts.collect()));
+            }
+            _ => panic!("rows are structs"),
+        }
+    }
+
+    let path = output("small-pheno.loci_sets");
+    parser::write::component(&component, &path)?;
+    let written = parser::load::component(&path)?;
+    assert_eq!(component.data, written.data);
+
+    // The elements are written with chr2 before chr10, and the missing element last.
+    let metadata = &written.metadata;
+    assert!(metadata.buffer_spec.uses_compression() && metadata.buffer_spec.uses_leb128());
+    let data = decompress_part_file(&std::fs::read(path.join("parts/part-0"))?)?;
+    let rows = parse_rows_ref::<UnsignedLEB128Encoder>(&data, &metadata.encoded_type)?;
+    let contig = |value: &HailValueRef| match value {
+        HailValueRef::Locus { contig, .. } => Some(contig.to_string()),
+        _ => None,
+    };
+    assert!(!rows.is_empty());
+    for row in &rows {
+        let fields = match row {
+            HailValueRef::Struct(fields) => fields,
+            row => panic!("expected a struct, found {:?}", row),
+        };
+        match &fields["loci"] {
+            HailValueRef::Set(loci) => assert_eq!(
+                loci.iter().map(contig).collect::<Vec<_>>(),
+                vec![Some("chr2".to_owned()), Some("chr10".to_owned()), None]
+            ),
+            loci => panic!("expected a set, found {:?}", loci),
+        }
+        match &fields["counts"] {
+            HailValueRef::Dict(counts) => assert_eq!(
+                counts
+                    .iter()
+                    .map(|(key, _)| contig(key))
+                    .collect::<Vec<_>>(),
+                vec![Some("chr2".to_owned()), Some("chr10".to_owned())]
+            ),
+            counts => panic!("expected a dict, found {:?}", counts),
+        }
+    }
+    Ok(())
+}
+
+fn round_trip_matrix(file: &str, name: &str) -> Result<()> {
+    let matrix = parser::load::matrix(resource(file))?;
+    let path = output(name);
+    parser::write::matrix(&matrix, &path)?;
+    let written = parser::load::matrix(&path)?;
+
+    for (original, written) in [
+        (&matrix.globals, &written.globals),
+        (&matrix.cols, &written.cols),
+        (&matrix.rows, &written.rows),
+        (&matrix.entries, &written.entries),
+    ] {
+        assert_eq!(original.data, written.data);
+    }
+    assert_eq!(
+        format!("{:?}", matrix.metadata.matrix_type),
+        format!("{:?}", written.metadata.matrix_type)
+    );
+
+    // Each component is also a table of its own.
+    for table in ["globals", "cols", "rows", "entries"] {
+        parser::load::table(path.join(table))?;
+    }
+    Ok(())
+}
+
+#[test]
+fn write_matrix_sample_vcf() -> Result<()> {
+    round_trip_matrix("sample.vcf.mt", "sample.vcf.mt")
+}
+
+#[test]
+fn write_matrix_sample_indexed() -> Result<()> {
+    round_trip_matrix("sample-indexed-0.2.52.mt", "sample-indexed.mt")
+}
+
+#[test]
+fn write_matrix_custom_references() -> Result<()> {
+    round_trip_matrix("custom_references.mt", "custom_references.mt")
+}
+
+#[test]
+fn write_matrix_required_globals() -> Result<()> {
+    round_trip_matrix("required_globals.mt", "required_globals.mt")
+}
+
+#[test]
+fn write_matrix_keeps_modifications() -> Result<()> {
+    let mut matrix = parser::load::matrix(resource("sample.vcf.mt"))?;
+    match &mut matrix.cols.data[0][0] {
+        parser::HailValue::Struct(fields) => {
+            fields.insert(
+                "s".to_owned(),
+                parser::HailValue::String("renamed".to_owned()),
+            );
+        }
+        _ => panic!("rows are structs"),
+    }
+
+    let path = output("sample.vcf.modified.mt");
+    parser::write::matrix(&matrix, &path)?;
+    let written = parser::load::matrix(&path)?;
+
+    assert_eq!(matrix.cols.data, written.cols.data);
+    Ok(())
+}
@@ -62,6 +62,24 @@ where
     Ok(result)
 }
 
+/// Deserializes the next row (see [parse_rows]) given the structure of `T`, or `None` at the end
+/// of the rows, along with the number of bytes read.
+pub(crate) fn parse_next_row<'de, T, E>(
+    i: &'de [u8],
+    structure: &StructureNode,
+) -> Result<(usize, Option<T>)>
+where
+    E: Encoding,
+    T: Deserialize<'de>,
+{
+    let mut deserializer: Deserializer<E> = Deserializer::from_bytes(i, structure);
+    let row = match deserializer.parse_bool()? {
+        true => Some(T::deserialize(&mut deserializer)?),
+        false => None,
+    };
+    Ok((i.len() - deserializer.input.len(), row))
+}
+
 impl<'de, 's, E: Encoding> Deserializer<'de, 's, E> {
     /// A helper function to unpack a nom Result and update the input value in the deserializer.
     fn update_and_return<T>(&mut self, parsed: nom::IResult<&'de [u8], T>) -> Result<T> {
@@ -207,14 +225,14 @@ impl<'de, 'a, 't, E: Encoding> serde::de::Deserializer<'de> for &'a mut Deserial
         visitor.visit_newtype_struct(self)
     }
 
-    fn deserialize_seq<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         let len = self.get_length()?;
 
         let sequence_structure = self.structure;
 
         if let StructureNode::VariableLengthSequence(element) = sequence_structure {
             visitor.visit_seq(HomogeneousSequenceAccess::new(
-                &mut self,
+                self,
                 sequence_structure,
                 element,
                 len,
@@ -226,10 +244,10 @@ impl<'de, 'a, 't, E: Encoding> serde::de::Deserializer<'de> for &'a mut Deserial
         }
     }
 
-    fn deserialize_tuple<V: Visitor<'de>>(mut self, len: usize, visitor: V) -> Result<V::Value> {
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
         let sequence_structure = self.structure;
 
-        if let StructureNode::GivenLengthSequence(optional_count, field_structures) =
+        if let StructureNode::GivenLengthSequence(optional_count, field_structures, _) =
             sequence_structure
         {
             // Here there are two options:
@@ -248,14 +266,14 @@ impl<'de, 'a, 't, E: Encoding> serde::de::Deserializer<'de> for &'a mut Deserial
                 self.load_options_flags(*optional_count)?;
 
                 visitor.visit_seq(HeterogeneousSequenceAccess {
-                    de: &mut self,
+                    de: self,
                     sequence_structure,
                     field_structures,
                     len,
                 })
             } else if field_structures.len() == 1 && len > 1 {
                 visitor.visit_seq(HomogeneousSequenceAccess::new(
-                    &mut self,
+                    self,
                     sequence_structure,
                     &field_structures[0],
                     len,
@@ -304,13 +322,13 @@ impl<'de, 'a, 't, E: Encoding> serde::de::Deserializer<'de> for &'a mut Deserial
         self.deserialize_unit(visitor)
     }
 
-    fn deserialize_map<V>(mut self, visitor: V) -> Result<V::Value>
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
         let len = self.get_length()?;
 
-        visitor.visit_map(MapSequenceAccess::new(&mut self, len)?)
+        visitor.visit_map(MapSequenceAccess::new(self, len)?)
     }
 }
 
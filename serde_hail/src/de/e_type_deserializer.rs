@@ -0,0 +1,821 @@
+use std::{convert::TryInto, marker::PhantomData, vec};
+
+use serde::de::{
+    value::{SeqDeserializer, StrDeserializer},
+    DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+use serde::Deserialize;
+
+use parser::parse;
+use parser::parse::Encoding;
+use parser::types::{encoding::VirtualHint, EType, ETypeShape};
+
+use crate::{Error, Result};
+
+/// Unlike the [Deserializer](crate::Deserializer), which relies on the Rust type to know where
+/// the optional fields are, this deserializer is driven by the [EType] of the component.
+///
+/// As the data is self-describing when paired with its encoded type, this allows:
+/// - `deserialize_any` (and so [serde_json::Value], untagged enums, `#[serde(flatten)]`, ...).
+/// - Struct fields to be matched by name, in any order, as structs are visited as maps.
+/// - Unknown fields to be skipped at the byte level using their encoded type, without being
+///   decoded (`deserialize_ignored_any`).
+///
+/// A field of a Rust struct that is not in the encoded type, or a missing value deserialized into
+/// anything but an `Option`, is an error (see [Error::MissingField] and [Error::MissingValue]).
+///
+/// Some notes on how Hail values are visited by `deserialize_any`:
+/// - Missing values are visited as `None`, present values are visited directly.
+/// - Structs, intervals and loci are visited as maps, tuples as sequences.
+/// - Arrays and sets are visited as sequences, and so are dicts: as a sequence of `key`/`value`
+///   structs (as in the JSON representation of Hail), as their keys may be any type.
+///   They are visited as maps when a map is requested (`deserialize_map`).
+/// - NDArrays are visited as a map with a `shape` and its `data` in column-major order.
+///
+/// [serde_json::Value]: https://docs.rs/serde_json/latest/serde_json/enum.Value.html
+#[derive(Debug)]
+pub struct ETypeDeserializer<'de, E: Encoding> {
+    input: &'de [u8],
+    /// The [Encoding] allows the deserializer to be generic over the physical encoding of the
+    /// primitive types.
+    encoding: PhantomData<E>,
+}
+
+impl<'de, E: Encoding> ETypeDeserializer<'de, E> {
+    fn from_bytes(input: &'de [u8]) -> Self {
+        ETypeDeserializer {
+            input,
+            encoding: PhantomData,
+        }
+    }
+
+    /// A helper function to unpack a nom Result and update the input value in the deserializer.
+    fn update_and_return<T>(&mut self, parsed: nom::IResult<&'de [u8], T>) -> Result<T> {
+        let (rest, value) = parsed?;
+        self.input = rest;
+        Ok(value)
+    }
+
+    fn presence_flags(&mut self, number_of_options: usize) -> Result<vec::IntoIter<bool>> {
+        let mut presence_array = self.update_and_return(parse::data::helpers::presence_array(
+            self.input,
+            number_of_options,
+        ))?;
+        presence_array.truncate(number_of_options);
+        Ok(presence_array.into_iter())
+    }
+
+    fn get_length(&mut self) -> Result<usize> {
+        let len = self.update_and_return(E::u32(self.input))?;
+
+        // Note: a u32 should always fit into a usize as long as it's on a 32/64 bits system.
+        len.try_into().map_err(|_| Error::InvalidLength(len))
+    }
+
+    fn parse_bool(&mut self) -> Result<bool> {
+        self.update_and_return(E::bool(self.input))
+    }
+}
+
+/// This is the main public interface of the module, it mirrors [parse_rows](crate::parse_rows)
+/// but uses the encoded type of the rows instead of the Rust type.
+pub fn parse_rows_with_type<'de, T, E>(i: &'de [u8], row_type: &EType) -> Result<Vec<T>>
+where
+    E: Encoding,
+    T: Deserialize<'de>,
+{
+    let mut result = Vec::new();
+    let mut deserializer: ETypeDeserializer<E> = ETypeDeserializer::from_bytes(i);
+    while deserializer.parse_bool()? {
+        let t = T::deserialize(Value::present(&mut deserializer, row_type))?;
+        result.push(t);
+    }
+    Ok(result)
+}
+
+/// Deserializes the next row (see [parse_rows_with_type]), or `None` at the end of the rows, along
+/// with the number of bytes read.
+pub(crate) fn parse_next_row_with_type<'de, T, E>(
+    i: &'de [u8],
+    row_type: &EType,
+) -> Result<(usize, Option<T>)>
+where
+    E: Encoding,
+    T: Deserialize<'de>,
+{
+    let mut deserializer: ETypeDeserializer<E> = ETypeDeserializer::from_bytes(i);
+    let row = match deserializer.parse_bool()? {
+        true => Some(T::deserialize(Value::present(&mut deserializer, row_type))?),
+        false => None,
+    };
+    Ok((i.len() - deserializer.input.len(), row))
+}
+
+/// Forwards to `deserialize_any`, once the value is known to be present (see
+/// [Value::check_present]).
+macro_rules! deserialize_present {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                self.check_present()?;
+                self.deserialize_any(visitor)
+            }
+        )*
+    };
+}
+
+/// What is being deserialized by a [Value].
+#[derive(Clone, Copy)]
+enum Node<'s> {
+    Type(&'s EType),
+    /// The elements of a NDArray, which have no length or presence flags of their own.
+    NDArrayElements(&'s EType, usize),
+}
+
+/// A single (possibly missing) value, the presence flags of optional values are read by the
+/// enclosing sequence.
+struct Value<'a, 'de, 's, E: Encoding> {
+    de: &'a mut ETypeDeserializer<'de, E>,
+    node: Node<'s>,
+    is_present: bool,
+    /// The name of the field being deserialized, if any, for error messages.
+    name: Option<&'s str>,
+}
+
+impl<'a, 'de, 's, E: Encoding> Value<'a, 'de, 's, E> {
+    fn present(de: &'a mut ETypeDeserializer<'de, E>, e_type: &'s EType) -> Self {
+        Value {
+            de,
+            node: Node::Type(e_type),
+            is_present: true,
+            name: None,
+        }
+    }
+
+    /// Missing values can only be deserialized as options (or by `deserialize_any`).
+    fn check_present(&self) -> Result<()> {
+        match self.is_present {
+            true => Ok(()),
+            false => Err(Error::MissingValue(self.name.map(str::to_owned))),
+        }
+    }
+
+    /// The encoded type of a present value.
+    fn e_type(&self) -> Option<&'s EType> {
+        match (self.is_present, self.node) {
+            (true, Node::Type(e_type)) => Some(e_type),
+            _ => None,
+        }
+    }
+}
+
+impl<'a, 'de, 's, E: Encoding> serde::de::Deserializer<'de> for Value<'a, 'de, 's, E> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if !self.is_present {
+            return visitor.visit_none();
+        }
+
+        let e_type = match self.node {
+            Node::Type(e_type) => e_type,
+            Node::NDArrayElements(element, len) => {
+                return visitor.visit_seq(ElementAccess::given_length(self.de, element, len))
+            }
+        };
+
+        let de = self.de;
+        match (&e_type.shape, &e_type.virtual_hint) {
+            (ETypeShape::Boolean, _) => {
+                visitor.visit_bool(de.update_and_return(E::bool(de.input))?)
+            }
+            (ETypeShape::Int32, _) => visitor.visit_i32(de.update_and_return(E::i32(de.input))?),
+            (ETypeShape::Int64, _) => visitor.visit_i64(de.update_and_return(E::i64(de.input))?),
+            (ETypeShape::Float32, _) => visitor.visit_f32(de.update_and_return(E::f32(de.input))?),
+            (ETypeShape::Float64, _) => visitor.visit_f64(de.update_and_return(E::f64(de.input))?),
+            (ETypeShape::Binary, Some(VirtualHint::String)) => {
+                visitor.visit_borrowed_str(de.update_and_return(E::str(de.input))?)
+            }
+            (ETypeShape::Binary, _) => {
+                visitor.visit_borrowed_bytes(de.update_and_return(E::bytes(de.input))?)
+            }
+
+            (ETypeShape::BaseStruct(fields), Some(VirtualHint::Tuple)) => {
+                visitor.visit_seq(FieldAccess::new(de, fields)?)
+            }
+            (ETypeShape::BaseStruct(fields), _) => visitor.visit_map(FieldAccess::new(de, fields)?),
+
+            (ETypeShape::Array(element), _) => {
+                visitor.visit_seq(ElementAccess::variable_length(de, element)?)
+            }
+
+            (ETypeShape::NdArrayColumnMajor(element, n), _) => {
+                visitor.visit_map(NDArrayAccess::new(de, element, *n)?)
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.is_present {
+            true => visitor.visit_some(self),
+            false => visitor.visit_none(),
+        }
+    }
+
+    // Newtype structs are transparent, as for the Deserializer.
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // Tuple structs (like Locus) and NDArrays match their fields by position.
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.check_present()?;
+        match self.e_type().map(|e_type| &e_type.shape) {
+            Some(ETypeShape::BaseStruct(fields)) => {
+                visitor.visit_seq(FieldAccess::new(self.de, fields)?)
+            }
+            Some(ETypeShape::NdArrayColumnMajor(element, n)) => {
+                visitor.visit_seq(NDArrayAccess::new(self.de, element, *n)?)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    // Structs match their fields by name, so the Rust struct may declare any subset of the fields
+    // in any order (the others are skipped, see deserialize_ignored_any).
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.check_present()?;
+        match self.e_type().map(|e_type| &e_type.shape) {
+            Some(ETypeShape::BaseStruct(fields)) => visitor
+                .visit_map(FieldAccess::new(self.de, fields)?)
+                .map_err(|error| match error {
+                    // Missing fields are reported along with the fields that are available.
+                    Error::MissingField { field, available } if available.is_empty() => {
+                        Error::MissingField {
+                            field,
+                            available: fields.iter().map(|(name, _)| name.clone()).collect(),
+                        }
+                    }
+                    error => error,
+                }),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    // Dicts are only visited as maps when requested, see [ETypeDeserializer].
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.check_present()?;
+        match self.e_type() {
+            Some(EType {
+                shape: ETypeShape::Array(entry),
+                virtual_hint: Some(VirtualHint::Dict),
+                ..
+            }) => visitor.visit_map(DictAccess::new(self.de, entry)?),
+            Some(EType {
+                shape: ETypeShape::BaseStruct(fields),
+                ..
+            }) => visitor.visit_map(FieldAccess::new(self.de, fields)?),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    // Binaries are visited as bytes, but they can also be requested as a sequence (`Vec<u8>`).
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.check_present()?;
+        match self.e_type() {
+            Some(EType {
+                shape: ETypeShape::Binary,
+                virtual_hint: None,
+                ..
+            }) => {
+                let bytes = self.de.update_and_return(E::bytes(self.de.input))?;
+                visitor.visit_seq(SeqDeserializer::<_, Error>::new(bytes.iter().copied()))
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    // Empty structs (like the globals of most tables) and unit structs are interchangeable.
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_ignored_any(visitor)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+
+    // Values that are not needed (like the fields missing from a Rust struct) are skipped at the
+    // byte level, without being decoded.
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let de = self.de;
+        match (self.is_present, self.node) {
+            (false, _) => {}
+            (true, Node::Type(e_type)) => de.update_and_return(e_type.skip_from::<E>(de.input))?,
+            (true, Node::NDArrayElements(element, len)) => {
+                let mut element = element.clone();
+                element.required = true;
+                for _ in 0..len {
+                    de.update_and_return(element.skip_from::<E>(de.input))?;
+                }
+            }
+        }
+        visitor.visit_unit()
+    }
+
+    deserialize_present! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_identifier
+    }
+
+    serde::forward_to_deserialize_any! { enum }
+}
+
+/// Visits the fields of a struct in order, as a sequence or as a map keyed by the field names.
+struct FieldAccess<'a, 'de, 's, E: Encoding> {
+    de: &'a mut ETypeDeserializer<'de, E>,
+    fields: std::slice::Iter<'s, (String, EType)>,
+    presence_flags: vec::IntoIter<bool>,
+    /// The field whose name was just visited (when visited as a map).
+    next_value: Option<&'s (String, EType)>,
+}
+
+impl<'a, 'de, 's, E: Encoding> FieldAccess<'a, 'de, 's, E> {
+    fn new(de: &'a mut ETypeDeserializer<'de, E>, fields: &'s [(String, EType)]) -> Result<Self> {
+        let number_of_options = fields.iter().filter(|(_, t)| !t.required).count();
+        let presence_flags = de.presence_flags(number_of_options)?;
+
+        Ok(FieldAccess {
+            de,
+            fields: fields.iter(),
+            presence_flags,
+            next_value: None,
+        })
+    }
+
+    fn value(&mut self, name: &'s str, e_type: &'s EType) -> Result<Value<'_, 'de, 's, E>> {
+        let is_present = match e_type.required {
+            true => true,
+            false => self
+                .presence_flags
+                .next()
+                .ok_or_else(|| Error::Custom("no presence flags remaining".to_owned()))?,
+        };
+
+        Ok(Value {
+            de: &mut *self.de,
+            node: Node::Type(e_type),
+            is_present,
+            name: Some(name),
+        })
+    }
+}
+
+impl<'a, 'de, 's, E: Encoding> SeqAccess<'de> for FieldAccess<'a, 'de, 's, E> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        match self.fields.next() {
+            Some((name, e_type)) => seed.deserialize(self.value(name, e_type)?).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.fields.len())
+    }
+}
+
+impl<'a, 'de, 's, E: Encoding> MapAccess<'de> for FieldAccess<'a, 'de, 's, E> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.fields.next() {
+            Some(field) => {
+                self.next_value = Some(field);
+                let key: StrDeserializer<Error> = field.0.as_str().into_deserializer();
+                seed.deserialize(key).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let (name, e_type) = self
+            .next_value
+            .take()
+            .ok_or_else(|| Error::Custom("a value was requested before its key".to_owned()))?;
+        seed.deserialize(self.value(name, e_type)?)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.fields.len())
+    }
+}
+
+/// Visits the elements of an array (or of a NDArray).
+struct ElementAccess<'a, 'de, 's, E: Encoding> {
+    de: &'a mut ETypeDeserializer<'de, E>,
+    element: &'s EType,
+    /// Only arrays of optional elements have presence flags.
+    presence_flags: Option<vec::IntoIter<bool>>,
+    len: usize,
+}
+
+impl<'a, 'de, 's, E: Encoding> ElementAccess<'a, 'de, 's, E> {
+    /// Arrays are prefixed by their length and, if their elements are optional, their presence
+    /// flags.
+    fn variable_length(de: &'a mut ETypeDeserializer<'de, E>, element: &'s EType) -> Result<Self> {
+        let len = de.get_length()?;
+        let presence_flags = match element.required {
+            true => {
+                parse::data::helpers::check_length(de.input, element, len)?;
+                None
+            }
+            false => Some(de.presence_flags(len)?),
+        };
+
+        Ok(ElementAccess {
+            de,
+            element,
+            presence_flags,
+            len,
+        })
+    }
+
+    /// The elements of a NDArray are assumed to be required (as in the parser).
+    fn given_length(de: &'a mut ETypeDeserializer<'de, E>, element: &'s EType, len: usize) -> Self {
+        ElementAccess {
+            de,
+            element,
+            presence_flags: None,
+            len,
+        }
+    }
+}
+
+impl<'a, 'de, 's, E: Encoding> SeqAccess<'de> for ElementAccess<'a, 'de, 's, E> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+
+        let is_present = match &mut self.presence_flags {
+            Some(flags) => flags
+                .next()
+                .ok_or_else(|| Error::Custom("no presence flags remaining".to_owned()))?,
+            None => true,
+        };
+
+        seed.deserialize(Value {
+            de: &mut *self.de,
+            node: Node::Type(self.element),
+            is_present,
+            name: None,
+        })
+        .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+/// Visits a dict, an array of `struct { key, value }`, as a map.
+struct DictAccess<'a, 'de, 's, E: Encoding> {
+    de: &'a mut ETypeDeserializer<'de, E>,
+    key: &'s EType,
+    value: &'s EType,
+    /// Whether the value of the current entry is present.
+    is_value_present: bool,
+    len: usize,
+}
+
+impl<'a, 'de, 's, E: Encoding> DictAccess<'a, 'de, 's, E> {
+    fn new(de: &'a mut ETypeDeserializer<'de, E>, entry: &'s EType) -> Result<Self> {
+        let (key, value) = match &entry.shape {
+            ETypeShape::BaseStruct(fields) => {
+                let field = |name: &str| fields.iter().find(|(n, _)| n == name).map(|(_, t)| t);
+                match (field("key"), field("value")) {
+                    (Some(key), Some(value)) => (key, value),
+                    _ => return Err(Error::UnsupportedEncodedType(entry.clone())),
+                }
+            }
+            _ => return Err(Error::UnsupportedEncodedType(entry.clone())),
+        };
+
+        let len = de.get_length()?;
+        Ok(DictAccess {
+            de,
+            key,
+            value,
+            is_value_present: true,
+            len,
+        })
+    }
+}
+
+impl<'a, 'de, 's, E: Encoding> MapAccess<'de> for DictAccess<'a, 'de, 's, E> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+
+        // Each entry is a struct, with its own presence flags.
+        let number_of_options = (!self.key.required) as usize + (!self.value.required) as usize;
+        let mut flags = self.de.presence_flags(number_of_options)?;
+        let is_key_present = self.key.required || flags.next().unwrap_or(false);
+        self.is_value_present = self.value.required || flags.next().unwrap_or(false);
+
+        seed.deserialize(Value {
+            de: &mut *self.de,
+            node: Node::Type(self.key),
+            is_present: is_key_present,
+            name: Some("key"),
+        })
+        .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        seed.deserialize(Value {
+            de: &mut *self.de,
+            node: Node::Type(self.value),
+            is_present: self.is_value_present,
+            name: Some("value"),
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+/// Visits a NDArray as its shape followed by its elements, either as a sequence (see
+/// [NDArray](crate::types::NDArray)) or as a map with the `shape` and `data` keys.
+struct NDArrayAccess<'a, 'de, 's, E: Encoding> {
+    de: &'a mut ETypeDeserializer<'de, E>,
+    element: &'s EType,
+    /// The shape is read upfront, as the number of elements depends on it.
+    shape: Vec<i64>,
+    /// The number of fields (shape and data) visited so far.
+    visited: usize,
+}
+
+impl<'a, 'de, 's, E: Encoding> NDArrayAccess<'a, 'de, 's, E> {
+    const KEYS: [&'static str; 2] = ["shape", "data"];
+
+    fn new(de: &'a mut ETypeDeserializer<'de, E>, element: &'s EType, n: u32) -> Result<Self> {
+        let shape = (0..n)
+            .map(|_| de.update_and_return(E::i64(de.input)))
+            .collect::<Result<Vec<i64>>>()?;
+
+        Ok(NDArrayAccess {
+            de,
+            element,
+            shape,
+            visited: 0,
+        })
+    }
+
+    fn next<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Value> {
+        self.visited += 1;
+        match self.visited {
+            1 => seed.deserialize(SeqDeserializer::new(self.shape.clone().into_iter())),
+            _ => {
+                let len = self
+                    .shape
+                    .iter()
+                    .try_fold(1usize, |len, &d| len.checked_mul(d.try_into().ok()?))
+                    .ok_or_else(|| {
+                        Error::Custom(format!("invalid ndarray shape {:?}", self.shape))
+                    })?;
+                parse::data::helpers::check_length(self.de.input, self.element, len)?;
+
+                seed.deserialize(Value {
+                    de: &mut *self.de,
+                    node: Node::NDArrayElements(self.element, len),
+                    is_present: true,
+                    name: Some("data"),
+                })
+            }
+        }
+    }
+}
+
+impl<'a, 'de, 's, E: Encoding> SeqAccess<'de> for NDArrayAccess<'a, 'de, 's, E> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        match self.visited < Self::KEYS.len() {
+            true => self.next(seed).map(Some),
+            false => Ok(None),
+        }
+    }
+}
+
+impl<'a, 'de, 's, E: Encoding> MapAccess<'de> for NDArrayAccess<'a, 'de, 's, E> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match Self::KEYS.get(self.visited) {
+            Some(key) => {
+                let key: StrDeserializer<Error> = key.into_deserializer();
+                seed.deserialize(key).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        self.next(seed)
+    }
+}
+
+mod tests {
+    #![allow(unused_imports)]
+    use serde::Deserialize;
+
+    use parser::parse::{StandardEncoder, UnsignedLEB128Encoder};
+    use parser::types::{encoding::VirtualHint, EType, ETypeShape};
+
+    use super::parse_rows_with_type;
+    use crate::Error;
+    #[cfg(test)]
+    const ROW: u8 = 1u8;
+    #[cfg(test)]
+    const NO_ROW: u8 = 0u8;
+
+    #[cfg(test)]
+    fn e_type(shape: ETypeShape, required: bool, virtual_hint: Option<VirtualHint>) -> EType {
+        EType {
+            shape,
+            required,
+            virtual_hint,
+        }
+    }
+
+    /// `+EBaseStruct{a:+EBoolean,b:EBoolean,c:+EArray[EBinary]}`, where the binary is a string.
+    #[cfg(test)]
+    fn row_type() -> EType {
+        let string = e_type(ETypeShape::Binary, false, Some(VirtualHint::String));
+        e_type(
+            ETypeShape::BaseStruct(vec![
+                ("a".to_owned(), e_type(ETypeShape::Boolean, true, None)),
+                ("b".to_owned(), e_type(ETypeShape::Boolean, false, None)),
+                (
+                    "c".to_owned(),
+                    e_type(ETypeShape::Array(Box::new(string)), true, None),
+                ),
+            ]),
+            true,
+            None,
+        )
+    }
+
+    #[cfg(test)]
+    const BYTES: [u8; 14] = [
+        ROW,
+        0b0000_0001, // struct start [flags], b is missing
+        1,
+        2, // c start [length]
+        0,
+        0,
+        0,
+        0b0000_0010, // c [flags], the second element is missing
+        1,           // c.0 [length]
+        0,
+        0,
+        0,
+        b'x',
+        NO_ROW,
+    ];
+
+    #[test]
+    fn test_any() {
+        let rows = parse_rows_with_type::<serde_json::Value, StandardEncoder>(&BYTES, &row_type());
+        assert_eq!(
+            vec![serde_json::json!({"a": true, "b": null, "c": ["x", null]})],
+            rows.unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fields_by_name() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Row<'a> {
+            #[serde(borrow)]
+            c: Vec<Option<&'a str>>,
+            a: bool,
+        }
+
+        let rows = parse_rows_with_type::<Row, StandardEncoder>(&BYTES, &row_type());
+        assert_eq!(
+            vec![Row {
+                c: vec![Some("x"), None],
+                a: true
+            }],
+            rows.unwrap()
+        );
+    }
+
+    #[test]
+    fn test_missing_field() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Row {
+            a: bool,
+            d: bool,
+        }
+
+        let rows = parse_rows_with_type::<Row, StandardEncoder>(&BYTES, &row_type());
+        assert_eq!(
+            Err(Error::MissingField {
+                field: "d",
+                available: vec!["a".to_owned(), "b".to_owned(), "c".to_owned()]
+            }),
+            rows
+        );
+    }
+
+    #[test]
+    fn test_missing_value() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Row {
+            b: bool,
+        }
+
+        let rows = parse_rows_with_type::<Row, StandardEncoder>(&BYTES, &row_type());
+        assert_eq!(Err(Error::MissingValue(Some("b".to_owned()))), rows);
+    }
+
+    #[test]
+    fn test_binary() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Row {
+            bytes: Vec<u8>,
+            #[serde(with = "serde_bytes")]
+            buf: Vec<u8>,
+        }
+
+        let binary = e_type(ETypeShape::Binary, true, None);
+        let row_type = e_type(
+            ETypeShape::BaseStruct(vec![
+                ("bytes".to_owned(), binary.clone()),
+                ("buf".to_owned(), binary),
+            ]),
+            true,
+            None,
+        );
+        // Neither binary is valid UTF-8.
+        let bytes = [ROW, 2, 0, 0, 0, 0xff, 0, 1, 0, 0, 0, 0xc3, NO_ROW];
+
+        let rows = parse_rows_with_type::<Row, StandardEncoder>(&bytes, &row_type);
+        assert_eq!(
+            vec![Row {
+                bytes: vec![0xff, 0],
+                buf: vec![0xc3]
+            }],
+            rows.unwrap()
+        );
+    }
+
+    #[test]
+    fn test_signed_int32() {
+        let row_type = e_type(ETypeShape::Int32, true, None);
+        // Hail writes the bits of -2 as unsigned LEB128.
+        let bytes = [ROW, 0xfe, 0xff, 0xff, 0xff, 0x0f, NO_ROW];
+
+        let rows = parse_rows_with_type::<i32, UnsignedLEB128Encoder>(&bytes, &row_type);
+        assert_eq!(vec![-2], rows.unwrap());
+        let rows = crate::parse_rows::<i32, UnsignedLEB128Encoder>(&bytes);
+        assert_eq!(vec![-2], rows.unwrap());
+    }
+}
@@ -7,6 +7,9 @@ use serde::{
 
 use crate::types::NDArray;
 
+/// The most elements allocated before they are actually deserialized.
+const MAX_PREALLOCATED_ELEMENTS: usize = 4096;
+
 /// A NDArray needs a custom deserialization method because it is a variable-length sequence which
 /// does not fit in the same structure as other Hail variable-length sequences.
 ///
@@ -22,12 +25,13 @@ impl<'de, T: 'de + DeserializeOwned, const N: usize> Deserialize<'de> for NDArra
         D: serde::Deserializer<'de>,
     {
         // Deserialize the data
-        let (dims, data) = deserializer.deserialize_tuple(2, NDArrayVisitor::<T, N>::new())?;
+        // It is named so that its structure can be recognised (see [SequenceKind](super::look_ahead::SequenceKind)).
+        let (dims, data) =
+            deserializer.deserialize_tuple_struct("NDArray", 2, NDArrayVisitor::<T, N>::new())?;
 
         // Put the data into a ndarray
         {
-            // This is safe, but will cause loss of data if any of the dimensions are more than
-            // the native usize.
+            // The dimensions have been checked by the visitor, they are not negative.
             let size_dims: Vec<usize> = dims.iter().map(|d| *d as usize).collect();
 
             let shape = {
@@ -83,15 +87,11 @@ impl<'de, T: Deserialize<'de>, const N: usize> Visitor<'de> for NDArrayVisitor<T
             .next_element_seed(SequenceWithLengthSeed::<i64>::new(N))?
             .ok_or_else(|| Error::custom("expected a sequence of ndarray dimensions"))?;
 
-        // Compute the total number of elements.
-        let number_of_elements: usize = {
-            // Overflow will cause a panic in debug mode, it's memory-safe but incorrect
-            // in release mode.
-            let n: i64 = dimensions.iter().product();
-
-            n.try_into()
-                .map_err(|_| Error::custom(&format!("unable to convert value to usize ({})", n)))?
-        };
+        // Compute the total number of elements, refusing negative dimensions and overflows.
+        let number_of_elements: usize = dimensions
+            .iter()
+            .try_fold(1usize, |n, &d| n.checked_mul(d.try_into().ok()?))
+            .ok_or_else(|| Error::custom(format!("invalid ndarray shape {:?}", dimensions)))?;
 
         let data: Vec<T> = seq
             .next_element_seed(SequenceWithLengthSeed::<T>::new(number_of_elements))?
@@ -139,7 +139,8 @@ impl<'de, T: Deserialize<'de>> DeserializeSeed<'de> for SequenceWithLengthSeed<T
             }
 
             fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
-                let mut data = Vec::with_capacity(self.0);
+                // The length comes from the data, so only a bounded amount is allocated upfront.
+                let mut data = Vec::with_capacity(self.0.min(MAX_PREALLOCATED_ELEMENTS));
                 for _ in 0..self.0 {
                     let value: T = seq
                         .next_element()?
@@ -11,7 +11,11 @@ pub enum StructureNode {
     /// Sequences (like a tuple or struct) for which the deserializer is told
     /// the length. The length is usually known at compile-time, but in exceptional cases
     /// (like [NDArray](crate::types::NDArray)) it might be provided dynamically.
-    GivenLengthSequence(NumberOfOptionalFields, Vec<(Required, StructureNode)>),
+    GivenLengthSequence(
+        NumberOfOptionalFields,
+        Vec<(Required, StructureNode)>,
+        SequenceKind,
+    ),
     /// Sequences with unknown length, the deserializer will have to get it from the data.
     /// These sequences are assumed to be homogeneous by [LookAheadDeserializer].
     VariableLengthSequence(Box<(Required, StructureNode)>),
@@ -24,7 +28,43 @@ pub enum StructureNode {
     /// A Leaf is anything that does not contain another type.
     /// Of note is the fact that a NewType Struct is considered to be transparent to the type it
     /// contains, so it's not gonna result in a Leaf (or anything else).
-    Leaf,
+    Leaf(LeafKind),
+}
+
+/// What a [StructureNode::GivenLengthSequence] stands for.
+///
+/// This is not needed to deserialize the data, but it allows an [EType](parser::types::EType) to
+/// be derived from the Rust type (see [encoded_type](crate::encoded_type)).
+/// The Hail types are recognised by the name of the Rust type (see [crate::types]).
+#[derive(Debug, Clone, PartialEq)]
+pub enum SequenceKind {
+    /// Tuples and tuple structs.
+    Tuple,
+    /// Structs, with the (possibly renamed) names of their fields.
+    Struct(&'static [&'static str]),
+    Locus,
+    Interval,
+    NDArray,
+}
+
+/// The primitive type of a [StructureNode::Leaf], see [SequenceKind].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LeafKind {
+    Boolean,
+    Int32,
+    Int64,
+    Float32,
+    Float64,
+    String,
+    Bytes,
+    /// Only supported as the element of a byte sequence (`Vec<u8>`), which is encoded as a binary.
+    UInt8,
+    /// A [Call](crate::types::Call), which is transparent to its inner integer.
+    Call,
+    /// Unit and unit structs, which hold no data (like an empty struct).
+    Unit,
+    /// Primitives that are not part of the Hail data model, they can still be deserialized.
+    Other,
 }
 
 /// This enum is used to determine whether a field will be an option.
@@ -55,7 +95,7 @@ pub(crate) fn from_type<'a, T: Deserialize<'a>>() -> Result<StructureNode> {
     deserializer.structure.pop().ok_or(Error::NoFinalValue)
 }
 
-impl<'de, 'a> serde::de::Deserializer<'de> for &'a mut LookAheadDeserializer {
+impl<'de> serde::de::Deserializer<'de> for &mut LookAheadDeserializer {
     type Error = Error;
 
     fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
@@ -64,82 +104,82 @@ impl<'de, 'a> serde::de::Deserializer<'de> for &'a mut LookAheadDeserializer {
     serde::forward_to_deserialize_any! { enum identifier ignored_any }
 
     fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::Boolean));
         visitor.visit_bool(false)
     }
 
     fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::Other));
         visitor.visit_i8(1)
     }
 
     fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::Other));
         visitor.visit_i16(1)
     }
 
     fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::Int32));
         visitor.visit_i32(1)
     }
 
     fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::Int64));
         visitor.visit_i64(1)
     }
 
     fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::UInt8));
         visitor.visit_u8(1)
     }
 
     fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::Other));
         visitor.visit_u16(1)
     }
 
     fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::Int32));
         visitor.visit_u32(1)
     }
 
     fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::Int64));
         visitor.visit_u64(1)
     }
 
     fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::Float32));
         visitor.visit_f32(1.)
     }
 
     fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::Float64));
         visitor.visit_f64(1.)
     }
 
     fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::Other));
         visitor.visit_char('1')
     }
 
     fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::String));
         visitor.visit_str("1")
     }
 
     fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::String));
         visitor.visit_string("1".to_owned())
     }
 
     fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::Bytes));
         visitor.visit_bytes(&[1])
     }
 
     fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::Bytes));
         visitor.visit_byte_buf(vec![1])
     }
 
@@ -155,7 +195,7 @@ impl<'de, 'a> serde::de::Deserializer<'de> for &'a mut LookAheadDeserializer {
     }
 
     fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::Unit));
         visitor.visit_unit()
     }
 
@@ -164,61 +204,97 @@ impl<'de, 'a> serde::de::Deserializer<'de> for &'a mut LookAheadDeserializer {
         _name: &'static str,
         visitor: V,
     ) -> Result<V::Value> {
-        self.structure.push(StructureNode::Leaf);
+        self.structure.push(StructureNode::Leaf(LeafKind::Unit));
         visitor.visit_unit()
     }
 
     fn deserialize_newtype_struct<V: Visitor<'de>>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value> {
         // Newtype structs are usually considered to be 'invisible' wrappers around their inner
         // value for deserialization purposes, so no Leaf here.
-        visitor.visit_newtype_struct(self)
+        let value = visitor.visit_newtype_struct(&mut *self)?;
+
+        // The exception being calls, which need to be told apart from plain integers.
+        if name == "Call" {
+            if let Some(StructureNode::Leaf(kind)) = self.structure.last_mut() {
+                *kind = LeafKind::Call;
+            }
+        }
+
+        Ok(value)
     }
 
-    fn deserialize_seq<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         visitor.visit_seq(VariableLengthSequenceAccess {
-            de: &mut self,
+            de: self,
             done: false,
         })
     }
 
-    fn deserialize_tuple<V: Visitor<'de>>(mut self, len: usize, visitor: V) -> Result<V::Value> {
-        visitor.visit_seq(GivenLengthSequenceAccess {
-            de: &mut self,
-            len,
-            fields: vec![],
-        })
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        self.given_length_sequence(len, SequenceKind::Tuple, visitor)
     }
 
     fn deserialize_tuple_struct<V: Visitor<'de>>(
         self,
-        _name: &'static str,
+        name: &'static str,
         len: usize,
         visitor: V,
     ) -> Result<V::Value> {
-        self.deserialize_tuple(len, visitor)
+        let kind = match name {
+            "Locus" => SequenceKind::Locus,
+            "NDArray" => SequenceKind::NDArray,
+            _ => SequenceKind::Tuple,
+        };
+        self.given_length_sequence(len, kind, visitor)
     }
 
     fn deserialize_struct<V: Visitor<'de>>(
         self,
-        _name: &'static str,
+        name: &'static str,
         fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value> {
-        self.deserialize_tuple(fields.len(), visitor)
+        let kind = match name {
+            "Interval" => SequenceKind::Interval,
+            _ => SequenceKind::Struct(fields),
+        };
+        self.given_length_sequence(fields.len(), kind, visitor)
     }
 
-    fn deserialize_map<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         visitor.visit_map(MapSequenceAccess {
-            de: &mut self,
+            de: self,
             key: None,
         })
     }
 }
 
+impl LookAheadDeserializer {
+    fn given_length_sequence<'de, V: Visitor<'de>>(
+        &mut self,
+        len: usize,
+        kind: SequenceKind,
+        visitor: V,
+    ) -> Result<V::Value> {
+        // Empty sequences have no elements to push the sequence node after, so it's pushed now.
+        if len == 0 {
+            self.structure
+                .push(StructureNode::GivenLengthSequence(0, vec![], kind.clone()));
+        }
+
+        visitor.visit_seq(GivenLengthSequenceAccess {
+            de: self,
+            len,
+            fields: vec![],
+            kind,
+        })
+    }
+}
+
 /// This struct handles any sequence of variable length (essentially calls to
 /// `deserializer.deserialize_seq`).
 /// For the LookAheadDeserializer variable length sequences are assumed to be homogeneous and only
@@ -281,6 +357,7 @@ struct GivenLengthSequenceAccess<'a> {
     de: &'a mut LookAheadDeserializer,
     len: usize,
     fields: Vec<(bool, StructureNode)>,
+    kind: SequenceKind,
 }
 impl<'de, 'a> serde::de::SeqAccess<'de> for GivenLengthSequenceAccess<'a> {
     type Error = Error;
@@ -320,8 +397,11 @@ impl<'de, 'a> serde::de::SeqAccess<'de> for GivenLengthSequenceAccess<'a> {
         if self.len == 0 {
             let number_of_options = self.fields.iter().filter(|(required, _)| !required).count();
 
-            let sequence_node =
-                StructureNode::GivenLengthSequence(number_of_options, self.fields.clone());
+            let sequence_node = StructureNode::GivenLengthSequence(
+                number_of_options,
+                self.fields.clone(),
+                self.kind.clone(),
+            );
 
             self.de.structure.push(sequence_node);
         };
@@ -497,17 +577,23 @@ mod tests {
 
     use serde::Deserialize;
 
-    use super::{from_type, StructureNode};
+    use super::{from_type, LeafKind, SequenceKind, StructureNode};
 
     #[test]
     fn test_bool() {
-        assert_eq!(StructureNode::Leaf, from_type::<bool>().unwrap());
+        assert_eq!(
+            StructureNode::Leaf(LeafKind::Boolean),
+            from_type::<bool>().unwrap()
+        );
     }
 
     #[test]
     fn test_seq_with_required_elements() {
         assert_eq!(
-            StructureNode::VariableLengthSequence(Box::new((true, StructureNode::Leaf))),
+            StructureNode::VariableLengthSequence(Box::new((
+                true,
+                StructureNode::Leaf(LeafKind::Boolean)
+            ))),
             from_type::<Vec<bool>>().unwrap()
         );
     }
@@ -515,7 +601,10 @@ mod tests {
     #[test]
     fn test_seq_with_optional_elements() {
         assert_eq!(
-            StructureNode::VariableLengthSequence(Box::new((false, StructureNode::Leaf))),
+            StructureNode::VariableLengthSequence(Box::new((
+                false,
+                StructureNode::Leaf(LeafKind::Boolean)
+            ))),
             from_type::<Vec<Option<bool>>>().unwrap()
         );
     }
@@ -528,7 +617,11 @@ mod tests {
         assert_eq!(
             StructureNode::GivenLengthSequence(
                 1,
-                vec!((true, StructureNode::Leaf), (false, StructureNode::Leaf))
+                vec!(
+                    (true, StructureNode::Leaf(LeafKind::Boolean)),
+                    (false, StructureNode::Leaf(LeafKind::Boolean))
+                ),
+                SequenceKind::Tuple
             ),
             from_type::<TestTuple>().unwrap()
         );
@@ -544,17 +637,26 @@ mod tests {
                         false,
                         StructureNode::GivenLengthSequence(
                             1,
-                            vec!((true, StructureNode::Leaf), (false, StructureNode::Leaf))
+                            vec!(
+                                (true, StructureNode::Leaf(LeafKind::Boolean)),
+                                (false, StructureNode::Leaf(LeafKind::Boolean))
+                            ),
+                            SequenceKind::Tuple
                         )
                     ),
                     (
                         true,
                         StructureNode::GivenLengthSequence(
                             1,
-                            vec!((true, StructureNode::Leaf), (false, StructureNode::Leaf))
+                            vec!(
+                                (true, StructureNode::Leaf(LeafKind::Boolean)),
+                                (false, StructureNode::Leaf(LeafKind::Boolean))
+                            ),
+                            SequenceKind::Tuple
                         )
                     )
-                ]
+                ],
+                SequenceKind::Tuple
             ),
             from_type::<ParentTestTuple>().unwrap()
         );
@@ -585,19 +687,21 @@ mod tests {
                         StructureNode::GivenLengthSequence(
                             1,
                             vec!(
-                                (true, StructureNode::Leaf),
-                                (false, StructureNode::Leaf),
+                                (true, StructureNode::Leaf(LeafKind::Boolean)),
+                                (false, StructureNode::Leaf(LeafKind::Boolean)),
                                 (
                                     true,
                                     StructureNode::GivenLengthSequence(
                                         1,
                                         vec![
-                                            (true, StructureNode::Leaf),
-                                            (false, StructureNode::Leaf),
-                                        ]
+                                            (true, StructureNode::Leaf(LeafKind::Boolean)),
+                                            (false, StructureNode::Leaf(LeafKind::Boolean)),
+                                        ],
+                                        SequenceKind::Tuple
                                     )
                                 ),
-                            )
+                            ),
+                            SequenceKind::Struct(&["a", "b", "c"])
                         )
                     ),
                     (
@@ -605,23 +709,26 @@ mod tests {
                         StructureNode::GivenLengthSequence(
                             1,
                             vec!(
-                                (true, StructureNode::Leaf),
-                                (false, StructureNode::Leaf),
+                                (true, StructureNode::Leaf(LeafKind::Boolean)),
+                                (false, StructureNode::Leaf(LeafKind::Boolean)),
                                 (
                                     true,
                                     StructureNode::GivenLengthSequence(
                                         1,
                                         vec![
-                                            (true, StructureNode::Leaf),
-                                            (false, StructureNode::Leaf),
-                                        ]
+                                            (true, StructureNode::Leaf(LeafKind::Boolean)),
+                                            (false, StructureNode::Leaf(LeafKind::Boolean)),
+                                        ],
+                                        SequenceKind::Tuple
                                     )
                                 ),
-                            )
+                            ),
+                            SequenceKind::Struct(&["a", "b", "c"])
                         )
                     ),
-                    (false, StructureNode::Leaf)
-                ]
+                    (false, StructureNode::Leaf(LeafKind::Boolean))
+                ],
+                SequenceKind::Struct(&["f", "s", "t"])
             ),
             from_type::<ParentStruct>().unwrap()
         );
@@ -633,8 +740,8 @@ mod tests {
         struct Test(BTreeMap<bool, bool>);
         assert_eq!(
             StructureNode::Map {
-                key: Box::new((true, StructureNode::Leaf)),
-                value: Box::new((true, StructureNode::Leaf)),
+                key: Box::new((true, StructureNode::Leaf(LeafKind::Boolean))),
+                value: Box::new((true, StructureNode::Leaf(LeafKind::Boolean))),
             },
             from_type::<Test>().unwrap()
         );
@@ -643,8 +750,8 @@ mod tests {
         struct TestTwo(BTreeMap<bool, Option<bool>>);
         assert_eq!(
             StructureNode::Map {
-                key: Box::new((true, StructureNode::Leaf)),
-                value: Box::new((false, StructureNode::Leaf)),
+                key: Box::new((true, StructureNode::Leaf(LeafKind::Boolean))),
+                value: Box::new((false, StructureNode::Leaf(LeafKind::Boolean))),
             },
             from_type::<TestTwo>().unwrap()
         );
@@ -653,8 +760,8 @@ mod tests {
         struct TestThree(BTreeMap<Option<bool>, Option<bool>>);
         assert_eq!(
             StructureNode::Map {
-                key: Box::new((false, StructureNode::Leaf)),
-                value: Box::new((false, StructureNode::Leaf)),
+                key: Box::new((false, StructureNode::Leaf(LeafKind::Boolean))),
+                value: Box::new((false, StructureNode::Leaf(LeafKind::Boolean))),
             },
             from_type::<TestThree>().unwrap()
         );
@@ -663,8 +770,8 @@ mod tests {
         struct TestFour(BTreeMap<Option<bool>, bool>);
         assert_eq!(
             StructureNode::Map {
-                key: Box::new((false, StructureNode::Leaf)),
-                value: Box::new((true, StructureNode::Leaf)),
+                key: Box::new((false, StructureNode::Leaf(LeafKind::Boolean))),
+                value: Box::new((true, StructureNode::Leaf(LeafKind::Boolean))),
             },
             from_type::<TestFour>().unwrap()
         );
@@ -1,6 +1,11 @@
 mod deserializer;
+mod e_type_deserializer;
 mod hail_ndarray;
 
 pub(crate) mod look_ahead;
 
 pub use deserializer::{parse_rows, Deserializer};
+pub use e_type_deserializer::{parse_rows_with_type, ETypeDeserializer};
+
+pub(crate) use deserializer::parse_next_row;
+pub(crate) use e_type_deserializer::parse_next_row_with_type;
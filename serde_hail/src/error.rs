@@ -2,6 +2,8 @@ use std::fmt::{self, Display};
 
 use serde::{de, ser};
 
+use parser::types::EType;
+
 use super::de::look_ahead::StructureNode;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -44,8 +46,34 @@ pub enum Error {
     /// is found.
     UnexpectedGivenLengthSequenceLayout(StructureNode, usize),
 
+    /// When serializing an option, the presence flag is recorded by the enclosing sequence.
+    /// This error is raised if there is no such sequence (the top-level value is an option).
+    NoEnclosingSequence,
+
+    /// Variable-length sequences either have all optional elements or all required ones, as the
+    /// presence flags of their elements are either all present or all absent.
+    /// This error is raised if only some of the elements of a sequence are options.
+    HeterogeneousSequence,
+
+    /// The [ETypeDeserializer](crate::ETypeDeserializer) found an encoded type it does not know how
+    /// to interpret (for example, a dict whose entries have no key or value).
+    UnsupportedEncodedType(EType),
+
+    /// A field of a Rust struct was not found among the fields of the encoded type, which are
+    /// listed when known.
+    MissingField {
+        field: &'static str,
+        available: Vec<String>,
+    },
+    /// A missing value was found, but it was not deserialized into an option.
+    /// The name of the field is included when known.
+    MissingValue(Option<String>),
+
     /// These types are unsupported because they are not part of the Hail data model.
     UnsupportedType,
+
+    /// The input ended in the middle of a value, so more of it is needed to deserialize it.
+    Incomplete(nom::Needed),
 }
 
 impl ser::Error for Error {
@@ -58,6 +86,13 @@ impl de::Error for Error {
     fn custom<T: Display>(msg: T) -> Self {
         Error::Custom(msg.to_string())
     }
+
+    fn missing_field(field: &'static str) -> Self {
+        Error::MissingField {
+            field,
+            available: vec![],
+        }
+    }
 }
 
 impl Display for Error {
@@ -90,7 +125,33 @@ impl Display for Error {
                 shape, len
             ),
 
+            NoEnclosingSequence => "an option was serialized outside of a sequence".to_owned(),
+
+            HeterogeneousSequence => {
+                "a sequence was found with both optional and required elements".to_owned()
+            }
+
+            UnsupportedEncodedType(e_type) => {
+                format!("the encoded type {} is not supported", e_type)
+            }
+
+            MissingField { field, available } => match available.is_empty() {
+                true => format!("the field `{}` is missing", field),
+                false => format!(
+                    "the field `{}` is missing, the available fields are: {}",
+                    field,
+                    available.join(", ")
+                ),
+            },
+            MissingValue(Some(field)) => format!(
+                "the field `{}` is missing a value, but it is not an Option",
+                field
+            ),
+            MissingValue(None) => "a value is missing, but it is not an Option".to_owned(),
+
             UnsupportedType => "the type contains an invalid Hail type".to_owned(),
+
+            Incomplete(_) => "the input ended in the middle of a value".to_owned(),
         };
 
         formatter.write_str(&message)
@@ -101,6 +162,9 @@ impl std::error::Error for Error {}
 
 impl From<nom::Err<nom::error::Error<&[u8]>>> for Error {
     fn from(e: nom::Err<nom::error::Error<&[u8]>>) -> Self {
-        Error::Custom(e.to_string())
+        match e {
+            nom::Err::Incomplete(needed) => Error::Incomplete(needed),
+            e => Error::Custom(e.to_string()),
+        }
     }
 }
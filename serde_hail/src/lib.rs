@@ -1,12 +1,14 @@
 #![forbid(unsafe_code)]
 mod de;
 mod error;
+mod ser;
 
 mod model_generation;
 
 pub mod load;
 pub mod types;
 
-pub use de::{parse_rows, Deserializer};
+pub use de::{parse_rows, parse_rows_with_type, Deserializer, ETypeDeserializer};
 pub use error::{Error, Result};
 pub use model_generation::encoded_type_to_rust_type;
+pub use ser::{encode_rows, encoded_type, Serializer};
@@ -2,12 +2,16 @@ use std::path::Path;
 
 use anyhow::Result;
 
+#[cfg(feature = "parallel")]
+use parser::load::parallel;
 use parser::{
-    parse::{Encoding, StandardEncoder, UnsignedLEB128Encoder},
-    types::metadata::shared::BufferSpec,
+    load::stream::decode_part_rows,
+    parse::{StandardEncoder, UnsignedLEB128Encoder},
+    types::{metadata::shared::BufferSpec, EType},
 };
 
-use crate::parse_rows;
+use super::Row;
+use crate::de::{look_ahead, parse_next_row, parse_next_row_with_type};
 
 pub fn load_component_data_with_serde<T>(
     part_files: &[String],
@@ -15,55 +19,79 @@ pub fn load_component_data_with_serde<T>(
     path: &Path,
 ) -> Result<Vec<Vec<T>>>
 where
-    T: serde::de::DeserializeOwned,
+    T: Row,
 {
-    let data = match buffer_spec.uses_leb128() {
-        true => _load_data_with_serde::<T, UnsignedLEB128Encoder>(
-            part_files,
-            buffer_spec.uses_compression(),
-            buffer_spec.appends_length(),
-            path,
-        ),
-        false => _load_data_with_serde::<T, StandardEncoder>(
-            part_files,
-            buffer_spec.uses_compression(),
-            buffer_spec.appends_length(),
-            path,
-        ),
-    }?;
-
-    Ok(data)
+    let structure = look_ahead::from_type::<T>()?;
+    match buffer_spec.uses_leb128() {
+        true => _load_data_with_serde(part_files, buffer_spec, path, |input| {
+            parse_next_row::<T, UnsignedLEB128Encoder>(input, &structure)
+        }),
+        false => _load_data_with_serde(part_files, buffer_spec, path, |input| {
+            parse_next_row::<T, StandardEncoder>(input, &structure)
+        }),
+    }
 }
 
-fn _load_data_with_serde<T, E>(
+/// Same as [load_component_data_with_serde], but the rows are deserialized using their encoded
+/// type (see [ETypeDeserializer](crate::ETypeDeserializer)).
+pub fn load_component_data_with_type<T>(
     part_files: &[String],
-    is_compressed: bool,
-    has_appended_length: bool,
+    buffer_spec: &BufferSpec,
+    row_type: &EType,
     path: &Path,
 ) -> Result<Vec<Vec<T>>>
 where
-    E: Encoding,
-    T: serde::de::DeserializeOwned,
+    T: Row,
 {
-    let mut data = Vec::new();
+    match buffer_spec.uses_leb128() {
+        true => _load_data_with_serde(part_files, buffer_spec, path, |input| {
+            parse_next_row_with_type::<T, UnsignedLEB128Encoder>(input, row_type)
+        }),
+        false => _load_data_with_serde(part_files, buffer_spec, path, |input| {
+            parse_next_row_with_type::<T, StandardEncoder>(input, row_type)
+        }),
+    }
+}
 
-    for part_name in part_files {
-        let part_data = {
-            let part_path = path.join(part_name);
-            let file = std::fs::read(&part_path)?;
-            if is_compressed {
-                parse_rows::<T, E>(&parser::load::compression::decompress_part_file(&file)?)?
-            } else {
-                let mut file: &[u8] = &file;
-                if has_appended_length {
-                    file = &file[4..];
-                };
-                parse_rows::<T, E>(file)?
-            }
-        };
+/// The part files are decoded one row at a time, from blocks read (and decompressed) into a
+/// reused buffer, see [decode_part_rows].
+fn _load_data_with_serde<T, F>(
+    part_files: &[String],
+    buffer_spec: &BufferSpec,
+    path: &Path,
+    parse_row: F,
+) -> Result<Vec<Vec<T>>>
+where
+    T: Row,
+    F: Fn(&[u8]) -> crate::Result<(usize, Option<T>)> + Sync,
+{
+    map_partitions(part_files, |part_name| {
+        decode_part_rows(&path.join(part_name), buffer_spec, |input| {
+            parse_row(input).map_err(|error| match error {
+                // The row goes on in the next blocks.
+                crate::Error::Incomplete(needed) => nom::Err::Incomplete(needed),
+                error => nom::Err::Error(error.into()),
+            })
+        })
+    })
+}
 
-        data.push(part_data);
-    }
+/// Decodes the partitions with [parallel::map_partitions], which needs the rows to be [Send].
+#[cfg(feature = "parallel")]
+fn map_partitions<T, F>(part_files: &[String], f: F) -> Result<Vec<Vec<T>>>
+where
+    T: Row,
+    F: Fn(&String) -> Result<Vec<T>> + Sync,
+{
+    parallel::map_partitions(part_files, f)
+}
 
-    Ok(data)
+/// Decodes the partitions one after the other, so that the rows need not be [Send].
+#[cfg(not(feature = "parallel"))]
+fn map_partitions<T, F>(part_files: &[String], f: F) -> Result<Vec<Vec<T>>>
+where
+    T: Row,
+    F: Fn(&String) -> Result<Vec<T>>,
+{
+    part_files.iter().map(f).collect()
 }
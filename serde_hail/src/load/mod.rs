@@ -1,4 +1,4 @@
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 use anyhow::Result;
 use serde::de::DeserializeOwned;
@@ -7,33 +7,116 @@ use crate::types::{SerdeComponent, SerdeMatrix, SerdeTable};
 
 mod helpers;
 
+/// The types the rows of a component can be loaded into: any [DeserializeOwned] type, which must
+/// also be [Send] with the `parallel` feature, as the partitions are then decoded on other threads.
+#[cfg(feature = "parallel")]
+pub trait Row: DeserializeOwned + Send {}
+#[cfg(feature = "parallel")]
+impl<T: DeserializeOwned + Send> Row for T {}
+
+/// The types the rows of a component can be loaded into: any [DeserializeOwned] type, which must
+/// also be [Send] with the `parallel` feature, as the partitions are then decoded on other threads.
+#[cfg(not(feature = "parallel"))]
+pub trait Row: DeserializeOwned {}
+#[cfg(not(feature = "parallel"))]
+impl<T: DeserializeOwned> Row for T {}
+
+/// How the rows are matched to the Rust types.
+#[derive(Clone, Copy)]
+enum Mode {
+    /// Using the Rust type only, see [Deserializer](crate::Deserializer).
+    RustType,
+    /// Using the encoded type of the component, see [ETypeDeserializer](crate::ETypeDeserializer).
+    EncodedType,
+}
+
 pub fn component<R, P>(path: P) -> Result<SerdeComponent<R>>
 where
-    R: DeserializeOwned,
+    R: Row,
+    P: AsRef<Path>,
+{
+    _component(path.as_ref(), Mode::RustType)
+}
+
+pub fn table<G, R, P>(path: P) -> Result<SerdeTable<G, R>>
+where
+    G: Row,
+    R: Row,
+    P: AsRef<Path>,
+{
+    _table(path.as_ref(), Mode::RustType)
+}
+
+pub fn matrix<G, C, R, E, P>(path: P) -> Result<SerdeMatrix<G, C, R, E>>
+where
+    G: Row,
+    C: Row,
+    R: Row,
+    E: Row,
+    P: AsRef<Path>,
+{
+    _matrix(path.as_ref(), Mode::RustType)
+}
+
+/// Loads a component using its encoded type to drive the deserialization, so that rows can be
+/// deserialized into self-describing types (like `serde_json::Value`) and struct fields are
+/// matched by name.
+pub fn component_with_type<R, P>(path: P) -> Result<SerdeComponent<R>>
+where
+    R: Row,
+    P: AsRef<Path>,
+{
+    _component(path.as_ref(), Mode::EncodedType)
+}
+
+/// See [component_with_type].
+pub fn table_with_type<G, R, P>(path: P) -> Result<SerdeTable<G, R>>
+where
+    G: Row,
+    R: Row,
+    P: AsRef<Path>,
+{
+    _table(path.as_ref(), Mode::EncodedType)
+}
+
+/// See [component_with_type].
+pub fn matrix_with_type<G, C, R, E, P>(path: P) -> Result<SerdeMatrix<G, C, R, E>>
+where
+    G: Row,
+    C: Row,
+    R: Row,
+    E: Row,
     P: AsRef<Path>,
 {
-    let path: &Path = path.as_ref();
+    _matrix(path.as_ref(), Mode::EncodedType)
+}
 
+fn _component<R: Row>(path: &Path, mode: Mode) -> Result<SerdeComponent<R>> {
     let metadata = parser::load::metadata::load_component_metadata(path)?;
     let data_path = path.join("parts");
 
-    let data = helpers::load_component_data_with_serde::<R>(
-        &metadata.part_files,
-        &metadata.buffer_spec,
-        &data_path,
-    )?;
+    let data = match mode {
+        Mode::RustType => helpers::load_component_data_with_serde::<R>(
+            &metadata.part_files,
+            &metadata.buffer_spec,
+            &data_path,
+        ),
+        Mode::EncodedType => helpers::load_component_data_with_type::<R>(
+            &metadata.part_files,
+            &metadata.buffer_spec,
+            &metadata.encoded_type,
+            &data_path,
+        ),
+    }?;
 
     Ok(SerdeComponent { data, metadata })
 }
 
-pub fn table<G, R, P>(path: P) -> Result<SerdeTable<G, R>>
+fn _table<G, R>(path: &Path, mode: Mode) -> Result<SerdeTable<G, R>>
 where
-    G: serde::de::DeserializeOwned,
-    R: serde::de::DeserializeOwned,
-    P: AsRef<Path>,
+    G: Row,
+    R: Row,
 {
-    let path: &Path = path.as_ref();
-
     let metadata = parser::load::metadata::load_table_metadata(path)?;
 
     let components = &metadata.components;
@@ -41,8 +124,8 @@ where
     let globals_path = path.join(&components.globals.rel_path);
     let rows_path = path.join(&components.rows.rel_path);
 
-    let globals = component::<G, PathBuf>(globals_path)?;
-    let rows = component::<R, PathBuf>(rows_path)?;
+    let globals = _component::<G>(&globals_path, mode)?;
+    let rows = _component::<R>(&rows_path, mode)?;
 
     Ok(SerdeTable {
         globals,
@@ -51,16 +134,13 @@ where
     })
 }
 
-pub fn matrix<G, C, R, E, P>(path: P) -> Result<SerdeMatrix<G, C, R, E>>
+fn _matrix<G, C, R, E>(path: &Path, mode: Mode) -> Result<SerdeMatrix<G, C, R, E>>
 where
-    G: serde::de::DeserializeOwned,
-    C: serde::de::DeserializeOwned,
-    R: serde::de::DeserializeOwned,
-    E: serde::de::DeserializeOwned,
-    P: AsRef<Path>,
+    G: Row,
+    C: Row,
+    R: Row,
+    E: Row,
 {
-    let path: &Path = path.as_ref();
-
     let metadata = parser::load::metadata::load_matrix_metadata(path)?;
 
     let components = &metadata.components;
@@ -70,10 +150,10 @@ where
     let rows_path = path.join(&components.rows.rel_path);
     let entries_path = path.join(&components.entries.rel_path);
 
-    let globals = component::<G, PathBuf>(globals_path)?;
-    let cols = component::<C, PathBuf>(cols_path)?;
-    let rows = component::<R, PathBuf>(rows_path)?;
-    let entries = component::<E, PathBuf>(entries_path)?;
+    let globals = _component::<G>(&globals_path, mode)?;
+    let cols = _component::<C>(&cols_path, mode)?;
+    let rows = _component::<R>(&rows_path, mode)?;
+    let entries = _component::<E>(&entries_path, mode)?;
 
     Ok(SerdeMatrix {
         globals,
@@ -1,8 +1,6 @@
 use anyhow::{anyhow, Result};
-use quote::{
-    __private::{Ident, TokenStream},
-    format_ident, quote,
-};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
 
 use parser::types::{encoding::VirtualHint, EType, ETypeShape};
 
@@ -63,15 +61,16 @@ fn process_type(
             quote! { NDArray<#inner_rust_type, #d> }
         }
 
-        ETypeShape::Binary => {
-            assert_eq!(e.virtual_hint, Some(VirtualHint::String));
-            quote! { String }
-        }
+        ETypeShape::Binary => match &e.virtual_hint {
+            Some(VirtualHint::String) => quote! { String },
+            None => quote! { Vec<u8> },
+            _ => return Err(unexpected_combination("EBinary", &e.virtual_hint)),
+        },
         ETypeShape::Float32 => quote! { f32 },
         ETypeShape::Float64 => quote! { f64 },
         ETypeShape::Int32 => match &e.virtual_hint {
             Some(VirtualHint::Call) => quote! { Call },
-            None => quote! { u32 },
+            None => quote! { i32 },
             _ => return Err(unexpected_combination("EInt32", &e.virtual_hint)),
         },
         ETypeShape::Int64 => quote! { i64 },
@@ -189,8 +188,7 @@ fn process_fields(
     // All the type defs go in a vector
     let typedefs: Vec<TokenStream> = rust_fields
         .iter()
-        .map(|(_, defs)| defs.to_owned())
-        .flatten()
+        .flat_map(|(_, defs)| defs.to_owned())
         .collect();
 
     Ok((rust_field_types, typedefs))
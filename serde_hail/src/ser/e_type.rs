@@ -0,0 +1,252 @@
+use serde::de::DeserializeOwned;
+
+use parser::types::{encoding::VirtualHint, EType, ETypeShape};
+
+use crate::{
+    de::look_ahead::{self, LeafKind, SequenceKind, StructureNode},
+    Error, Result,
+};
+
+/// Derives the [EType] matching the layout the [Serializer](crate::Serializer) writes for `T`,
+/// using the same look-ahead analysis as the [Deserializer](crate::Deserializer).
+///
+/// A few notes on how Rust types are mapped to Hail types:
+/// - Field names are taken from the Rust type, so `#[serde(rename = "...")]` is needed wherever
+///   the Hail name is not a valid Rust identifier.
+/// - Variable-length sequences are always arrays, a set cannot be told apart from an array.
+///   Byte sequences (`Vec<u8>`, or `serde_bytes`) are binaries, without a string hint.
+/// - Loci are assumed to use the `GRCh37` reference genome (the Hail default), as the Rust type
+///   does not carry the reference genome.
+pub fn encoded_type<T: DeserializeOwned>() -> Result<EType> {
+    let structure = look_ahead::from_type::<T>()?;
+    e_type(true, &structure)
+}
+
+fn e_type(required: bool, node: &StructureNode) -> Result<EType> {
+    let (shape, virtual_hint) = match node {
+        StructureNode::Leaf(kind) => match kind {
+            LeafKind::Boolean => (ETypeShape::Boolean, None),
+            LeafKind::Int32 => (ETypeShape::Int32, None),
+            LeafKind::Int64 => (ETypeShape::Int64, None),
+            LeafKind::Float32 => (ETypeShape::Float32, None),
+            LeafKind::Float64 => (ETypeShape::Float64, None),
+            LeafKind::String => (ETypeShape::Binary, Some(VirtualHint::String)),
+            LeafKind::Bytes => (ETypeShape::Binary, None),
+            LeafKind::Call => (ETypeShape::Int32, Some(VirtualHint::Call)),
+            LeafKind::Unit => (ETypeShape::BaseStruct(vec![]), None),
+            LeafKind::UInt8 | LeafKind::Other => return Err(Error::UnsupportedType),
+        },
+
+        StructureNode::GivenLengthSequence(_, fields, kind) => match kind {
+            SequenceKind::Struct(names) => {
+                let fields = names
+                    .iter()
+                    .zip(fields)
+                    .map(|(name, (required, field))| {
+                        Ok((name.to_string(), e_type(*required, field)?))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                (ETypeShape::BaseStruct(fields), None)
+            }
+            SequenceKind::Tuple => {
+                let fields = fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (required, field))| {
+                        Ok((format!("`{}`", i), e_type(*required, field)?))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                (ETypeShape::BaseStruct(fields), Some(VirtualHint::Tuple))
+            }
+            SequenceKind::Locus => {
+                let fields = vec![
+                    (
+                        "contig".to_owned(),
+                        e_type(true, &StructureNode::Leaf(LeafKind::String))?,
+                    ),
+                    (
+                        "position".to_owned(),
+                        e_type(true, &StructureNode::Leaf(LeafKind::Int32))?,
+                    ),
+                ];
+                (
+                    ETypeShape::BaseStruct(fields),
+                    Some(VirtualHint::Locus("GRCh37".to_owned())),
+                )
+            }
+            SequenceKind::Interval => {
+                let names = ["start", "end", "includesStart", "includesEnd"];
+                let fields = names
+                    .iter()
+                    .zip(fields)
+                    .map(|(name, (required, field))| {
+                        Ok((name.to_string(), e_type(*required, field)?))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                (ETypeShape::BaseStruct(fields), Some(VirtualHint::Interval))
+            }
+            // The dimensions come first, followed by a single (homogeneous) element.
+            SequenceKind::NDArray => match fields.as_slice() {
+                [(_, StructureNode::GivenLengthSequence(_, dimensions, _)), (_, elements)] => {
+                    let element = match elements {
+                        StructureNode::GivenLengthSequence(_, element, _) => element.first(),
+                        _ => None,
+                    };
+                    let (element_required, element) = element.ok_or_else(|| {
+                        Error::UnexpectedGivenLengthSequenceLayout(node.clone(), 2)
+                    })?;
+                    (
+                        ETypeShape::NdArrayColumnMajor(
+                            Box::new(e_type(*element_required, element)?),
+                            dimensions.len() as u32,
+                        ),
+                        None,
+                    )
+                }
+                _ => return Err(Error::UnexpectedGivenLengthSequenceLayout(node.clone(), 2)),
+            },
+        },
+
+        StructureNode::VariableLengthSequence(element) => match element.as_ref() {
+            // Byte sequences have the same layout as binaries: a length followed by the bytes.
+            (true, StructureNode::Leaf(LeafKind::UInt8)) => (ETypeShape::Binary, None),
+            (required, element) => (
+                ETypeShape::Array(Box::new(e_type(*required, element)?)),
+                None,
+            ),
+        },
+
+        StructureNode::Map { key, value } => {
+            let (key_required, key) = key.as_ref();
+            let (value_required, value) = value.as_ref();
+            let entry = EType {
+                shape: ETypeShape::BaseStruct(vec![
+                    ("key".to_owned(), e_type(*key_required, key)?),
+                    ("value".to_owned(), e_type(*value_required, value)?),
+                ]),
+                required: true,
+                virtual_hint: None,
+            };
+            (ETypeShape::Array(Box::new(entry)), Some(VirtualHint::Dict))
+        }
+    };
+
+    Ok(EType {
+        shape,
+        required,
+        virtual_hint,
+    })
+}
+
+mod tests {
+    #![allow(unused_imports)]
+    use std::collections::BTreeMap;
+
+    use serde::Deserialize;
+
+    use parser::types::encoding::VirtualHint;
+    use parser::types::{EType, ETypeShape};
+
+    use super::encoded_type;
+    use crate::types::{Call, Interval, Locus, NDArray};
+
+    #[cfg(test)]
+    fn e_type(shape: ETypeShape, required: bool, virtual_hint: Option<VirtualHint>) -> EType {
+        EType {
+            shape,
+            required,
+            virtual_hint,
+        }
+    }
+
+    #[test]
+    fn test_struct() {
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        struct Row {
+            a: Option<i32>,
+            b: Vec<Option<String>>,
+            c: (bool, Option<f64>),
+        }
+
+        let expected = e_type(
+            ETypeShape::BaseStruct(vec![
+                ("a".to_owned(), e_type(ETypeShape::Int32, false, None)),
+                (
+                    "b".to_owned(),
+                    e_type(
+                        ETypeShape::Array(Box::new(e_type(
+                            ETypeShape::Binary,
+                            false,
+                            Some(VirtualHint::String),
+                        ))),
+                        true,
+                        None,
+                    ),
+                ),
+                (
+                    "c".to_owned(),
+                    e_type(
+                        ETypeShape::BaseStruct(vec![
+                            ("`0`".to_owned(), e_type(ETypeShape::Boolean, true, None)),
+                            ("`1`".to_owned(), e_type(ETypeShape::Float64, false, None)),
+                        ]),
+                        true,
+                        Some(VirtualHint::Tuple),
+                    ),
+                ),
+            ]),
+            true,
+            None,
+        );
+
+        assert_eq!(expected, encoded_type::<Row>().unwrap());
+    }
+
+    #[test]
+    fn test_hail_types() {
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        struct Row {
+            locus: Locus,
+            call: Option<Call>,
+            interval: Interval<i64>,
+            dict: BTreeMap<String, Option<i64>>,
+            ndarray: NDArray<Option<f64>, 2>,
+        }
+
+        assert_eq!(
+            "+EBaseStruct{\
+                locus:+EBaseStruct{contig:+EBinary,position:+EInt32},\
+                call:EInt32,\
+                interval:+EBaseStruct{start:+EInt64,end:+EInt64,includesStart:+EBoolean,includesEnd:+EBoolean},\
+                dict:+EArray[+EBaseStruct{key:+EBinary,value:EInt64}],\
+                ndarray:+ENDArrayColumnMajor[EFloat64,2]\
+            }",
+            encoded_type::<Row>().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_unsupported_type() {
+        assert!(encoded_type::<Vec<i8>>().is_err());
+    }
+
+    #[test]
+    fn test_bytes() {
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        struct Row {
+            bytes: Vec<u8>,
+            buf: Option<serde_bytes::ByteBuf>,
+        }
+
+        assert_eq!(
+            "+EBaseStruct{bytes:+EBinary,buf:EBinary}",
+            encoded_type::<Row>().unwrap().to_string()
+        );
+        // Only sequences of (required) bytes are binaries.
+        assert!(encoded_type::<u8>().is_err());
+        assert!(encoded_type::<Vec<Option<u8>>>().is_err());
+    }
+}
@@ -0,0 +1,50 @@
+use serde::{
+    de::DeserializeOwned,
+    ser::{Error, SerializeTuple, SerializeTupleStruct},
+    Serialize, Serializer,
+};
+
+use crate::types::NDArray;
+
+/// The inverse of the custom [Deserialize](serde::Deserialize) implementation: a sequence of two
+/// elements, the size of each axis followed by the elements in column-major order.
+impl<T: DeserializeOwned + Serialize, const N: usize> Serialize for NDArray<T, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0.ndim() != N {
+            return Err(S::Error::custom(format!(
+                "expected an ndarray with {} dimensions, found {}",
+                N,
+                self.0.ndim()
+            )));
+        }
+
+        let mut array = serializer.serialize_tuple_struct("NDArray", 2)?;
+        array.serialize_field(&Dimensions(self.0.shape()))?;
+        array.serialize_field(&ColumnMajor(&self.0))?;
+        array.end()
+    }
+}
+
+struct Dimensions<'a>(&'a [usize]);
+impl<'a> Serialize for Dimensions<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut dimensions = serializer.serialize_tuple(self.0.len())?;
+        for &dimension in self.0 {
+            dimensions.serialize_element(&(dimension as i64))?;
+        }
+        dimensions.end()
+    }
+}
+
+struct ColumnMajor<'a, T>(&'a ndarray::ArrayD<T>);
+impl<'a, T: Serialize> Serialize for ColumnMajor<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut elements = serializer.serialize_tuple(self.0.len())?;
+        // Reversing the axes and iterating in the default (row-major) order yields the elements
+        // in column-major order.
+        for element in self.0.t().iter() {
+            elements.serialize_element(element)?;
+        }
+        elements.end()
+    }
+}
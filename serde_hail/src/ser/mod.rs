@@ -0,0 +1,6 @@
+mod e_type;
+mod hail_ndarray;
+mod serializer;
+
+pub use e_type::encoded_type;
+pub use serializer::{encode_rows, Serializer};
@@ -0,0 +1,550 @@
+use std::{convert::TryFrom, marker::PhantomData};
+
+use serde::{
+    ser::{self, Impossible},
+    Serialize,
+};
+
+use parser::parse::{data::helpers::write_presence_array, Encoding};
+
+use crate::{Error, Result};
+
+/// The serializer writes values with the layout expected by the [Deserializer](crate::Deserializer).
+///
+/// The presence flags of the optional fields of a sequence come before the fields themselves,
+/// but whether a field is present (or optional at all) is only known once it has been serialized.
+/// Sequences are therefore serialized first, and their prefix (the length and/or presence flags)
+/// is inserted in front of them once they are complete.
+/// This also means that, unlike the [Deserializer](crate::Deserializer), no look-ahead analysis
+/// of the type is needed.
+#[derive(Debug)]
+pub struct Serializer<E: Encoding> {
+    output: Vec<u8>,
+    /// The presence of the optional fields (or elements) of each sequence being serialized, the
+    /// innermost sequence last.
+    /// Options record their presence in the innermost sequence as they are serialized.
+    presence_flags: Vec<Vec<bool>>,
+    /// The [Encoding] allows the serializer to be generic over the physical encoding of the
+    /// primitive types.
+    encoding: PhantomData<E>,
+}
+
+impl<E: Encoding> Default for Serializer<E> {
+    fn default() -> Self {
+        Serializer {
+            output: vec![],
+            presence_flags: vec![],
+            encoding: PhantomData,
+        }
+    }
+}
+
+impl<E: Encoding> Serializer<E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the bytes written so far.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.output
+    }
+
+    fn record_presence(&mut self, is_present: bool) -> Result<()> {
+        match self.presence_flags.last_mut() {
+            Some(flags) => {
+                flags.push(is_present);
+                Ok(())
+            }
+            None => Err(Error::NoEnclosingSequence),
+        }
+    }
+}
+
+/// This is the main public interface of the module, and the inverse of
+/// [parse_rows](crate::parse_rows): each row is preceded by a `true` marker, and the rows are
+/// followed by a `false` one.
+pub fn encode_rows<T, E>(rows: &[T]) -> Result<Vec<u8>>
+where
+    E: Encoding,
+    T: Serialize,
+{
+    let mut serializer: Serializer<E> = Serializer::new();
+    for row in rows {
+        E::write_bool(&mut serializer.output, true);
+        row.serialize(&mut serializer)?;
+    }
+    E::write_bool(&mut serializer.output, false);
+    Ok(serializer.into_inner())
+}
+
+impl<'a, E: Encoding> ser::Serializer for &'a mut Serializer<E> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = SequenceSerializer<'a, E>;
+    type SerializeTuple = SequenceSerializer<'a, E>;
+    type SerializeTupleStruct = SequenceSerializer<'a, E>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = SequenceSerializer<'a, E>;
+    type SerializeStruct = SequenceSerializer<'a, E>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        E::write_bool(&mut self.output, v);
+        Ok(())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        E::write_i32(&mut self.output, v);
+        Ok(())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        E::write_i64(&mut self.output, v);
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        E::write_u8(&mut self.output, v);
+        Ok(())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        E::write_u32(&mut self.output, v);
+        Ok(())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        E::write_u64(&mut self.output, v);
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        E::write_f32(&mut self.output, v);
+        Ok(())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        E::write_f64(&mut self.output, v);
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        E::write_bytes(&mut self.output, v.as_bytes());
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        E::write_bytes(&mut self.output, v);
+        Ok(())
+    }
+
+    // These types are not currently supported, as for the Deserializer.
+    fn serialize_i8(self, _v: i8) -> Result<()> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<()> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<()> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_char(self, _v: char) -> Result<()> {
+        Err(Error::UnsupportedType)
+    }
+
+    // Options are not written where they are, their presence is recorded by the enclosing
+    // sequence instead, which writes it among its presence flags.
+    fn serialize_none(self) -> Result<()> {
+        self.record_presence(false)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<()> {
+        self.record_presence(true)?;
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        self.serialize_unit()
+    }
+
+    // Newtype structs are transparent, as for the Deserializer.
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(SequenceSerializer::new(self, SequenceKind::VariableLength))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Ok(SequenceSerializer::new(self, SequenceKind::GivenLength))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_tuple(len)
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.serialize_tuple(len)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(SequenceSerializer::new(self, SequenceKind::Map))
+    }
+
+    // Enums are not part of the Hail data model.
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<()> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(Error::UnsupportedType)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(Error::UnsupportedType)
+    }
+}
+
+enum SequenceKind {
+    /// Sequences prefixed by their length and, if their (homogeneous) elements are optional, by
+    /// one presence flag per element.
+    VariableLength,
+    /// Tuples and structs, prefixed by the presence flags of their optional fields.
+    GivenLength,
+    /// Maps are prefixed by their length, and each key-value pair is a struct of its own.
+    Map,
+}
+
+/// Serializes the elements of a sequence, then inserts its prefix in front of them.
+pub struct SequenceSerializer<'a, E: Encoding> {
+    ser: &'a mut Serializer<E>,
+    kind: SequenceKind,
+    /// The position of the sequence in the output, where the prefix will be inserted.
+    start: usize,
+    /// The position of the current key-value pair, for maps.
+    entry_start: usize,
+    len: usize,
+}
+
+impl<'a, E: Encoding> SequenceSerializer<'a, E> {
+    fn new(ser: &'a mut Serializer<E>, kind: SequenceKind) -> Self {
+        // Maps record the presence flags of each key-value pair instead.
+        if !matches!(kind, SequenceKind::Map) {
+            ser.presence_flags.push(vec![]);
+        }
+
+        let start = ser.output.len();
+        SequenceSerializer {
+            ser,
+            kind,
+            start,
+            entry_start: start,
+            len: 0,
+        }
+    }
+
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        value.serialize(&mut *self.ser)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        let mut prefix = vec![];
+
+        match self.kind {
+            SequenceKind::VariableLength => {
+                let flags = self.ser.presence_flags.pop().unwrap_or_default();
+                E::write_u32(&mut prefix, length(self.len)?);
+                if !flags.is_empty() {
+                    if flags.len() != self.len {
+                        return Err(Error::HeterogeneousSequence);
+                    }
+                    write_presence_array(&mut prefix, flags.into_iter());
+                }
+            }
+            SequenceKind::GivenLength => {
+                let flags = self.ser.presence_flags.pop().unwrap_or_default();
+                write_presence_array(&mut prefix, flags.into_iter());
+            }
+            SequenceKind::Map => E::write_u32(&mut prefix, length(self.len)?),
+        }
+
+        self.ser.output.splice(self.start..self.start, prefix);
+        Ok(())
+    }
+}
+
+fn length(len: usize) -> Result<u32> {
+    u32::try_from(len).map_err(|_| Error::Custom(format!("the sequence is too long ({})", len)))
+}
+
+impl<'a, E: Encoding> ser::SerializeSeq for SequenceSerializer<'a, E> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        SequenceSerializer::end(self)
+    }
+}
+
+impl<'a, E: Encoding> ser::SerializeTuple for SequenceSerializer<'a, E> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        SequenceSerializer::end(self)
+    }
+}
+
+impl<'a, E: Encoding> ser::SerializeTupleStruct for SequenceSerializer<'a, E> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        SequenceSerializer::end(self)
+    }
+}
+
+impl<'a, E: Encoding> ser::SerializeStruct for SequenceSerializer<'a, E> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        SequenceSerializer::end(self)
+    }
+}
+
+impl<'a, E: Encoding> ser::SerializeMap for SequenceSerializer<'a, E> {
+    type Ok = ();
+    type Error = Error;
+
+    // Each key-value pair is a struct (struct { key: KeyType, value: ValueType }), with its own
+    // presence flags.
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+        self.entry_start = self.ser.output.len();
+        self.ser.presence_flags.push(vec![]);
+        key.serialize(&mut *self.ser)
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        value.serialize(&mut *self.ser)?;
+
+        let flags = self.ser.presence_flags.pop().unwrap_or_default();
+        let mut prefix = vec![];
+        write_presence_array(&mut prefix, flags.into_iter());
+        self.ser
+            .output
+            .splice(self.entry_start..self.entry_start, prefix);
+
+        self.len += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        SequenceSerializer::end(self)
+    }
+}
+
+mod tests {
+    #![allow(unused_imports)]
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Serialize};
+
+    use parser::parse::{StandardEncoder, UnsignedLEB128Encoder};
+
+    use super::encode_rows;
+    use crate::parse_rows;
+    #[cfg(test)]
+    const ROW: u8 = 1u8;
+    #[cfg(test)]
+    const NO_ROW: u8 = 0u8;
+
+    #[test]
+    fn test_bool() {
+        assert_eq!(
+            vec![ROW, 0u8, ROW, 1u8, NO_ROW],
+            encode_rows::<bool, StandardEncoder>(&[false, true]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_seq_with_required_elements() {
+        assert_eq!(
+            vec![ROW, 5u8, 0, 0, 0, 0, 1, 0, 1, 1, NO_ROW],
+            encode_rows::<Vec<bool>, StandardEncoder>(&[vec![false, true, false, true, true]])
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_seq_with_optional_elements() {
+        assert_eq!(
+            vec![ROW, 0u8, 0, 0, 0, NO_ROW],
+            encode_rows::<Vec<Option<bool>>, StandardEncoder>(&[vec![]]).unwrap()
+        );
+        assert_eq!(
+            vec![ROW, 5u8, 0, 0, 0, 0b0000_1010, 0, 0, 1, NO_ROW],
+            encode_rows::<Vec<Option<bool>>, StandardEncoder>(&[vec![
+                Some(false),
+                None,
+                Some(false),
+                None,
+                Some(true)
+            ]])
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_heterogeneous_seq() {
+        #[derive(Serialize)]
+        #[serde(untagged)]
+        enum Element {
+            Optional(Option<bool>),
+            Required(bool),
+        }
+
+        assert!(encode_rows::<Vec<Element>, StandardEncoder>(&[vec![
+            Element::Optional(Some(true)),
+            Element::Required(false)
+        ]])
+        .is_err());
+    }
+
+    #[test]
+    fn test_struct() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Tuple(bool, Option<bool>);
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct BaseStruct {
+            a: bool,
+            b: Option<bool>,
+            c: Tuple,
+        }
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct ParentStruct {
+            f: BaseStruct,
+            s: Option<BaseStruct>,
+            t: Option<bool>,
+        }
+
+        let rows = vec![ParentStruct {
+            f: BaseStruct {
+                a: true,
+                b: Some(false),
+                c: Tuple(true, None),
+            },
+            s: Some(BaseStruct {
+                a: true,
+                b: None,
+                c: Tuple(true, None),
+            }),
+            t: None,
+        }];
+        let bytes = vec![
+            ROW,
+            0b0000_0010, // parent start [flags]
+            0b0000_0000, // child 1 start [flags]
+            1,
+            0,
+            0b0000_0001, // Child 1.3 start [flags]
+            1,
+            0b0000_0001, // child 2 start [flags]
+            1,
+            0b0000_0001, // Child 2.3 start [flags]
+            1,
+            NO_ROW,
+        ];
+
+        assert_eq!(
+            bytes,
+            encode_rows::<ParentStruct, StandardEncoder>(&rows).unwrap()
+        );
+        assert_eq!(
+            rows,
+            parse_rows::<ParentStruct, StandardEncoder>(&bytes).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_map() {
+        type Row = BTreeMap<String, Option<i64>>;
+
+        let mut map = BTreeMap::new();
+        map.insert("a".to_owned(), Some(300));
+        map.insert("b".to_owned(), None);
+        let rows: Vec<Row> = vec![map, BTreeMap::new()];
+
+        let bytes = encode_rows::<Row, UnsignedLEB128Encoder>(&rows).unwrap();
+        assert_eq!(
+            rows,
+            parse_rows::<Row, UnsignedLEB128Encoder>(&bytes).unwrap()
+        );
+    }
+}
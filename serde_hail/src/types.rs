@@ -1,6 +1,11 @@
-use serde::{de::DeserializeOwned, Deserialize};
+use std::convert::TryFrom;
 
-use parser::types::metadata::{ComponentMetadata, MatrixMetadata, TableMetadata};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use parser::types::{
+    call::CallError,
+    metadata::{ComponentMetadata, MatrixMetadata, TableMetadata},
+};
 
 /// This a variant of [Matrix](parser::Matrix) that serde to deserialize the data
 /// directly into Rust data structures, with no indirection.
@@ -31,13 +36,30 @@ pub struct SerdeComponent<R> {
     // index: Option<Index>,
 }
 
-#[derive(Debug, Deserialize)]
+/// A call, as packed by Hail, see [parser::types::Call] to decode it (with [Call::decode]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub struct Call(pub u32);
 
-#[derive(Debug, Deserialize)]
+impl Call {
+    /// The ploidy, phasing, and alleles of the call.
+    pub fn decode(self) -> Result<parser::types::Call, CallError> {
+        parser::types::Call::try_from(self.0)
+    }
+}
+
+impl From<parser::types::Call> for Call {
+    fn from(call: parser::types::Call) -> Self {
+        Call(call.packed())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Locus(pub String, pub u32);
 
-#[derive(Debug, Deserialize)]
+/// The fields are named as in Hail, so that they can also be matched by name (see
+/// [ETypeDeserializer](crate::ETypeDeserializer)).
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Interval<T> {
     pub start: T,
     pub end: T,
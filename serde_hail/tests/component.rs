@@ -2,13 +2,16 @@
 use std::path::PathBuf;
 
 use anyhow::{Context, Result};
-use serde::{de::DeserializeOwned, Deserialize};
+use serde::Deserialize;
 
-use serde_hail::types::{Call, Locus};
+use serde_hail::{
+    load::Row,
+    types::{Call, Locus},
+};
 
 fn parse_component<T>(file: &str) -> Result<()>
 where
-    T: DeserializeOwned,
+    T: Row,
 {
     let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .join("../resources")
@@ -72,6 +75,23 @@ fn component_sample_columns() -> Result<()> {
     parse_component::<Component>("sample.vcf.mt/cols/rows")
 }
 
+/// Without the `parallel` feature, the rows are decoded on the calling thread and need not be Send.
+#[cfg(not(feature = "parallel"))]
+#[test]
+fn component_not_send() -> Result<()> {
+    #[derive(Debug, Deserialize)]
+    pub struct Component {
+        s: Option<String>,
+        #[serde(skip)]
+        not_send: std::marker::PhantomData<*const ()>,
+    }
+    let path =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../resources/sample.vcf.mt/cols/rows");
+    let component = serde_hail::load::component::<Component, _>(path)?;
+    assert!(component.data.iter().flatten().all(|row| row.s.is_some()));
+    Ok(())
+}
+
 #[test]
 fn component_sample_entries() -> Result<()> {
     #[derive(Debug, Deserialize)]
@@ -88,3 +108,121 @@ fn component_sample_entries() -> Result<()> {
     }
     parse_component::<Component>("sample.vcf.mt/entries/rows")
 }
+
+#[test]
+fn component_sample_calls() -> Result<()> {
+    #[derive(Debug, Deserialize)]
+    pub struct Entry {
+        gt: Option<Call>,
+        ad: Option<Vec<u32>>,
+        dp: Option<u32>,
+        gq: Option<u32>,
+        pl: Option<Vec<u32>>,
+    }
+    #[derive(Debug, Deserialize)]
+    pub struct Component {
+        entries: Option<Vec<Option<Entry>>>,
+    }
+    let path =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../resources/sample.vcf.mt/entries/rows");
+    let component = serde_hail::load::component::<Component, _>(&path)?;
+
+    let entries = component
+        .data
+        .into_iter()
+        .flatten()
+        .flat_map(|row| row.entries.unwrap_or_default())
+        .flatten();
+    for entry in entries {
+        if let (Some(gt), Some(pl)) = (entry.gt, entry.pl) {
+            let call = gt.decode()?;
+            assert_eq!(Call::from(call), gt);
+            assert_eq!(call.ploidy(), 2);
+            // The called genotype has the best likelihood.
+            assert_eq!(pl[call.unphased_diploid_gt_index().unwrap() as usize], 0);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn component_rows_across_blocks() -> Result<()> {
+    use parser::types::{metadata::shared::BufferSpec, EType, ETypeShape, VType, VTypeShape};
+    use parser::HailValue;
+
+    use crate::common::{output, resource};
+
+    // Small blocks, so that the rows straddle them, and a last row much larger than a block.
+    let mut component = parser::load::component(resource("small-pheno.t/rows"))?;
+    component.metadata.buffer_spec = BufferSpec::LEB128BufferSpec {
+        child: Box::new(BufferSpec::BlockingBufferSpec {
+            block_size: 1024,
+            child: Box::new(BufferSpec::LZ4FastBlockBufferSpec {
+                block_size: 1024,
+                child: Box::new(BufferSpec::StreamBlockBufferSpec),
+            }),
+        }),
+    };
+    let values: VType = "Array[Int32]".parse()?;
+    let mut encoded_values: EType = "+EArray[+EInt32]".parse()?;
+    encoded_values.virtual_hint = None;
+    match (
+        &mut component.metadata.virtual_type.shape,
+        &mut component.metadata.encoded_type.shape,
+    ) {
+        (VTypeShape::Struct(virtual_fields), ETypeShape::BaseStruct(encoded_fields)) => {
+            virtual_fields.push(("values".to_owned(), values));
+            encoded_fields.push(("values".to_owned(), encoded_values));
+        }
+        _ => panic!("rows are structs"),
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    pub struct Row {
+        s: Option<String>,
+        phenotype: Option<f64>,
+        values: Vec<i32>,
+    }
+    let rows = component.data.iter_mut().flatten().collect::<Vec<_>>();
+    let last = rows.len() - 1;
+    let mut expected = vec![];
+    for (index, row) in rows.into_iter().enumerate() {
+        let values = match index == last {
+            true => (0..100_000).collect(),
+            false => vec![index as i32; index],
+        };
+        let fields = match row {
+            HailValue::Struct(fields) => fields,
+            _ => panic!("rows are structs"),
+        };
+        fields.insert(
+            "values".to_owned(),
+            HailValue::Array(values.iter().copied().map(HailValue::Int32).collect()),
+        );
+        expected.push(Row {
+            s: match &fields["s"] {
+                HailValue::String(s) => Some(s.clone()),
+                _ => None,
+            },
+            phenotype: match &fields["phenotype"] {
+                HailValue::Float64(phenotype) => Some(*phenotype),
+                _ => None,
+            },
+            values,
+        });
+    }
+    let path = output("small-pheno.blocks");
+    parser::write::component(&component, &path)?;
+
+    let loaded = serde_hail::load::component::<Row, _>(&path)?;
+    assert_eq!(
+        loaded.data.into_iter().flatten().collect::<Vec<_>>(),
+        expected
+    );
+    let loaded = serde_hail::load::component_with_type::<Row, _>(&path)?;
+    assert_eq!(
+        loaded.data.into_iter().flatten().collect::<Vec<_>>(),
+        expected
+    );
+    Ok(())
+}
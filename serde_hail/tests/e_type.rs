@@ -0,0 +1,221 @@
+#![allow(dead_code)]
+use std::{collections::BTreeMap, path::PathBuf};
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use serde_hail::types::{Call, Interval, Locus, NDArray};
+
+use crate::common::resource;
+
+fn compat_150_table(index: usize) -> PathBuf {
+    resource("backward_compatability/1.5.0/table").join(format!("{}.ht", index))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EmptyGlobal;
+
+#[test]
+fn json_values_compat_150() -> Result<()> {
+    for index in 0..8 {
+        let table = serde_hail::load::table_with_type::<serde_json::Value, serde_json::Value, _>(
+            compat_150_table(index),
+        )?;
+
+        let rows: Vec<&serde_json::Value> = table.rows.data.iter().flatten().collect();
+        assert!(!rows.is_empty());
+        for (idx, row) in rows.into_iter().enumerate() {
+            assert_eq!(row["idx"], idx);
+            assert!(row["h38"]["contig"].is_string());
+            assert!(row["i"]["includesStart"].is_boolean());
+            assert!(row["t"].is_array());
+            assert!(row["nd"]["shape"].is_array());
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn json_values_sample_vcf() -> Result<()> {
+    let matrix = serde_hail::load::matrix_with_type::<
+        serde_json::Value,
+        serde_json::Value,
+        serde_json::Value,
+        serde_json::Value,
+        _,
+    >(resource("sample.vcf.mt"))?;
+
+    let row = &matrix.rows.data[0][0];
+    assert!(row["locus"]["position"].is_number());
+    assert!(row["alleles"].is_array());
+    // Missing values are null.
+    assert!(row["info"]
+        .as_object()
+        .unwrap()
+        .values()
+        .any(|value| value.is_null()));
+
+    Ok(())
+}
+
+#[test]
+fn fields_by_name() -> Result<()> {
+    // A subset of the fields, in a different order.
+    #[derive(Debug, Deserialize)]
+    pub struct Row {
+        #[serde(rename = "h38")]
+        locus: Locus,
+        mc: Option<Call>,
+        i: Interval<Locus>,
+        idx: u32,
+    }
+
+    for index in 0..8 {
+        let path = compat_150_table(index).join("rows");
+        let rows = serde_hail::load::component_with_type::<Row, _>(&path)?;
+        let values = serde_hail::load::component_with_type::<serde_json::Value, _>(&path)?;
+
+        let rows = rows.data.iter().flatten();
+        for (row, value) in rows.zip(values.data.iter().flatten()) {
+            assert_eq!(value["idx"], row.idx);
+            assert_eq!(value["h38"]["position"], row.locus.1);
+            assert_eq!(value["mc"].is_null(), row.mc.is_none());
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn flatten_and_untagged() -> Result<()> {
+    #[derive(Debug, Deserialize)]
+    #[serde(untagged)]
+    pub enum Phenotype {
+        Number(f64),
+        Text(String),
+    }
+    #[derive(Debug, Deserialize)]
+    pub struct Row {
+        s: Option<String>,
+        #[serde(flatten)]
+        other: BTreeMap<String, Option<Phenotype>>,
+    }
+
+    let table =
+        serde_hail::load::table_with_type::<EmptyGlobal, Row, _>(resource("small-pheno.t"))?;
+    let rows: Vec<&Row> = table.rows.data.iter().flatten().collect();
+
+    assert!(!rows.is_empty());
+    for row in rows {
+        assert!(row.other.contains_key("phenotype"));
+        assert!(matches!(
+            row.other["phenotype"],
+            None | Some(Phenotype::Number(_))
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn same_as_positional_compat_150() -> Result<()> {
+    #[derive(Debug, Deserialize)]
+    pub struct Astruct {
+        a: Option<u32>,
+        b: f64,
+    }
+    #[derive(Debug, Deserialize)]
+    pub struct Mstruct {
+        x: u32,
+        y: String,
+    }
+    #[derive(Debug, Deserialize)]
+    pub struct Row {
+        idx: u32,
+        #[serde(rename = "f32")]
+        f_32: f32,
+        #[serde(rename = "i64")]
+        i_64: i64,
+        m: Option<f64>,
+        astruct: Astruct,
+        mstruct: Option<Mstruct>,
+        aset: Vec<String>,
+        mset: Option<Vec<f64>>,
+        d: BTreeMap<Vec<Option<String>>, f64>,
+        md: Option<BTreeMap<u32, String>>,
+        #[serde(rename = "h38")]
+        h_38: Locus,
+        ml: Option<Locus>,
+        i: Interval<Locus>,
+        c: Call,
+        mc: Option<Call>,
+        t: (Call, String, Option<String>),
+        mt: Option<(Locus, bool)>,
+        nd: NDArray<u32, 2usize>,
+    }
+
+    for index in 0..8 {
+        let path = compat_150_table(index).join("rows");
+        let positional = serde_hail::load::component::<Row, _>(&path)?;
+        let by_name = serde_hail::load::component_with_type::<Row, _>(&path)?;
+        assert_eq!(
+            format!("{:?}", positional.data),
+            format!("{:?}", by_name.data)
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn subset_of_fields_sample_vcf() -> Result<()> {
+    // The info struct (and most other fields) are skipped.
+    #[derive(Debug, Deserialize)]
+    pub struct Row {
+        qual: f64,
+        locus: Locus,
+        alleles: Vec<String>,
+    }
+
+    let path = resource("sample.vcf.mt/rows/rows");
+    let rows = serde_hail::load::component_with_type::<Row, _>(&path)?;
+    let values = parser::load::component(&path)?;
+
+    let rows = rows.data.iter().flatten();
+    for (row, value) in rows.zip(values.data.iter().flatten()) {
+        let fields = match value {
+            parser::HailValue::Struct(fields) => fields,
+            value => panic!("expected a struct, found {:?}", value),
+        };
+        assert_eq!(fields["qual"], parser::HailValue::Float64(row.qual));
+        match &fields["locus"] {
+            parser::HailValue::Locus {
+                contig, position, ..
+            } => assert_eq!((contig, *position), (&row.locus.0, row.locus.1)),
+            locus => panic!("expected a locus, found {:?}", locus),
+        }
+        assert_eq!(
+            fields["alleles"],
+            parser::HailValue::Array(
+                row.alleles
+                    .iter()
+                    .map(|allele| parser::HailValue::String(allele.clone()))
+                    .collect()
+            )
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn unknown_field_sample_vcf() {
+    #[derive(Debug, Deserialize)]
+    pub struct Row {
+        locus: Locus,
+        not_a_field: bool,
+    }
+
+    let error =
+        serde_hail::load::component_with_type::<Row, _>(resource("sample.vcf.mt/rows/rows"))
+            .unwrap_err()
+            .to_string();
+    assert!(error.contains("`not_a_field`"));
+    assert!(error.contains("alleles"));
+}
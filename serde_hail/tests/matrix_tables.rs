@@ -5,14 +5,17 @@ use std::{collections::BTreeMap, path::PathBuf};
 use anyhow::{Context, Result};
 use serde::Deserialize;
 
-use serde_hail::types::{Call, Interval, Locus, NDArray};
+use serde_hail::{
+    load::Row,
+    types::{Call, Interval, Locus, NDArray},
+};
 
 fn parse_matrix<G, C, R, E>(file: &str) -> Result<()>
 where
-    G: serde::de::DeserializeOwned,
-    C: serde::de::DeserializeOwned,
-    R: serde::de::DeserializeOwned,
-    E: serde::de::DeserializeOwned,
+    G: Row,
+    C: Row,
+    R: Row,
+    E: Row,
 {
     let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .join("../resources")
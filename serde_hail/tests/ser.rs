@@ -0,0 +1,167 @@
+#![allow(dead_code)]
+use std::{collections::BTreeMap, fmt::Debug};
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use parser::{
+    parse::{Encoding, StandardEncoder, UnsignedLEB128Encoder},
+    types::metadata::ComponentMetadata,
+};
+use serde_hail::types::{Call, Interval, Locus, NDArray};
+
+use crate::common::resource;
+
+/// Serializes the rows of each partition, then checks that they deserialize to the same rows.
+fn round_trip<R>(metadata: &ComponentMetadata, partitions: &[Vec<R>]) -> Result<()>
+where
+    R: Serialize + DeserializeOwned + Debug,
+{
+    match metadata.buffer_spec.uses_leb128() {
+        true => _round_trip::<R, UnsignedLEB128Encoder>(partitions),
+        false => _round_trip::<R, StandardEncoder>(partitions),
+    }
+}
+
+fn _round_trip<R, E>(partitions: &[Vec<R>]) -> Result<()>
+where
+    R: Serialize + DeserializeOwned + Debug,
+    E: Encoding,
+{
+    for rows in partitions {
+        let bytes = serde_hail::encode_rows::<R, E>(rows)?;
+        let parsed = serde_hail::parse_rows::<R, E>(&bytes)?;
+        assert_eq!(format!("{:?}", rows), format!("{:?}", parsed));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EmptyGlobal;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ThreeKeyRow {
+    x: i32,
+    y: i32,
+    z: i32,
+}
+
+#[test]
+fn serialize_three_key_as_written_by_hail() -> Result<()> {
+    let path = resource("three_key.ht");
+    let table = serde_hail::load::table::<EmptyGlobal, ThreeKeyRow, _>(&path)?;
+    let metadata = &table.rows.metadata;
+
+    for (part_file, rows) in metadata.part_files.iter().zip(&table.rows.data) {
+        let file = std::fs::read(path.join("rows/parts").join(part_file))?;
+        let expected = parser::load::compression::decompress_part_file(&file)?;
+
+        let bytes = match metadata.buffer_spec.uses_leb128() {
+            true => serde_hail::encode_rows::<_, UnsignedLEB128Encoder>(rows)?,
+            false => serde_hail::encode_rows::<_, StandardEncoder>(rows)?,
+        };
+        assert_eq!(expected, bytes);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn encoded_type_of_three_key() -> Result<()> {
+    let metadata = parser::load::metadata::load_component_metadata(resource("three_key.ht/rows"))?;
+    assert_eq!(
+        metadata.encoded_type,
+        serde_hail::encoded_type::<ThreeKeyRow>()?
+    );
+    Ok(())
+}
+
+#[test]
+fn round_trip_small_pheno() -> Result<()> {
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct Row {
+        s: Option<String>,
+        phenotype: Option<f64>,
+    }
+
+    let table = serde_hail::load::table::<EmptyGlobal, Row, _>(resource("small-pheno.t"))?;
+    round_trip(&table.rows.metadata, &table.rows.data)
+}
+
+#[test]
+fn round_trip_compat_150() -> Result<()> {
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct Astruct {
+        a: Option<u32>,
+        b: f64,
+    }
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct Mstruct {
+        x: u32,
+        y: String,
+    }
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct Row {
+        idx: u32,
+        f_32: f32,
+        i_64: i64,
+        m: Option<f64>,
+        astruct: Astruct,
+        mstruct: Option<Mstruct>,
+        aset: Vec<String>,
+        mset: Option<Vec<f64>>,
+        d: BTreeMap<Vec<Option<String>>, f64>,
+        md: Option<BTreeMap<u32, String>>,
+        h_38: Locus,
+        ml: Option<Locus>,
+        i: Interval<Locus>,
+        c: Call,
+        mc: Option<Call>,
+        t: (Call, String, Option<String>),
+        mt: Option<(Locus, bool)>,
+        nd: NDArray<u32, 2usize>,
+    }
+
+    // Both with and without LEB128.
+    for table in &["0.ht", "1.ht"] {
+        let path = resource("backward_compatability/1.5.0/table").join(table);
+        let rows = serde_hail::load::component::<Row, _>(path.join("rows"))?;
+        round_trip(&rows.metadata, &rows.data)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn binaries_are_decoded_by_the_parser() -> Result<()> {
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct Row {
+        bytes: Vec<u8>,
+        buf: Option<serde_bytes::ByteBuf>,
+    }
+
+    // Neither binary is valid UTF-8.
+    let rows = vec![Row {
+        bytes: vec![0xff, 0xfe],
+        buf: Some(serde_bytes::ByteBuf::from(vec![0xc3])),
+    }];
+    _round_trip::<Row, StandardEncoder>(&[rows])?;
+
+    let rows = vec![Row {
+        bytes: vec![0xff, 0xfe],
+        buf: None,
+    }];
+    let bytes = serde_hail::encode_rows::<Row, StandardEncoder>(&rows)?;
+    let row_type = serde_hail::encoded_type::<Row>()?;
+    let values = parser::parse::data::parse_rows::<StandardEncoder>(&bytes, &row_type)?;
+    let expected: BTreeMap<String, parser::HailValue> = vec![
+        (
+            "bytes".to_owned(),
+            parser::HailValue::Binary(vec![0xff, 0xfe]),
+        ),
+        ("buf".to_owned(), parser::HailValue::Missing),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(values, vec![parser::HailValue::Struct(expected)]);
+    Ok(())
+}
@@ -3,14 +3,17 @@
 use std::{collections::BTreeMap, path::PathBuf};
 
 use anyhow::{Context, Result};
-use serde::{de::DeserializeOwned, Deserialize};
+use serde::Deserialize;
 
-use serde_hail::types::{Call, Interval, Locus, NDArray};
+use serde_hail::{
+    load::Row,
+    types::{Call, Interval, Locus, NDArray},
+};
 
 fn parse_table<G, R>(file: &str) -> Result<()>
 where
-    G: DeserializeOwned,
-    R: DeserializeOwned,
+    G: Row,
+    R: Row,
 {
     let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .join("../resources")
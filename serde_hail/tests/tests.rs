@@ -1,3 +1,7 @@
+mod common;
+
 mod component;
+mod e_type;
 mod matrix_tables;
+mod ser;
 mod table;